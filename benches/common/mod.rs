@@ -0,0 +1,104 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Synthetic contract data shared by the benchmarks.
+
+#![allow(dead_code)]
+
+use amplify::confinement::Confined;
+use bp::seals::txout::{CloseMethod, TxPtr};
+use bp::{Tx, Txid};
+use rgb::validation::{ResolveTx, TxResolverError};
+use rgb::{
+    AnchoredBundle, AssetTag, Assign, AssignmentType, BlindingFactor, Consignment, GraphSeal,
+    Input, Inputs, Layer1, Operation, Opout, RevealedValue, SealDefinition, Terminal, Transition,
+    TransitionBundle, TransitionType, TypedAssigns,
+};
+use strict_encoding::StrictDumb;
+
+pub const ASSIGNMENT_TYPE: AssignmentType = AssignmentType::with(1);
+
+/// Resolver which doesn't know any transaction.
+pub struct NoResolver;
+
+impl ResolveTx for NoResolver {
+    fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+        Err(TxResolverError::Unknown(txid))
+    }
+}
+
+/// Constructs transition spending `prev` output and assigning fungible state
+/// to a single seal.
+pub fn transition(prev: Option<Opout>, no: u64) -> Transition {
+    let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+        CloseMethod::TapretFirst,
+        TxPtr::WitnessTx,
+        0,
+        no,
+    ));
+    let value =
+        RevealedValue::with_blinding(100u64, BlindingFactor::EMPTY, AssetTag::from([1; 32]));
+    let mut transition = Transition::strict_dumb();
+    if let Some(prev) = prev {
+        transition.inputs = Inputs::from(Confined::try_from_iter([Input::with(prev)]).unwrap());
+    }
+    transition
+        .assignments
+        .insert(
+            ASSIGNMENT_TYPE,
+            TypedAssigns::Fungible(
+                Confined::try_from_iter([Assign::revealed(seal, value)]).unwrap(),
+            ),
+        )
+        .unwrap();
+    transition
+}
+
+/// Constructs consignment with a linear history of `len` transitions, each of
+/// them in a separate bundle.
+pub fn consignment(len: usize) -> Consignment {
+    let mut consignment = Consignment::strict_dumb();
+    consignment.genesis.schema_id = consignment.schema.schema_id();
+    let mut prev = None;
+    for no in 0..len {
+        let mut transition = transition(prev, no as u64);
+        transition.schema_id = consignment.genesis.schema_id;
+        // Dumb schema doesn't declare any transition types
+        transition.transition_type = TransitionType::BLANK;
+        let opid = transition.id();
+        let seal = transition.assignments[&ASSIGNMENT_TYPE].to_confidential_seals()[0];
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        if no == len - 1 {
+            consignment
+                .terminals
+                .push(Terminal {
+                    bundle_id: anchored.bundle.bundle_id(),
+                    seal,
+                })
+                .unwrap();
+        }
+        consignment.bundles.push(anchored).unwrap();
+        prev = Some(Opout::new(opid, ASSIGNMENT_TYPE, 0));
+    }
+    consignment
+}
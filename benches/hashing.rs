@@ -0,0 +1,66 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of the SHA-256 hot paths: commit-encoding of operations and
+//! computation of operation and bundle ids and LNPBP-4 commitment trees.
+//! Compare the results with and without the `sha-asm` feature:
+//!
+//! ```console
+//! $ cargo bench --bench hashing
+//! $ cargo bench --bench hashing --features sha-asm
+//! ```
+
+mod common;
+
+use commit_verify::mpc::{self, MultiSource};
+use commit_verify::{CommitEncode, TryCommitVerify};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rgb::{Operation, TransitionBundle};
+
+fn commitments(c: &mut Criterion) {
+    let transition = common::transition(None, 0);
+    c.bench_function("commit_encode", |b| {
+        b.iter(|| {
+            let mut buf = Vec::new();
+            transition.commit_encode(&mut buf);
+            black_box(buf)
+        })
+    });
+    c.bench_function("opid", |b| b.iter(|| black_box(&transition).id()));
+
+    let bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+    c.bench_function("bundle_id", |b| b.iter(|| black_box(&bundle).bundle_id()));
+
+    let mut source = MultiSource::default();
+    for no in 0..64u8 {
+        source
+            .messages
+            .insert(mpc::ProtocolId::from([no; 32]), mpc::Message::from([no; 32]))
+            .unwrap();
+    }
+    c.bench_function("mpc_tree_64", |b| {
+        b.iter(|| mpc::MerkleTree::try_commit(black_box(&source)).unwrap())
+    });
+}
+
+criterion_group!(benches, commitments);
+criterion_main!(benches);
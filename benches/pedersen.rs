@@ -0,0 +1,53 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks of Pedersen commitments and verification of their balance, as
+//! performed by the `PCVS` AluVM instruction.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rgb::{AssetTag, BlindingFactor, PedersenCommitment, PedersenContext, RevealedValue};
+use secp256k1_zkp::rand::thread_rng;
+
+fn pedersen(c: &mut Criterion) {
+    let tag = AssetTag::from([1; 32]);
+    let context = PedersenContext::new();
+    let revealed = RevealedValue::with_random_blinding(100u64, &mut thread_rng(), tag);
+
+    c.bench_function("pedersen_commit", |b| b.iter(|| context.commit(black_box(&revealed))));
+
+    let mut group = c.benchmark_group("pedersen_balance");
+    for count in [2usize, 16, 128] {
+        // Inputs and outputs with the same values and blinding factors are
+        // balanced.
+        let commitments = (0..count as u64)
+            .map(|value| RevealedValue::with_blinding(value, BlindingFactor::EMPTY, tag))
+            .map(|revealed| context.commit(&revealed))
+            .collect::<Vec<PedersenCommitment>>();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &commitments, |b, c| {
+            b.iter(|| assert!(context.verify_sum(c, c)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pedersen);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rgb::untrusted::deserialize_untrusted;
+use rgb::{AnchoredBundle, TransitionBundle};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bundle) = deserialize_untrusted::<TransitionBundle>(data) {
+        let _ = bundle.bundle_id();
+        let _ = bundle.known_transitions().count();
+    }
+    if let Ok(anchored) = deserialize_untrusted::<AnchoredBundle>(data) {
+        let _ = anchored.bundle.bundle_id();
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rgb::untrusted::deserialize_untrusted;
+use rgb::Consignment;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(consignment) = deserialize_untrusted::<Consignment>(data) {
+        let _ = consignment.consignment_id();
+        let _ = consignment.contract_id();
+    }
+});
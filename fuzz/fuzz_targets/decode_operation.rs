@@ -0,0 +1,21 @@
+#![no_main]
+
+use commit_verify::Conceal;
+use libfuzzer_sys::fuzz_target;
+use rgb::untrusted::deserialize_untrusted;
+use rgb::{Extension, Genesis, Operation, Transition};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(genesis) = deserialize_untrusted::<Genesis>(data) {
+        let _ = genesis.id();
+        let _ = genesis.conceal();
+    }
+    if let Ok(transition) = deserialize_untrusted::<Transition>(data) {
+        let _ = transition.id();
+        let _ = transition.conceal();
+    }
+    if let Ok(extension) = deserialize_untrusted::<Extension>(data) {
+        let _ = extension.id();
+        let _ = extension.conceal();
+    }
+});
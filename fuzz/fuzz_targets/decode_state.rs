@@ -0,0 +1,18 @@
+#![no_main]
+
+use commit_verify::Conceal;
+use libfuzzer_sys::fuzz_target;
+use rgb::untrusted::deserialize_untrusted;
+use rgb::{RevealedAttach, RevealedData, RevealedValue};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(state) = deserialize_untrusted::<RevealedValue>(data) {
+        let _ = state.conceal();
+    }
+    if let Ok(state) = deserialize_untrusted::<RevealedData>(data) {
+        let _ = state.conceal();
+    }
+    if let Ok(state) = deserialize_untrusted::<RevealedAttach>(data) {
+        let _ = state.conceal();
+    }
+});
@@ -0,0 +1,71 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{env, fs, process};
+
+use rgb::vectors::TestVectors;
+
+const USAGE: &str = "Usage: rgbcore-vectors <generate|verify> <FILE>
+
+  generate  computes commitment ids for the fixtures from FILE and prints test
+            vectors to STDOUT
+  verify    verifies test vectors from FILE";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let [command, file] = args.as_slice() else {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    };
+    let json = fs::read_to_string(file).unwrap_or_else(|err| {
+        eprintln!("Error: unable to read {file}: {err}");
+        process::exit(2);
+    });
+    let vectors = TestVectors::from_json(&json).unwrap_or_else(|err| {
+        eprintln!("Error: {err}");
+        process::exit(2);
+    });
+
+    match command.as_str() {
+        "generate" => match vectors.generate() {
+            Ok(vectors) => println!("{}", vectors.to_json()),
+            Err(err) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            }
+        },
+        "verify" => {
+            let errors = vectors.verify();
+            for err in &errors {
+                eprintln!("Error: {err}");
+            }
+            if !errors.is_empty() {
+                process::exit(1);
+            }
+            println!("All {} test vectors are valid", vectors.len());
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! UniFFI bindings for Kotlin and Swift.
+//!
+//! The bindings expose consignment validation, contract state queries, id
+//! computation and transition bundle construction. Consensus data are passed
+//! in their strict-serialized form; identifiers are passed as strings.
+//!
+//! Foreign language sources are generated from the compiled library with
+//! `uniffi-bindgen generate --library`.
+
+use std::sync::Arc;
+
+use amplify::confinement::U32;
+use bp::{ConsensusDecode, Tx, Txid};
+use commit_verify::CommitmentId;
+use strict_encoding::{StrictDecode, StrictSerialize};
+
+use crate::untrusted::deserialize_untrusted;
+use crate::validation::{ResolveTx, TxResolverError, Validity};
+use crate::{
+    AssignmentType, ChainNet, Consignment, ContractState, Genesis, Layer1, Output, StateView,
+    Transition, TransitionBundle,
+};
+
+/// Errors returned by the bindings.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, uniffi::Error)]
+#[display(doc_comments)]
+#[uniffi(flat_error)]
+pub enum BindingsError {
+    /// provided data can't be deserialized.
+    Decode,
+
+    /// invalid transition bundle. Details: {0}
+    Bundle(String),
+}
+
+/// Result of consignment validation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, uniffi::Enum)]
+pub enum Validation {
+    Valid,
+    UnminedTerminals,
+    UnresolvedTransactions,
+    Invalid,
+}
+
+impl From<Validity> for Validation {
+    fn from(validity: Validity) -> Self {
+        match validity {
+            Validity::Valid => Validation::Valid,
+            Validity::UnminedTerminals => Validation::UnminedTerminals,
+            Validity::UnresolvedTransactions => Validation::UnresolvedTransactions,
+            Validity::Invalid => Validation::Invalid,
+        }
+    }
+}
+
+/// Blockchain network of the contract.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, uniffi::Enum)]
+pub enum Network {
+    BitcoinMainnet,
+    BitcoinTestnet,
+    BitcoinSignet,
+    BitcoinRegtest,
+    LiquidMainnet,
+    LiquidTestnet,
+}
+
+impl From<Network> for ChainNet {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::BitcoinMainnet => ChainNet::BitcoinMainnet,
+            Network::BitcoinTestnet => ChainNet::BitcoinTestnet,
+            Network::BitcoinSignet => ChainNet::BitcoinSignet,
+            Network::BitcoinRegtest => ChainNet::BitcoinRegtest,
+            Network::LiquidMainnet => ChainNet::LiquidMainnet,
+            Network::LiquidTestnet => ChainNet::LiquidTestnet,
+        }
+    }
+}
+
+/// Fungible state assigned to a transaction output.
+#[derive(Clone, PartialEq, Eq, Debug, uniffi::Record)]
+pub struct FungibleAllocation {
+    pub opid: String,
+    pub assignment_type: u16,
+    pub no: u16,
+    pub liquid: bool,
+    pub txid: String,
+    pub vout: u32,
+    /// Decimal representation of the value, which may exceed 64 bits.
+    pub value: String,
+}
+
+/// State transition together with the witness transaction input index it
+/// closes seals for.
+#[derive(Clone, PartialEq, Eq, Debug, uniffi::Record)]
+pub struct BundleInput {
+    pub input: u16,
+    pub transition: Vec<u8>,
+}
+
+/// Resolver of witness transactions implemented by the wallet.
+#[uniffi::export(callback_interface)]
+pub trait WitnessResolver: Send + Sync {
+    /// Returns consensus-serialized transaction with the given id, or `None`
+    /// if the transaction is not known.
+    fn resolve_tx(&self, liquid: bool, txid: String) -> Option<Vec<u8>>;
+}
+
+struct BindingsResolver(Box<dyn WitnessResolver>);
+
+impl ResolveTx for BindingsResolver {
+    fn resolve_tx(&self, layer1: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+        let data = self
+            .0
+            .resolve_tx(layer1 == Layer1::Liquid, txid.to_string())
+            .ok_or(TxResolverError::Unknown(txid))?;
+        Tx::consensus_deserialize(data).map_err(|err| TxResolverError::Other(txid, err.to_string()))
+    }
+}
+
+fn decode<T: StrictDecode>(data: Vec<u8>) -> Result<T, BindingsError> {
+    deserialize_untrusted(&data).map_err(|_| BindingsError::Decode)
+}
+
+/// Computes id of a strict-serialized consignment.
+#[uniffi::export]
+pub fn consignment_id(consignment: Vec<u8>) -> Result<String, BindingsError> {
+    Ok(decode::<Consignment>(consignment)?
+        .consignment_id()
+        .to_string())
+}
+
+/// Computes contract id of a strict-serialized genesis.
+#[uniffi::export]
+pub fn genesis_contract_id(genesis: Vec<u8>) -> Result<String, BindingsError> {
+    Ok(decode::<Genesis>(genesis)?.commitment_id().to_string())
+}
+
+/// Computes operation id of a strict-serialized state transition.
+#[uniffi::export]
+pub fn transition_id(transition: Vec<u8>) -> Result<String, BindingsError> {
+    Ok(decode::<Transition>(transition)?
+        .commitment_id()
+        .to_string())
+}
+
+/// Computes bundle id of a strict-serialized transition bundle.
+#[uniffi::export]
+pub fn bundle_id(bundle: Vec<u8>) -> Result<String, BindingsError> {
+    Ok(decode::<TransitionBundle>(bundle)?.bundle_id().to_string())
+}
+
+/// Validates strict-serialized consignment.
+#[uniffi::export]
+pub fn validate_consignment(
+    consignment: Vec<u8>,
+    network: Network,
+    resolver: Box<dyn WitnessResolver>,
+) -> Result<Validation, BindingsError> {
+    let consignment = decode::<Consignment>(consignment)?;
+    let status = consignment.validate(&BindingsResolver(resolver), network.into());
+    Ok(status.validity().into())
+}
+
+/// Constructs strict-serialized transition bundle from a set of
+/// strict-serialized state transitions.
+#[uniffi::export]
+pub fn build_bundle(inputs: Vec<BundleInput>) -> Result<Vec<u8>, BindingsError> {
+    let transitions = inputs
+        .into_iter()
+        .map(|item| Ok((item.input, decode::<Transition>(item.transition)?)))
+        .collect::<Result<Vec<_>, BindingsError>>()?;
+    let bundle = TransitionBundle::try_from_transitions(transitions)
+        .map_err(|err| BindingsError::Bundle(err.to_string()))?;
+    Ok(bundle
+        .to_strict_serialized::<U32>()
+        .expect("bundle size is confined to u8 items")
+        .into_inner())
+}
+
+/// Contract state object.
+#[derive(Debug, uniffi::Object)]
+pub struct ContractStateHandle(ContractState);
+
+#[uniffi::export]
+impl ContractStateHandle {
+    /// Deserializes strict-serialized contract state.
+    #[uniffi::constructor]
+    pub fn new(state: Vec<u8>) -> Result<Arc<Self>, BindingsError> {
+        Ok(Arc::new(Self(decode(state)?)))
+    }
+
+    pub fn contract_id(&self) -> String { self.0.contract_id().to_string() }
+
+    pub fn schema_id(&self) -> String { self.0.schema_id().to_string() }
+
+    /// Returns all known fungible allocations of a given assignment type.
+    pub fn fungible_allocations(&self, assignment_type: u16) -> Vec<FungibleAllocation> {
+        self.0
+            .fungible_outputs(AssignmentType::with(assignment_type), StateView::Pending)
+            .map(|output| {
+                let (liquid, outpoint) = match output.output {
+                    Output::Bitcoin(outpoint) => (false, outpoint),
+                    Output::Liquid(outpoint) => (true, outpoint),
+                };
+                FungibleAllocation {
+                    opid: output.opout.op.to_string(),
+                    assignment_type,
+                    no: output.opout.no,
+                    liquid,
+                    txid: outpoint.txid.to_string(),
+                    vout: outpoint.vout.into_u32(),
+                    value: output.state.value.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Serializes the contract state.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0
+            .to_strict_serialized::<U32>()
+            .expect("contract state exceeds 4GB")
+            .into_inner()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::Operation;
+
+    #[test]
+    fn ids() {
+        let genesis = Genesis::strict_dumb();
+        let data = genesis.to_strict_serialized::<U32>().unwrap().into_inner();
+        assert_eq!(genesis_contract_id(data).unwrap(), genesis.contract_id().to_string());
+        assert_eq!(genesis_contract_id(vec![0]), Err(BindingsError::Decode));
+    }
+
+    #[test]
+    fn bundle() {
+        let transition = Transition::strict_dumb();
+        let data = transition
+            .to_strict_serialized::<U32>()
+            .unwrap()
+            .into_inner();
+        let bundle = build_bundle(vec![BundleInput {
+            input: 0,
+            transition: data,
+        }])
+        .unwrap();
+        let expected = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        assert_eq!(bundle_id(bundle).unwrap(), expected.bundle_id().to_string());
+    }
+}
@@ -0,0 +1,390 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Construction of state transitions from the schema action templates.
+
+use std::collections::BTreeMap;
+
+use strict_encoding::FieldName;
+
+use crate::schema::OccurrencesMismatch;
+use crate::{
+    ActionTemplate, Assign, AssignmentType, ContractId, Ffv, GlobalStateType, GraphSeal, Input,
+    Opout, RevealedAttach, RevealedData, RevealedValue, Schema, SchemaRoot, SealDefinition,
+    StateType, Transition, TransitionType, TypedAssigns, VoidState, WitnessTemplate,
+    WitnessWarning,
+};
+
+/// Errors constructing state transitions from action templates.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ActionError {
+    /// action {0} is not declared by the contract schema.
+    UnknownAction(FieldName),
+
+    /// state transition has type {found}, while the action requires type
+    /// {expected}.
+    TransitionTypeMismatch {
+        expected: TransitionType,
+        found: TransitionType,
+    },
+
+    /// action doesn't allow global state of type {0}.
+    GlobalTypeUnexpected(GlobalStateType),
+
+    /// action doesn't allow inputs of type {0}.
+    InputTypeUnexpected(AssignmentType),
+
+    /// action doesn't allow assignments of type {0}.
+    AssignmentTypeUnexpected(AssignmentType),
+
+    /// assignment of type {0} uses state of type {1}, while previous
+    /// assignments of the same type use {2}.
+    StateTypeMismatch(AssignmentType, StateType, StateType),
+
+    /// number of global state items of type {0} doesn't match the action
+    /// template: {1}.
+    GlobalOccurrences(GlobalStateType, OccurrencesMismatch),
+
+    /// number of inputs of type {0} doesn't match the action template: {1}.
+    InputOccurrences(AssignmentType, OccurrencesMismatch),
+
+    /// number of assignments of type {0} doesn't match the action template:
+    /// {1}.
+    AssignmentOccurrences(AssignmentType, OccurrencesMismatch),
+
+    /// state transition exceeds its size limits.
+    TooLarge,
+}
+
+impl ActionTemplate {
+    /// Checks that the state transition matches the template.
+    pub fn check(&self, transition: &Transition) -> Result<(), ActionError> {
+        if transition.transition_type != self.transition_type {
+            return Err(ActionError::TransitionTypeMismatch {
+                expected: self.transition_type,
+                found: transition.transition_type,
+            });
+        }
+
+        let mut inputs = BTreeMap::<AssignmentType, u16>::new();
+        for input in &transition.inputs {
+            *inputs.entry(input.prev_out.ty).or_default() += 1;
+        }
+
+        for ty in transition.globals.keys() {
+            if !self.globals.contains_key(ty) {
+                return Err(ActionError::GlobalTypeUnexpected(*ty));
+            }
+        }
+        for ty in inputs.keys() {
+            if !self.inputs.contains_key(ty) {
+                return Err(ActionError::InputTypeUnexpected(*ty));
+            }
+        }
+        for ty in transition.assignments.keys() {
+            if !self.assignments.contains_key(ty) {
+                return Err(ActionError::AssignmentTypeUnexpected(*ty));
+            }
+        }
+
+        for (ty, occ) in &self.globals {
+            let count = transition
+                .globals
+                .get(ty)
+                .map(|values| values.len() as u16)
+                .unwrap_or_default();
+            occ.check(count)
+                .map_err(|err| ActionError::GlobalOccurrences(*ty, err))?;
+        }
+        for (ty, occ) in &self.inputs {
+            let count = inputs.get(ty).copied().unwrap_or_default();
+            occ.check(count)
+                .map_err(|err| ActionError::InputOccurrences(*ty, err))?;
+        }
+        for (ty, occ) in &self.assignments {
+            let count = transition
+                .assignments
+                .get(ty)
+                .map(TypedAssigns::len_u16)
+                .unwrap_or_default();
+            occ.check(count)
+                .map_err(|err| ActionError::AssignmentOccurrences(*ty, err))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder constructing a state transition from an action template declared
+/// by the contract schema.
+///
+/// The transition is checked against the template when the construction is
+/// completed, such that malformed transitions are detected before they get
+/// signed and published.
+#[derive(Clone, Debug)]
+pub struct ActionBuilder {
+    template: ActionTemplate,
+    transition: Transition,
+}
+
+impl ActionBuilder {
+    /// Starts construction of a state transition for the `action` declared by
+    /// the contract schema.
+    pub fn with<Root: SchemaRoot>(
+        schema: &Schema<Root>,
+        contract_id: ContractId,
+        action: &FieldName,
+    ) -> Result<Self, ActionError> {
+        let template = schema
+            .action(action)
+            .ok_or_else(|| ActionError::UnknownAction(action.clone()))?
+            .clone();
+        let transition = Transition {
+            ffv: Ffv::default(),
+            contract_id,
+            schema_id: schema.schema_id(),
+            transition_type: template.transition_type,
+            metadata: empty!(),
+            globals: empty!(),
+            inputs: empty!(),
+            assignments: empty!(),
+            valencies: empty!(),
+            proof: None,
+            signature: None,
+        };
+        Ok(ActionBuilder {
+            template,
+            transition,
+        })
+    }
+
+    pub fn add_input(mut self, opout: Opout) -> Result<Self, ActionError> {
+        if !self.template.inputs.contains_key(&opout.ty) {
+            return Err(ActionError::InputTypeUnexpected(opout.ty));
+        }
+        self.transition
+            .inputs
+            .push(Input::with(opout))
+            .map_err(|_| ActionError::TooLarge)?;
+        Ok(self)
+    }
+
+    pub fn add_global(
+        mut self,
+        ty: GlobalStateType,
+        data: RevealedData,
+    ) -> Result<Self, ActionError> {
+        if !self.template.globals.contains_key(&ty) {
+            return Err(ActionError::GlobalTypeUnexpected(ty));
+        }
+        self.transition
+            .globals
+            .add_state(ty, data)
+            .map_err(|_| ActionError::TooLarge)?;
+        Ok(self)
+    }
+
+    pub fn add_rights(
+        self,
+        ty: AssignmentType,
+        seal: SealDefinition<GraphSeal>,
+    ) -> Result<Self, ActionError> {
+        let assign = Assign::revealed(seal, VoidState::default());
+        self.add_assignment(ty, TypedAssigns::Declarative(small_vec![assign]))
+    }
+
+    pub fn add_fungible(
+        self,
+        ty: AssignmentType,
+        seal: SealDefinition<GraphSeal>,
+        value: RevealedValue,
+    ) -> Result<Self, ActionError> {
+        let assign = Assign::revealed(seal, value);
+        self.add_assignment(ty, TypedAssigns::Fungible(small_vec![assign]))
+    }
+
+    pub fn add_data(
+        self,
+        ty: AssignmentType,
+        seal: SealDefinition<GraphSeal>,
+        data: RevealedData,
+    ) -> Result<Self, ActionError> {
+        let assign = Assign::revealed(seal, data);
+        self.add_assignment(ty, TypedAssigns::Structured(small_vec![assign]))
+    }
+
+    pub fn add_attachment(
+        self,
+        ty: AssignmentType,
+        seal: SealDefinition<GraphSeal>,
+        attach: RevealedAttach,
+    ) -> Result<Self, ActionError> {
+        let assign = Assign::revealed(seal, attach);
+        self.add_assignment(ty, TypedAssigns::Attachment(small_vec![assign]))
+    }
+
+    fn add_assignment(
+        mut self,
+        ty: AssignmentType,
+        assign: TypedAssigns<GraphSeal>,
+    ) -> Result<Self, ActionError> {
+        if !self.template.assignments.contains_key(&ty) {
+            return Err(ActionError::AssignmentTypeUnexpected(ty));
+        }
+        let assignments = &mut self.transition.assignments;
+        let Some(typed) = assignments.get_mut(&ty) else {
+            assignments
+                .insert(ty, assign)
+                .map_err(|_| ActionError::TooLarge)?;
+            return Ok(self);
+        };
+        match (typed, assign) {
+            (TypedAssigns::Declarative(vec), TypedAssigns::Declarative(new)) => vec.extend(new),
+            (TypedAssigns::Fungible(vec), TypedAssigns::Fungible(new)) => vec.extend(new),
+            (TypedAssigns::Structured(vec), TypedAssigns::Structured(new)) => vec.extend(new),
+            (TypedAssigns::Attachment(vec), TypedAssigns::Attachment(new)) => vec.extend(new),
+            (typed, assign) => {
+                return Err(ActionError::StateTypeMismatch(
+                    ty,
+                    assign.state_type(),
+                    typed.state_type(),
+                ));
+            }
+        }
+        .map_err(|_| ActionError::TooLarge)?;
+        Ok(self)
+    }
+
+    /// Checks the witness transaction template against the state transition
+    /// under construction, returning warnings about issues which may prevent
+    /// the transaction from being relayed or may lead to the loss of state.
+    pub fn check_witness(&self, template: &WitnessTemplate) -> Vec<WitnessWarning> {
+        template.check_transition(&self.transition)
+    }
+
+    /// Completes construction of the state transition, checking it against the
+    /// action template.
+    pub fn complete(self) -> Result<Transition, ActionError> {
+        self.template.check(&self.transition)?;
+        Ok(self.transition)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::validation::Failure;
+    use crate::{
+        AssetTag, BlindingFactor, FungibleType, Occurrences, OpId, StateSchema, SubSchema,
+        TransitionSchema,
+    };
+
+    #[test]
+    fn action_builder() {
+        let ty = AssignmentType::with(1);
+        let transition_type = TransitionType::with(1);
+        let mut schema = SubSchema::default();
+        schema
+            .owned_types
+            .insert(ty, StateSchema::Fungible(FungibleType::Unsigned64Bit))
+            .unwrap();
+        let mut transition_schema = TransitionSchema::default();
+        transition_schema
+            .inputs
+            .insert(ty, Occurrences::OnceOrMore)
+            .unwrap();
+        transition_schema
+            .assignments
+            .insert(ty, Occurrences::OnceOrMore)
+            .unwrap();
+        schema
+            .transitions
+            .insert(transition_type, transition_schema)
+            .unwrap();
+        let template = ActionTemplate {
+            transition_type,
+            globals: empty!(),
+            inputs: tiny_bmap! { ty => Occurrences::Once },
+            assignments: tiny_bmap! { ty => Occurrences::NoneOrUpTo(2) },
+        };
+        schema.actions.insert(fname!("transfer"), template).unwrap();
+
+        let contract_id = ContractId::strict_dumb();
+        assert_eq!(
+            ActionBuilder::with(&schema, contract_id, &fname!("burn")).unwrap_err(),
+            ActionError::UnknownAction(fname!("burn"))
+        );
+
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            1,
+        ));
+        let value =
+            RevealedValue::with_blinding(10, BlindingFactor::EMPTY, AssetTag::from([1; 32]));
+        let builder = ActionBuilder::with(&schema, contract_id, &fname!("transfer"))
+            .unwrap()
+            .add_input(Opout::new(OpId::strict_dumb(), ty, 0))
+            .unwrap();
+        assert_eq!(
+            builder
+                .clone()
+                .add_input(Opout::new(OpId::strict_dumb(), AssignmentType::with(2), 0))
+                .unwrap_err(),
+            ActionError::InputTypeUnexpected(AssignmentType::with(2))
+        );
+        let builder = builder.add_fungible(ty, seal, value).unwrap();
+        assert_eq!(
+            builder.clone().add_rights(ty, seal).unwrap_err(),
+            ActionError::StateTypeMismatch(ty, StateType::Void, StateType::Fungible)
+        );
+        assert!(matches!(
+            builder
+                .clone()
+                .add_fungible(ty, seal, value)
+                .and_then(|builder| builder.add_fungible(ty, seal, value))
+                .and_then(ActionBuilder::complete),
+            Err(ActionError::AssignmentOccurrences(t, _)) if t == ty
+        ));
+
+        let transition = builder.complete().unwrap();
+        assert_eq!(transition.transition_type, transition_type);
+        assert_eq!(transition.schema_id, schema.schema_id());
+        assert_eq!(transition.inputs.len(), 1);
+
+        let mut template = schema.action(&fname!("transfer")).unwrap().clone();
+        template.transition_type = TransitionType::with(2);
+        schema.actions.insert(fname!("mint"), template).unwrap();
+        assert!(schema
+            .verify()
+            .failures
+            .contains(&Failure::SchemaActionTransitionUnknown(
+                fname!("mint"),
+                TransitionType::with(2)
+            )));
+    }
+}
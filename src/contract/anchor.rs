@@ -23,12 +23,13 @@
 use std::cmp::Ordering;
 use std::ops::Deref;
 
+use amplify::confinement::TinyOrdMap;
 use bp::dbc;
 use bp::dbc::anchor::MergeError;
 use commit_verify::mpc;
 use strict_encoding::StrictDumb;
 
-use crate::{TransitionBundle, WitnessId, WitnessOrd, LIB_NAME_RGB};
+use crate::{ContractId, OnLayer1, TransitionBundle, WitnessId, WitnessOrd, LIB_NAME_RGB};
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -81,21 +82,14 @@ impl<P: mpc::Proof + StrictDumb> Anchor<P> {
     }
 
     #[inline]
-    pub fn witness_id(&self) -> WitnessId {
-        match self {
-            Anchor::Bitcoin(anchor) => WitnessId::Bitcoin(anchor.txid),
-            Anchor::Liquid(anchor) => WitnessId::Liquid(anchor.txid),
-        }
-    }
+    pub fn witness_id(&self) -> WitnessId { WitnessId::with_layer1(self.layer1(), self.txid) }
 
     pub fn map<Q: mpc::Proof + StrictDumb, E>(
         self,
         f: impl FnOnce(dbc::Anchor<P>) -> Result<dbc::Anchor<Q>, E>,
     ) -> Result<Anchor<Q>, E> {
-        match self {
-            Anchor::Bitcoin(anchor) => f(anchor).map(Anchor::Bitcoin),
-            Anchor::Liquid(anchor) => f(anchor).map(Anchor::Liquid),
-        }
+        let (layer1, anchor) = self.into_parts();
+        f(anchor).map(|anchor| Anchor::with_layer1(layer1, anchor))
     }
 }
 
@@ -113,6 +107,98 @@ impl Anchor<mpc::MerkleBlock> {
     }
 }
 
+/// Errors in multi-contract anchor sets.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AnchorSetError {
+    /// transition bundle for contract {0} is not committed by the anchor.
+    NotCommitted(ContractId),
+
+    /// anchor commits to a bundle for contract {0} which is different from the
+    /// provided one.
+    BundleMismatch(ContractId),
+
+    /// transition bundle for contract {0} claims the same witness transaction
+    /// input more than once.
+    InvalidBundle(ContractId),
+
+    /// number of contracts in the anchor set exceeds 255.
+    TooLarge,
+}
+
+/// Transition bundles for multiple contracts anchored to a single witness
+/// transaction.
+///
+/// Since all the bundles are committed by the same witness transaction, they
+/// either all get into the contract history or none of them do, which allows
+/// atomic multi-contract operations, like asset-for-asset swaps. Different
+/// contracts may have seals closed by the same witness transaction input.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AnchorSet {
+    anchor: Anchor<mpc::MerkleBlock>,
+    bundles: TinyOrdMap<ContractId, TransitionBundle>,
+}
+
+impl AnchorSet {
+    /// Constructs anchor set, verifying that each of the bundles is internally
+    /// consistent and is committed by the anchor.
+    pub fn with(
+        anchor: Anchor<mpc::MerkleBlock>,
+        bundles: impl IntoIterator<Item = (ContractId, TransitionBundle)>,
+    ) -> Result<Self, AnchorSetError> {
+        let bundles = TinyOrdMap::try_from_iter(bundles).map_err(|_| AnchorSetError::TooLarge)?;
+        let set = AnchorSet { anchor, bundles };
+        set.verify()?;
+        Ok(set)
+    }
+
+    pub fn anchor(&self) -> &Anchor<mpc::MerkleBlock> { &self.anchor }
+
+    pub fn bundles(&self) -> &TinyOrdMap<ContractId, TransitionBundle> { &self.bundles }
+
+    #[inline]
+    pub fn witness_id(&self) -> WitnessId { self.anchor.witness_id() }
+
+    /// Verifies that all bundles in the set are internally consistent and are
+    /// committed by the anchor. This doesn't verify the commitment against
+    /// the witness transaction, which is done during consignment validation.
+    pub fn verify(&self) -> Result<(), AnchorSetError> {
+        let messages = self.anchor.mpc_proof.to_known_message_map();
+        for (contract_id, bundle) in &self.bundles {
+            if !bundle.validate() {
+                return Err(AnchorSetError::InvalidBundle(*contract_id));
+            }
+            let message = messages
+                .get(&mpc::ProtocolId::from(*contract_id))
+                .ok_or(AnchorSetError::NotCommitted(*contract_id))?;
+            if *message != mpc::Message::from(bundle.bundle_id()) {
+                return Err(AnchorSetError::BundleMismatch(*contract_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the set into per-contract anchored bundles, each containing the
+    /// merkle proof of the commitment for the specific contract only.
+    pub fn into_anchored_bundles(self) -> impl Iterator<Item = (ContractId, AnchoredBundle)> {
+        let anchor = self.anchor;
+        self.bundles.into_iter().map(move |(contract_id, bundle)| {
+            let anchor = anchor
+                .clone()
+                .map(|a| a.into_merkle_proof(contract_id))
+                .expect("anchor set is verified to commit to all its bundles");
+            (contract_id, AnchoredBundle { anchor, bundle })
+        })
+    }
+}
+
 /// Txid and height information ordered according to the RGB consensus rules.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -155,9 +241,10 @@ impl WitnessAnchor {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+/// Layer 1 blockchain used for anchoring; bitcoin by default.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Display)]
 #[display(lowercase)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
 #[cfg_attr(
     feature = "serde",
@@ -167,7 +254,7 @@ impl WitnessAnchor {
 #[repr(u8)]
 #[non_exhaustive]
 pub enum Layer1 {
-    #[strict_type(dumb)]
+    #[default]
     Bitcoin = 0,
     Liquid = 1,
 }
@@ -0,0 +1,241 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ASCII armoring of consignments and contract state for transfers over text
+//! channels.
+//!
+//! Armored data are Base85-encoded strict-serialized data wrapped into
+//! PEM-like plates, with a set of headers describing the data and containing
+//! SHA256 checksum of the serialized data:
+//!
+//! ```text
+//! -----BEGIN RGB CONSIGNMENT-----
+//! Id: <consignment id>
+//! Contract: <contract id>
+//! Schema: <schema id>
+//! Version: 1
+//! Check: <checksum>
+//!
+//! <base85 data>
+//!
+//! -----END RGB CONSIGNMENT-----
+//! ```
+
+use std::fmt::Write;
+
+use amplify::confinement::{Confined, U32};
+use amplify::hex::ToHex;
+use commit_verify::{Digest, Sha256};
+use strict_encoding::{DecodeError, DeserializeError, StrictDeserialize, StrictSerialize};
+
+use crate::{Consignment, ContractState};
+
+/// Version of the ASCII armor format.
+pub const ARMOR_VERSION: u8 = 1;
+
+const ARMOR_LINE_LEN: usize = 64;
+
+/// Errors parsing ASCII-armored data.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ArmorParseError {
+    /// armored data lack the opening plate line for {0}.
+    NoOpeningPlate(&'static str),
+
+    /// armored data lack the closing plate line for {0}.
+    NoClosingPlate(&'static str),
+
+    /// armor header '{0}' has invalid format.
+    InvalidHeader(String),
+
+    /// armored data lack mandatory '{0}' header.
+    NoHeader(&'static str),
+
+    /// unsupported armor version '{0}'.
+    UnsupportedVersion(String),
+
+    /// armored data are not valid Base85 encoding.
+    InvalidEncoding,
+
+    /// armored data are corrupted: checksum doesn't match.
+    ChecksumMismatch,
+
+    /// armored data don't match the value of '{0}' header.
+    HeaderMismatch(&'static str),
+
+    /// armored data can't be deserialized. Details: {0}
+    #[from]
+    #[from(DecodeError)]
+    Deserialize(DeserializeError),
+}
+
+/// ASCII armoring of strict-serializable data.
+pub trait Armor: StrictSerialize + StrictDeserialize {
+    /// Title used in the opening and closing plate lines.
+    const PLATE_TITLE: &'static str;
+
+    /// Headers describing the data, which are verified to match the data
+    /// during dearmoring.
+    fn armor_headers(&self) -> Vec<(&'static str, String)>;
+
+    fn to_ascii_armored_string(&self) -> String {
+        let data = self
+            .to_strict_serialized::<U32>()
+            .expect("data exceed 4GB and can't be armored");
+        let mut s = format!("-----BEGIN {}-----\n", Self::PLATE_TITLE);
+        for (name, value) in self.armor_headers() {
+            writeln!(s, "{name}: {value}").expect("writing to string");
+        }
+        writeln!(s, "Version: {ARMOR_VERSION}").expect("writing to string");
+        writeln!(s, "Check: {}", Sha256::digest(data.as_slice()).to_hex())
+            .expect("writing to string");
+        s.push('\n');
+        let encoded = base85::encode(data.as_slice());
+        for line in encoded.as_bytes().chunks(ARMOR_LINE_LEN) {
+            s.push_str(std::str::from_utf8(line).expect("Base85 is ASCII"));
+            s.push('\n');
+        }
+        write!(s, "\n-----END {}-----\n", Self::PLATE_TITLE).expect("writing to string");
+        s
+    }
+
+    fn from_ascii_armored_str(s: &str) -> Result<Self, ArmorParseError> {
+        let mut lines = s.lines().map(str::trim).skip_while(|line| line.is_empty());
+        if lines.next() != Some(&format!("-----BEGIN {}-----", Self::PLATE_TITLE)) {
+            return Err(ArmorParseError::NoOpeningPlate(Self::PLATE_TITLE));
+        }
+
+        let mut headers = vec![];
+        for line in lines.by_ref().take_while(|line| !line.is_empty()) {
+            let (name, value) = line
+                .split_once(": ")
+                .ok_or_else(|| ArmorParseError::InvalidHeader(line.to_owned()))?;
+            headers.push((name, value));
+        }
+        let header = |name: &'static str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, value)| *value)
+                .ok_or(ArmorParseError::NoHeader(name))
+        };
+        let version = header("Version")?;
+        if version != ARMOR_VERSION.to_string() {
+            return Err(ArmorParseError::UnsupportedVersion(version.to_owned()));
+        }
+        let check = header("Check")?;
+
+        let closing = format!("-----END {}-----", Self::PLATE_TITLE);
+        let mut encoded = String::new();
+        let mut closed = false;
+        for line in lines {
+            if line == closing {
+                closed = true;
+                break;
+            }
+            encoded.push_str(line);
+        }
+        if !closed {
+            return Err(ArmorParseError::NoClosingPlate(Self::PLATE_TITLE));
+        }
+
+        let data = base85::decode(&encoded).map_err(|_| ArmorParseError::InvalidEncoding)?;
+        if Sha256::digest(&data).to_hex() != check {
+            return Err(ArmorParseError::ChecksumMismatch);
+        }
+        let data = Confined::try_from(data).map_err(DecodeError::from)?;
+        let me = Self::from_strict_serialized::<U32>(data)?;
+
+        for (name, value) in me.armor_headers() {
+            if header(name)? != value {
+                return Err(ArmorParseError::HeaderMismatch(name));
+            }
+        }
+        Ok(me)
+    }
+}
+
+impl Armor for Consignment {
+    const PLATE_TITLE: &'static str = "RGB CONSIGNMENT";
+
+    fn armor_headers(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Id", self.consignment_id().to_string()),
+            ("Contract", self.contract_id().to_string()),
+            ("Schema", self.schema.schema_id().to_string()),
+        ]
+    }
+}
+
+impl Armor for ContractState {
+    const PLATE_TITLE: &'static str = "RGB CONTRACT STATE";
+
+    fn armor_headers(&self) -> Vec<(&'static str, String)> {
+        vec![("Contract", self.contract_id().to_string()), ("Schema", self.schema_id().to_string())]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let consignment = Consignment::strict_dumb();
+        let armored = consignment.to_ascii_armored_string();
+        assert!(armored.starts_with("-----BEGIN RGB CONSIGNMENT-----\n"));
+        assert!(armored.ends_with("\n-----END RGB CONSIGNMENT-----\n"));
+        assert_eq!(Consignment::from_ascii_armored_str(&armored).unwrap(), consignment);
+    }
+
+    #[test]
+    fn corrupted() {
+        let armored = Consignment::strict_dumb().to_ascii_armored_string();
+
+        let wrong_version = armored.replace("Version: 1", "Version: 2");
+        assert_eq!(
+            Consignment::from_ascii_armored_str(&wrong_version),
+            Err(ArmorParseError::UnsupportedVersion(s!("2")))
+        );
+
+        let no_plate = armored.replace("-----END RGB CONSIGNMENT-----", "");
+        assert_eq!(
+            Consignment::from_ascii_armored_str(&no_plate),
+            Err(ArmorParseError::NoClosingPlate("RGB CONSIGNMENT"))
+        );
+
+        let mut lines = armored.lines().map(str::to_owned).collect::<Vec<_>>();
+        let data_line = lines.iter().position(String::is_empty).unwrap() + 1;
+        let replacement = if lines[data_line].starts_with('0') {
+            "1"
+        } else {
+            "0"
+        };
+        lines[data_line].replace_range(0..1, replacement);
+        assert_eq!(
+            Consignment::from_ascii_armored_str(&lines.join("\n")),
+            Err(ArmorParseError::ChecksumMismatch)
+        );
+    }
+}
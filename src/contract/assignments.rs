@@ -34,8 +34,9 @@ use strict_encoding::{StrictDumb, StrictEncode, StrictWriter};
 use super::ExposedState;
 use crate::contract::seal::GenesisSeal;
 use crate::{
-    AssignmentType, ExposedSeal, GraphSeal, RevealedAttach, RevealedData, RevealedValue,
-    SealDefinition, SecretSeal, StateType, VoidState, LIB_NAME_RGB,
+    AssignPaymentTag, AssignmentType, ConcealedPaymentTag, ExposedSeal, GraphSeal, PaymentTag,
+    RevealedAttach, RevealedData, RevealedValue, SealDefinition, SecretSeal, StateType, VoidState,
+    LIB_NAME_RGB,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
@@ -43,6 +44,22 @@ use crate::{
 /// the requested data are not present.
 pub struct UnknownDataError;
 
+/// Errors revealing confidential assignment data.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum RevealError {
+    /// revealed seal conceals to {found}, while the assignment has seal
+    /// {expected}.
+    SealMismatch {
+        expected: SecretSeal,
+        found: SecretSeal,
+    },
+
+    /// revealed state of type {0} doesn't match the confidential state of the
+    /// assignment.
+    StateMismatch(StateType),
+}
+
 pub type AssignRights<Seal> = Assign<VoidState, Seal>;
 pub type AssignFungible<Seal> = Assign<RevealedValue, Seal>;
 pub type AssignData<Seal> = Assign<RevealedData, Seal>;
@@ -56,7 +73,7 @@ pub type AssignAttach<Seal> = Assign<RevealedAttach, Seal>;
 #[strict_type(
     lib = LIB_NAME_RGB,
     tags = custom,
-    dumb = { Self::Confidential { seal: strict_dumb!(), state: strict_dumb!() } }
+    dumb = { Self::Confidential { seal: strict_dumb!(), state: strict_dumb!(), payment_tag: None } }
 )]
 #[cfg_attr(
     feature = "serde",
@@ -74,18 +91,33 @@ pub enum Assign<State: ExposedState, Seal: ExposedSeal> {
     Confidential {
         seal: SecretSeal,
         state: State::Confidential,
+        /// Tag binding the assignment to a payment request.
+        #[cfg_attr(feature = "serde", serde(default))]
+        payment_tag: Option<AssignPaymentTag>,
     },
     #[strict_type(tag = 0x03)]
     Revealed {
         seal: SealDefinition<Seal>,
         state: State,
+        /// Tag binding the assignment to a payment request.
+        #[cfg_attr(feature = "serde", serde(default))]
+        payment_tag: Option<AssignPaymentTag>,
     },
     #[strict_type(tag = 0x02)]
-    ConfidentialSeal { seal: SecretSeal, state: State },
+    ConfidentialSeal {
+        seal: SecretSeal,
+        state: State,
+        /// Tag binding the assignment to a payment request.
+        #[cfg_attr(feature = "serde", serde(default))]
+        payment_tag: Option<AssignPaymentTag>,
+    },
     #[strict_type(tag = 0x01)]
     ConfidentialState {
         seal: SealDefinition<Seal>,
         state: State::Confidential,
+        /// Tag binding the assignment to a payment request.
+        #[cfg_attr(feature = "serde", serde(default))]
+        payment_tag: Option<AssignPaymentTag>,
     },
 }
 
@@ -107,7 +139,8 @@ impl<State: ExposedState, Seal: ExposedSeal> Ord for Assign<State, Seal> {
 impl<State: ExposedState, Seal: ExposedSeal> PartialEq for Assign<State, Seal> {
     fn eq(&self, other: &Self) -> bool {
         self.to_confidential_seal() == other.to_confidential_seal() &&
-            self.to_confidential_state() == other.to_confidential_state()
+            self.to_confidential_state() == other.to_confidential_state() &&
+            self.to_confidential_payment_tag() == other.to_confidential_payment_tag()
     }
 }
 
@@ -117,30 +150,75 @@ impl<State: ExposedState, Seal: ExposedSeal> Hash for Assign<State, Seal> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.to_confidential_seal().hash(state);
         self.to_confidential_state().hash(state);
+        self.to_confidential_payment_tag().hash(state);
     }
 }
 
 impl<State: ExposedState, Seal: ExposedSeal> Assign<State, Seal> {
     pub fn revealed(seal: SealDefinition<Seal>, state: State) -> Self {
-        Assign::Revealed { seal, state }
+        Assign::Revealed {
+            seal,
+            state,
+            payment_tag: None,
+        }
+    }
+
+    /// Binds the assignment to a payment request with the given tag.
+    pub fn with_payment_tag(mut self, tag: impl Into<AssignPaymentTag>) -> Self {
+        *self.payment_tag_mut() = Some(tag.into());
+        self
     }
 
     pub fn with_seal_replaced(assignment: &Self, seal: SealDefinition<Seal>) -> Self {
+        let payment_tag = assignment.payment_tag().copied();
         match assignment {
-            Assign::Confidential { seal: _, state } |
-            Assign::ConfidentialState { seal: _, state } => Assign::ConfidentialState {
-                seal,
-                state: *state,
-            },
-            Assign::ConfidentialSeal { seal: _, state } | Assign::Revealed { seal: _, state } => {
+            Assign::Confidential { state, .. } | Assign::ConfidentialState { state, .. } => {
+                Assign::ConfidentialState {
+                    seal,
+                    state: *state,
+                    payment_tag,
+                }
+            }
+            Assign::ConfidentialSeal { state, .. } | Assign::Revealed { state, .. } => {
                 Assign::Revealed {
                     seal,
                     state: state.clone(),
+                    payment_tag,
                 }
             }
         }
     }
 
+    pub fn payment_tag(&self) -> Option<&AssignPaymentTag> {
+        match self {
+            Assign::Confidential { payment_tag, .. } |
+            Assign::ConfidentialState { payment_tag, .. } |
+            Assign::Revealed { payment_tag, .. } |
+            Assign::ConfidentialSeal { payment_tag, .. } => payment_tag.as_ref(),
+        }
+    }
+
+    fn payment_tag_mut(&mut self) -> &mut Option<AssignPaymentTag> {
+        match self {
+            Assign::Confidential { payment_tag, .. } |
+            Assign::ConfidentialState { payment_tag, .. } |
+            Assign::Revealed { payment_tag, .. } |
+            Assign::ConfidentialSeal { payment_tag, .. } => payment_tag,
+        }
+    }
+
+    pub fn to_confidential_payment_tag(&self) -> Option<ConcealedPaymentTag> {
+        self.payment_tag().map(AssignPaymentTag::conceal)
+    }
+
+    /// Detects whether the assignment is bound to a payment request with the
+    /// given tag. Always returns `false` if the tag is concealed.
+    pub fn matches_payment_tag(&self, tag: PaymentTag) -> bool {
+        self.payment_tag()
+            .map(|payment_tag| payment_tag.matches(tag))
+            .unwrap_or_default()
+    }
+
     pub fn to_confidential_seal(&self) -> SecretSeal {
         match self {
             Assign::Revealed { seal, .. } | Assign::ConfidentialState { seal, .. } => {
@@ -189,24 +267,75 @@ impl<State: ExposedState, Seal: ExposedSeal> Assign<State, Seal> {
 
     pub fn as_revealed(&self) -> Option<(&SealDefinition<Seal>, &State)> {
         match self {
-            Assign::Revealed { seal, state } => Some((seal, state)),
+            Assign::Revealed { seal, state, .. } => Some((seal, state)),
             _ => None,
         }
     }
 
     pub fn to_revealed(&self) -> Option<(SealDefinition<Seal>, State)> {
         match self {
-            Assign::Revealed { seal, state } => Some((*seal, state.clone())),
+            Assign::Revealed { seal, state, .. } => Some((*seal, state.clone())),
             _ => None,
         }
     }
 
     pub fn into_revealed(self) -> Option<(SealDefinition<Seal>, State)> {
         match self {
-            Assign::Revealed { seal, state } => Some((seal, state)),
+            Assign::Revealed { seal, state, .. } => Some((seal, state)),
             _ => None,
         }
     }
+
+    /// Upgrades the assignment with the revealed seal and/or state, checking
+    /// that they match the confidential data of the assignment. Data already
+    /// revealed in the assignment are checked in the same way, so they can't
+    /// be replaced.
+    pub fn reveal_with(
+        &self,
+        seal: Option<SealDefinition<Seal>>,
+        state: Option<State>,
+    ) -> Result<Self, RevealError> {
+        if let Some(seal) = seal {
+            let expected = self.to_confidential_seal();
+            let found = seal.conceal();
+            if found != expected {
+                return Err(RevealError::SealMismatch { expected, found });
+            }
+        }
+        if let Some(state) = &state {
+            // Confidential states are compared by their commitments, since the
+            // non-committed data (like range proofs) may be different.
+            let mut expected = vec![];
+            self.to_confidential_state().commit_encode(&mut expected);
+            let mut found = vec![];
+            state.conceal().commit_encode(&mut found);
+            if found != expected {
+                return Err(RevealError::StateMismatch(state.state_type()));
+            }
+        }
+
+        let payment_tag = self.payment_tag().copied();
+        let seal = seal.or_else(|| self.revealed_seal());
+        let state = state.or_else(|| self.as_revealed_state().cloned());
+        Ok(match (seal, state) {
+            (Some(seal), Some(state)) => Assign::Revealed {
+                seal,
+                state,
+                payment_tag,
+            },
+            (Some(seal), None) => Assign::ConfidentialState {
+                seal,
+                state: self.to_confidential_state(),
+                payment_tag,
+            },
+            (None, Some(state)) => Assign::ConfidentialSeal {
+                seal: self.to_confidential_seal(),
+                state,
+                payment_tag,
+            },
+            (None, None) => self.clone(),
+        })
+    }
 }
 
 impl<State: ExposedState, Seal: ExposedSeal> Conceal for Assign<State, Seal>
@@ -215,20 +344,13 @@ where Self: Clone
     type Concealed = Self;
 
     fn conceal(&self) -> Self::Concealed {
-        match self {
-            Assign::Confidential { .. } => self.clone(),
-            Assign::ConfidentialState { seal, state } => Self::Confidential {
-                seal: seal.conceal(),
-                state: *state,
-            },
-            Assign::Revealed { seal, state } => Self::Confidential {
-                seal: seal.conceal(),
-                state: state.conceal(),
-            },
-            Assign::ConfidentialSeal { seal, state } => Self::Confidential {
-                seal: *seal,
-                state: state.conceal(),
-            },
+        let payment_tag = self
+            .to_confidential_payment_tag()
+            .map(AssignPaymentTag::Concealed);
+        Self::Confidential {
+            seal: self.to_confidential_seal(),
+            state: self.to_confidential_state(),
+            payment_tag,
         }
     }
 }
@@ -240,23 +362,28 @@ where Self: Clone
 {
     fn commit_encode(&self, e: &mut impl io::Write) {
         match self {
-            Assign::Confidential { seal, state } => {
+            Assign::Confidential { seal, state, .. } => {
                 seal.commit_encode(e);
                 state.commit_encode(e);
             }
-            Assign::ConfidentialState { seal, state } => {
+            Assign::ConfidentialState { seal, state, .. } => {
                 seal.commit_encode(e);
                 state.commit_encode(e);
             }
-            Assign::Revealed { seal, state } => {
+            Assign::Revealed { seal, state, .. } => {
                 seal.commit_encode(e);
                 state.commit_encode(e);
             }
-            Assign::ConfidentialSeal { seal, state } => {
+            Assign::ConfidentialSeal { seal, state, .. } => {
                 seal.commit_encode(e);
                 state.commit_encode(e);
             }
         }
+        // Assignments without payment tag keep the commitment of the
+        // assignments created before the tags were introduced
+        if let Some(payment_tag) = self.to_confidential_payment_tag() {
+            payment_tag.commit_encode(e);
+        }
     }
 }
 
@@ -270,21 +397,41 @@ where Self: Clone
 impl<State: ExposedState> Assign<State, GenesisSeal> {
     pub fn transmutate_seals(&self) -> Assign<State, GraphSeal> {
         match self {
-            Assign::Confidential { seal, state } => Assign::Confidential {
+            Assign::Confidential {
+                seal,
+                state,
+                payment_tag,
+            } => Assign::Confidential {
                 seal: *seal,
                 state: *state,
+                payment_tag: *payment_tag,
             },
-            Assign::ConfidentialSeal { seal, state } => Assign::ConfidentialSeal {
+            Assign::ConfidentialSeal {
+                seal,
+                state,
+                payment_tag,
+            } => Assign::ConfidentialSeal {
                 seal: *seal,
                 state: state.clone(),
+                payment_tag: *payment_tag,
             },
-            Assign::Revealed { seal, state } => Assign::Revealed {
+            Assign::Revealed {
+                seal,
+                state,
+                payment_tag,
+            } => Assign::Revealed {
                 seal: seal.transmutate(),
                 state: state.clone(),
+                payment_tag: *payment_tag,
             },
-            Assign::ConfidentialState { seal, state } => Assign::ConfidentialState {
+            Assign::ConfidentialState {
+                seal,
+                state,
+                payment_tag,
+            } => Assign::ConfidentialState {
                 seal: seal.transmutate(),
                 state: *state,
+                payment_tag: *payment_tag,
             },
         }
     }
@@ -495,6 +642,28 @@ impl<Seal: ExposedSeal> TypedAssigns<Seal> {
         }
     }
 
+    /// Returns indexes of the assignments bound to a payment request with the
+    /// given tag.
+    pub fn find_payment_tag(&self, tag: PaymentTag) -> Vec<u16> {
+        fn find<State: ExposedState, Seal: ExposedSeal>(
+            assignments: &[Assign<State, Seal>],
+            tag: PaymentTag,
+        ) -> Vec<u16> {
+            assignments
+                .iter()
+                .enumerate()
+                .filter(|(_, assign)| assign.matches_payment_tag(tag))
+                .map(|(no, _)| no as u16)
+                .collect()
+        }
+        match self {
+            TypedAssigns::Declarative(s) => find(s, tag),
+            TypedAssigns::Fungible(s) => find(s, tag),
+            TypedAssigns::Structured(s) => find(s, tag),
+            TypedAssigns::Attachment(s) => find(s, tag),
+        }
+    }
+
     pub fn as_structured_state_at(
         &self,
         index: u16,
@@ -565,7 +734,9 @@ impl<Seal: ExposedSeal> CommitStrategy for TypedAssigns<Seal> {
 
 impl<Seal: ExposedSeal> MerkleLeaves for TypedAssigns<Seal> {
     type Leaf = MerkleNode;
-    type LeafIter<'tmp> = vec::IntoIter<MerkleNode> where Self: 'tmp;
+    type LeafIter<'tmp>
+        = vec::IntoIter<MerkleNode>
+    where Self: 'tmp;
 
     fn merkle_leaves(&self) -> Self::LeafIter<'_> {
         match self {
@@ -674,6 +845,14 @@ impl AssignmentsRef<'_> {
 
     pub fn is_empty(&self) -> bool { self.len() == 0 }
 
+    /// Total number of assignments of all types.
+    pub fn count(&self) -> usize {
+        match self {
+            AssignmentsRef::Genesis(a) => a.values().map(|a| a.len_u16() as usize).sum(),
+            AssignmentsRef::Graph(a) => a.values().map(|a| a.len_u16() as usize).sum(),
+        }
+    }
+
     pub fn types(&self) -> BTreeSet<AssignmentType> {
         match self {
             AssignmentsRef::Genesis(a) => a.keys().copied().collect(),
@@ -688,6 +867,24 @@ impl AssignmentsRef<'_> {
         }
     }
 
+    /// Returns types and indexes of the assignments bound to a payment request
+    /// with the given tag.
+    pub fn find_payment_tag(&self, tag: PaymentTag) -> Vec<(AssignmentType, u16)> {
+        fn find<Seal: ExposedSeal>(
+            assignments: &Assignments<Seal>,
+            tag: PaymentTag,
+        ) -> Vec<(AssignmentType, u16)> {
+            assignments
+                .iter()
+                .flat_map(|(ty, a)| a.find_payment_tag(tag).into_iter().map(|no| (*ty, no)))
+                .collect()
+        }
+        match self {
+            AssignmentsRef::Genesis(a) => find(a, tag),
+            AssignmentsRef::Graph(a) => find(a, tag),
+        }
+    }
+
     pub fn get(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
         match self {
             AssignmentsRef::Genesis(a) => a.get(&t).map(TypedAssigns::transmutate_seals),
@@ -695,3 +892,61 @@ impl AssignmentsRef<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+
+    use super::*;
+    use crate::{AssetTag, BlindingFactor};
+
+    #[test]
+    fn reveal_with() {
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GraphSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                TxPtr::WitnessTx,
+                vout,
+                1,
+            ))
+        };
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let revealed = Assign::revealed(seal(0), value(10));
+        let concealed = revealed.conceal();
+
+        let with_seal = concealed.reveal_with(Some(seal(0)), None).unwrap();
+        assert_eq!(with_seal.revealed_seal(), Some(seal(0)));
+        assert_eq!(with_seal.as_revealed_state(), None);
+        let with_state = concealed.reveal_with(None, Some(value(10))).unwrap();
+        assert_eq!(with_state.revealed_seal(), None);
+        assert_eq!(with_state.as_revealed_state(), Some(&value(10)));
+        assert_eq!(
+            with_seal
+                .reveal_with(None, Some(value(10)))
+                .unwrap()
+                .to_revealed(),
+            Some((seal(0), value(10)))
+        );
+
+        assert_eq!(
+            concealed.reveal_with(Some(seal(1)), None).unwrap_err(),
+            RevealError::SealMismatch {
+                expected: seal(0).conceal(),
+                found: seal(1).conceal()
+            }
+        );
+        assert_eq!(
+            concealed.reveal_with(None, Some(value(11))).unwrap_err(),
+            RevealError::StateMismatch(StateType::Fungible)
+        );
+        assert_eq!(
+            revealed.reveal_with(Some(seal(1)), None).unwrap_err(),
+            RevealError::SealMismatch {
+                expected: seal(0).conceal(),
+                found: seal(1).conceal()
+            }
+        );
+    }
+}
@@ -25,8 +25,8 @@ use std::str::FromStr;
 use amplify::{ByteArray, Bytes32};
 use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32};
 use bp::secp256k1::rand::{thread_rng, RngCore};
-use commit_verify::{CommitVerify, Conceal, StrictEncodedProtocol};
-use strict_encoding::StrictEncode;
+use commit_verify::{CommitVerify, Conceal, DigestExt, Sha256, StrictEncodedProtocol};
+use strict_encoding::{StrictEncode, StrictWriter};
 
 use super::{ConfidentialState, ExposedState};
 use crate::{MediaType, StateCommitment, StateData, StateType, LIB_NAME_RGB};
@@ -80,6 +80,9 @@ pub struct RevealedAttach {
     /// used
     pub media_type: MediaType,
     pub salt: u64,
+    /// Declared size of the attachment in bytes; zero if not declared.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size: u64,
 }
 
 impl RevealedAttach {
@@ -90,6 +93,15 @@ impl RevealedAttach {
             id,
             media_type,
             salt: thread_rng().next_u64(),
+            size: 0,
+        }
+    }
+
+    /// Creates new revealed attachment declaring its size in bytes.
+    pub fn with_size(id: AttachId, media_type: MediaType, size: u64) -> Self {
+        Self {
+            size,
+            ..Self::new(id, media_type)
         }
     }
 }
@@ -132,7 +144,23 @@ impl ConfidentialState for ConcealedAttach {
 }
 
 impl CommitVerify<RevealedAttach, StrictEncodedProtocol> for ConcealedAttach {
-    fn commit(revealed: &RevealedAttach) -> Self { Bytes32::commit(revealed).into() }
+    fn commit(revealed: &RevealedAttach) -> Self {
+        // Undeclared size doesn't contribute to the commitment, such that
+        // attachments without size keep their concealed form
+        let mut engine = Sha256::from_tag(*b"urn:lnpbp:lnpbp0007:strict:v01#A");
+        let w = StrictWriter::with(u32::MAX as usize, &mut engine);
+        revealed
+            .id
+            .strict_encode(w)
+            .and_then(|w| revealed.media_type.strict_encode(w))
+            .and_then(|w| revealed.salt.strict_encode(w))
+            .and_then(|w| match revealed.size {
+                0 => Ok(w),
+                size => size.strict_encode(w),
+            })
+            .ok();
+        engine.finish().into()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,282 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Blank state transitions.
+//!
+//! A witness transaction spending an output, which carries state of a contract
+//! not taking part in the transfer, closes the seals of that contract as well.
+//! Unless the state is moved to a new seal with a blank state transition, it
+//! is lost. [`ContractState::blank_bundle`] constructs such a transition,
+//! carrying over all state assigned to the spent outputs to a change seal.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use amplify::confinement::{Confined, LargeOrdSet, SmallVec, TinyOrdSet};
+
+use crate::{
+    Assign, AssignmentType, Assignments, BundleError, ContractId, ContractState, ExposedState, Ffv,
+    GraphSeal, Input, Inputs, Opout, Output, OutputAssignment, SealDefinition, Transition,
+    TransitionBundle, TransitionType, TypedAssigns,
+};
+
+/// Errors constructing blank state transitions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum BlankError {
+    /// number of outputs of contract {0} spent by the witness transaction
+    /// exceeds 255.
+    TooManyInputs(ContractId),
+
+    /// number of outputs of contract {0} with state of type {1} spent by the
+    /// witness transaction exceeds 65535.
+    TooManyAssignments(ContractId, AssignmentType),
+
+    /// too many assignment types are spent by the witness transaction.
+    TooManyTypes(ContractId),
+
+    #[from]
+    #[display(inner)]
+    Bundle(BundleError),
+}
+
+impl ContractState {
+    /// Constructs a blank state transition moving all unspent state assigned
+    /// to the `spent` outputs to the `change` seal. Each output is carried
+    /// over as a separate assignment with the same state, thus the blinding
+    /// factors of the fungible state remain balanced.
+    ///
+    /// Returns `None` if no state of the contract is assigned to the outputs.
+    pub fn blank_transition(
+        &self,
+        spent: &BTreeSet<Output>,
+        change: SealDefinition<GraphSeal>,
+    ) -> Result<Option<Transition>, BlankError> {
+        self.blank_closing(spent, change)
+            .map(|blank| blank.map(|(transition, _)| transition))
+    }
+
+    /// Constructs blank state transition together with the set of outputs, for
+    /// which it closes the seals.
+    fn blank_closing(
+        &self,
+        spent: &BTreeSet<Output>,
+        change: SealDefinition<GraphSeal>,
+    ) -> Result<Option<(Transition, BTreeSet<Output>)>, BlankError> {
+        fn carry<State: ExposedState>(
+            state: &ContractState,
+            outputs: &LargeOrdSet<OutputAssignment<State>>,
+            spent: &BTreeSet<Output>,
+            change: SealDefinition<GraphSeal>,
+            inputs: &mut BTreeSet<Opout>,
+            closed: &mut BTreeSet<Output>,
+        ) -> Result<BTreeMap<AssignmentType, SmallVec<Assign<State, GraphSeal>>>, BlankError>
+        {
+            let mut assignments = BTreeMap::<_, SmallVec<_>>::new();
+            for output in outputs
+                .iter()
+                .filter(|output| spent.contains(&output.output))
+                .filter(|output| !state.is_spent(output.opout))
+            {
+                let ty = output.opout.ty;
+                assignments
+                    .entry(ty)
+                    .or_default()
+                    .push(Assign::revealed(change, output.state.clone()))
+                    .map_err(|_| BlankError::TooManyAssignments(state.contract_id(), ty))?;
+                inputs.insert(output.opout);
+                closed.insert(output.output);
+            }
+            Ok(assignments)
+        }
+
+        let mut inputs = BTreeSet::new();
+        let mut closed = BTreeSet::new();
+        let rights = carry(self, self.rights(), spent, change, &mut inputs, &mut closed)?;
+        let fungibles = carry(self, self.fungibles(), spent, change, &mut inputs, &mut closed)?;
+        let data = carry(self, self.data(), spent, change, &mut inputs, &mut closed)?;
+        let attach = carry(self, self.attach(), spent, change, &mut inputs, &mut closed)?;
+        if inputs.is_empty() {
+            return Ok(None);
+        }
+
+        let assignments = rights
+            .into_iter()
+            .map(|(ty, vec)| (ty, TypedAssigns::Declarative(vec)))
+            .chain(
+                fungibles
+                    .into_iter()
+                    .map(|(ty, vec)| (ty, TypedAssigns::Fungible(vec))),
+            )
+            .chain(
+                data.into_iter()
+                    .map(|(ty, vec)| (ty, TypedAssigns::Structured(vec))),
+            )
+            .chain(
+                attach
+                    .into_iter()
+                    .map(|(ty, vec)| (ty, TypedAssigns::Attachment(vec))),
+            );
+        let assignments = Confined::try_from_iter(assignments)
+            .map_err(|_| BlankError::TooManyTypes(self.contract_id()))?;
+        let inputs = TinyOrdSet::try_from_iter(inputs.into_iter().map(Input::with))
+            .map_err(|_| BlankError::TooManyInputs(self.contract_id()))?;
+
+        let transition = Transition {
+            ffv: Ffv::default(),
+            contract_id: self.contract_id(),
+            schema_id: self.schema_id(),
+            transition_type: TransitionType::BLANK,
+            metadata: empty!(),
+            globals: empty!(),
+            inputs: Inputs::from(inputs),
+            assignments: Assignments::from(assignments),
+            valencies: empty!(),
+            proof: None,
+            signature: None,
+        };
+        Ok(Some((transition, closed)))
+    }
+
+    /// Constructs a bundle with a blank state transition for the witness
+    /// transaction spending `inputs` outputs, listed in the order of the
+    /// witness transaction inputs. The transition moves all state of the
+    /// contract assigned to these outputs to the `change` seal.
+    ///
+    /// Returns `None` if no state of the contract is assigned to the outputs.
+    pub fn blank_bundle(
+        &self,
+        inputs: &[Output],
+        change: SealDefinition<GraphSeal>,
+    ) -> Result<Option<TransitionBundle>, BlankError> {
+        let spent = inputs.iter().copied().collect();
+        let Some((transition, closed)) = self.blank_closing(&spent, change)? else {
+            return Ok(None);
+        };
+        let transitions = inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| closed.contains(output))
+            .map(|(no, _)| (no as u16, transition.clone()));
+        Ok(Some(TransitionBundle::try_from_transitions(transitions)?))
+    }
+}
+
+/// Constructs bundles with blank state transitions for each of the contracts
+/// which state is assigned to the outputs spent by a witness transaction. See
+/// [`ContractState::blank_bundle`] for the details.
+pub fn blank_bundles<'state>(
+    contracts: impl IntoIterator<Item = &'state ContractState>,
+    inputs: &[Output],
+    change: SealDefinition<GraphSeal>,
+) -> Result<BTreeMap<ContractId, TransitionBundle>, BlankError> {
+    let mut bundles = BTreeMap::new();
+    for state in contracts {
+        if let Some(bundle) = state.blank_bundle(inputs, change)? {
+            bundles.insert(state.contract_id(), bundle);
+        }
+    }
+    Ok(bundles)
+}
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use bp::{Outpoint, Txid};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, BlindingFactor, ContractHistory, GenesisSeal, Operation, RevealedValue, SubSchema,
+    };
+
+    #[test]
+    fn blank_bundle() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        let mut genesis = crate::Genesis::strict_dumb();
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([
+                        Assign::revealed(seal(0), value(100)),
+                        Assign::revealed(seal(1), value(50)),
+                    ])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history: ContractHistory::with(
+                genesis.schema_id,
+                None,
+                genesis.contract_id(),
+                &genesis,
+            ),
+        };
+        let change = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            2,
+            1,
+        ));
+        let output = |vout| Output::Bitcoin(Outpoint::new(Txid::from([1; 32]), vout));
+
+        assert_eq!(state.blank_bundle(&[output(5)], change), Ok(None));
+
+        let bundle = state
+            .blank_bundle(&[output(5), output(1)], change)
+            .unwrap()
+            .unwrap();
+        assert!(bundle.validate());
+        let (_, item) = bundle.iter().next().unwrap();
+        assert_eq!(item.inputs.iter().copied().collect::<Vec<_>>(), vec![1]);
+        let transition = item.transition.as_ref().unwrap();
+        assert_eq!(transition.transition_type, TransitionType::BLANK);
+        assert_eq!(transition.contract_id, genesis.contract_id());
+        assert_eq!(transition.inputs.len(), 1);
+        assert!(transition
+            .inputs
+            .iter()
+            .all(|input| input.prev_out == Opout::new(genesis.id(), ty, 1)));
+        let assigns = transition.assignments.get(&ty).unwrap();
+        assert_eq!(assigns.len_u16(), 1);
+        assert_eq!(assigns.revealed_seal_at(0), Ok(Some(change)));
+        assert_eq!(assigns.as_fungible_state_at(0), Ok(Some(&value(50))));
+
+        let bundles = blank_bundles([&state], &[output(0)], change).unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert!(bundles.contains_key(&genesis.contract_id()));
+    }
+}
@@ -20,17 +20,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
 use amplify::confinement::{TinyOrdMap, TinyOrdSet};
-use amplify::{Bytes32, Wrapper};
+use amplify::{ByteArray, Bytes32, Wrapper};
+use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32CHECKSUM};
 use commit_verify::{mpc, CommitStrategy, CommitmentId, Conceal};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
 
+use super::operations::strict_len;
 use super::{OpId, Transition};
-use crate::LIB_NAME_RGB;
+use crate::{Operation, LIB_NAME_RGB};
+
+/// Errors constructing state transition bundle.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum BundleError {
+    /// witness transaction input #{0} is claimed both by state transition {1}
+    /// and {2}.
+    InputReused(u16, OpId, OpId),
+
+    /// number of state transitions or inputs claimed by a single transition
+    /// exceeds 255.
+    TooLarge,
+}
 
 /// Unique state transition bundle identifier equivalent to the bundle
 /// commitment hash
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
-#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
@@ -44,6 +65,29 @@ pub struct BundleId(
     Bytes32,
 );
 
+impl ToBaid58<32> for BundleId {
+    const HRI: &'static str = "bundle";
+    const CHUNKING: Option<Chunking> = CHUNKING_32CHECKSUM;
+    fn to_baid58_payload(&self) -> [u8; 32] { self.to_byte_array() }
+    fn to_baid58_string(&self) -> String { self.to_string() }
+}
+impl FromBaid58<32> for BundleId {}
+impl Display for BundleId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{::^}", self.to_baid58())
+        } else {
+            write!(f, "{::^.3}", self.to_baid58())
+        }
+    }
+}
+impl FromStr for BundleId {
+    type Err = Baid58ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_baid58_maybe_chunked_str(s, ':', '#')
+    }
+}
+
 impl From<BundleId> for mpc::Message {
     fn from(id: BundleId) -> Self { mpc::Message::from_inner(id.into_inner()) }
 }
@@ -80,6 +124,25 @@ impl Conceal for BundleItem {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
 pub struct TransitionBundle(TinyOrdMap<OpId, BundleItem>);
 
+/// Size and validation cost estimates of a transition bundle.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct BundleWeight {
+    /// Length of the bundle strict serialization in bytes.
+    pub size: usize,
+    /// Estimated validation cost, measured as the number of bundle items,
+    /// transition inputs, assignments and global state entries which have to
+    /// be validated.
+    pub cost: usize,
+}
+
+impl StrictSerialize for TransitionBundle {}
+impl StrictDeserialize for TransitionBundle {}
+
 impl Conceal for TransitionBundle {
     type Concealed = Self;
 
@@ -89,6 +152,10 @@ impl Conceal for TransitionBundle {
     }
 }
 
+// Bundle is always committed in its fully concealed form, i.e. only the opids
+// and input maps of its members are committed. Thus partially concealed
+// bundles, produced with `TransitionBundle::conceal_except`, have the same
+// bundle id as the original fully revealed bundle.
 impl CommitStrategy for TransitionBundle {
     // TODO: Use merklization strategy
     type Strategy = commit_verify::strategies::ConcealStrict;
@@ -100,10 +167,94 @@ impl CommitmentId for TransitionBundle {
 }
 
 impl TransitionBundle {
+    /// Constructs bundle from a set of state transitions, each provided
+    /// together with the witness transaction input index it closes seals for.
+    ///
+    /// Transitions closing seals in several inputs may be provided multiple
+    /// times; their inputs are merged into a single bundle item.
+    ///
+    /// # Errors
+    ///
+    /// If some of the witness transaction inputs is claimed by more than one
+    /// transition or the bundle exceeds its size limits.
+    pub fn try_from_transitions(
+        transitions: impl IntoIterator<Item = (u16, Transition)>,
+    ) -> Result<Self, BundleError> {
+        let mut used_inputs = BTreeMap::<u16, OpId>::new();
+        let mut items = BTreeMap::<OpId, BundleItem>::new();
+        for (input, transition) in transitions {
+            let opid = transition.id();
+            match used_inputs.entry(input) {
+                Entry::Occupied(entry) if *entry.get() != opid => {
+                    return Err(BundleError::InputReused(input, *entry.get(), opid));
+                }
+                Entry::Occupied(_) => continue,
+                Entry::Vacant(entry) => {
+                    entry.insert(opid);
+                }
+            }
+            let item = items.entry(opid).or_insert_with(|| BundleItem {
+                inputs: empty!(),
+                transition: Some(transition),
+            });
+            item.inputs.push(input).map_err(|_| BundleError::TooLarge)?;
+        }
+        TinyOrdMap::try_from(items)
+            .map(TransitionBundle)
+            .map_err(|_| BundleError::TooLarge)
+    }
+
     pub fn bundle_id(&self) -> BundleId { self.commitment_id() }
+
+    /// Conceals all state transitions in the bundle except those with the
+    /// provided ids, keeping the input map and operation ids of all bundle
+    /// members. Used to hide operations which are not part of the history
+    /// path of the consignment receiver.
+    ///
+    /// The resulting bundle has the same [`BundleId`] as the original one.
+    pub fn conceal_except(&self, keep: &[OpId]) -> Self {
+        let concealed = self.iter().map(|(id, item)| {
+            let item = if keep.contains(id) {
+                item.clone()
+            } else {
+                item.conceal()
+            };
+            (*id, item)
+        });
+        TransitionBundle(TinyOrdMap::try_from_iter(concealed).expect("same size"))
+    }
+
+    /// Iterates over state transitions which are revealed in the bundle.
+    pub fn known_transitions(&self) -> impl Iterator<Item = &Transition> {
+        self.values().filter_map(|item| item.transition.as_ref())
+    }
 }
 
 impl TransitionBundle {
+    /// Estimates size and validation cost of the bundle without serializing
+    /// it. Concealed bundle items contribute to the size but cost a single
+    /// unit.
+    pub fn weight(&self) -> BundleWeight {
+        let cost = self
+            .values()
+            .map(|item| {
+                1 + item.transition.as_ref().map_or(0, |transition| {
+                    transition.inputs.len() +
+                        transition.assignments().count() +
+                        transition
+                            .globals
+                            .values()
+                            .map(|values| values.len())
+                            .sum::<usize>()
+                })
+            })
+            .sum();
+        BundleWeight {
+            size: strict_len(self),
+            cost,
+        }
+    }
+
     pub fn validate(&self) -> bool {
         let mut used_inputs = bset! {};
         for item in self.values() {
@@ -115,3 +266,72 @@ impl TransitionBundle {
         true
     }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::TransitionType;
+
+    #[test]
+    fn bundle_id_display_from_str() {
+        let id = BundleId::from_byte_array([0x6c; 32]);
+        let s = id.to_string();
+        assert!(s.starts_with("bundle:"));
+        assert_eq!(Ok(id), BundleId::from_str(&s));
+        assert_eq!(Ok(id), BundleId::from_str(&format!("{id:#}")));
+        assert!(BundleId::from_str(&s.replace("bundle:", "op:")).is_err());
+    }
+
+    #[test]
+    fn concealed_bundle_id() {
+        let transition = Transition::strict_dumb();
+        let opid = transition.id();
+        let bundle = TransitionBundle::try_from_transitions([
+            (0, transition.clone()),
+            (1, transition.clone()),
+        ])
+        .unwrap();
+        assert_eq!(bundle.len(), 1);
+        assert_eq!(bundle.known_transitions().count(), 1);
+
+        let concealed = bundle.conceal_except(&[]);
+        assert_eq!(concealed.known_transitions().count(), 0);
+        assert_eq!(concealed.get(&opid).unwrap().inputs, bundle.get(&opid).unwrap().inputs);
+        assert_eq!(concealed.bundle_id(), bundle.bundle_id());
+        assert_eq!(bundle.conceal_except(&[opid]), bundle);
+    }
+
+    #[test]
+    fn reused_input() {
+        let transition1 = Transition::strict_dumb();
+        let mut transition2 = Transition::strict_dumb();
+        transition2.transition_type = TransitionType::with(1);
+        assert_eq!(
+            TransitionBundle::try_from_transitions([
+                (0, transition1.clone()),
+                (0, transition2.clone())
+            ]),
+            Err(BundleError::InputReused(0, transition1.id(), transition2.id()))
+        );
+    }
+
+    #[test]
+    fn weight() {
+        let transition = Transition::strict_dumb();
+        assert_eq!(
+            transition.encoded_len(),
+            transition.to_strict_serialized::<U32>().unwrap().len()
+        );
+
+        let bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        let weight = bundle.weight();
+        assert_eq!(weight.size, bundle.to_strict_serialized::<U32>().unwrap().len());
+        assert_eq!(weight.cost, 1);
+        let concealed = bundle.conceal_except(&[]);
+        assert!(concealed.weight().size < weight.size);
+        assert_eq!(concealed.weight().cost, 1);
+    }
+}
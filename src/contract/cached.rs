@@ -0,0 +1,186 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use amplify::confinement::{self, SmallBlob};
+use commit_verify::{CommitEncode, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::{
+    AssignmentType, GlobalState, GraphSeal, Inputs, OpId, Transition, TypedAssigns, Valencies,
+};
+
+fn commit_encoded(val: &impl CommitEncode) -> Vec<u8> {
+    let mut data = vec![];
+    val.commit_encode(&mut data);
+    data
+}
+
+fn assigns_encoded(ty: AssignmentType, assigns: &TypedAssigns<GraphSeal>) -> Vec<u8> {
+    let mut data = vec![];
+    let w = StrictWriter::with(u32::MAX as usize, &mut data);
+    ty.strict_encode(w).ok();
+    assigns.commit_encode(&mut data);
+    data
+}
+
+/// State transition with cached commit-encoding of its sections.
+///
+/// Commit-encoding of assignments requires concealment of all seals and
+/// state, including computation of Pedersen commitments for fungible state,
+/// which dominates computation of the operation id. The cache keeps
+/// commit-encoded global state, inputs, valencies and each of the typed
+/// assignments, such that after an edit only the modified section is
+/// re-encoded. Operation id computed from the cache is always equal to the id
+/// of the transition.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CachedTransition {
+    transition: Transition,
+    globals: Vec<u8>,
+    inputs: Vec<u8>,
+    assignments: BTreeMap<AssignmentType, Vec<u8>>,
+    valencies: Vec<u8>,
+}
+
+impl From<Transition> for CachedTransition {
+    fn from(transition: Transition) -> Self { CachedTransition::new(transition) }
+}
+
+impl CachedTransition {
+    pub fn new(transition: Transition) -> Self {
+        CachedTransition {
+            globals: commit_encoded(&transition.globals),
+            inputs: commit_encoded(&transition.inputs),
+            assignments: transition
+                .assignments
+                .iter()
+                .map(|(ty, assigns)| (*ty, assigns_encoded(*ty, assigns)))
+                .collect(),
+            valencies: commit_encoded(&transition.valencies),
+            transition,
+        }
+    }
+
+    #[inline]
+    pub fn as_transition(&self) -> &Transition { &self.transition }
+
+    #[inline]
+    pub fn into_transition(self) -> Transition { self.transition }
+
+    pub fn set_metadata(&mut self, metadata: SmallBlob) { self.transition.metadata = metadata; }
+
+    pub fn set_globals(&mut self, globals: GlobalState) {
+        self.globals = commit_encoded(&globals);
+        self.transition.globals = globals;
+    }
+
+    pub fn set_inputs(&mut self, inputs: Inputs) {
+        self.inputs = commit_encoded(&inputs);
+        self.transition.inputs = inputs;
+    }
+
+    pub fn set_valencies(&mut self, valencies: Valencies) {
+        self.valencies = commit_encoded(&valencies);
+        self.transition.valencies = valencies;
+    }
+
+    /// Sets assignments of the given type, returning the previously present
+    /// ones.
+    pub fn set_assignments(
+        &mut self,
+        ty: AssignmentType,
+        assigns: TypedAssigns<GraphSeal>,
+    ) -> Result<Option<TypedAssigns<GraphSeal>>, confinement::Error> {
+        let data = assigns_encoded(ty, &assigns);
+        let prev = self.transition.assignments.insert(ty, assigns)?;
+        self.assignments.insert(ty, data);
+        Ok(prev)
+    }
+
+    /// Removes assignments of the given type, returning them.
+    pub fn remove_assignments(
+        &mut self,
+        ty: AssignmentType,
+    ) -> Result<Option<TypedAssigns<GraphSeal>>, confinement::Error> {
+        let prev = self.transition.assignments.remove(&ty)?;
+        self.assignments.remove(&ty);
+        Ok(prev)
+    }
+
+    /// Computes operation id from the cached commit-encoding of the
+    /// transition sections.
+    pub fn id(&self) -> OpId {
+        let transition = &self.transition;
+        let mut engine = Sha256::from_tag(Transition::TAG);
+        transition.ffv.commit_encode(&mut engine);
+        transition.contract_id.commit_encode(&mut engine);
+        transition.schema_id.commit_encode(&mut engine);
+        transition.transition_type.commit_encode(&mut engine);
+        transition.metadata.commit_encode(&mut engine);
+        engine.write_all(&self.globals).ok();
+        engine.write_all(&self.inputs).ok();
+        let w = StrictWriter::with(u32::MAX as usize, &mut engine);
+        (self.assignments.len() as u8).strict_encode(w).ok();
+        for data in self.assignments.values() {
+            engine.write_all(data).ok();
+        }
+        engine.write_all(&self.valencies).ok();
+        engine.finish().into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssetTag, Assign, BlindingFactor, Operation, RevealedValue, SealDefinition};
+
+    #[test]
+    fn id_equivalence() {
+        let mut cached = CachedTransition::new(Transition::strict_dumb());
+        assert_eq!(cached.id(), cached.as_transition().id());
+
+        let seal = SealDefinition::Bitcoin(GraphSeal::strict_dumb());
+        for ty in 1..4u16 {
+            let value = RevealedValue::with_blinding(
+                ty as u64 * 100,
+                BlindingFactor::EMPTY,
+                AssetTag::from([0x11; 32]),
+            );
+            let assigns = TypedAssigns::Fungible(
+                Confined::try_from(vec![Assign::revealed(seal, value)]).unwrap(),
+            );
+            cached
+                .set_assignments(AssignmentType::with(ty), assigns)
+                .unwrap();
+            assert_eq!(cached.id(), cached.as_transition().id());
+        }
+
+        cached.set_metadata(Confined::try_from(b"edited".to_vec()).unwrap());
+        cached.remove_assignments(AssignmentType::with(2)).unwrap();
+        assert_eq!(cached.id(), cached.clone().into_transition().id());
+    }
+}
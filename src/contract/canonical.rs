@@ -0,0 +1,239 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Collections with canonical order of items.
+//!
+//! Consignment collections which are not maps or sets still participate in
+//! the consignment commitment, thus their order must be deterministic. Items of
+//! such collections are ordered by their canonical key (usually an id) without
+//! repetitions, and the order is enforced when the collection is decoded.
+
+use std::ops::Deref;
+use std::{io, mem};
+
+use amplify::confinement::{self, Confined, LargeVec, U32};
+use bp::Txid;
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDumb, StrictEncode, StrictType, TypeName, TypedRead,
+    TypedWrite,
+};
+
+use crate::{AnchoredBundle, BundleId, EmbeddedWitness, Extension, Layer1, OpId, Operation};
+
+/// Items which have canonical order within a [`CanonicalVec`].
+pub trait CanonicalOrd {
+    /// Key defining canonical order of the items; no two items of a collection
+    /// may have the same key.
+    type Key: Ord;
+
+    fn canonical_key(&self) -> Self::Key;
+}
+
+/// Anchored bundles are ordered by their bundle ids.
+impl CanonicalOrd for AnchoredBundle {
+    type Key = BundleId;
+    fn canonical_key(&self) -> Self::Key { self.bundle.bundle_id() }
+}
+
+/// State extensions are ordered by their operation ids.
+impl CanonicalOrd for Extension {
+    type Key = OpId;
+    fn canonical_key(&self) -> Self::Key { self.id() }
+}
+
+/// Embedded witnesses are ordered by layer 1 and then by transaction id.
+impl CanonicalOrd for EmbeddedWitness {
+    type Key = (Layer1, Txid);
+    fn canonical_key(&self) -> Self::Key { (self.layer1, self.txid()) }
+}
+
+/// Error indicating that collection items are not in the canonical order.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display("collection items are not ordered by their canonical keys or repeat")]
+pub struct CanonicalOrderError;
+
+/// Vector of items kept in the canonical order of their keys, containing at
+/// most one item per key.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CanonicalVec<T: CanonicalOrd>(LargeVec<T>);
+
+impl<T: CanonicalOrd> Default for CanonicalVec<T> {
+    fn default() -> Self { CanonicalVec(LargeVec::new()) }
+}
+
+impl<T: CanonicalOrd> Deref for CanonicalVec<T> {
+    type Target = LargeVec<T>;
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl<T: CanonicalOrd> CanonicalVec<T> {
+    pub fn new() -> Self { CanonicalVec(LargeVec::new()) }
+
+    /// Constructs collection from items in arbitrary order. If several items
+    /// have the same key, the last of them is kept.
+    pub fn try_from_iter(iter: impl IntoIterator<Item = T>) -> Result<Self, confinement::Error> {
+        let mut vec = CanonicalVec::new();
+        for item in iter {
+            vec.push(item)?;
+        }
+        Ok(vec)
+    }
+
+    /// Inserts item at the position defined by its key, replacing the item
+    /// with the same key, if any.
+    pub fn push(&mut self, item: T) -> Result<(), confinement::Error> {
+        let key = item.canonical_key();
+        let mut vec = mem::take(&mut self.0).into_inner();
+        match vec.binary_search_by(|probe| probe.canonical_key().cmp(&key)) {
+            Ok(pos) => vec[pos] = item,
+            Err(_) if vec.len() >= U32 => {
+                let len = vec.len() + 1;
+                self.0 = Confined::from_collection_unsafe(vec);
+                return Err(confinement::Error::Oversize { len, max_len: U32 });
+            }
+            Err(pos) => vec.insert(pos, item),
+        }
+        self.0 = Confined::from_collection_unsafe(vec);
+        Ok(())
+    }
+
+    /// Checks whether items are ordered by their keys without repetitions.
+    pub fn is_canonical(items: &[T]) -> bool {
+        items
+            .windows(2)
+            .all(|pair| pair[0].canonical_key() < pair[1].canonical_key())
+    }
+
+    pub fn into_vec(self) -> Vec<T> { self.0.into_inner() }
+}
+
+impl<T: CanonicalOrd> TryFrom<LargeVec<T>> for CanonicalVec<T> {
+    type Error = CanonicalOrderError;
+
+    /// Converts vector into canonical one, requiring items to be already in
+    /// the canonical order.
+    fn try_from(vec: LargeVec<T>) -> Result<Self, Self::Error> {
+        if !Self::is_canonical(&vec) {
+            return Err(CanonicalOrderError);
+        }
+        Ok(CanonicalVec(vec))
+    }
+}
+
+impl<T: CanonicalOrd> IntoIterator for CanonicalVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.into_iter() }
+}
+
+impl<'a, T: CanonicalOrd> IntoIterator for &'a CanonicalVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+impl<T: CanonicalOrd + StrictType> StrictType for CanonicalVec<T> {
+    const STRICT_LIB_NAME: &'static str = <LargeVec<T> as StrictType>::STRICT_LIB_NAME;
+    fn strict_name() -> Option<TypeName> { None }
+}
+
+impl<T: CanonicalOrd + StrictEncode + StrictDumb> StrictEncode for CanonicalVec<T> {
+    fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+        self.0.strict_encode(writer)
+    }
+}
+
+impl<T: CanonicalOrd + StrictDecode> StrictDecode for CanonicalVec<T> {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        let vec = LargeVec::<T>::strict_decode(reader)?;
+        CanonicalVec::try_from(vec).map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod _serde {
+    use serde_crate::de::Error;
+    use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    impl<T: CanonicalOrd + Serialize> Serialize for CanonicalVec<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    impl<'de, T: CanonicalOrd + Deserialize<'de>> Deserialize<'de> for CanonicalVec<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let vec = LargeVec::<T>::deserialize(deserializer)?;
+            CanonicalVec::try_from(vec).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::{StrictReader, StrictWriter};
+
+    use super::*;
+    use crate::ExtensionType;
+
+    fn extensions() -> Vec<Extension> {
+        (0u16..4)
+            .map(|ty| {
+                let mut extension = Extension::strict_dumb();
+                extension.extension_type = ExtensionType::with(ty);
+                extension
+            })
+            .collect()
+    }
+
+    #[test]
+    fn canonical_order() {
+        let mut items = extensions();
+        let vec = CanonicalVec::try_from_iter(items.iter().rev().cloned()).unwrap();
+        assert!(CanonicalVec::is_canonical(&vec));
+        assert_eq!(vec.len(), 4);
+
+        items.sort_by_key(Extension::id);
+        assert_eq!(vec.as_slice(), items.as_slice());
+
+        let mut dup = vec.clone();
+        dup.push(items[0].clone()).unwrap();
+        assert_eq!(dup, vec);
+
+        items.reverse();
+        let writer = StrictWriter::in_memory(U32);
+        let writer = LargeVec::try_from(items)
+            .unwrap()
+            .strict_encode(writer)
+            .unwrap();
+        let data = writer.unbox();
+        let mut reader = StrictReader::with(U32, data.as_slice());
+        assert!(matches!(
+            CanonicalVec::<Extension>::strict_decode(&mut reader),
+            Err(DecodeError::DataIntegrityError(_))
+        ));
+    }
+}
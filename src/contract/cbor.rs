@@ -0,0 +1,159 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic CBOR encoding of consensus data.
+//!
+//! The encoding follows core deterministic encoding requirements of RFC 8949
+//! (section 4.2.1): integers and lengths use the shortest form, all items have
+//! definite length and map keys are sorted in the bytewise lexicographic order
+//! of their encodings. Decoding rejects data which are not deterministically
+//! encoded.
+
+use ciborium::Value;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    AnchoredBundle, Consignment, ContractState, Extension, Genesis, Schema, SchemaRoot, Transition,
+    TransitionBundle,
+};
+
+/// Errors in CBOR encoding.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CborError {
+    /// data can't be encoded with CBOR. Details: {0}
+    Encode(String),
+
+    /// invalid CBOR data. Details: {0}
+    Decode(String),
+
+    /// CBOR data are not deterministically encoded.
+    NonDeterministic,
+}
+
+fn canonicalize(value: Value) -> Result<Value, CborError> {
+    Ok(match value {
+        Value::Tag(tag, value) => Value::Tag(tag, Box::new(canonicalize(*value)?)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(canonicalize)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Map(entries) => {
+            let mut entries = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = canonicalize(key)?;
+                    let mut encoded = vec![];
+                    ciborium::into_writer(&key, &mut encoded)
+                        .map_err(|err| CborError::Encode(err.to_string()))?;
+                    Ok((encoded, key, canonicalize(value)?))
+                })
+                .collect::<Result<Vec<_>, CborError>>()?;
+            entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+            Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(_, key, value)| (key, value))
+                    .collect(),
+            )
+        }
+        value => value,
+    })
+}
+
+/// Deterministic CBOR serialization.
+pub trait CborSerialize: Serialize + DeserializeOwned {
+    fn to_cbor(&self) -> Result<Vec<u8>, CborError> {
+        let value = Value::serialized(self).map_err(|err| CborError::Encode(err.to_string()))?;
+        let mut data = vec![];
+        ciborium::into_writer(&canonicalize(value)?, &mut data)
+            .map_err(|err| CborError::Encode(err.to_string()))?;
+        Ok(data)
+    }
+
+    fn from_cbor(data: &[u8]) -> Result<Self, CborError> {
+        let me: Self =
+            ciborium::from_reader(data).map_err(|err| CborError::Decode(err.to_string()))?;
+        if me.to_cbor()? != data {
+            return Err(CborError::NonDeterministic);
+        }
+        Ok(me)
+    }
+}
+
+impl<Root: SchemaRoot + Serialize + DeserializeOwned> CborSerialize for Schema<Root> {}
+impl CborSerialize for Genesis {}
+impl CborSerialize for Transition {}
+impl CborSerialize for Extension {}
+impl CborSerialize for TransitionBundle {}
+impl CborSerialize for AnchoredBundle {}
+impl CborSerialize for ContractState {}
+impl CborSerialize for Consignment {}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let transition = Transition::strict_dumb();
+        let data = transition.to_cbor().unwrap();
+        assert_eq!(Transition::from_cbor(&data).unwrap(), transition);
+
+        let consignment = Consignment::strict_dumb();
+        let data = consignment.to_cbor().unwrap();
+        assert_eq!(Consignment::from_cbor(&data).unwrap(), consignment);
+    }
+
+    #[test]
+    fn canonical_map_order() {
+        let value = Value::Map(vec![
+            (Value::Text(s!("bb")), Value::Null),
+            (Value::Text(s!("c")), Value::Null),
+            (Value::Integer(10.into()), Value::Null),
+        ]);
+        assert_eq!(
+            canonicalize(value).unwrap(),
+            Value::Map(vec![
+                (Value::Integer(10.into()), Value::Null),
+                (Value::Text(s!("c")), Value::Null),
+                (Value::Text(s!("bb")), Value::Null),
+            ])
+        );
+    }
+
+    #[test]
+    fn non_deterministic() {
+        let mut data = Transition::strict_dumb().to_cbor().unwrap();
+        // Replace top-level definite-length map header with indefinite-length one
+        let len = data[0] & 0x1F;
+        data[0] = 0xBF;
+        data.push(0xFF);
+        assert_ne!(len, 0x1F);
+        assert_eq!(Transition::from_cbor(&data), Err(CborError::NonDeterministic));
+    }
+}
@@ -0,0 +1,104 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Genesis proofs of the contract creation time.
+//!
+//! An issuer may attach to the contract genesis a [`ChainProof`], committing
+//! to a recent block of the contract blockchain. Since the block hash can't be
+//! known in advance, the contract is proven to be created not before the
+//! block was mined. The validator checks that the block is known to the
+//! resolver and that it precedes all witness transactions of the contract.
+
+use std::io;
+
+use amplify::ByteArray;
+use bp::BlockHash;
+use commit_verify::CommitEncode;
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::LIB_NAME_RGB;
+
+/// Commitment of the genesis to a recent block, providing a "not created
+/// before" timestamp for the contract.
+///
+/// An empty proof (with all-zero block hash) doesn't contribute to the
+/// genesis commitment, such that contracts issued without it keep their ids.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ChainProof {
+    /// Hash of the block which must precede all witness transactions of the
+    /// contract.
+    pub block_hash: BlockHash,
+}
+
+impl Default for ChainProof {
+    fn default() -> Self {
+        ChainProof {
+            block_hash: BlockHash::from([0u8; 32]),
+        }
+    }
+}
+
+impl CommitEncode for ChainProof {
+    fn commit_encode(&self, mut e: &mut impl io::Write) {
+        if self.is_empty() {
+            return;
+        }
+        let w = StrictWriter::with(u32::MAX as usize, &mut e);
+        self.block_hash.strict_encode(w).ok();
+    }
+}
+
+impl ChainProof {
+    pub fn with(block_hash: BlockHash) -> Self { ChainProof { block_hash } }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.block_hash.to_byte_array() == [0u8; 32] }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::{Genesis, Operation};
+
+    #[test]
+    fn genesis_commitment() {
+        let mut genesis = Genesis::strict_dumb();
+        let contract_id = genesis.contract_id();
+        assert!(genesis.chain_proof.is_empty());
+
+        genesis.chain_proof = ChainProof::with(BlockHash::from([0x42; 32]));
+        assert!(!genesis.chain_proof.is_empty());
+        assert_ne!(genesis.contract_id(), contract_id);
+
+        let data = genesis.to_strict_serialized::<{ usize::MAX }>().unwrap();
+        assert_eq!(Genesis::from_strict_serialized::<{ usize::MAX }>(data).unwrap(), genesis);
+    }
+}
@@ -0,0 +1,330 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Off-chain state channel updates (experimental).
+//!
+//! Two parties controlling contract outputs assigned to a channel funding
+//! output may exchange state transitions spending these outputs off-chain,
+//! much like lightning commitment transactions. Each [`ChannelUpdate`] is
+//! signed by both parties and supersedes all updates with lower sequence
+//! numbers. Updates are validated only within the context of their
+//! [`Channel`] and never get into the contract history or its global state:
+//! only the latest update, once the channel is closed, is anchored on-chain as
+//! a regular state transition.
+
+use std::io;
+
+use amplify::confinement::{TinyOrdMap, TinyOrdSet};
+use amplify::{Bytes32, Bytes64, Wrapper};
+use bp::secp256k1::schnorr::Signature;
+use bp::secp256k1::{Keypair, Message, SecretKey, SECP256K1};
+use bp::XOnlyPk;
+use commit_verify::{CommitEncode, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::{ContractId, OpId, Operation, Opout, Transition, LIB_NAME_RGB};
+
+const CHANNEL_UPDATE_TAG: &str = "urn:lnpbp:rgb:channel-update#240125";
+
+/// Unique channel identifier equivalent to the commitment hash of the channel
+/// parameters.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ChannelId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Errors validating off-chain channel updates.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ChannelError {
+    /// channel update belongs to a different channel {0}.
+    ChannelMismatch(ChannelId),
+
+    /// channel update transition belongs to a different contract {0}.
+    ContractMismatch(ContractId),
+
+    /// channel update #{seq_no} is superseded by the update #{latest}.
+    Outdated { seq_no: u64, latest: u64 },
+
+    /// channel update transition must spend exactly the channel funding
+    /// outputs.
+    InputsMismatch,
+
+    /// channel update lacks a valid signature of the party {0}.
+    InvalidSignature(XOnlyPk),
+
+    /// channel update is signed by key {0}, which doesn't belong to a
+    /// channel party.
+    UnknownParty(XOnlyPk),
+}
+
+/// Off-chain state update, signed by both channel parties.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ChannelUpdate {
+    pub channel_id: ChannelId,
+    /// Sequence number; an update supersedes all updates with lower numbers.
+    pub seq_no: u64,
+    pub transition: Transition,
+    /// BIP-340 signatures of the channel parties over
+    /// [`ChannelUpdate::sig_hash`].
+    pub sigs: TinyOrdMap<XOnlyPk, Bytes64>,
+}
+
+impl ChannelUpdate {
+    pub fn new(channel_id: ChannelId, seq_no: u64, transition: Transition) -> Self {
+        ChannelUpdate {
+            channel_id,
+            seq_no,
+            transition,
+            sigs: empty!(),
+        }
+    }
+
+    /// Computes hash signed by the channel parties, which commits to the
+    /// channel, sequence number and the id of the update transition.
+    pub fn sig_hash(channel_id: ChannelId, seq_no: u64, opid: OpId) -> Bytes32 {
+        let mut engine = Sha256::from_tag(CHANNEL_UPDATE_TAG);
+        engine.input_raw(channel_id.as_slice());
+        engine.input_raw(&seq_no.to_le_bytes());
+        engine.input_raw(opid.as_slice());
+        engine.finish().into()
+    }
+
+    fn message(&self) -> Message {
+        let hash = Self::sig_hash(self.channel_id, self.seq_no, self.transition.id());
+        Message::from_digest(hash.to_byte_array())
+    }
+
+    /// Signs the update with the key of one of the channel parties.
+    pub fn sign(&mut self, secret_key: &SecretKey) -> Result<(), ChannelError> {
+        let keypair = Keypair::from_secret_key(SECP256K1, secret_key);
+        let pubkey = XOnlyPk::from(keypair.x_only_public_key().0);
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(&self.message(), &keypair);
+        self.sigs
+            .insert(pubkey, Bytes64::from(sig.serialize()))
+            .map_err(|_| ChannelError::UnknownParty(pubkey))?;
+        Ok(())
+    }
+
+    /// Checks that the update contains valid signature of the given key.
+    pub fn verify_sig(&self, pubkey: XOnlyPk) -> bool {
+        let Some(sig) = self.sigs.get(&pubkey) else {
+            return false;
+        };
+        Signature::from_slice(sig.as_slice())
+            .and_then(|sig| SECP256K1.verify_schnorr(&sig, &self.message(), &pubkey))
+            .is_ok()
+    }
+}
+
+/// Context of a state channel, in which off-chain updates are validated.
+///
+/// Only the channel parameters are committed to by the channel id; the latest
+/// update is not.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Channel {
+    pub contract_id: ContractId,
+    /// Contract outputs assigned to the channel funding output, which are
+    /// spent by each of the channel updates.
+    pub funding: TinyOrdSet<Opout>,
+    pub local: XOnlyPk,
+    pub remote: XOnlyPk,
+    latest: Option<ChannelUpdate>,
+}
+
+impl CommitEncode for Channel {
+    fn commit_encode(&self, mut e: &mut impl io::Write) {
+        let w = StrictWriter::with(u32::MAX as usize, &mut e);
+        self.contract_id
+            .strict_encode(w)
+            .and_then(|w| self.funding.strict_encode(w))
+            .and_then(|w| self.local.strict_encode(w))
+            .and_then(|w| self.remote.strict_encode(w))
+            .ok();
+    }
+}
+
+impl CommitmentId for Channel {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:channel:v1#240125A";
+    type Id = ChannelId;
+}
+
+impl Channel {
+    pub fn new(
+        contract_id: ContractId,
+        funding: TinyOrdSet<Opout>,
+        local: XOnlyPk,
+        remote: XOnlyPk,
+    ) -> Self {
+        Channel {
+            contract_id,
+            funding,
+            local,
+            remote,
+            latest: None,
+        }
+    }
+
+    #[inline]
+    pub fn channel_id(&self) -> ChannelId { self.commitment_id() }
+
+    /// Returns the latest accepted update, if any.
+    #[inline]
+    pub fn latest(&self) -> Option<&ChannelUpdate> { self.latest.as_ref() }
+
+    /// Validates the update in the context of the channel without accepting
+    /// it.
+    pub fn validate(&self, update: &ChannelUpdate) -> Result<(), ChannelError> {
+        let channel_id = self.channel_id();
+        if update.channel_id != channel_id {
+            return Err(ChannelError::ChannelMismatch(update.channel_id));
+        }
+        if update.transition.contract_id != self.contract_id {
+            return Err(ChannelError::ContractMismatch(update.transition.contract_id));
+        }
+        if let Some(latest) = &self.latest {
+            if update.seq_no <= latest.seq_no {
+                return Err(ChannelError::Outdated {
+                    seq_no: update.seq_no,
+                    latest: latest.seq_no,
+                });
+            }
+        }
+        let inputs = update.transition.inputs.iter().map(|input| input.prev_out);
+        if !inputs.eq(self.funding.iter().copied()) {
+            return Err(ChannelError::InputsMismatch);
+        }
+        if let Some(pubkey) = update
+            .sigs
+            .keys()
+            .find(|pubkey| **pubkey != self.local && **pubkey != self.remote)
+        {
+            return Err(ChannelError::UnknownParty(*pubkey));
+        }
+        for pubkey in [self.local, self.remote] {
+            if !update.verify_sig(pubkey) {
+                return Err(ChannelError::InvalidSignature(pubkey));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the update and accepts it as the latest channel state.
+    pub fn update(&mut self, update: ChannelUpdate) -> Result<(), ChannelError> {
+        self.validate(&update)?;
+        self.latest = Some(update);
+        Ok(())
+    }
+
+    /// Closes the channel, returning the transition of the latest update, which
+    /// has to be anchored on-chain to get into the contract history.
+    pub fn close(self) -> Option<Transition> { self.latest.map(|update| update.transition) }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssignmentType, Input};
+
+    #[test]
+    fn channel_updates() {
+        let key = |byte| SecretKey::from_slice(&[byte; 32]).unwrap();
+        let pubkey = |byte| XOnlyPk::from(key(byte).x_only_public_key(SECP256K1).0);
+        let contract_id = ContractId::from([1; 32]);
+        let funding = Opout::new(OpId::from([2; 32]), AssignmentType::with(1), 0);
+        let mut channel =
+            Channel::new(contract_id, tiny_bset![funding], pubkey(0x11), pubkey(0x22));
+        let channel_id = channel.channel_id();
+
+        let update = |seq_no, prev_out, signers: &[u8]| {
+            let mut transition = Transition::strict_dumb();
+            transition.contract_id = contract_id;
+            transition.inputs.push(Input::with(prev_out)).unwrap();
+            let mut update = ChannelUpdate::new(channel_id, seq_no, transition);
+            for signer in signers {
+                update.sign(&key(*signer)).unwrap();
+            }
+            update
+        };
+
+        let first = update(1, funding, &[0x11, 0x22]);
+        assert_eq!(channel.update(first.clone()), Ok(()));
+        assert_eq!(channel.latest(), Some(&first));
+        assert_eq!(
+            channel.validate(&first),
+            Err(ChannelError::Outdated {
+                seq_no: 1,
+                latest: 1
+            })
+        );
+
+        assert_eq!(
+            channel.validate(&update(2, funding, &[0x11])),
+            Err(ChannelError::InvalidSignature(pubkey(0x22)))
+        );
+        assert_eq!(
+            channel.validate(&update(2, funding, &[0x11, 0x22, 0x33])),
+            Err(ChannelError::UnknownParty(pubkey(0x33)))
+        );
+        let other = Opout::new(OpId::from([3; 32]), AssignmentType::with(1), 0);
+        assert_eq!(
+            channel.validate(&update(2, other, &[0x11, 0x22])),
+            Err(ChannelError::InputsMismatch)
+        );
+        let mut foreign = update(2, funding, &[0x11, 0x22]);
+        foreign.channel_id = ChannelId::from([4; 32]);
+        assert_eq!(
+            channel.validate(&foreign),
+            Err(ChannelError::ChannelMismatch(ChannelId::from([4; 32])))
+        );
+
+        let second = update(2, funding, &[0x22, 0x11]);
+        assert_eq!(channel.update(second.clone()), Ok(()));
+        assert_eq!(channel.close(), Some(second.transition));
+    }
+}
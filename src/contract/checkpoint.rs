@@ -0,0 +1,123 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! History checkpoints (experimental).
+//!
+//! A validated prefix of the contract history may be replaced in a
+//! consignment with a [`HistoryCheckpoint`]. The checkpoint commits to the
+//! ids of all operations of the prefix and lists the frontier operations,
+//! i.e. the operations from the prefix which outputs may still be spent by
+//! the rest of the history. Frontier operations with their anchors are kept
+//! in the consignment, while all of their ancestors are pruned.
+//!
+//! Validity of the pruned history is attested by a proof, which may be a
+//! recursive validity proof or a signature, depending on the policy of the
+//! contract schema; the proof is checked with
+//! [`crate::validation::ZkVerifier::verify_checkpoint`]. Checkpoints are
+//! hash-linked, such that a checkpoint proof may attest validity of the
+//! previous checkpoint instead of the complete history.
+
+use std::collections::BTreeSet;
+use std::io;
+
+use amplify::confinement::MediumOrdSet;
+use amplify::{Bytes32, Wrapper};
+use commit_verify::{CommitEncode, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::{ContractId, OpId, ZkProof, LIB_NAME_RGB};
+
+const HISTORY_TAG: &str = "urn:lnpbp:rgb:history#240115";
+
+/// Unique history checkpoint identifier equivalent to the commitment hash of
+/// the checkpoint data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct CheckpointId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Checkpoint replacing a validated prefix of the contract history.
+///
+/// The proof is not committed to by the checkpoint id, allowing it to use the
+/// id as a public input.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct HistoryCheckpoint {
+    pub contract_id: ContractId,
+    /// Previous checkpoint of the same contract history, if any.
+    pub prev: Option<CheckpointId>,
+    /// Commitment to the ids of all operations of the validated prefix, see
+    /// [`HistoryCheckpoint::history_root`].
+    pub history: Bytes32,
+    /// Operations of the prefix which are kept in the consignment.
+    pub frontier: MediumOrdSet<OpId>,
+    pub proof: ZkProof,
+}
+
+impl CommitEncode for HistoryCheckpoint {
+    fn commit_encode(&self, mut e: &mut impl io::Write) {
+        let w = StrictWriter::with(u32::MAX as usize, &mut e);
+        self.contract_id
+            .strict_encode(w)
+            .and_then(|w| self.prev.strict_encode(w))
+            .and_then(|w| self.history.strict_encode(w))
+            .and_then(|w| self.frontier.strict_encode(w))
+            .ok();
+    }
+}
+
+impl CommitmentId for HistoryCheckpoint {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:checkpoint#240115A";
+    type Id = CheckpointId;
+}
+
+impl HistoryCheckpoint {
+    #[inline]
+    pub fn checkpoint_id(&self) -> CheckpointId { self.commitment_id() }
+
+    /// Computes commitment to a set of operation ids.
+    pub fn history_root<'a>(opids: impl IntoIterator<Item = &'a OpId>) -> Bytes32 {
+        let opids = opids.into_iter().collect::<BTreeSet<_>>();
+        let mut engine = Sha256::from_tag(HISTORY_TAG);
+        engine.input_raw(&(opids.len() as u32).to_le_bytes());
+        for opid in opids {
+            engine.input_raw(opid.as_slice());
+        }
+        engine.finish().into()
+    }
+}
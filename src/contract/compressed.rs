@@ -0,0 +1,141 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compressed envelope for strict-serialized consignments and contract state.
+//!
+//! The envelope consists of a single format version byte, followed by a SHA256
+//! hash of the uncompressed strict-serialized data and a zstd frame containing
+//! the compressed data.
+
+use std::io::{self, Read};
+
+use amplify::confinement::{Confined, U32};
+use amplify::IoError;
+use commit_verify::{Digest, Sha256};
+use strict_encoding::{
+    DecodeError, DeserializeError, SerializeError, StrictDeserialize, StrictSerialize,
+};
+
+use crate::{Consignment, ContractState};
+
+/// Version of the compressed envelope format.
+pub const COMPRESSED_FORMAT_V1: u8 = 1;
+
+const COMPRESSION_LEVEL: i32 = 19;
+const HEADER_LEN: usize = 33;
+
+/// Errors in compressed data.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum CompressionError {
+    /// I/O error during data compression. Details: {0}
+    #[from(io::Error)]
+    Io(IoError),
+
+    /// compressed data are too short to contain envelope header.
+    TooShort,
+
+    /// unsupported version {0} of the compressed data format.
+    UnsupportedVersion(u8),
+
+    /// compressed data are corrupted: integrity hash doesn't match.
+    IntegrityMismatch,
+
+    /// data can't be serialized. Details: {0}
+    #[from]
+    Serialize(SerializeError),
+
+    /// data can't be deserialized. Details: {0}
+    #[from]
+    #[from(DecodeError)]
+    Deserialize(DeserializeError),
+}
+
+/// Conversion of strict-serializable data to and from the compressed envelope.
+pub trait CompressedSerialize: StrictSerialize + StrictDeserialize {
+    fn to_compressed(&self) -> Result<Vec<u8>, CompressionError> {
+        let data = self.to_strict_serialized::<U32>()?;
+        let mut compressed = Vec::with_capacity(HEADER_LEN + data.len() / 3);
+        compressed.push(COMPRESSED_FORMAT_V1);
+        compressed.extend(Sha256::digest(data.as_slice()));
+        zstd::stream::copy_encode(data.as_slice(), &mut compressed, COMPRESSION_LEVEL)?;
+        Ok(compressed)
+    }
+
+    fn from_compressed(compressed: &[u8]) -> Result<Self, CompressionError> {
+        if compressed.len() < HEADER_LEN {
+            return Err(CompressionError::TooShort);
+        }
+        let (header, frame) = compressed.split_at(HEADER_LEN);
+        if header[0] != COMPRESSED_FORMAT_V1 {
+            return Err(CompressionError::UnsupportedVersion(header[0]));
+        }
+        let mut data = Vec::new();
+        zstd::stream::Decoder::new(frame)?
+            .take(U32 as u64 + 1)
+            .read_to_end(&mut data)?;
+        if Sha256::digest(&data).as_slice() != &header[1..] {
+            return Err(CompressionError::IntegrityMismatch);
+        }
+        let data = Confined::try_from(data).map_err(DecodeError::from)?;
+        Self::from_strict_serialized::<U32>(data).map_err(CompressionError::from)
+    }
+}
+
+impl CompressedSerialize for Consignment {}
+impl CompressedSerialize for ContractState {}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let consignment = Consignment::strict_dumb();
+        let compressed = consignment.to_compressed().unwrap();
+        assert_eq!(compressed[0], COMPRESSED_FORMAT_V1);
+        assert_eq!(Consignment::from_compressed(&compressed).unwrap(), consignment);
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut compressed = Consignment::strict_dumb().to_compressed().unwrap();
+        assert_eq!(
+            Consignment::from_compressed(&compressed[..10]),
+            Err(CompressionError::TooShort)
+        );
+
+        compressed[1] ^= 0xFF;
+        assert_eq!(
+            Consignment::from_compressed(&compressed),
+            Err(CompressionError::IntegrityMismatch)
+        );
+
+        compressed[0] = 2;
+        assert_eq!(
+            Consignment::from_compressed(&compressed),
+            Err(CompressionError::UnsupportedVersion(2))
+        );
+    }
+}
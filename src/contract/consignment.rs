@@ -0,0 +1,1683 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::{io, iter};
+
+use amplify::confinement::{LargeOrdSet, LargeVec, TinyOrdMap};
+use amplify::{Bytes32, Wrapper};
+use commit_verify::{CommitStrategy, CommitmentId};
+use strict_encoding::{
+    DecodeError, StrictDecode, StrictDeserialize, StrictReader, StrictSerialize,
+};
+
+use crate::validation::{
+    op_schema_failure, CheckLimits, ConsensusLimits, ConsensusParams, ConsignmentApi, Failure,
+    ResolveTx, Status, ValidationHooks, Validator, ZkVerifier,
+};
+use crate::{
+    AnchoredBundle, AssetTag, AssignmentType, BundleId, CanonicalOrd, CanonicalOrderError,
+    CanonicalVec, ChainNet, ContractId, EmbeddedWitness, Extension, Genesis, HistoryCheckpoint,
+    OpId, OpRef, Operation, Opout, PaymentProof, PaymentTag, SecretSeal, SubSchema, Transition,
+    TransitionBundle, LIB_NAME_RGB,
+};
+
+/// Unique consignment identifier equivalent to the commitment hash of the
+/// consignment data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ConsignmentId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Final seal ("endpoint") of the consignment, assigned by a state transition
+/// from the bundle with the given id.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{bundle_id}/{seal}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Terminal {
+    pub bundle_id: BundleId,
+    pub seal: SecretSeal,
+}
+
+/// Consignment is a container for the contract data transferred between
+/// parties, which must be validated by the receiver before it is accepted into
+/// the contract state.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Consignment {
+    pub schema: SubSchema,
+    pub asset_tags: TinyOrdMap<AssignmentType, AssetTag>,
+    pub genesis: Genesis,
+    /// Checkpoint replacing the history preceding the bundles and extensions
+    /// of the consignment, if the history was compressed.
+    pub checkpoint: Option<HistoryCheckpoint>,
+    pub terminals: LargeOrdSet<Terminal>,
+    /// Anchored bundles ordered by their bundle ids.
+    pub bundles: CanonicalVec<AnchoredBundle>,
+    /// State extensions ordered by their operation ids.
+    pub extensions: CanonicalVec<Extension>,
+    /// Witness transactions embedded for validation without access to the
+    /// blockchain, ordered by layer 1 and transaction ids.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub witnesses: CanonicalVec<EmbeddedWitness>,
+}
+
+impl StrictSerialize for Consignment {}
+impl StrictDeserialize for Consignment {}
+
+impl CommitStrategy for Consignment {
+    type Strategy = commit_verify::strategies::Strict;
+}
+
+impl CommitmentId for Consignment {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:consignment#231015";
+    type Id = ConsignmentId;
+}
+
+impl Consignment {
+    #[inline]
+    pub fn consignment_id(&self) -> ConsignmentId { self.commitment_id() }
+
+    #[inline]
+    pub fn contract_id(&self) -> ContractId { self.genesis.contract_id() }
+
+    /// Validates the consignment against RGB consensus rules, resolving
+    /// witness transactions with the provided resolver.
+    pub fn validate(&self, resolver: &impl ResolveTx, chain_net: ChainNet) -> Status {
+        Validator::validate(&IndexedConsignment::new(self), resolver, chain_net)
+    }
+
+    /// Validates the consignment using consensus versions activated according
+    /// to the provided parameters, verifying zero-knowledge proofs with the
+    /// provided verifier and running the provided validation hooks.
+    pub fn validate_with_hooks(
+        &self,
+        resolver: &impl ResolveTx,
+        chain_net: ChainNet,
+        params: &ConsensusParams,
+        zk_verifier: Option<&dyn ZkVerifier>,
+        hooks: ValidationHooks,
+    ) -> Status {
+        Validator::validate_with_hooks(
+            &IndexedConsignment::new(self),
+            resolver,
+            chain_net,
+            params,
+            zk_verifier,
+            hooks,
+        )
+    }
+
+    /// Returns ids of all operations of the consignment which are ancestors
+    /// of the `frontier` operations, including the frontier operations
+    /// themselves.
+    pub fn history_prefix(&self, frontier: &BTreeSet<OpId>) -> BTreeSet<OpId> {
+        let mut prefix = BTreeSet::new();
+        let mut queue = frontier.iter().copied().collect::<VecDeque<_>>();
+        while let Some(opid) = queue.pop_front() {
+            if !prefix.insert(opid) {
+                continue;
+            }
+            if let Some(op) = self.operation(opid) {
+                queue.extend(op.prev_ops());
+            }
+        }
+        prefix
+    }
+
+    /// Returns outputs of the consignment operations which are bound to a
+    /// payment request with the given tag. Concealed payment tags are not
+    /// detected.
+    pub fn outputs_by_payment_tag(&self, tag: PaymentTag) -> BTreeSet<Opout> {
+        iter::once(OpRef::Genesis(&self.genesis))
+            .chain(
+                self.bundles
+                    .iter()
+                    .flat_map(|ab| ab.bundle.known_transitions())
+                    .map(OpRef::Transition),
+            )
+            .chain(self.extensions.iter().map(OpRef::Extension))
+            .flat_map(|op| {
+                let opid = op.id();
+                op.assignments()
+                    .find_payment_tag(tag)
+                    .into_iter()
+                    .map(move |(ty, no)| Opout::new(opid, ty, no))
+            })
+            .collect()
+    }
+
+    /// Extracts proof of the fungible state assigned by the output of a state
+    /// transition, verifiable by third parties without the consignment. The
+    /// consignment must be validated before the proof is extracted.
+    pub fn payment_proof(&self, opout: Opout) -> Option<PaymentProof> {
+        self.bundles
+            .iter()
+            .find(|ab| ab.bundle.contains_key(&opout.op))
+            .and_then(|ab| PaymentProof::with(ab, opout))
+    }
+
+    /// Replaces the history preceding the checkpoint frontier with the
+    /// checkpoint. Bundles and extensions are removed if all of their
+    /// operations are ancestors of the frontier operations; the checkpoint
+    /// proof must attest validity of the removed history.
+    pub fn compress(mut self, checkpoint: HistoryCheckpoint) -> Consignment {
+        let frontier = checkpoint.frontier.iter().copied().collect();
+        let pruned = &self.history_prefix(&frontier) - &frontier;
+        self.bundles = CanonicalVec::try_from_iter(
+            self.bundles
+                .into_iter()
+                .filter(|ab| !ab.bundle.keys().all(|opid| pruned.contains(opid))),
+        )
+        .expect("number of bundles is only reduced");
+        self.extensions = CanonicalVec::try_from_iter(
+            self.extensions
+                .into_iter()
+                .filter(|extension| !pruned.contains(&extension.id())),
+        )
+        .expect("number of extensions is only reduced");
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+}
+
+impl ConsignmentApi for Consignment {
+    type BundleIter<'container> = std::slice::Iter<'container, AnchoredBundle>;
+
+    fn schema(&self) -> &SubSchema { &self.schema }
+
+    fn asset_tags(&self) -> &BTreeMap<AssignmentType, AssetTag> { self.asset_tags.as_inner() }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        if self.genesis.id() == opid {
+            return Some(OpRef::Genesis(&self.genesis));
+        }
+        self.transition(opid)
+            .map(OpRef::Transition)
+            .or_else(|| self.extension(opid).map(OpRef::Extension))
+    }
+
+    fn genesis(&self) -> &Genesis { &self.genesis }
+
+    fn checkpoint(&self) -> Option<&HistoryCheckpoint> { self.checkpoint.as_ref() }
+
+    fn transition(&self, opid: OpId) -> Option<&Transition> {
+        self.bundles
+            .iter()
+            .find_map(|ab| ab.bundle.get(&opid))
+            .and_then(|item| item.transition.as_ref())
+    }
+
+    fn extension(&self, opid: OpId) -> Option<&Extension> {
+        self.extensions
+            .iter()
+            .find(|extension| extension.id() == opid)
+    }
+
+    fn terminals(&self) -> BTreeSet<(BundleId, SecretSeal)> {
+        self.terminals
+            .iter()
+            .map(|terminal| (terminal.bundle_id, terminal.seal))
+            .collect()
+    }
+
+    fn anchored_bundles(&self) -> Self::BundleIter<'_> { self.bundles.iter() }
+
+    fn bundle_by_id(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        self.bundles
+            .iter()
+            .map(|ab| &ab.bundle)
+            .find(|bundle| bundle.bundle_id() == bundle_id)
+    }
+
+    fn op_ids_except(&self, ids: &BTreeSet<OpId>) -> BTreeSet<OpId> {
+        let mut opids = bset![self.genesis.id()];
+        opids.extend(self.bundles.iter().flat_map(|ab| ab.bundle.keys().copied()));
+        opids.extend(self.extensions.iter().map(Extension::id));
+        &opids - ids
+    }
+
+    fn has_operation(&self, opid: OpId) -> bool { self.operation(opid).is_some() }
+
+    fn witnesses(&self) -> &[EmbeddedWitness] { &self.witnesses }
+
+    fn known_transitions_by_bundle_id(&self, bundle_id: BundleId) -> Option<Vec<&Transition>> {
+        self.bundle_by_id(bundle_id)
+            .map(|bundle| bundle.known_transitions().collect())
+    }
+}
+
+/// View of the consignment with operations and bundles indexed by their ids,
+/// used during the validation instead of scanning the consignment for each
+/// lookup.
+struct IndexedConsignment<'consignment> {
+    consignment: &'consignment Consignment,
+    genesis_id: OpId,
+    transition_index: BTreeMap<OpId, usize>,
+    extension_index: BTreeMap<OpId, usize>,
+    bundle_index: BTreeMap<BundleId, usize>,
+}
+
+impl<'consignment> IndexedConsignment<'consignment> {
+    fn new(consignment: &'consignment Consignment) -> Self {
+        let mut transition_index = BTreeMap::new();
+        let mut bundle_index = BTreeMap::new();
+        for (idx, ab) in consignment.bundles.iter().enumerate() {
+            bundle_index.entry(ab.bundle.bundle_id()).or_insert(idx);
+            for opid in ab.bundle.keys() {
+                transition_index.entry(*opid).or_insert(idx);
+            }
+        }
+        let mut extension_index = BTreeMap::new();
+        for (idx, extension) in consignment.extensions.iter().enumerate() {
+            extension_index.entry(extension.id()).or_insert(idx);
+        }
+        IndexedConsignment {
+            consignment,
+            genesis_id: consignment.genesis.id(),
+            transition_index,
+            extension_index,
+            bundle_index,
+        }
+    }
+}
+
+impl ConsignmentApi for IndexedConsignment<'_> {
+    type BundleIter<'container>
+        = std::slice::Iter<'container, AnchoredBundle>
+    where Self: 'container;
+
+    fn schema(&self) -> &SubSchema { &self.consignment.schema }
+
+    fn asset_tags(&self) -> &BTreeMap<AssignmentType, AssetTag> {
+        self.consignment.asset_tags.as_inner()
+    }
+
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>> {
+        if self.genesis_id == opid {
+            return Some(OpRef::Genesis(&self.consignment.genesis));
+        }
+        self.transition(opid)
+            .map(OpRef::Transition)
+            .or_else(|| self.extension(opid).map(OpRef::Extension))
+    }
+
+    fn genesis(&self) -> &Genesis { &self.consignment.genesis }
+
+    fn checkpoint(&self) -> Option<&HistoryCheckpoint> { self.consignment.checkpoint.as_ref() }
+
+    fn transition(&self, opid: OpId) -> Option<&Transition> {
+        let idx = *self.transition_index.get(&opid)?;
+        self.consignment.bundles[idx]
+            .bundle
+            .get(&opid)
+            .and_then(|item| item.transition.as_ref())
+    }
+
+    fn extension(&self, opid: OpId) -> Option<&Extension> {
+        let idx = *self.extension_index.get(&opid)?;
+        Some(&self.consignment.extensions[idx])
+    }
+
+    fn terminals(&self) -> BTreeSet<(BundleId, SecretSeal)> { self.consignment.terminals() }
+
+    fn anchored_bundles(&self) -> Self::BundleIter<'_> { self.consignment.bundles.iter() }
+
+    fn bundle_by_id(&self, bundle_id: BundleId) -> Option<&TransitionBundle> {
+        let idx = *self.bundle_index.get(&bundle_id)?;
+        Some(&self.consignment.bundles[idx].bundle)
+    }
+
+    fn op_ids_except(&self, ids: &BTreeSet<OpId>) -> BTreeSet<OpId> {
+        let mut opids = bset![self.genesis_id];
+        opids.extend(self.transition_index.keys().copied());
+        opids.extend(self.extension_index.keys().copied());
+        &opids - ids
+    }
+
+    fn has_operation(&self, opid: OpId) -> bool { self.operation(opid).is_some() }
+
+    fn witnesses(&self) -> &[EmbeddedWitness] { &self.consignment.witnesses }
+
+    fn known_transitions_by_bundle_id(&self, bundle_id: BundleId) -> Option<Vec<&Transition>> {
+        self.bundle_by_id(bundle_id)
+            .map(|bundle| bundle.known_transitions().collect())
+    }
+}
+
+fn check_limits(data: &impl CheckLimits, limits: &ConsensusLimits) -> Result<(), DecodeError> {
+    data.check_limits(limits)
+        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+}
+
+fn check_order<T: CanonicalOrd>(last: &mut Option<T::Key>, item: &T) -> Result<(), DecodeError>
+where T::Key: Copy {
+    let key = item.canonical_key();
+    if matches!(last, Some(last) if *last >= key) {
+        return Err(DecodeError::DataIntegrityError(CanonicalOrderError.to_string()));
+    }
+    *last = Some(key);
+    Ok(())
+}
+
+/// Reader decoding strict-encoded [`Consignment`] from a stream item by item,
+/// such that anchored bundles and extensions are never required to be all kept
+/// in memory at the same time.
+///
+/// Each of the anchored bundles is checked for the internal consistency as it
+/// is read from the stream; bundles and extensions are also checked to follow
+/// the canonical order. With [`ConsignmentReader::next_validated_bundle`] the
+/// bundles are additionally validated against the anchor and the schema
+/// before the rest of the stream is read.
+pub struct ConsignmentReader<R: io::Read> {
+    reader: StrictReader<R>,
+    schema: SubSchema,
+    asset_tags: TinyOrdMap<AssignmentType, AssetTag>,
+    genesis: Genesis,
+    contract_id: ContractId,
+    checkpoint: Option<HistoryCheckpoint>,
+    terminals: LargeOrdSet<Terminal>,
+    bundles_left: u32,
+    extensions_left: Option<u32>,
+    last_bundle: Option<BundleId>,
+    last_extension: Option<OpId>,
+    limits: ConsensusLimits,
+}
+
+impl<R: io::Read> ConsignmentReader<R> {
+    /// Constructs reader decoding at most `limit` bytes from the stream and
+    /// reads consignment data preceding anchored bundles. Fails if the genesis
+    /// doesn't commit to the consignment schema.
+    pub fn with(limit: usize, reader: R) -> Result<Self, DecodeError> {
+        Self::with_limits(limit, reader, ConsensusLimits::default())
+    }
+
+    /// Constructs reader like [`ConsignmentReader::with`], checking each of the
+    /// decoded operations and bundles against consensus limits.
+    pub fn with_limits(
+        limit: usize,
+        reader: R,
+        limits: ConsensusLimits,
+    ) -> Result<Self, DecodeError> {
+        let mut reader = StrictReader::with(limit, reader);
+        let schema = SubSchema::strict_decode(&mut reader)?;
+        let asset_tags = StrictDecode::strict_decode(&mut reader)?;
+        let genesis = Genesis::strict_decode(&mut reader)?;
+        check_limits(&genesis, &limits)?;
+        let schema_id = schema.schema_id();
+        if genesis.schema_id != schema_id {
+            return Err(DecodeError::DataIntegrityError(
+                Failure::SchemaMismatch {
+                    expected: genesis.schema_id,
+                    actual: schema_id,
+                }
+                .to_string(),
+            ));
+        }
+        let checkpoint = StrictDecode::strict_decode(&mut reader)?;
+        let terminals = StrictDecode::strict_decode(&mut reader)?;
+        let bundles_left = u32::strict_decode(&mut reader)?;
+        Ok(ConsignmentReader {
+            reader,
+            contract_id: genesis.contract_id(),
+            schema,
+            asset_tags,
+            genesis,
+            checkpoint,
+            terminals,
+            bundles_left,
+            extensions_left: None,
+            last_bundle: None,
+            last_extension: None,
+            limits,
+        })
+    }
+
+    pub fn schema(&self) -> &SubSchema { &self.schema }
+
+    pub fn asset_tags(&self) -> &TinyOrdMap<AssignmentType, AssetTag> { &self.asset_tags }
+
+    pub fn genesis(&self) -> &Genesis { &self.genesis }
+
+    pub fn checkpoint(&self) -> Option<&HistoryCheckpoint> { self.checkpoint.as_ref() }
+
+    pub fn terminals(&self) -> &LargeOrdSet<Terminal> { &self.terminals }
+
+    /// Reads next anchored bundle from the stream, returning `None` once all
+    /// bundles are read.
+    pub fn next_bundle(&mut self) -> Result<Option<AnchoredBundle>, DecodeError> {
+        if self.bundles_left == 0 {
+            return Ok(None);
+        }
+        let anchored_bundle = AnchoredBundle::strict_decode(&mut self.reader)?;
+        if !anchored_bundle.bundle.validate() {
+            return Err(DecodeError::DataIntegrityError(format!(
+                "bundle {} claims the same witness input more than once",
+                anchored_bundle.bundle.bundle_id()
+            )));
+        }
+        check_limits(&anchored_bundle, &self.limits)?;
+        check_order(&mut self.last_bundle, &anchored_bundle)?;
+        self.bundles_left -= 1;
+        Ok(Some(anchored_bundle))
+    }
+
+    /// Reads next anchored bundle like [`ConsignmentReader::next_bundle`],
+    /// validating it as it is read. The anchor must commit to the bundle, and
+    /// each of the transitions must commit to the contract schema, belong to
+    /// the contract and use transition type declared by the schema.
+    ///
+    /// Checks depending on other operations, like validation of the spent
+    /// state and of the closed seals, are performed only by the full
+    /// consignment validation.
+    pub fn next_validated_bundle(
+        &mut self,
+    ) -> Result<Option<(AnchoredBundle, Status)>, DecodeError> {
+        let Some(anchored_bundle) = self.next_bundle()? else {
+            return Ok(None);
+        };
+        let schema_id = self.genesis.schema_id;
+        let contract_id = self.contract_id;
+        let bundle_id = anchored_bundle.bundle.bundle_id();
+        let committed = anchored_bundle
+            .anchor
+            .convolve(contract_id, bundle_id.into())
+            .is_ok();
+        let mut status = Status::new();
+        for transition in anchored_bundle.bundle.known_transitions() {
+            let opid = transition.id();
+            if !committed {
+                status.add_failure(Failure::NotInAnchor(opid));
+            }
+            if let Some(failure) = op_schema_failure(
+                &self.schema,
+                schema_id,
+                contract_id,
+                OpRef::Transition(transition),
+            ) {
+                status.add_failure(failure);
+            }
+        }
+        Ok(Some((anchored_bundle, status)))
+    }
+
+    /// Reads next state extension from the stream, returning `None` once all
+    /// extensions are read. Anchored bundles which were not read yet are
+    /// skipped.
+    pub fn next_extension(&mut self) -> Result<Option<Extension>, DecodeError> {
+        let left = match self.extensions_left {
+            Some(left) => left,
+            None => {
+                while self.next_bundle()?.is_some() {}
+                u32::strict_decode(&mut self.reader)?
+            }
+        };
+        if left == 0 {
+            self.extensions_left = Some(0);
+            return Ok(None);
+        }
+        let extension = Extension::strict_decode(&mut self.reader)?;
+        check_limits(&extension, &self.limits)?;
+        check_order(&mut self.last_extension, &extension)?;
+        self.extensions_left = Some(left - 1);
+        Ok(Some(extension))
+    }
+
+    /// Reads the rest of the stream, constructing the complete consignment.
+    pub fn into_consignment(mut self) -> Result<Consignment, DecodeError> {
+        let mut bundles = LargeVec::new();
+        while let Some(anchored_bundle) = self.next_bundle()? {
+            bundles.push(anchored_bundle)?;
+        }
+        let mut extensions = LargeVec::new();
+        while let Some(extension) = self.next_extension()? {
+            extensions.push(extension)?;
+        }
+        // Order was already checked while reading
+        let bundles = CanonicalVec::try_from(bundles).expect("canonical order");
+        let extensions = CanonicalVec::try_from(extensions).expect("canonical order");
+        Ok(Consignment {
+            schema: self.schema,
+            asset_tags: self.asset_tags,
+            genesis: self.genesis,
+            checkpoint: self.checkpoint,
+            terminals: self.terminals,
+            bundles,
+            extensions,
+            witnesses: StrictDecode::strict_decode(&mut self.reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+
+    use amplify::confinement::{Confined, MediumOrdMap, TinyOrdSet, TinyString, U32};
+    use amplify::{ByteArray, Bytes32};
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use bp::{BlockHash, BlockHeader, Tx, Txid};
+    use commit_verify::Conceal;
+    use strict_encoding::{Sizing, StrictDumb, StrictEncode};
+    use strict_types::{SemId, Ty, TypeSystem};
+
+    use super::*;
+    use crate::validation::{
+        CancelToken, ConsensusVersion, Failure, SpvResolver, StringError, TxResolverError,
+        ValidationPhase, ValidationPolicy, Warning,
+    };
+    use crate::{
+        check_pow, Anchor, Assign, AttachId, AttachLimits, ChainProof, GenesisSeal,
+        GlobalStateSchema, GlobalStateType, GraphSeal, Input, Inputs, Layer1, MediaType,
+        Occurrences, OpRef, RevealedAttach, RevealedData, RevealedPaymentTag, SchemaId,
+        SealDefinition, SpvError, SpvProof, StateSchema, SubSchema, TransitionSchema,
+        TransitionType, TypedAssigns, VerifyingKeyId, VoidState, ZkProof,
+    };
+
+    struct NoResolver;
+    impl ResolveTx for NoResolver {
+        fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+            Err(TxResolverError::Unknown(txid))
+        }
+    }
+
+    /// Consignment without operations under the schema using only the unit
+    /// type.
+    fn unit_consignment() -> Consignment {
+        let mut consignment = Consignment::strict_dumb();
+        let unit = Ty::<SemId>::UNIT;
+        let mut types = MediumOrdMap::new();
+        types.insert(unit.id(None), unit).unwrap();
+        consignment.schema.type_system = TypeSystem::from(types);
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        consignment
+    }
+
+    /// Blank state transition of the consignment contract.
+    fn blank_transition(consignment: &Consignment) -> Transition {
+        let mut transition = Transition::strict_dumb();
+        transition.transition_type = TransitionType::BLANK;
+        transition.contract_id = consignment.genesis.contract_id();
+        transition.schema_id = consignment.genesis.schema_id;
+        transition
+    }
+
+    /// Extends the consignment with the transition in a separate anchored
+    /// bundle, which is also added to the consignment terminals.
+    fn consignment_with(consignment: &Consignment, transition: Transition) -> Consignment {
+        let mut consignment = consignment.clone();
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        let bundle_id = anchored.bundle.bundle_id();
+        consignment.bundles.push(anchored).unwrap();
+        consignment
+            .terminals
+            .push(Terminal {
+                bundle_id,
+                seal: SecretSeal::strict_dumb(),
+            })
+            .unwrap();
+        consignment
+    }
+
+    #[test]
+    fn stream_decode() {
+        let mut consignment = Consignment::strict_dumb();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        consignment
+            .bundles
+            .push(AnchoredBundle::strict_dumb())
+            .unwrap();
+        consignment
+            .extensions
+            .push(Extension::strict_dumb())
+            .unwrap();
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+
+        let mut reader = ConsignmentReader::with(U32, data.as_slice()).unwrap();
+        assert_eq!(reader.genesis(), &consignment.genesis);
+        assert_eq!(reader.next_extension().unwrap(), Some(Extension::strict_dumb()));
+        assert_eq!(reader.next_extension().unwrap(), None);
+        assert_eq!(reader.next_bundle().unwrap(), None);
+
+        let reader = ConsignmentReader::with(U32, data.as_slice()).unwrap();
+        assert_eq!(reader.into_consignment().unwrap(), consignment);
+        // Genesis not committing to the schema is rejected before reading bundles
+        consignment.genesis.schema_id = strict_dumb!();
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+        assert!(matches!(
+            ConsignmentReader::with(U32, data.as_slice()),
+            Err(DecodeError::DataIntegrityError(_))
+        ));
+    }
+
+    #[test]
+    fn indexed_lookups() {
+        let mut consignment = Consignment::strict_dumb();
+        let transition = Transition::strict_dumb();
+        let opid = transition.id();
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        let bundle_id = anchored.bundle.bundle_id();
+        consignment.bundles.push(anchored).unwrap();
+        let extension = Extension::strict_dumb();
+        let ext_id = extension.id();
+        consignment.extensions.push(extension).unwrap();
+
+        let indexed = IndexedConsignment::new(&consignment);
+        let genesis_id = consignment.genesis.id();
+        for id in [genesis_id, opid, ext_id, OpId::from([0xFF; 32])] {
+            assert_eq!(indexed.operation(id), consignment.operation(id));
+            assert_eq!(indexed.has_operation(id), consignment.has_operation(id));
+        }
+        assert_eq!(indexed.transition(opid), consignment.transition(opid));
+        assert_eq!(indexed.extension(ext_id), consignment.extension(ext_id));
+        assert_eq!(indexed.bundle_by_id(bundle_id), consignment.bundle_by_id(bundle_id));
+        assert_eq!(indexed.bundle_by_id(BundleId::from([0xFF; 32])), None);
+        assert_eq!(indexed.op_ids_except(&bset![opid]), consignment.op_ids_except(&bset![opid]));
+    }
+
+    #[test]
+    fn stream_validate() {
+        let mut consignment = Consignment::strict_dumb();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let contract_id = consignment.genesis.contract_id();
+        let anchored = |transition_type: TransitionType, metadata: u8| {
+            let mut transition = Transition::strict_dumb();
+            transition.transition_type = transition_type;
+            transition.contract_id = contract_id;
+            transition.schema_id = consignment.genesis.schema_id;
+            transition.metadata = Confined::try_from(vec![metadata]).unwrap();
+            let mut anchored = AnchoredBundle::strict_dumb();
+            anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+            anchored
+        };
+        let unknown_type = TransitionType::with(0x99);
+        let mut bundles = [
+            anchored(TransitionType::BLANK, 1),
+            anchored(unknown_type, 2),
+            anchored(TransitionType::BLANK, 3),
+        ];
+        // Multi-protocol commitment proof with a position not matching the tree width
+        let invalid_proof = [1u8, 0, 0, 0, 0, 0, 0];
+        let invalid_proof =
+            StrictDecode::strict_decode(&mut StrictReader::with(U32, invalid_proof.as_slice()))
+                .unwrap();
+        match &mut bundles[2].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.mpc_proof = invalid_proof,
+        }
+        for anchored in &bundles {
+            consignment.bundles.push(anchored.clone()).unwrap();
+        }
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+
+        // Malformed bundle doesn't stop validation of the bundles following it
+        let mut reader = ConsignmentReader::with(U32, data.as_slice()).unwrap();
+        let mut count = 0;
+        while let Some((anchored, status)) = reader.next_validated_bundle().unwrap() {
+            let transition = anchored.bundle.known_transitions().next().unwrap();
+            let opid = transition.id();
+            let expected = match transition.metadata[0] {
+                2 => vec![Failure::SchemaUnknownTransitionType(opid, unknown_type)],
+                3 => vec![Failure::NotInAnchor(opid)],
+                _ => vec![],
+            };
+            assert_eq!(status.failures, expected);
+            count += 1;
+        }
+        assert_eq!(count, 3);
+
+        // Bundle which can't be decoded fails the stream once it is read
+        let encode = |anchored: &AnchoredBundle| {
+            let mut data = vec![];
+            anchored.strict_write(U32, &mut data).unwrap();
+            data
+        };
+        let first = &consignment.bundles[0];
+        let (first_data, second) = (encode(first), encode(&consignment.bundles[1]));
+        let offset = data
+            .windows(first_data.len())
+            .position(|window| window == first_data.as_slice())
+            .unwrap() +
+            first_data.len();
+        let truncated = &data[..offset + second.len() / 2];
+        let mut reader = ConsignmentReader::with(U32, truncated).unwrap();
+        let (anchored, _) = reader.next_validated_bundle().unwrap().unwrap();
+        assert_eq!(&anchored, first);
+        assert!(reader.next_validated_bundle().is_err());
+    }
+
+    #[test]
+    fn compress() {
+        let prev = Transition::strict_dumb();
+        let mut next = Transition::strict_dumb();
+        next.inputs = Inputs::from(
+            TinyOrdSet::try_from_iter([Input::with(Opout::new(
+                prev.id(),
+                AssignmentType::with(1),
+                0,
+            ))])
+            .unwrap(),
+        );
+        let mut consignment = Consignment::strict_dumb();
+        for transition in [prev.clone(), next.clone()] {
+            let mut anchored = AnchoredBundle::strict_dumb();
+            anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+            consignment.bundles.push(anchored).unwrap();
+        }
+
+        let frontier = bset![next.id()];
+        assert_eq!(consignment.history_prefix(&frontier), bset![prev.id(), next.id()]);
+
+        let mut checkpoint = HistoryCheckpoint::strict_dumb();
+        checkpoint.history = HistoryCheckpoint::history_root(&bset![prev.id(), next.id()]);
+        checkpoint.frontier = Confined::try_from(frontier).unwrap();
+        let id = checkpoint.checkpoint_id();
+        checkpoint.proof.data = Confined::try_from(vec![1u8; 32]).unwrap();
+        assert_eq!(checkpoint.checkpoint_id(), id);
+
+        let compressed = consignment.compress(checkpoint.clone());
+        assert_eq!(compressed.checkpoint, Some(checkpoint));
+        assert_eq!(compressed.bundles.len(), 1);
+        assert!(compressed.transition(next.id()).is_some());
+        assert!(compressed.transition(prev.id()).is_none());
+    }
+
+    #[test]
+    fn payment_tag() {
+        let tag = PaymentTag::from([7; 32]);
+        let revealed = RevealedPaymentTag::new(tag);
+        let ty = AssignmentType::with(1);
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GraphSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                TxPtr::WitnessTx,
+                vout,
+                1,
+            ))
+        };
+        let untagged = Assign::revealed(seal(0), VoidState::default());
+        let tagged = Assign::revealed(seal(1), VoidState::default()).with_payment_tag(revealed);
+        let concealed = tagged.conceal();
+        assert_ne!(
+            untagged.commitment_id(),
+            untagged.clone().with_payment_tag(revealed).commitment_id()
+        );
+        assert_eq!(tagged.commitment_id(), concealed.commitment_id());
+        assert!(!concealed.matches_payment_tag(tag));
+
+        let mut transition = Transition::strict_dumb();
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([untagged, tagged.clone()]).unwrap(),
+                ),
+            )
+            .unwrap();
+        let mut consignment = Consignment::strict_dumb();
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle =
+            TransitionBundle::try_from_transitions([(0, transition.clone())]).unwrap();
+        consignment.bundles.push(anchored).unwrap();
+
+        let no = transition.assignments[&ty]
+            .as_declarative()
+            .iter()
+            .position(|assign| assign == &tagged)
+            .unwrap() as u16;
+        assert_eq!(consignment.outputs_by_payment_tag(tag), bset![Opout::new(
+            transition.id(),
+            ty,
+            no
+        )]);
+        assert!(consignment
+            .outputs_by_payment_tag(PaymentTag::from([8; 32]))
+            .is_empty());
+    }
+
+    #[test]
+    fn operation_schema() {
+        let consignment = unit_consignment();
+        let schema_id = consignment.genesis.schema_id;
+        let mut transition = blank_transition(&consignment);
+        transition.schema_id = strict_dumb!();
+        let validate = |transition: &Transition| {
+            consignment_with(&consignment, transition.clone())
+                .validate(&NoResolver, ChainNet::BitcoinMainnet)
+        };
+
+        let status = validate(&transition);
+        assert_eq!(status.failures, vec![Failure::OperationSchemaMismatch {
+            opid: transition.id(),
+            expected: schema_id,
+            actual: transition.schema_id,
+        }]);
+
+        transition.schema_id = schema_id;
+        let status = validate(&transition);
+        assert_eq!(status.failures, vec![]);
+    }
+
+    #[test]
+    fn operation_replay() {
+        let consignment = unit_consignment();
+        let contract_id = consignment.genesis.contract_id();
+        // Another contract under the same schema
+        let mut other = consignment.genesis.clone();
+        other.chain_proof = ChainProof::with(BlockHash::from([0x42; 32]));
+        let other_id = other.contract_id();
+        assert_ne!(contract_id, other_id);
+
+        let replayed = blank_transition(&consignment);
+        let mut transition = replayed.clone();
+        transition.contract_id = other_id;
+        assert_ne!(transition.id(), replayed.id());
+
+        let validate = |transition: &Transition| {
+            consignment_with(&consignment, transition.clone())
+                .validate(&NoResolver, ChainNet::BitcoinMainnet)
+        };
+
+        let status = validate(&transition);
+        assert_eq!(status.failures, vec![Failure::OperationContractMismatch {
+            opid: transition.id(),
+            expected: contract_id,
+            actual: other_id,
+        }]);
+
+        let status = validate(&replayed);
+        assert_eq!(status.failures, vec![]);
+    }
+
+    #[test]
+    fn attach_limits() {
+        let ty = AssignmentType::with(1);
+        let mut consignment = unit_consignment();
+        consignment
+            .schema
+            .owned_types
+            .insert(ty, StateSchema::Attachment(MediaType::Any))
+            .unwrap();
+        consignment
+            .schema
+            .genesis
+            .assignments
+            .insert(ty, Occurrences::NoneOrMore)
+            .unwrap();
+        consignment.schema.attach_limits = Some(AttachLimits::with(1, 100));
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([0xA5; 32]),
+                vout,
+                1,
+            ))
+        };
+        let id = AttachId::from([0x22; 32]);
+        let attach = |vout, size| {
+            Assign::revealed(seal(vout), RevealedAttach::with_size(id, MediaType::Any, size))
+        };
+        let validate = |assigns: Vec<Assign<RevealedAttach, GenesisSeal>>| {
+            let mut consignment = consignment.clone();
+            consignment
+                .genesis
+                .assignments
+                .insert(ty, TypedAssigns::Attachment(Confined::try_from(assigns).unwrap()))
+                .unwrap();
+            (consignment.genesis.id(), consignment.validate(&NoResolver, ChainNet::BitcoinMainnet))
+        };
+
+        let (_, status) = validate(vec![attach(0, 100)]);
+        assert_eq!(status.failures, vec![]);
+
+        let (opid, status) = validate(vec![attach(0, 0), attach(1, 101)]);
+        assert_eq!(status.failures, vec![
+            Failure::AttachmentSizeUndeclared(opid, ty),
+            Failure::AttachmentSizeExceeded {
+                opid,
+                state_type: ty,
+                size: 101,
+                max: 100,
+            },
+            Failure::AttachmentCountExceeded {
+                opid,
+                count: 2,
+                max: 1,
+            },
+        ]);
+    }
+
+    #[test]
+    fn validation_policy() {
+        struct Policy {
+            blacklist: BTreeSet<SchemaId>,
+        }
+        impl ValidationPolicy for Policy {
+            fn check_contract(&self, schema: &SubSchema, _: &Genesis) -> Status {
+                let mut status = Status::new();
+                if self.blacklist.contains(&schema.schema_id()) {
+                    status.add_failure(Failure::Custom(s!("blacklisted schema")));
+                }
+                status
+            }
+            fn check_operation(&self, operation: OpRef, depth: u32) -> Status {
+                let mut status = Status::new();
+                status.add_warning(Warning::Custom(format!("{} {depth}", operation.id())));
+                status
+            }
+            fn check_witness(
+                &self,
+                _: OpRef,
+                _: Layer1,
+                txid: Txid,
+                height: Option<u32>,
+            ) -> Status {
+                let mut status = Status::new();
+                if height.is_none() {
+                    status.add_warning(Warning::Custom(format!("{txid} unconfirmed")));
+                }
+                status
+            }
+        }
+
+        let consignment = unit_consignment();
+        let schema_id = consignment.genesis.schema_id;
+        let transition = blank_transition(&consignment);
+        let consignment = consignment_with(&consignment, transition.clone());
+        let txid = match &consignment.bundles[0].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid,
+        };
+
+        let validate = |blacklist| {
+            consignment.validate_with_hooks(
+                &NoResolver,
+                ChainNet::BitcoinMainnet,
+                &ConsensusParams::default(),
+                None,
+                ValidationHooks {
+                    policy: Some(&Policy { blacklist }),
+                    ..default!()
+                },
+            )
+        };
+
+        let status = validate(bset![]);
+        assert_eq!(status.failures, vec![]);
+        assert!(status
+            .warnings
+            .contains(&Warning::Custom(format!("{} 0", transition.id()))));
+        assert!(status
+            .warnings
+            .contains(&Warning::Custom(format!("{txid} unconfirmed"))));
+
+        let status = validate(bset![schema_id]);
+        assert_eq!(status.failures, vec![Failure::Custom(s!("blacklisted schema"))]);
+    }
+
+    #[test]
+    fn validation_progress() {
+        let consignment = unit_consignment();
+        let consignment = consignment_with(&consignment, blank_transition(&consignment));
+
+        let reports = RefCell::new(vec![]);
+        let progress =
+            |validated, total, phase| reports.borrow_mut().push((validated, total, phase));
+        let status = consignment.validate_with_hooks(
+            &NoResolver,
+            ChainNet::BitcoinMainnet,
+            &ConsensusParams::default(),
+            None,
+            ValidationHooks {
+                progress: Some(&progress),
+                ..default!()
+            },
+        );
+        assert_eq!(status.failures, vec![]);
+        assert_eq!(reports.into_inner(), vec![
+            (0, 2, ValidationPhase::Schema),
+            (0, 2, ValidationPhase::Genesis),
+            (1, 2, ValidationPhase::Reserves),
+            (1, 2, ValidationPhase::ChainProof),
+            (1, 2, ValidationPhase::Checkpoint),
+            (1, 2, ValidationPhase::History),
+            (2, 2, ValidationPhase::History),
+            (2, 2, ValidationPhase::Finished),
+        ]);
+    }
+
+    #[test]
+    fn validation_cancel() {
+        let consignment = unit_consignment();
+        let consignment = consignment_with(&consignment, blank_transition(&consignment));
+
+        let cancel = CancelToken::new();
+        let reports = RefCell::new(vec![]);
+        let progress = |validated, _, phase| {
+            if phase == ValidationPhase::History {
+                cancel.cancel();
+            }
+            reports.borrow_mut().push((validated, phase));
+        };
+        let status = consignment.validate_with_hooks(
+            &NoResolver,
+            ChainNet::BitcoinMainnet,
+            &ConsensusParams::default(),
+            None,
+            ValidationHooks {
+                progress: Some(&progress),
+                cancel: Some(&cancel),
+                ..default!()
+            },
+        );
+        assert_eq!(status.failures, vec![Failure::ValidationCancelled]);
+        assert_eq!(reports.into_inner().last(), Some(&(1, ValidationPhase::Finished)));
+
+        let status = consignment.validate_with_hooks(
+            &NoResolver,
+            ChainNet::BitcoinMainnet,
+            &ConsensusParams::default(),
+            None,
+            ValidationHooks {
+                cancel: Some(&cancel),
+                ..default!()
+            },
+        );
+        assert_eq!(status.failures, vec![Failure::ValidationCancelled]);
+    }
+
+    #[test]
+    fn chain_proof() {
+        struct Resolver;
+        impl ResolveTx for Resolver {
+            fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+                Err(TxResolverError::Unknown(txid))
+            }
+            fn resolve_height(&self, _: Layer1, _: Txid) -> Option<u32> { Some(799_999) }
+            fn resolve_block_height(&self, _: Layer1, block_hash: BlockHash) -> Option<u32> {
+                (block_hash == BlockHash::from([0x42; 32])).then_some(800_000)
+            }
+        }
+
+        let consignment = unit_consignment();
+        let mut consignment = consignment_with(&consignment, blank_transition(&consignment));
+        let txid = match &consignment.bundles[0].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid,
+        };
+
+        let failures = |consignment: &Consignment| {
+            consignment
+                .validate(&Resolver, ChainNet::BitcoinMainnet)
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::ChainProofUnknownBlock(_) |
+                            Failure::ChainProofWitnessPrecedes { .. }
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(failures(&consignment), vec![]);
+
+        let unknown = BlockHash::from([0x11; 32]);
+        consignment.genesis.chain_proof = ChainProof::with(unknown);
+        assert_eq!(failures(&consignment), vec![Failure::ChainProofUnknownBlock(unknown)]);
+
+        consignment.genesis.chain_proof = ChainProof::with(BlockHash::from([0x42; 32]));
+        assert_eq!(failures(&consignment), vec![Failure::ChainProofWitnessPrecedes {
+            txid,
+            height: 799_999,
+            block_height: 800_000,
+        }]);
+    }
+
+    #[test]
+    fn expiry() {
+        struct Resolver(Option<u32>);
+        impl ResolveTx for Resolver {
+            fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+                Err(TxResolverError::Unknown(txid))
+            }
+            fn resolve_height(&self, _: Layer1, _: Txid) -> Option<u32> { self.0 }
+        }
+
+        let ty = AssignmentType::with(1);
+        let mut consignment = unit_consignment();
+        consignment
+            .schema
+            .owned_types
+            .insert(ty, StateSchema::Declarative)
+            .unwrap();
+        consignment.schema.expiry.insert(ty, 800_000).unwrap();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let opout = Opout::new(consignment.genesis.id(), ty, 0);
+        let mut transition = blank_transition(&consignment);
+        transition.inputs = Inputs::from(TinyOrdSet::try_from_iter([Input::with(opout)]).unwrap());
+        let opid = transition.id();
+        let consignment = consignment_with(&consignment, transition);
+
+        let failures = |height: Option<u32>| {
+            consignment
+                .validate(&Resolver(height), ChainNet::BitcoinMainnet)
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::OutputExpired { .. } | Failure::OutputExpiryUnknown(..)
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(failures(Some(800_000)), vec![]);
+        assert_eq!(failures(Some(800_001)), vec![Failure::OutputExpired {
+            opid,
+            opout,
+            expiry: 800_000,
+            height: 800_001,
+        }]);
+
+        // Expiry of outputs spent by unmined terminals is reported as a warning
+        let status = consignment.validate(&Resolver(None), ChainNet::BitcoinMainnet);
+        assert!(!status
+            .failures
+            .contains(&Failure::OutputExpiryUnknown(opid, opout)));
+        assert!(status
+            .warnings
+            .contains(&Warning::OutputExpiryUnchecked(opid, opout)));
+        let witness_txid = match &consignment.bundles[0].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid,
+        };
+        assert_eq!(status.unmined_terminals, vec![witness_txid]);
+    }
+
+    #[test]
+    fn prev_outs() {
+        let ty = AssignmentType::with(1);
+        let mut consignment = unit_consignment();
+        consignment
+            .schema
+            .owned_types
+            .insert(ty, StateSchema::Declarative)
+            .unwrap();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let seal = SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            Txid::from([0x11; 32]),
+            0,
+            1,
+        ));
+        consignment
+            .genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([Assign::revealed(seal, VoidState::default())])
+                        .unwrap(),
+                ),
+            )
+            .unwrap();
+        let genesis_id = consignment.genesis.id();
+        let spending = |opout: Opout, metadata: &[u8]| {
+            let mut transition = blank_transition(&consignment);
+            transition.metadata = Confined::try_from(metadata.to_vec()).unwrap();
+            transition.inputs =
+                Inputs::from(TinyOrdSet::try_from_iter([Input::with(opout)]).unwrap());
+            transition
+        };
+        let failures = |transitions: &[&Transition]| {
+            let mut failures = transitions
+                .iter()
+                .fold(consignment.clone(), |consignment, transition| {
+                    consignment_with(&consignment, (*transition).clone())
+                })
+                .validate(&NoResolver, ChainNet::BitcoinMainnet)
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::DoubleSpend { .. } |
+                            Failure::OperationAbsent(_) |
+                            Failure::NoPrevState { .. } |
+                            Failure::NoPrevOut(..)
+                    )
+                })
+                .collect::<Vec<_>>();
+            // Absent operations and outputs are also reported by the schema validation
+            failures.dedup();
+            failures
+        };
+
+        let opout = Opout::new(genesis_id, ty, 0);
+        let valid = spending(opout, &[]);
+        assert_eq!(failures(&[&valid]), vec![]);
+
+        // Transitions spending the same output differ only in their metadata;
+        // which of them is validated first depends on the terminal order
+        let double = spending(opout, &[1]);
+        let double_spends = failures(&[&valid, &double]);
+        assert_eq!(double_spends.len(), 1);
+        let Failure::DoubleSpend {
+            opout: spent,
+            first,
+            second,
+        } = double_spends[0]
+        else {
+            panic!("double spend is not detected")
+        };
+        assert_eq!(spent, opout);
+        assert_eq!(bset![first, second], bset![valid.id(), double.id()]);
+
+        let absent = OpId::from([0x22; 32]);
+        let transition = spending(Opout::new(absent, ty, 0), &[]);
+        assert_eq!(failures(&[&transition]), vec![Failure::OperationAbsent(absent)]);
+
+        let state_type = AssignmentType::with(2);
+        let transition = spending(Opout::new(genesis_id, state_type, 0), &[]);
+        assert_eq!(failures(&[&transition]), vec![Failure::NoPrevState {
+            opid: transition.id(),
+            prev_id: genesis_id,
+            state_type,
+        }]);
+
+        let prevout = Opout::new(genesis_id, ty, 1);
+        let transition = spending(prevout, &[]);
+        assert_eq!(failures(&[&transition]), vec![Failure::NoPrevOut(transition.id(), prevout)]);
+    }
+
+    #[test]
+    fn maturity() {
+        struct Resolver(Txid, Option<u32>);
+        impl ResolveTx for Resolver {
+            fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+                Err(TxResolverError::Unknown(txid))
+            }
+            fn resolve_height(&self, _: Layer1, txid: Txid) -> Option<u32> {
+                if txid == self.0 {
+                    self.1
+                } else {
+                    Some(800_000)
+                }
+            }
+        }
+
+        let ty = AssignmentType::with(1);
+        let mut consignment = unit_consignment();
+        consignment
+            .schema
+            .owned_types
+            .insert(ty, StateSchema::Declarative)
+            .unwrap();
+        consignment.schema.genesis.maturity = 100;
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let seal_txid = Txid::from([0x11; 32]);
+        let seal = SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            seal_txid,
+            0,
+            1,
+        ));
+        consignment
+            .genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([Assign::revealed(seal, VoidState::default())])
+                        .unwrap(),
+                ),
+            )
+            .unwrap();
+        let opout = Opout::new(consignment.genesis.id(), ty, 0);
+        let mut transition = blank_transition(&consignment);
+        transition.inputs = Inputs::from(TinyOrdSet::try_from_iter([Input::with(opout)]).unwrap());
+        let opid = transition.id();
+        let consignment = consignment_with(&consignment, transition);
+
+        let failures = |consignment: &Consignment, seal_height: Option<u32>| {
+            consignment
+                .validate(&Resolver(seal_txid, seal_height), ChainNet::BitcoinMainnet)
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::OutputImmature { .. } | Failure::OutputMaturityUnknown(..)
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(failures(&consignment, Some(799_900)), vec![]);
+        assert_eq!(failures(&consignment, Some(799_950)), vec![Failure::OutputImmature {
+            opid,
+            opout,
+            maturity: 100,
+            depth: 50,
+        }]);
+        assert_eq!(failures(&consignment, None), vec![Failure::OutputMaturityUnknown(opid, opout)]);
+
+        // Maturity of outputs spent by unmined terminals is reported as a warning
+        let witness_txid = match &consignment.bundles[0].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid,
+        };
+        let status = consignment.validate(&Resolver(witness_txid, None), ChainNet::BitcoinMainnet);
+        assert!(!status
+            .failures
+            .contains(&Failure::OutputMaturityUnknown(opid, opout)));
+        assert!(status
+            .warnings
+            .contains(&Warning::OutputMaturityUnchecked(opid, opout)));
+        assert_eq!(status.unmined_terminals, vec![witness_txid]);
+
+        // Maturity of outputs with concealed seals can't be checked
+        let mut concealed = consignment.clone();
+        concealed
+            .genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([Assign::ConfidentialSeal {
+                        seal: seal.conceal(),
+                        state: VoidState::default(),
+                        payment_tag: None,
+                    }])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        assert_eq!(concealed.genesis.id(), opout.op);
+        assert_eq!(failures(&concealed, Some(799_900)), vec![Failure::OutputMaturityUnknown(
+            opid, opout
+        )]);
+    }
+
+    #[test]
+    fn zk_proof() {
+        struct Verifier;
+        impl ZkVerifier for Verifier {
+            fn verify(
+                &self,
+                _: VerifyingKeyId,
+                _: &Transition,
+                proof: &[u8],
+            ) -> Result<(), String> {
+                if proof == b"valid" {
+                    Ok(())
+                } else {
+                    Err(s!("proof doesn't verify"))
+                }
+            }
+        }
+
+        let vk = VerifyingKeyId::from([0x11; 32]);
+        let ty = TransitionType::with(1);
+        let mut consignment = unit_consignment();
+        let unit_id = Ty::<SemId>::UNIT.id(None);
+        let assignment_type = AssignmentType::with(1);
+        consignment
+            .schema
+            .owned_types
+            .insert(assignment_type, StateSchema::Declarative)
+            .unwrap();
+        consignment
+            .schema
+            .transitions
+            .insert(ty, TransitionSchema {
+                metadata: unit_id,
+                inputs: tiny_bmap! { assignment_type => Occurrences::NoneOrMore },
+                proof: Some(vk),
+                ..default!()
+            })
+            .unwrap();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let with_proof = |transition_type: TransitionType, proof: Option<ZkProof>| {
+            let mut transition = blank_transition(&consignment);
+            transition.transition_type = transition_type;
+            transition.proof = proof;
+            let opid = transition.id();
+            (consignment_with(&consignment, transition), opid)
+        };
+        let proof = |vk: VerifyingKeyId, data: &[u8]| {
+            Some(ZkProof {
+                vk,
+                data: Confined::try_from(data.to_vec()).unwrap(),
+            })
+        };
+        let failures = |consignment: &Consignment, verifier: Option<&dyn ZkVerifier>| {
+            consignment
+                .validate_with_hooks(
+                    &NoResolver,
+                    ChainNet::BitcoinMainnet,
+                    &ConsensusParams::default(),
+                    verifier,
+                    ValidationHooks::default(),
+                )
+                .failures
+                .into_iter()
+                .filter(|failure| {
+                    matches!(
+                        failure,
+                        Failure::ZkProofMissing(..) |
+                            Failure::ZkProofUnexpected(_) |
+                            Failure::ZkProofKeyMismatch { .. } |
+                            Failure::ZkVerifierAbsent(..) |
+                            Failure::ZkProofInvalid(..)
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let (valid, valid_opid) = with_proof(ty, proof(vk, b"valid"));
+        assert_eq!(failures(&valid, Some(&Verifier)), vec![]);
+        let (blank, _) = with_proof(TransitionType::BLANK, None);
+        assert_eq!(failures(&blank, None), vec![]);
+
+        let (missing, opid) = with_proof(ty, None);
+        assert_eq!(failures(&missing, Some(&Verifier)), vec![Failure::ZkProofMissing(opid, vk)]);
+
+        let (unexpected, opid) = with_proof(TransitionType::BLANK, proof(vk, b"valid"));
+        assert_eq!(failures(&unexpected, Some(&Verifier)), vec![Failure::ZkProofUnexpected(opid)]);
+
+        let other_vk = VerifyingKeyId::from([0x22; 32]);
+        let (mismatch, opid) = with_proof(ty, proof(other_vk, b"valid"));
+        assert_eq!(failures(&mismatch, Some(&Verifier)), vec![Failure::ZkProofKeyMismatch {
+            opid,
+            expected: vk,
+            actual: other_vk,
+        }]);
+
+        assert_eq!(failures(&valid, None), vec![Failure::ZkVerifierAbsent(valid_opid, vk)]);
+
+        let (invalid, opid) = with_proof(ty, proof(vk, b"invalid"));
+        assert_eq!(failures(&invalid, Some(&Verifier)), vec![Failure::ZkProofInvalid(
+            opid,
+            s!("proof doesn't verify")
+        )]);
+    }
+
+    #[test]
+    fn string_checks() {
+        struct Resolver(Option<u32>);
+        impl ResolveTx for Resolver {
+            fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+                Err(TxResolverError::Unknown(txid))
+            }
+            fn resolve_height(&self, _: Layer1, _: Txid) -> Option<u32> { self.0 }
+        }
+
+        let ty = TransitionType::with(1);
+        let global_type = GlobalStateType::with(1);
+        let assignment_type = AssignmentType::with(1);
+        let mut consignment = Consignment::strict_dumb();
+        let unit = Ty::<SemId>::UNIT;
+        let unit_id = unit.id(None);
+        let unicode = Ty::<SemId>::UNICODE;
+        let string = Ty::<SemId>::list(unicode.id(None), Sizing::new(0, 255));
+        let string_id = string.id(None);
+        let mut types = MediumOrdMap::new();
+        types.insert(unit_id, unit).unwrap();
+        types.insert(unicode.id(None), unicode).unwrap();
+        types.insert(string_id, string).unwrap();
+        consignment.schema.type_system = TypeSystem::from(types);
+        consignment
+            .schema
+            .owned_types
+            .insert(assignment_type, StateSchema::Declarative)
+            .unwrap();
+        consignment
+            .schema
+            .global_types
+            .insert(global_type, GlobalStateSchema::once(string_id))
+            .unwrap();
+        consignment
+            .schema
+            .transitions
+            .insert(ty, TransitionSchema {
+                metadata: unit_id,
+                globals: tiny_bmap! { global_type => Occurrences::Once },
+                inputs: tiny_bmap! { assignment_type => Occurrences::NoneOrMore },
+                ..default!()
+            })
+            .unwrap();
+        consignment.genesis.schema_id = consignment.schema.schema_id();
+        let mut transition = blank_transition(&consignment);
+        transition.transition_type = ty;
+        let name = TinyString::try_from(s!("Cafe\u{0301}")).unwrap();
+        transition
+            .globals
+            .add_state(global_type, RevealedData::from_typed(&name).unwrap())
+            .unwrap();
+        let opid = transition.id();
+        let consignment = consignment_with(&consignment, transition);
+
+        let params =
+            ConsensusParams::new().with_activation(Layer1::Bitcoin, 800_000, ConsensusVersion::V2);
+        let failures = |height: Option<u32>| {
+            consignment
+                .validate_with_hooks(
+                    &Resolver(height),
+                    ChainNet::BitcoinMainnet,
+                    &params,
+                    None,
+                    ValidationHooks::default(),
+                )
+                .failures
+                .into_iter()
+                .filter(|failure| matches!(failure, Failure::SchemaInvalidString(..)))
+                .collect::<Vec<_>>()
+        };
+        // Strings are not checked before the activation of the consensus version
+        // introducing the checks
+        assert_eq!(failures(Some(799_999)), vec![]);
+        assert_eq!(failures(None), vec![]);
+        assert_eq!(failures(Some(800_000)), vec![Failure::SchemaInvalidString(
+            opid,
+            string_id,
+            StringError::NotNormalized
+        )]);
+    }
+
+    #[test]
+    fn embedded_witness() {
+        let tx = Tx::strict_dumb();
+        let txid = tx.txid();
+        // Witness "mined" by the sender in a block with the minimal difficulty
+        let mut header = BlockHeader {
+            version: 1,
+            prev_block_hash: BlockHash::from([0xA5; 32]),
+            merkle_root: Bytes32::from_byte_array(txid.to_byte_array()),
+            time: 1231006505,
+            bits: 0x207fffff,
+            nonce: 0,
+        };
+        while check_pow(&header).is_err() {
+            header.nonce += 1;
+        }
+        let witness = EmbeddedWitness {
+            layer1: Layer1::Bitcoin,
+            tx,
+            spv: Some(SpvProof {
+                height: 800_000,
+                header,
+                position: 0,
+                merkle_path: empty!(),
+            }),
+        };
+        assert_eq!(witness.spv.as_ref().unwrap().verify(txid), Ok(()));
+
+        let consignment = unit_consignment();
+        let mut consignment = consignment_with(&consignment, blank_transition(&consignment));
+        let mut bundles = consignment.bundles.into_vec();
+        match &mut bundles[0].anchor {
+            Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid = txid,
+        }
+        consignment.bundles = CanonicalVec::try_from_iter(bundles).unwrap();
+        consignment.witnesses.push(witness.clone()).unwrap();
+
+        // Embedded witness doesn't replace the resolver
+        let status = consignment.validate(&NoResolver, ChainNet::BitcoinMainnet);
+        assert_eq!(status.unmined_terminals, vec![txid]);
+
+        // Self-mined block is not a part of the trusted header chain
+        let mut resolver = SpvResolver::with(
+            Layer1::Bitcoin,
+            799_999,
+            BlockHash::from([0x11; 32]),
+            [],
+            0x1d00ffff,
+        )
+        .unwrap();
+        assert_eq!(resolver.add_embedded(&consignment.witnesses), Err(SpvError::UnknownBlock));
+        let status = consignment.validate(&resolver, ChainNet::BitcoinMainnet);
+        assert_eq!(status.unmined_terminals, vec![txid]);
+    }
+}
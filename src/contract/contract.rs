@@ -22,23 +22,34 @@
 
 //! Extraction of contract state.
 
+use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::mem;
 use std::num::ParseIntError;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, RangeBounds};
 use std::str::FromStr;
+use std::sync::Arc;
 
-use amplify::confinement::{LargeOrdMap, LargeOrdSet, SmallVec, TinyOrdMap};
-use amplify::hex;
-use bp::Outpoint;
-use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
+use amplify::confinement::{Confined, LargeOrdMap, LargeOrdSet, SmallVec, TinyOrdMap, TinyOrdSet};
+use baid58::Baid58ParseError;
+use bp::{Outpoint, Txid};
+use commit_verify::merkle::MerkleNode;
+use commit_verify::{CommitmentId, Conceal};
+use strict_encoding::{
+    DecodeError, ReadStruct, StrictDecode, StrictDeserialize, StrictDumb, StrictEncode,
+    StrictSerialize, TypedRead,
+};
 
+use super::merkle::{self, StateLeaf, StateProof};
 use crate::{
-    Assign, AssignmentType, Assignments, AssignmentsRef, ContractId, ExposedSeal, ExposedState,
-    Extension, Genesis, GlobalStateType, OpId, Operation, RevealedAttach, RevealedData,
-    RevealedValue, SchemaId, SealDefinition, SubSchema, Transition, TypedAssigns, VoidState,
-    WitnessAnchor, WitnessId, LIB_NAME_RGB,
+    Assign, AssignmentType, Assignments, AssignmentsRef, ChainNet, ContractId, Disclosure,
+    DisclosureError, ExposedSeal, ExposedState, Extension, Genesis, GlobalRetention, GlobalState,
+    GlobalStateType, HistoryStore, OpId, OpRef, OpType, Operation, RevealedAttach, RevealedData,
+    RevealedValue, SchemaId, SealDefinition, SealFilter, SecretSeal, StateData, SubSchema,
+    Transition, TypedAssigns, VoidState, WitnessAnchor, WitnessId, WitnessOrd, LIB_NAME_RGB,
 };
 
 /// Seal outpoint is **not a seal definition**. It is an accessory structure
@@ -91,7 +102,7 @@ impl Opout {
 #[display(inner)]
 pub enum OpoutParseError {
     #[from]
-    InvalidNodeId(hex::Error),
+    InvalidNodeId(Baid58ParseError),
 
     InvalidType(ParseIntError),
 
@@ -166,6 +177,12 @@ impl<State: ExposedState> Ord for OutputAssignment<State> {
     }
 }
 
+// Output assignments are ordered and compared by their opout only, so they can
+// be looked up in ordered sets by the opout value.
+impl<State: ExposedState> Borrow<Opout> for OutputAssignment<State> {
+    fn borrow(&self) -> &Opout { &self.opout }
+}
+
 impl<State: ExposedState> OutputAssignment<State> {
     /// # Panics
     ///
@@ -211,6 +228,16 @@ impl<State: ExposedState> OutputAssignment<State> {
             witness: None,
         }
     }
+
+    /// Converts the assignment into a state-type-agnostic form.
+    pub fn to_state_data(&self) -> OutputAssignment<StateData> {
+        OutputAssignment {
+            opout: self.opout,
+            output: self.output,
+            state: self.state.state_data(),
+            witness: self.witness,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
@@ -223,6 +250,7 @@ impl<State: ExposedState> OutputAssignment<State> {
 )]
 pub struct GlobalOrd {
     pub witness_anchor: Option<WitnessAnchor>,
+    pub opid: OpId,
     pub idx: u16,
 }
 
@@ -236,28 +264,53 @@ impl Ord for GlobalOrd {
             return Ordering::Equal;
         }
         match (self.witness_anchor, &other.witness_anchor) {
-            (None, None) => self.idx.cmp(&other.idx),
             (None, Some(_)) => Ordering::Less,
             (Some(_), None) => Ordering::Greater,
-            (Some(ord1), Some(ord2)) if ord1 == *ord2 => self.idx.cmp(&other.idx),
-            (Some(ord1), Some(ord2)) => ord1.cmp(ord2),
+            // Operations mined at the same position in the blockchain (or sharing the
+            // same witness, or having none) are ordered by their ids
+            (Some(anchor1), Some(anchor2)) => anchor1
+                .witness_ord
+                .cmp(&anchor2.witness_ord)
+                .then(self.opid.cmp(&other.opid))
+                .then(self.idx.cmp(&other.idx))
+                .then(anchor1.witness_id.cmp(&anchor2.witness_id)),
+            (None, None) => self.opid.cmp(&other.opid).then(self.idx.cmp(&other.idx)),
         }
     }
 }
 
 impl GlobalOrd {
-    pub fn with_anchor(ord_txid: WitnessAnchor, idx: u16) -> Self {
+    pub fn with_anchor(ord_txid: WitnessAnchor, opid: OpId, idx: u16) -> Self {
         GlobalOrd {
             witness_anchor: Some(ord_txid),
+            opid,
             idx,
         }
     }
-    pub fn genesis(idx: u16) -> Self {
+    pub fn genesis(opid: OpId, idx: u16) -> Self {
         GlobalOrd {
             witness_anchor: None,
+            opid,
             idx,
         }
     }
+
+    /// Returns block height at which the global state was defined. Genesis
+    /// state is reported at height zero; state defined by a witness transaction
+    /// which is not mined yet has no height.
+    pub fn height(&self) -> Option<u32> {
+        match self.witness_anchor {
+            None => Some(0),
+            Some(WitnessAnchor {
+                witness_ord: WitnessOrd::OnChain(pos),
+                ..
+            }) => Some(pos.height().get()),
+            Some(WitnessAnchor {
+                witness_ord: WitnessOrd::OffChain,
+                ..
+            }) => None,
+        }
+    }
 }
 
 pub type RightsOutput = OutputAssignment<VoidState>;
@@ -265,19 +318,293 @@ pub type FungibleOutput = OutputAssignment<RevealedValue>;
 pub type DataOutput = OutputAssignment<RevealedData>;
 pub type AttachOutput = OutputAssignment<RevealedAttach>;
 
+/// Operation stored in the contract history, which may be shared between
+/// multiple histories.
+#[derive(Clone, PartialEq, Eq, Debug, From)]
+pub enum SharedOp {
+    #[from]
+    Genesis(Arc<Genesis>),
+    #[from]
+    Transition(Arc<Transition>),
+    #[from]
+    Extension(Arc<Extension>),
+}
+
+impl SharedOp {
+    pub fn as_op_ref(&self) -> OpRef<'_> {
+        match self {
+            SharedOp::Genesis(op) => OpRef::Genesis(op),
+            SharedOp::Transition(op) => OpRef::Transition(op),
+            SharedOp::Extension(op) => OpRef::Extension(op),
+        }
+    }
+}
+
+/// Content-addressed in-memory store of contract operations.
+///
+/// Each operation is kept only once, independently of how many times it was
+/// added to the history, and cloning of the store shares the operations
+/// instead of copying them. The store is not serialized together with the
+/// contract history and is not taken into account when histories are
+/// compared.
+///
+/// Operations which are not kept in memory are loaded from the backend
+/// [`HistoryStore`], if one is attached.
+#[derive(Clone, Debug, Default)]
+pub struct OpStore {
+    ops: BTreeMap<OpId, SharedOp>,
+    backend: Option<Arc<dyn HistoryStore>>,
+}
+
+impl PartialEq for OpStore {
+    fn eq(&self, _: &Self) -> bool { true }
+}
+
+impl Eq for OpStore {}
+
+impl OpStore {
+    /// Returns number of operations kept in memory.
+    #[inline]
+    pub fn len(&self) -> usize { self.ops.len() }
+
+    /// Checks whether there are no operations kept in memory.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.ops.is_empty() }
+
+    /// Checks whether the operation is known either in memory or to the
+    /// backend store.
+    pub fn contains(&self, opid: OpId) -> bool {
+        self.ops.contains_key(&opid) ||
+            self.backend
+                .as_ref()
+                .map(|backend| backend.contains(opid))
+                .unwrap_or_default()
+    }
+
+    /// Returns operation kept in memory. Use [`OpStore::get_shared`] to also
+    /// load operations from the backend store.
+    pub fn get(&self, opid: OpId) -> Option<OpRef<'_>> {
+        self.ops.get(&opid).map(SharedOp::as_op_ref)
+    }
+
+    /// Returns shared operation, which may be added to other histories without
+    /// copying, loading it from the backend store if it is not kept in memory.
+    pub fn get_shared(&self, opid: OpId) -> Option<SharedOp> {
+        self.ops
+            .get(&opid)
+            .cloned()
+            .or_else(|| self.backend.as_ref()?.load(opid))
+    }
+
+    /// Iterates over operations kept in memory.
+    pub fn iter(&self) -> impl Iterator<Item = (OpId, OpRef<'_>)> {
+        self.ops.iter().map(|(opid, op)| (*opid, op.as_op_ref()))
+    }
+
+    /// Iterates over shared operations kept in memory, for instance to write
+    /// them to a backend store.
+    pub fn shared(&self) -> impl Iterator<Item = &SharedOp> { self.ops.values() }
+
+    /// Returns backend store, if attached.
+    pub fn backend(&self) -> Option<&Arc<dyn HistoryStore>> { self.backend.as_ref() }
+
+    /// Adds operation, constructing it only if it is not known to the store.
+    fn insert_with(&mut self, opid: OpId, op: impl FnOnce() -> SharedOp) {
+        if self.contains(opid) {
+            return;
+        }
+        self.ops.insert(opid, op());
+    }
+}
+
+impl HistoryStore for OpStore {
+    fn contains(&self, opid: OpId) -> bool { OpStore::contains(self, opid) }
+
+    fn load(&self, opid: OpId) -> Option<SharedOp> { self.get_shared(opid) }
+
+    fn len(&self) -> usize { self.ops.len() + self.backend.as_ref().map_or(0, |b| b.len()) }
+}
+
+/// Selection of contract state by the confirmation status of witness
+/// transactions.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default, Display)]
+#[display(lowercase)]
+pub enum StateView {
+    /// State defined by genesis and operations with mined witness
+    /// transactions only. This is the view which must be used for
+    /// consensus-critical decisions.
+    #[default]
+    Confirmed,
+
+    /// State including operations with unconfirmed witness transactions,
+    /// e.g. for displaying pending balances.
+    Pending,
+}
+
+/// Errors updating witness transactions of the contract history.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum WitnessUpdateError {
+    /// witness transaction {0} is not known to the contract history.
+    UnknownWitness(WitnessId),
+
+    /// operation {0} anchored to witness transaction {1} is not present in the
+    /// history operation store, since it was added before the history was
+    /// deserialized.
+    OperationUnavailable(OpId, WitnessId),
+
+    /// witness transaction {0} already has the maximal number of replacement
+    /// candidates.
+    TooManyCandidates(WitnessId),
+}
+
+/// Errors adding operations to the contract history.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum HistoryUpdateError {
+    /// output {0} is already spent by the maximal number of conflicting
+    /// operations.
+    TooManySpenders(Opout),
+}
+
+/// Errors extracting operation ancestry from the contract history.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum AncestryError {
+    /// operation {0} is not present in the history operation store.
+    UnknownOperation(OpId),
+
+    /// output {0} is not defined by the operation.
+    UnknownOutput(Opout),
+}
+
+/// Minimal set of operations proving contract state back to genesis, as
+/// returned by [`ContractHistory::ancestry`]. Used to assemble transfer
+/// consignments.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct OperationPath {
+    pub transitions: BTreeSet<OpId>,
+    pub extensions: BTreeSet<OpId>,
+    /// Witness transactions anchoring bundles with the transitions and
+    /// extensions from the path.
+    pub witnesses: BTreeSet<WitnessId>,
+}
+
+impl OperationPath {
+    /// Checks whether the path contains an operation other than genesis.
+    pub fn contains(&self, opid: OpId) -> bool {
+        self.transitions.contains(&opid) || self.extensions.contains(&opid)
+    }
+
+    /// Iterates over all transitions and extensions from the path.
+    pub fn operations(&self) -> impl Iterator<Item = OpId> + '_ {
+        self.transitions.iter().chain(&self.extensions).copied()
+    }
+}
+
+/// Operations spending the same output, each with its witness anchor.
+#[derive(Wrapper, WrapperMut, Clone, PartialEq, Eq, Debug, Default, From)]
+#[wrapper(Deref)]
+#[wrapper_mut(DerefMut)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Spenders(TinyOrdMap<OpId, Option<WitnessAnchor>>);
+
+/// Fungible amount reported by [`SupplyAudit`], together with the operations
+/// supporting it.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SupplyFigure {
+    /// Amount in the smallest indivisible units.
+    pub amount: u128,
+    /// Operations contributing to the amount.
+    pub opids: BTreeSet<OpId>,
+}
+
+impl SupplyFigure {
+    fn add(&mut self, opid: OpId, amount: u128) {
+        self.amount = self.amount.saturating_add(amount);
+        self.opids.insert(opid);
+    }
+}
+
+/// Contract outputs affected by a set of witness transactions, reported by
+/// [`ContractState::affected_outputs`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AffectedOutputs {
+    /// Outputs assigned by the operations anchored to the transactions.
+    pub assigned: BTreeSet<Opout>,
+    /// Outputs spent by the operations anchored to the transactions.
+    pub spent: BTreeSet<Opout>,
+}
+
+impl AffectedOutputs {
+    /// Detects whether the transactions didn't affect any contract outputs.
+    pub fn is_empty(&self) -> bool { self.assigned.is_empty() && self.spent.is_empty() }
+}
+
+/// Supply of a fungible owned state type, as it follows from the best branch
+/// of the contract history.
+///
+/// The sum of the state assigned by an operation is compared to the sum of
+/// the state it spends: the excess of the assigned state is reported as
+/// issued, the excess of the spent state as burned, and the spent state
+/// re-assigned to new outputs as replaced. Thus, if all the state is
+/// revealed, the circulating supply (the sum of unspent outputs) equals to
+/// the issued supply less the burned one.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SupplyAudit {
+    pub issued: SupplyFigure,
+    pub burned: SupplyFigure,
+    pub replaced: SupplyFigure,
+    pub circulating: SupplyFigure,
+}
+
 /// Contract history accumulates raw data from the contract history, extracted
 /// from a series of consignments over the time. It does consensus ordering of
 /// the state data, but it doesn't interpret or validates the state against the
 /// schema.
 ///
 /// To access the valid contract state use [`Contract`] APIs.
+///
+/// Lookup indexes of the outputs are not serialized: they are rebuilt when
+/// the history is decoded, failing the decoding if the serialized data are
+/// not consistent.
 #[derive(Getters, Clone, Eq, PartialEq, Debug)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
-    serde(crate = "serde_crate", rename_all = "camelCase")
+    serde(
+        crate = "serde_crate",
+        rename_all = "camelCase",
+        try_from = "serde_history::ContractHistoryData"
+    )
 )]
 pub struct ContractHistory {
     #[getter(as_copy)]
@@ -286,12 +613,75 @@ pub struct ContractHistory {
     root_schema_id: Option<SchemaId>,
     #[getter(as_copy)]
     contract_id: ContractId,
+    #[getter(as_copy)]
+    chain_net: ChainNet,
     #[getter(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "serde_map_seq::nested"))]
     global: TinyOrdMap<GlobalStateType, LargeOrdMap<GlobalOrd, RevealedData>>,
     rights: LargeOrdSet<RightsOutput>,
     fungibles: LargeOrdSet<FungibleOutput>,
     data: LargeOrdSet<DataOutput>,
     attach: LargeOrdSet<AttachOutput>,
+    /// Concealed seals of the known outputs.
+    #[getter(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "serde_map_seq"))]
+    seals: LargeOrdMap<Opout, SecretSeal>,
+    #[getter(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "serde_map_seq"))]
+    spent_index: LargeOrdMap<Opout, Spenders>,
+    /// Alternative (RBF replacement) transactions for unconfirmed witnesses.
+    #[getter(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "serde_map_seq"))]
+    rbf_index: LargeOrdMap<WitnessId, TinyOrdSet<WitnessId>>,
+    #[getter(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "serde_map_seq"))]
+    witness_ords: LargeOrdMap<WitnessId, WitnessOrd>,
+    #[getter(skip)]
+    #[strict_type(skip, dumb = empty!())]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    type_index: TinyOrdMap<AssignmentType, LargeOrdSet<Opout>>,
+    #[getter(skip)]
+    #[strict_type(skip, dumb = empty!())]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seal_index: LargeOrdMap<SecretSeal, LargeOrdSet<Opout>>,
+    #[getter(skip)]
+    #[strict_type(skip, dumb = empty!())]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    witness_index: LargeOrdMap<WitnessId, LargeOrdSet<Opout>>,
+    /// Operations added to the history, which are not serialized.
+    #[strict_type(skip, dumb = OpStore::default())]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    operations: OpStore,
+}
+
+impl StrictDecode for ContractHistory {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_struct(|r| {
+            let mut history = ContractHistory {
+                schema_id: r.read_field(fname!("schemaId"))?,
+                root_schema_id: r.read_field(fname!("rootSchemaId"))?,
+                contract_id: r.read_field(fname!("contractId"))?,
+                chain_net: r.read_field(fname!("chainNet"))?,
+                global: r.read_field(fname!("global"))?,
+                rights: r.read_field(fname!("rights"))?,
+                fungibles: r.read_field(fname!("fungibles"))?,
+                data: r.read_field(fname!("data"))?,
+                attach: r.read_field(fname!("attach"))?,
+                seals: r.read_field(fname!("seals"))?,
+                spent_index: r.read_field(fname!("spentIndex"))?,
+                rbf_index: r.read_field(fname!("rbfIndex"))?,
+                witness_ords: r.read_field(fname!("witnessOrds"))?,
+                type_index: empty!(),
+                seal_index: empty!(),
+                witness_index: empty!(),
+                operations: OpStore::default(),
+            };
+            history
+                .rebuild_indexes()
+                .map_err(DecodeError::DataIntegrityError)?;
+            Ok(history)
+        })
+    }
 }
 
 impl ContractHistory {
@@ -309,11 +699,20 @@ impl ContractHistory {
             schema_id,
             root_schema_id,
             contract_id,
+            chain_net: genesis.chain_net,
             global: empty!(),
             rights: empty!(),
             fungibles: empty!(),
             data: empty!(),
             attach: empty!(),
+            seals: empty!(),
+            spent_index: empty!(),
+            rbf_index: empty!(),
+            witness_ords: empty!(),
+            type_index: empty!(),
+            seal_index: empty!(),
+            witness_index: empty!(),
+            operations: OpStore::default(),
         };
         state.update_genesis(genesis);
         state
@@ -323,27 +722,121 @@ impl ContractHistory {
     ///
     /// If genesis violates RGB consensus rules and wasn't checked against the
     /// schema before adding to the history.
-    pub fn update_genesis(&mut self, genesis: &Genesis) { self.add_operation(genesis, None); }
+    pub fn update_genesis(&mut self, genesis: &Genesis) {
+        let opid = genesis.id();
+        self.operations
+            .insert_with(opid, || Arc::new(genesis.clone()).into());
+        self.add_operation(opid, genesis, None)
+            .expect("genesis has no inputs");
+    }
 
+    /// # Errors
+    ///
+    /// If one of the transition inputs is already spent by the maximal number
+    /// of conflicting operations; the history is left unchanged.
+    ///
     /// # Panics
     ///
     /// If state transition violates RGB consensus rules and wasn't checked
     /// against the schema before adding to the history.
-    pub fn add_transition(&mut self, transition: &Transition, witness_anchor: WitnessAnchor) {
-        self.add_operation(transition, Some(witness_anchor));
+    pub fn add_transition(
+        &mut self,
+        transition: &Transition,
+        witness_anchor: WitnessAnchor,
+    ) -> Result<(), HistoryUpdateError> {
+        let opid = transition.id();
+        self.add_operation(opid, transition, Some(witness_anchor))?;
+        self.operations
+            .insert_with(opid, || Arc::new(transition.clone()).into());
+        Ok(())
     }
 
+    /// Global state defined by the extension is ordered not by the provided
+    /// `witness_anchor`, but by the anchor of the first operation spending
+    /// the extension outputs; until such operation is known the state is
+    /// ordered next to the genesis state.
+    ///
+    /// # Errors
+    ///
+    /// If one of the extension inputs is already spent by the maximal number
+    /// of conflicting operations; the history is left unchanged.
+    ///
     /// # Panics
     ///
     /// If state extension violates RGB consensus rules and wasn't checked
     /// against the schema before adding to the history.
-    pub fn add_extension(&mut self, extension: &Extension, witness_anchor: WitnessAnchor) {
-        self.add_operation(extension, Some(witness_anchor));
+    pub fn add_extension(
+        &mut self,
+        extension: &Extension,
+        witness_anchor: WitnessAnchor,
+    ) -> Result<(), HistoryUpdateError> {
+        let opid = extension.id();
+        self.add_operation(opid, extension, Some(witness_anchor))?;
+        self.operations
+            .insert_with(opid, || Arc::new(extension.clone()).into());
+        Ok(())
     }
 
-    fn add_operation(&mut self, op: &impl Operation, witness_anchor: Option<WitnessAnchor>) {
-        let opid = op.id();
+    /// Adds operation shared with another history, without copying it.
+    ///
+    /// # Errors
+    ///
+    /// If one of the operation inputs is already spent by the maximal number
+    /// of conflicting operations; the history is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// If the operation violates RGB consensus rules and wasn't checked against
+    /// the schema before adding to the history.
+    pub fn add_shared(
+        &mut self,
+        op: SharedOp,
+        witness_anchor: Option<WitnessAnchor>,
+    ) -> Result<(), HistoryUpdateError> {
+        let opid = op.as_op_ref().id();
+        self.add_operation(opid, &op.as_op_ref(), witness_anchor)?;
+        self.operations.insert_with(opid, || op);
+        Ok(())
+    }
+
+    /// Returns operation added to the history, if known, loading it from the
+    /// backend store when it is not kept in memory.
+    ///
+    /// Operations are not serialized together with the history, thus the
+    /// method returns `None` for operations added before the history was
+    /// deserialized, unless they are provided by the backend store (see
+    /// [`ContractHistory::attach_store`]).
+    pub fn operation(&self, opid: OpId) -> Option<SharedOp> { self.operations.get_shared(opid) }
+
+    /// Attaches backend store providing operations not kept in memory, and
+    /// drops from memory all operations which are present in the store.
+    pub fn attach_store(&mut self, store: Arc<dyn HistoryStore>) {
+        self.operations.ops.retain(|opid, _| !store.contains(*opid));
+        self.operations.backend = Some(store);
+    }
+
+    fn add_operation(
+        &mut self,
+        opid: OpId,
+        op: &impl Operation,
+        witness_anchor: Option<WitnessAnchor>,
+    ) -> Result<(), HistoryUpdateError> {
+        // Checked before any modification, so the history is never left with
+        // a partially added operation
+        for input in &op.inputs() {
+            if let Some(spenders) = self.spent_index.get(&input.prev_out) {
+                if spenders.len() >= u8::MAX as usize && !spenders.contains_key(&opid) {
+                    return Err(HistoryUpdateError::TooManySpenders(input.prev_out));
+                }
+            }
+        }
 
+        // State extensions have no witness of their own, thus their global state
+        // is ordered by the first anchored operation spending their outputs
+        let global_anchor = match op.op_type() {
+            OpType::StateExtension => self.descendant_anchor(opid),
+            _ => witness_anchor,
+        };
         for (ty, state) in op.globals() {
             let map = match self.global.get_mut(ty) {
                 Some(map) => map,
@@ -359,7 +852,8 @@ impl ContractHistory {
             for (idx, s) in state.iter().enumerate() {
                 let idx = idx as u16;
                 let glob_idx = GlobalOrd {
-                    witness_anchor,
+                    witness_anchor: global_anchor,
+                    opid,
                     idx,
                 };
                 map.insert(glob_idx, s.clone())
@@ -400,6 +894,43 @@ impl ContractHistory {
         }
          */
 
+        // Conflicting spends are kept as competing branches of the history
+        for input in &op.inputs() {
+            let spenders = match self.spent_index.get_mut(&input.prev_out) {
+                Some(spenders) => spenders,
+                None => {
+                    self.spent_index
+                        .insert(input.prev_out, empty!())
+                        .expect("contract spent index exceeded 2^32 items, which is unrealistic");
+                    self.spent_index
+                        .get_mut(&input.prev_out)
+                        .expect("just inserted")
+                }
+            };
+            match spenders.get_mut(&opid) {
+                Some(anchor) => *anchor = witness_anchor,
+                None => spenders
+                    .insert(opid, witness_anchor)
+                    .map(|_| ())
+                    .expect("number of spenders is checked above"),
+            }
+            if let Some(anchor) = witness_anchor {
+                if matches!(self.operation(input.prev_out.op), Some(SharedOp::Extension(_))) {
+                    self.anchor_extension(input.prev_out.op, anchor);
+                }
+            }
+        }
+
+        if let Some(WitnessAnchor {
+            witness_ord,
+            witness_id,
+        }) = witness_anchor
+        {
+            self.witness_ords
+                .insert(witness_id, witness_ord)
+                .expect("contract witness index exceeded 2^32 items, which is unrealistic");
+        }
+
         let witness_id = witness_anchor.map(|wa| wa.witness_id);
         match op.assignments() {
             AssignmentsRef::Genesis(assignments) => {
@@ -409,6 +940,45 @@ impl ContractHistory {
                 self.add_assignments(witness_id, opid, assignments)
             }
         }
+        Ok(())
+    }
+
+    /// Returns the first anchor among the operations spending outputs of the
+    /// operation `opid`.
+    fn descendant_anchor(&self, opid: OpId) -> Option<WitnessAnchor> {
+        self.spent_index
+            .iter()
+            .filter(|(opout, _)| opout.op == opid)
+            .flat_map(|(_, spenders)| spenders.values())
+            .filter_map(|anchor| *anchor)
+            .min()
+    }
+
+    /// Re-anchors global state of the state extension `opid` to the anchor of
+    /// its descendant, if the anchor precedes the one currently used.
+    fn anchor_extension(&mut self, opid: OpId, anchor: WitnessAnchor) {
+        for (_, map) in self.global.keyed_values_mut() {
+            let ords = map
+                .keys()
+                .filter(|ord| ord.opid == opid)
+                .filter(|ord| ord.witness_anchor.map_or(true, |current| anchor < current))
+                .copied()
+                .collect::<Vec<_>>();
+            for ord in ords {
+                let data = map
+                    .remove(&ord)
+                    .expect("collection allows zero elements")
+                    .expect("key is present");
+                map.insert(
+                    GlobalOrd {
+                        witness_anchor: Some(anchor),
+                        ..ord
+                    },
+                    data,
+                )
+                .expect("same size as before");
+            }
+        }
     }
 
     fn add_assignments<Seal: ExposedSeal>(
@@ -423,7 +993,8 @@ impl ContractHistory {
             opid: OpId,
             ty: AssignmentType,
             witness_id: Option<WitnessId>,
-        ) {
+        ) -> Vec<(Opout, SecretSeal)> {
+            let mut indexed = vec![];
             for (no, seal, state) in assignments
                 .iter()
                 .enumerate()
@@ -435,14 +1006,16 @@ impl ContractHistory {
                     }
                     None => OutputAssignment::with_no_witness(seal, state, opid, ty, no as u16),
                 };
+                indexed.push((assigned_state.opout, seal.conceal()));
                 contract_state
                     .push(assigned_state)
                     .expect("contract state exceeded 2^32 items, which is unrealistic");
             }
+            indexed
         }
 
         for (ty, assignments) in assignments.iter() {
-            match assignments {
+            let indexed = match assignments {
                 TypedAssigns::Declarative(assignments) => {
                     process(&mut self.rights, assignments, opid, *ty, witness_id)
                 }
@@ -455,49 +1028,2067 @@ impl ContractHistory {
                 TypedAssigns::Attachment(assignments) => {
                     process(&mut self.attach, assignments, opid, *ty, witness_id)
                 }
+            };
+            for (opout, seal) in indexed {
+                self.index_output(opout, seal, witness_id);
             }
         }
     }
-}
 
-/// Contract state provides API to read consensus-valid data from the
-/// [`ContractHistory`].
-#[derive(Clone, Eq, PartialEq, Debug)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
-#[strict_type(lib = LIB_NAME_RGB)]
-#[cfg_attr(
-    feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(crate = "serde_crate", rename_all = "camelCase")
-)]
-pub struct ContractState {
-    pub schema: SubSchema,
-    pub history: ContractHistory,
-}
+    /// Returns global state of a given type defined by operations mined within
+    /// the provided range of block heights, in consensus order. Genesis state
+    /// is considered to be defined at height zero; state from unmined
+    /// operations is never returned.
+    pub fn global_by_height(
+        &self,
+        ty: GlobalStateType,
+        heights: impl RangeBounds<u32>,
+    ) -> impl Iterator<Item = (GlobalOrd, &RevealedData)> {
+        self.global
+            .get(&ty)
+            .into_iter()
+            .flat_map(|state| state.iter())
+            .filter(move |(ord, _)| {
+                ord.height()
+                    .map(|h| heights.contains(&h))
+                    .unwrap_or_default()
+            })
+            .map(|(ord, data)| (*ord, data))
+    }
 
-impl Deref for ContractState {
-    type Target = ContractHistory;
-    fn deref(&self) -> &Self::Target { &self.history }
-}
+    /// Prunes confirmed global state of a given type, keeping only `count`
+    /// most recent confirmed items according to the consensus ordering. State
+    /// defined by unconfirmed operations is never pruned and doesn't count
+    /// towards `count`, since it may be replaced or removed later.
+    pub fn retain_global(&mut self, ty: GlobalStateType, count: u16) {
+        let Some(map) = self.global.get_mut(&ty) else {
+            return;
+        };
+        let confirmed = map
+            .keys()
+            .filter(|ord| {
+                ord.witness_anchor
+                    .map_or(true, |anchor| matches!(anchor.witness_ord, WitnessOrd::OnChain(_)))
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        let excess = confirmed.len().saturating_sub(count as usize);
+        for ord in &confirmed[..excess] {
+            map.remove(ord).expect("collection allows zero elements");
+        }
+    }
 
-impl DerefMut for ContractState {
-    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.history }
-}
+    /// Registers `candidate` transaction as an alternative to the unconfirmed
+    /// witness transaction `witness_id`, which may replace it with RBF. The
+    /// state keeps referencing the original witness until one of the candidates
+    /// is confirmed with [`ContractHistory::confirm_witness`].
+    pub fn add_witness_candidate(
+        &mut self,
+        witness_id: WitnessId,
+        candidate: WitnessId,
+    ) -> Result<(), WitnessUpdateError> {
+        if self.anchored_operations(witness_id).is_empty() {
+            return Err(WitnessUpdateError::UnknownWitness(witness_id));
+        }
+        if candidate == witness_id {
+            return Ok(());
+        }
+        match self.rbf_index.get_mut(&witness_id) {
+            Some(candidates) if candidates.contains(&candidate) => Ok(()),
+            Some(candidates) => candidates
+                .push(candidate)
+                .map_err(|_| WitnessUpdateError::TooManyCandidates(witness_id)),
+            None => {
+                self.rbf_index
+                    .insert(witness_id, Confined::try_from_iter([candidate]).expect("single item"))
+                    .expect("contract witness index exceeded 2^32 items, which is unrealistic");
+                Ok(())
+            }
+        }
+    }
 
-impl ContractState {
-    /// # Safety
+    /// Returns alternative transactions which may replace the unconfirmed
+    /// witness transaction.
+    pub fn witness_candidates(
+        &self,
+        witness_id: WitnessId,
+    ) -> impl Iterator<Item = WitnessId> + '_ {
+        self.rbf_index
+            .get(&witness_id)
+            .into_iter()
+            .flat_map(|candidates| candidates.iter().copied())
+    }
+
+    /// Updates witness of the operations anchored to `witness_id` or to the
+    /// transaction it is a candidate replacement for. All state defined by
+    /// these operations is re-anchored to the provided witness, and the rest
+    /// of the candidate transactions are discarded.
     ///
-    /// If the specified state type is not part of the schema.
-    pub unsafe fn global_unchecked(&self, state_type: GlobalStateType) -> SmallVec<&RevealedData> {
-        let schema = self
-            .schema
-            .global_types
-            .get(&state_type)
-            .expect("global type is not in the schema");
-        let Some(state) = self.global.get(&state_type) else {
-            return SmallVec::new();
+    /// # Errors
+    ///
+    /// If the witness is not known to the history, or some of the operations
+    /// anchored to it are not present in the history operation store.
+    pub fn confirm_witness(
+        &mut self,
+        witness_id: WitnessId,
+        witness_ord: WitnessOrd,
+    ) -> Result<(), WitnessUpdateError> {
+        let current = if self.anchored_operations(witness_id).is_empty() {
+            self.rbf_index
+                .iter()
+                .find(|(_, candidates)| candidates.contains(&witness_id))
+                .map(|(current, _)| *current)
+                .ok_or(WitnessUpdateError::UnknownWitness(witness_id))?
+        } else {
+            witness_id
         };
-        let iter = state.values().take(schema.max_items as usize);
-        SmallVec::try_from_iter(iter).expect("same size as previous confined collection")
+        let opids = self.anchored_operations(current);
+        let ops = opids
+            .iter()
+            .map(|opid| {
+                self.operations
+                    .get_shared(*opid)
+                    .ok_or(WitnessUpdateError::OperationUnavailable(*opid, current))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.remove_anchored(current, &opids);
+        self.rbf_index
+            .remove(&current)
+            .expect("collection allows zero elements");
+
+        let witness_anchor = WitnessAnchor {
+            witness_ord,
+            witness_id,
+        };
+        for op in ops {
+            let op = op.as_op_ref();
+            self.add_operation(op.id(), &op, Some(witness_anchor))
+                .expect("operations were just removed from the spent index");
+        }
+        Ok(())
+    }
+
+    fn anchored_operations(&self, witness_id: WitnessId) -> BTreeSet<OpId> {
+        let spenders = self
+            .spent_index
+            .values()
+            .flat_map(|spenders| spenders.iter())
+            .filter(|(_, anchor)| anchor.map(|a| a.witness_id) == Some(witness_id))
+            .map(|(opid, _)| *opid);
+        self.witness_index
+            .get(&witness_id)
+            .into_iter()
+            .flat_map(|opouts| opouts.iter().map(|opout| opout.op))
+            .chain(spenders)
+            .collect()
+    }
+
+    /// Removes all state defined by the operations anchored to the witness.
+    fn remove_anchored(&mut self, witness_id: WitnessId, opids: &BTreeSet<OpId>) {
+        fn retain_set<T: Ord>(set: &mut LargeOrdSet<T>, f: impl FnMut(&T) -> bool) {
+            let mut inner = mem::take(set).into_inner();
+            inner.retain(f);
+            *set = Confined::try_from(inner).expect("subset of the confined collection");
+        }
+        fn retain_map<K: Ord + Hash, V, const MAX: usize>(
+            map: &mut Confined<BTreeMap<K, V>, 0, MAX>,
+            mut f: impl FnMut(&K, &mut V) -> bool,
+        ) {
+            let mut inner = mem::take(map).into_inner();
+            inner.retain(|k, v| f(k, v));
+            *map = Confined::try_from(inner).expect("subset of the confined collection");
+        }
+
+        // State extensions anchored by their descendants are kept and re-anchored
+        // once the index of spenders gets updated
+        let mut extensions = BTreeSet::new();
+        retain_map(&mut self.global, |_, state| {
+            retain_map(state, |ord, _| {
+                if ord.witness_anchor.map(|a| a.witness_id) != Some(witness_id) {
+                    return true;
+                }
+                if !opids.contains(&ord.opid) {
+                    extensions.insert(ord.opid);
+                }
+                false
+            });
+            true
+        });
+        let keep = |opout: &Opout| !opids.contains(&opout.op);
+        retain_set(&mut self.rights, |o| keep(&o.opout));
+        retain_set(&mut self.fungibles, |o| keep(&o.opout));
+        retain_set(&mut self.data, |o| keep(&o.opout));
+        retain_set(&mut self.attach, |o| keep(&o.opout));
+        retain_map(&mut self.seals, |opout, _| keep(opout));
+        retain_map(&mut self.type_index, |_, set| {
+            retain_set(set, keep);
+            !set.is_empty()
+        });
+        retain_map(&mut self.seal_index, |_, set| {
+            retain_set(set, keep);
+            !set.is_empty()
+        });
+        self.witness_index
+            .remove(&witness_id)
+            .expect("collection allows zero elements");
+        self.witness_ords
+            .remove(&witness_id)
+            .expect("collection allows zero elements");
+        retain_map(&mut self.spent_index, |_, spenders| {
+            retain_map(&mut spenders.0, |opid, _| !opids.contains(opid));
+            !spenders.is_empty()
+        });
+        for opid in extensions {
+            if let Some(op) = self.operation(opid) {
+                let op = op.as_op_ref();
+                for (ty, state) in op.globals() {
+                    let map = self.global.get_mut(ty).expect("type was present");
+                    for (idx, s) in state.iter().enumerate() {
+                        map.insert(
+                            GlobalOrd {
+                                witness_anchor: None,
+                                opid,
+                                idx: idx as u16,
+                            },
+                            s.clone(),
+                        )
+                        .expect("same size as before");
+                    }
+                }
+                if let Some(anchor) = self.descendant_anchor(opid) {
+                    self.anchor_extension(opid, anchor);
+                }
+            }
+        }
+    }
+
+    fn index_output(&mut self, opout: Opout, seal: SecretSeal, witness_id: Option<WitnessId>) {
+        self.seals
+            .insert(opout, seal)
+            .expect("contract state exceeded 2^32 items, which is unrealistic");
+        self.index_lookup(opout, seal, witness_id);
+    }
+
+    fn index_lookup(&mut self, opout: Opout, seal: SecretSeal, witness_id: Option<WitnessId>) {
+        fn index<K: Ord + Hash, const MAX: usize>(
+            index: &mut Confined<BTreeMap<K, LargeOrdSet<Opout>>, 0, MAX>,
+            key: K,
+            opout: Opout,
+        ) {
+            match index.get_mut(&key) {
+                Some(set) => set.push(opout),
+                None => index
+                    .insert(key, Confined::try_from_iter([opout]).expect("single item"))
+                    .map(|_| ()),
+            }
+            .expect("contract state index exceeded its size limit, which is unrealistic");
+        }
+
+        index(&mut self.type_index, opout.ty, opout);
+        index(&mut self.seal_index, seal, opout);
+        if let Some(witness_id) = witness_id {
+            index(&mut self.witness_index, witness_id, opout);
+        }
+    }
+
+    /// Rebuilds lookup indexes of the decoded history, checking that the
+    /// outputs, their seals and witnesses are consistent.
+    fn rebuild_indexes(&mut self) -> Result<(), String> {
+        let outputs = self
+            .rights
+            .iter()
+            .map(|o| (o.opout, o.witness))
+            .chain(self.fungibles.iter().map(|o| (o.opout, o.witness)))
+            .chain(self.data.iter().map(|o| (o.opout, o.witness)))
+            .chain(self.attach.iter().map(|o| (o.opout, o.witness)))
+            .collect::<Vec<_>>();
+        if outputs.len() != self.seals.len() {
+            return Err(s!("number of output seals doesn't match the number of outputs"));
+        }
+        let known_witness = |witness_id: &WitnessId| -> Result<(), String> {
+            if self.witness_ords.contains_key(witness_id) {
+                Ok(())
+            } else {
+                Err(format!("witness {witness_id} is not known to the history"))
+            }
+        };
+        for (_, witness_id) in &outputs {
+            witness_id.as_ref().map(known_witness).transpose()?;
+        }
+        for spenders in self.spent_index.values() {
+            for witness_anchor in spenders.values().flatten() {
+                known_witness(&witness_anchor.witness_id)?;
+            }
+        }
+        for witness_id in self.rbf_index.keys() {
+            known_witness(witness_id)?;
+        }
+
+        self.type_index = empty!();
+        self.seal_index = empty!();
+        self.witness_index = empty!();
+        for (opout, witness_id) in outputs {
+            let seal = *self
+                .seals
+                .get(&opout)
+                .ok_or_else(|| format!("output {opout} has no seal"))?;
+            self.index_lookup(opout, seal, witness_id);
+        }
+        Ok(())
+    }
+
+    fn output_state(&self, opout: &Opout) -> Option<OutputAssignment<StateData>> {
+        self.rights
+            .get(opout)
+            .map(OutputAssignment::to_state_data)
+            .or_else(|| {
+                self.fungibles
+                    .get(opout)
+                    .map(OutputAssignment::to_state_data)
+            })
+            .or_else(|| self.data.get(opout).map(OutputAssignment::to_state_data))
+            .or_else(|| self.attach.get(opout).map(OutputAssignment::to_state_data))
+    }
+
+    /// Leaves of the contract state commitment: global state in the consensus
+    /// order followed by all owned outputs ordered by their opouts.
+    fn state_leaves(&self) -> Vec<StateLeaf> {
+        let global = self.global.iter().flat_map(|(ty, state)| {
+            state.iter().map(|(ord, data)| StateLeaf::Global {
+                ty: *ty,
+                ord: *ord,
+                data: data.clone(),
+            })
+        });
+        let mut owned = self
+            .rights
+            .iter()
+            .map(OutputAssignment::to_state_data)
+            .chain(self.fungibles.iter().map(OutputAssignment::to_state_data))
+            .chain(self.data.iter().map(OutputAssignment::to_state_data))
+            .chain(self.attach.iter().map(OutputAssignment::to_state_data))
+            .collect::<Vec<_>>();
+        owned.sort_by_key(|output| output.opout);
+        global
+            .chain(owned.into_iter().map(StateLeaf::Owned))
+            .collect()
+    }
+
+    /// Returns ordering information of the witness transaction, if it anchors
+    /// some operations known to the history.
+    pub fn witness_ord(&self, witness_id: WitnessId) -> Option<WitnessOrd> {
+        self.witness_ords.get(&witness_id).copied()
+    }
+
+    /// Checks whether state defined by an operation with the given witness is
+    /// a part of the state view. State without witness, i.e. defined by
+    /// genesis, is always visible.
+    pub fn is_visible(&self, witness_id: Option<WitnessId>, view: StateView) -> bool {
+        match (witness_id, view) {
+            (None, _) | (Some(_), StateView::Pending) => true,
+            (Some(witness_id), StateView::Confirmed) => {
+                matches!(self.witness_ord(witness_id), Some(WitnessOrd::OnChain(_)))
+            }
+        }
+    }
+
+    fn outputs_of_type<'a, State: ExposedState>(
+        &'a self,
+        outputs: &'a LargeOrdSet<OutputAssignment<State>>,
+        ty: AssignmentType,
+        view: StateView,
+    ) -> impl Iterator<Item = &'a OutputAssignment<State>> {
+        self.type_index
+            .get(&ty)
+            .into_iter()
+            .flat_map(|set| set.iter())
+            .filter_map(|opout| outputs.get(opout))
+            .filter(move |output| self.is_visible(output.witness, view))
+    }
+
+    /// Returns all known rights (declarative state) assigned under a given
+    /// assignment type.
+    pub fn rights_outputs(
+        &self,
+        ty: AssignmentType,
+        view: StateView,
+    ) -> impl Iterator<Item = &RightsOutput> {
+        self.outputs_of_type(&self.rights, ty, view)
+    }
+
+    /// Returns all known fungible state assigned under a given assignment type.
+    pub fn fungible_outputs(
+        &self,
+        ty: AssignmentType,
+        view: StateView,
+    ) -> impl Iterator<Item = &FungibleOutput> {
+        self.outputs_of_type(&self.fungibles, ty, view)
+    }
+
+    /// Returns all known structured state assigned under a given assignment
+    /// type.
+    pub fn data_outputs(
+        &self,
+        ty: AssignmentType,
+        view: StateView,
+    ) -> impl Iterator<Item = &DataOutput> {
+        self.outputs_of_type(&self.data, ty, view)
+    }
+
+    /// Returns all known attachments assigned under a given assignment type.
+    pub fn attach_outputs(
+        &self,
+        ty: AssignmentType,
+        view: StateView,
+    ) -> impl Iterator<Item = &AttachOutput> {
+        self.outputs_of_type(&self.attach, ty, view)
+    }
+
+    /// Returns all known state assigned to a seal with the given concealed
+    /// representation, independently of the state type.
+    pub fn outputs_by_seal(
+        &self,
+        seal: SecretSeal,
+        view: StateView,
+    ) -> impl Iterator<Item = OutputAssignment<StateData>> + '_ {
+        self.seal_index
+            .get(&seal)
+            .into_iter()
+            .flat_map(|set| set.iter())
+            .filter_map(|opout| self.output_state(opout))
+            .filter(move |output| self.is_visible(output.witness, view))
+    }
+
+    /// Constructs compact probabilistic filter of all seals holding unspent
+    /// state visible in the given state view, which allows light clients to
+    /// detect whether their seals are involved into the contract.
+    pub fn seal_filter(&self, view: StateView) -> SealFilter {
+        let seals = self
+            .seal_index
+            .iter()
+            .filter(|(seal, _)| {
+                self.outputs_by_seal(**seal, view)
+                    .any(|output| !self.is_spent(output.opout))
+            })
+            .map(|(seal, _)| *seal);
+        SealFilter::with(self.contract_id, seals)
+    }
+
+    /// Checks whether the output is spent by some operation known to the
+    /// history.
+    #[inline]
+    pub fn is_spent(&self, opout: Opout) -> bool { self.spent_index.contains_key(&opout) }
+
+    /// Returns id of the operation spending the output, if known. If several
+    /// conflicting operations spend the same output, the one from the best
+    /// branch (see [`ContractHistory::spenders`]) is returned.
+    #[inline]
+    pub fn spent_by(&self, opout: Opout) -> Option<OpId> {
+        self.spenders(opout).first().map(|(opid, _)| *opid)
+    }
+
+    /// Returns all operations spending the output together with their witness
+    /// anchors, ordered from the best branch to the worst one. Branches with
+    /// witnesses mined earlier are preferred over the ones mined later, which
+    /// are preferred over unconfirmed witnesses.
+    pub fn spenders(&self, opout: Opout) -> Vec<(OpId, Option<WitnessAnchor>)> {
+        let mut spenders = self
+            .spent_index
+            .get(&opout)
+            .into_iter()
+            .flat_map(|spenders| spenders.iter())
+            .map(|(opid, witness_anchor)| (*opid, *witness_anchor))
+            .collect::<Vec<_>>();
+        spenders.sort_by_key(|(opid, witness_anchor)| {
+            (witness_anchor.is_none(), *witness_anchor, *opid)
+        });
+        spenders
+    }
+
+    /// Returns inputs of the operation which are already spent by other
+    /// operations known to the history, together with the spending operation
+    /// ids. Used to detect conflicting branches before merging the operation
+    /// into the history.
+    pub fn conflicting_spends(&self, op: &impl Operation) -> Vec<(Opout, OpId)> {
+        let opid = op.id();
+        op.inputs()
+            .iter()
+            .filter_map(|input| {
+                self.spent_by(input.prev_out)
+                    .filter(|spender| *spender != opid)
+                    .map(|spender| (input.prev_out, spender))
+            })
+            .collect()
+    }
+
+    /// Returns all operations, which are required to prove the operation back
+    /// to the contract genesis, including the operation itself.
+    ///
+    /// # Errors
+    ///
+    /// If the operation or some of its ancestors are not present in the history
+    /// operation store.
+    pub fn ancestry(&self, opid: OpId) -> Result<OperationPath, AncestryError> {
+        let mut path = OperationPath::default();
+        let mut queue = vec![opid];
+        while let Some(opid) = queue.pop() {
+            if path.contains(opid) {
+                continue;
+            }
+            let op = self
+                .operation(opid)
+                .ok_or(AncestryError::UnknownOperation(opid))?;
+            let op = op.as_op_ref();
+            match op {
+                OpRef::Genesis(_) => continue,
+                OpRef::Transition(_) => path.transitions.insert(opid),
+                OpRef::Extension(_) => path.extensions.insert(opid),
+            };
+            path.witnesses.extend(self.operation_witness(opid));
+            queue.extend(op.prev_ops());
+        }
+        Ok(path)
+    }
+
+    /// Returns all operations, which are required to prove the output back to
+    /// the contract genesis.
+    ///
+    /// # Errors
+    ///
+    /// If the output is not defined by a known operation, or some of the
+    /// operation ancestors are not present in the history operation store.
+    pub fn output_ancestry(&self, opout: Opout) -> Result<OperationPath, AncestryError> {
+        let op = self
+            .operation(opout.op)
+            .ok_or(AncestryError::UnknownOperation(opout.op))?;
+        let defined = op
+            .as_op_ref()
+            .assignments_by_type(opout.ty)
+            .map(|assigns| opout.no < assigns.len_u16())
+            .unwrap_or_default();
+        if !defined {
+            return Err(AncestryError::UnknownOutput(opout));
+        }
+        self.ancestry(opout.op)
+    }
+
+    /// Adds outputs revealed by the disclosure to the contract state. The
+    /// history is not updated if any of the revealed outputs fail to verify.
+    ///
+    /// # Errors
+    ///
+    /// If the disclosure is made for a different contract, reveals outputs of
+    /// unknown transitions, or doesn't match the concealed data of the
+    /// transitions.
+    pub fn add_disclosure(&mut self, disclosure: &Disclosure) -> Result<(), DisclosureError> {
+        if disclosure.contract_id != self.contract_id {
+            return Err(DisclosureError::ContractMismatch(disclosure.contract_id));
+        }
+        let mut revealed = Vec::with_capacity(disclosure.reveals.len());
+        for reveal in &disclosure.reveals {
+            let opid = reveal.opout.op;
+            let Some(SharedOp::Transition(transition)) = self.operation(opid) else {
+                return Err(DisclosureError::UnknownTransition(opid));
+            };
+            reveal.verify(&transition)?;
+            let witness_id = self
+                .operation_witness(opid)
+                .ok_or(DisclosureError::UnknownWitness(opid))?;
+            let output = reveal
+                .seal
+                .output_or_witness(witness_id)
+                .map_err(|_| DisclosureError::Layer1Mismatch(reveal.opout))?;
+            revealed.push((reveal, output, witness_id));
+        }
+
+        fn push<State: ExposedState>(
+            contract_state: &mut LargeOrdSet<OutputAssignment<State>>,
+            opout: Opout,
+            output: Output,
+            state: State,
+            witness_id: WitnessId,
+        ) {
+            contract_state
+                .push(OutputAssignment {
+                    opout,
+                    output,
+                    state,
+                    witness: Some(witness_id),
+                })
+                .expect("contract state exceeded 2^32 items, which is unrealistic");
+        }
+
+        for (reveal, output, witness_id) in revealed {
+            let opout = reveal.opout;
+            match &reveal.state {
+                StateData::Void => {
+                    push(&mut self.rights, opout, output, VoidState::default(), witness_id)
+                }
+                StateData::Fungible(value) => {
+                    push(&mut self.fungibles, opout, output, *value, witness_id)
+                }
+                StateData::Structured(data) => {
+                    push(&mut self.data, opout, output, data.clone(), witness_id)
+                }
+                StateData::Attachment(attach) => {
+                    push(&mut self.attach, opout, output, attach.clone(), witness_id)
+                }
+            }
+            self.index_output(opout, reveal.seal.conceal(), Some(witness_id));
+        }
+        Ok(())
+    }
+
+    /// Detects witness transaction anchoring the operation.
+    fn operation_witness(&self, opid: OpId) -> Option<WitnessId> {
+        self.witness_index
+            .iter()
+            .find(|(_, opouts)| opouts.iter().any(|opout| opout.op == opid))
+            .map(|(witness_id, _)| *witness_id)
+            .or_else(|| {
+                self.spent_index
+                    .values()
+                    .find_map(|spenders| spenders.get(&opid).copied().flatten())
+                    .map(|anchor| anchor.witness_id)
+            })
+    }
+
+    /// Returns all known state assigned by the operations anchored to the given
+    /// witness transaction, independently of the state type.
+    pub fn outputs_by_witness(
+        &self,
+        witness_id: WitnessId,
+    ) -> impl Iterator<Item = OutputAssignment<StateData>> + '_ {
+        self.witness_index
+            .get(&witness_id)
+            .into_iter()
+            .flat_map(|set| set.iter())
+            .filter_map(|opout| self.output_state(opout))
+    }
+}
+
+/// Minimal view of the contract state containing only the outputs controlled
+/// by a wallet and the global state, used for wallet backups and watch-only
+/// exports. Constructed with [`ContractState::filter_owned`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct OwnedStateExport {
+    pub schema_id: SchemaId,
+    pub contract_id: ContractId,
+    pub chain_net: ChainNet,
+    pub global: GlobalState,
+    pub rights: LargeOrdSet<RightsOutput>,
+    pub fungibles: LargeOrdSet<FungibleOutput>,
+    pub data: LargeOrdSet<DataOutput>,
+    pub attach: LargeOrdSet<AttachOutput>,
+}
+
+impl StrictSerialize for OwnedStateExport {}
+impl StrictDeserialize for OwnedStateExport {}
+
+/// Contract state provides API to read consensus-valid data from the
+/// [`ContractHistory`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ContractState {
+    pub schema: SubSchema,
+    pub history: ContractHistory,
+}
+
+impl StrictSerialize for ContractState {}
+impl StrictDeserialize for ContractState {}
+
+impl Deref for ContractState {
+    type Target = ContractHistory;
+    fn deref(&self) -> &Self::Target { &self.history }
+}
+
+impl DerefMut for ContractState {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.history }
+}
+
+impl ContractState {
+    /// Adds state transition to the contract history, enforcing global state
+    /// retention policy defined by the schema.
+    ///
+    /// # Errors
+    ///
+    /// See [`ContractHistory::add_transition`].
+    ///
+    /// # Panics
+    ///
+    /// If state transition violates RGB consensus rules and wasn't checked
+    /// against the schema before adding to the history.
+    pub fn add_transition(
+        &mut self,
+        transition: &Transition,
+        witness_anchor: WitnessAnchor,
+    ) -> Result<(), HistoryUpdateError> {
+        self.history.add_transition(transition, witness_anchor)?;
+        self.enforce_retention();
+        Ok(())
+    }
+
+    /// Adds state extension to the contract history, enforcing global state
+    /// retention policy defined by the schema.
+    ///
+    /// # Errors
+    ///
+    /// See [`ContractHistory::add_extension`].
+    ///
+    /// # Panics
+    ///
+    /// If state extension violates RGB consensus rules and wasn't checked
+    /// against the schema before adding to the history.
+    pub fn add_extension(
+        &mut self,
+        extension: &Extension,
+        witness_anchor: WitnessAnchor,
+    ) -> Result<(), HistoryUpdateError> {
+        self.history.add_extension(extension, witness_anchor)?;
+        self.enforce_retention();
+        Ok(())
+    }
+
+    /// Confirms witness of the contract operations (see
+    /// [`ContractHistory::confirm_witness`]), enforcing global state retention
+    /// policy defined by the schema on the newly confirmed state.
+    pub fn confirm_witness(
+        &mut self,
+        witness_id: WitnessId,
+        witness_ord: WitnessOrd,
+    ) -> Result<(), WitnessUpdateError> {
+        self.history.confirm_witness(witness_id, witness_ord)?;
+        self.enforce_retention();
+        Ok(())
+    }
+
+    /// Computes Merkle commitment to all global state and owned outputs known
+    /// to the contract state.
+    pub fn commitment(&self) -> MerkleNode {
+        let leaves = self
+            .state_leaves()
+            .iter()
+            .map(StateLeaf::commitment_id)
+            .collect::<Vec<_>>();
+        merkle::merklize(&leaves)
+    }
+
+    /// Constructs proof that an output with the given `opout` is included into
+    /// the state [`ContractState::commitment`]. Returns `None` if the output is
+    /// not known to the contract state.
+    pub fn prove_output(&self, opout: Opout) -> Option<StateProof> {
+        let leaves = self.state_leaves();
+        let pos = leaves
+            .iter()
+            .position(|leaf| matches!(leaf, StateLeaf::Owned(output) if output.opout == opout))?;
+        let ids = leaves
+            .iter()
+            .map(StateLeaf::commitment_id)
+            .collect::<Vec<_>>();
+        Some(merkle::prove(leaves[pos].clone(), &ids, pos as u32))
+    }
+
+    /// Returns outputs spent by more than one operation, i.e. the outputs on
+    /// which the contract history forks into competing branches.
+    pub fn conflicting_outputs(&self) -> impl Iterator<Item = Opout> + '_ {
+        self.spent_index
+            .iter()
+            .filter(|(_, spenders)| spenders.len() > 1)
+            .map(|(opout, _)| *opout)
+    }
+
+    /// Returns operations which are not a part of the best history branch:
+    /// operations losing a conflict over some output to an operation with a
+    /// better witness, together with all their descendants.
+    pub fn suspended_operations(&self) -> BTreeSet<OpId> {
+        let mut suspended = self
+            .conflicting_outputs()
+            .flat_map(|opout| self.spenders(opout).into_iter().skip(1))
+            .map(|(opid, _)| opid)
+            .collect::<BTreeSet<_>>();
+        loop {
+            let descendants = self
+                .spent_index
+                .iter()
+                .filter(|(opout, _)| suspended.contains(&opout.op))
+                .flat_map(|(_, spenders)| spenders.keys())
+                .filter(|opid| !suspended.contains(*opid))
+                .copied()
+                .collect::<Vec<_>>();
+            if descendants.is_empty() {
+                return suspended;
+            }
+            suspended.extend(descendants);
+        }
+    }
+
+    /// Returns outputs assigned by the suspended operations (see
+    /// [`ContractState::suspended_operations`]), which must not be considered
+    /// as owned unless the branch they belong to becomes the best one.
+    pub fn suspended_outputs(&self) -> Vec<OutputAssignment<StateData>> {
+        let suspended = self.suspended_operations();
+        self.rights
+            .iter()
+            .map(OutputAssignment::to_state_data)
+            .chain(self.fungibles.iter().map(OutputAssignment::to_state_data))
+            .chain(self.data.iter().map(OutputAssignment::to_state_data))
+            .chain(self.attach.iter().map(OutputAssignment::to_state_data))
+            .filter(|output| suspended.contains(&output.opout.op))
+            .collect()
+    }
+
+    /// Audits supply of each of the fungible owned state types known to the
+    /// contract state (see [`SupplyAudit`]). Operations which are not a part of
+    /// the best history branch are ignored; state which is not revealed to
+    /// the contract state can't be accounted for.
+    pub fn supply_audit(&self) -> BTreeMap<AssignmentType, SupplyAudit> {
+        let suspended = self.suspended_operations();
+        let values = self
+            .fungibles
+            .iter()
+            .filter(|output| !suspended.contains(&output.opout.op))
+            .map(|output| (output.opout, output.state.value.atoms()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut flows = BTreeMap::<(AssignmentType, OpId), (u128, u128)>::new();
+        let mut audit = BTreeMap::<AssignmentType, SupplyAudit>::new();
+        for (opout, value) in &values {
+            let flow = flows.entry((opout.ty, opout.op)).or_default();
+            flow.1 = flow.1.saturating_add(*value);
+            match self.spent_by(*opout) {
+                Some(spender) if !suspended.contains(&spender) => {
+                    let flow = flows.entry((opout.ty, spender)).or_default();
+                    flow.0 = flow.0.saturating_add(*value);
+                }
+                _ => audit
+                    .entry(opout.ty)
+                    .or_default()
+                    .circulating
+                    .add(opout.op, *value),
+            }
+        }
+
+        for ((ty, opid), (spent, assigned)) in flows {
+            let audit = audit.entry(ty).or_default();
+            if assigned > spent {
+                audit.issued.add(opid, assigned - spent);
+            }
+            if spent > assigned {
+                audit.burned.add(opid, spent - assigned);
+            }
+            if spent > 0 && assigned > 0 {
+                audit.replaced.add(opid, spent.min(assigned));
+            }
+        }
+        audit
+    }
+
+    /// Exports unspent outputs assigned to the given seals together with the
+    /// global state of the contract. Only the confirmed state is exported.
+    pub fn filter_owned(&self, seals: impl IntoIterator<Item = SecretSeal>) -> OwnedStateExport {
+        fn select<State: ExposedState>(
+            outputs: &LargeOrdSet<OutputAssignment<State>>,
+            opouts: &BTreeSet<Opout>,
+        ) -> LargeOrdSet<OutputAssignment<State>> {
+            Confined::from_iter_unsafe(
+                opouts
+                    .iter()
+                    .filter_map(|opout| outputs.get(opout))
+                    .cloned(),
+            )
+        }
+
+        let view = StateView::Confirmed;
+        let opouts = seals
+            .into_iter()
+            .flat_map(|seal| self.outputs_by_seal(seal, view))
+            .map(|output| output.opout)
+            .filter(|opout| !self.is_spent(*opout))
+            .collect::<BTreeSet<_>>();
+
+        let mut global = GlobalState::default();
+        for ty in self.schema.global_types.keys() {
+            // Safety: the type is taken from the schema
+            let values = unsafe { self.global_unchecked(*ty, view) };
+            if !values.is_empty() {
+                global
+                    .extend_state(*ty, values.into_iter().cloned())
+                    .expect("same size as the contract global state");
+            }
+        }
+
+        OwnedStateExport {
+            schema_id: self.schema_id,
+            contract_id: self.contract_id,
+            chain_net: self.chain_net,
+            global,
+            rights: select(&self.rights, &opouts),
+            fungibles: select(&self.fungibles, &opouts),
+            data: select(&self.data, &opouts),
+            attach: select(&self.attach, &opouts),
+        }
+    }
+
+    /// Returns all witness transactions anchoring operations of the contract
+    /// history, together with their known RBF replacements. Watch-only
+    /// wallets need to track only these transactions to follow the contract.
+    pub fn relevant_witnesses(&self) -> BTreeSet<WitnessId> {
+        self.witness_ords
+            .keys()
+            .copied()
+            .chain(self.rbf_index.values().flat_map(|set| set.iter().copied()))
+            .collect()
+    }
+
+    /// Returns contract outputs assigned and spent by the operations anchored
+    /// to the given transactions, which is used for rescanning wallet history.
+    /// Known RBF replacements of the witness transactions are reported as
+    /// affecting the same outputs as the replaced transactions.
+    pub fn affected_outputs(&self, txids: impl IntoIterator<Item = Txid>) -> AffectedOutputs {
+        let txids = txids.into_iter().collect::<BTreeSet<_>>();
+        let witnesses = self
+            .witness_ords
+            .keys()
+            .filter(|witness_id| {
+                txids.contains(&witness_id.txid()) ||
+                    self.witness_candidates(**witness_id)
+                        .any(|candidate| txids.contains(&candidate.txid()))
+            })
+            .copied()
+            .collect::<BTreeSet<_>>();
+
+        let assigned = witnesses
+            .iter()
+            .filter_map(|witness_id| self.witness_index.get(witness_id))
+            .flat_map(|set| set.iter().copied())
+            .collect();
+        let spent = self
+            .spent_index
+            .iter()
+            .filter(|(_, spenders)| {
+                spenders.values().any(|witness_anchor| {
+                    matches!(witness_anchor, Some(anchor) if witnesses.contains(&anchor.witness_id))
+                })
+            })
+            .map(|(opout, _)| *opout)
+            .collect();
+        AffectedOutputs { assigned, spent }
+    }
+
+    fn enforce_retention(&mut self) {
+        for (ty, schema) in &self.schema.global_types {
+            if let GlobalRetention::KeepLast(count) = schema.retention {
+                self.history.retain_global(*ty, count);
+            }
+        }
+    }
+
+    /// # Safety
+    ///
+    /// If the specified state type is not part of the schema.
+    pub unsafe fn global_unchecked(
+        &self,
+        state_type: GlobalStateType,
+        view: StateView,
+    ) -> SmallVec<&RevealedData> {
+        let schema = self
+            .schema
+            .global_types
+            .get(&state_type)
+            .expect("global type is not in the schema");
+        let Some(state) = self.global.get(&state_type) else {
+            return SmallVec::new();
+        };
+        let iter = state
+            .iter()
+            .filter(|(ord, _)| {
+                self.is_visible(ord.witness_anchor.map(|anchor| anchor.witness_id), view)
+            })
+            .map(|(_, data)| data)
+            .take(schema.max_items as usize);
+        SmallVec::try_from_iter(iter).expect("same size as previous confined collection")
+    }
+
+    /// Reconstructs global state of a given type as it was known at the block
+    /// `height`, i.e. taking into account only genesis and operations mined at
+    /// or below that height.
+    ///
+    /// State pruned according to the [`GlobalRetention`] policy of the schema
+    /// is not known to the history, thus for the heights preceding the
+    /// retained state the returned state is incomplete.
+    ///
+    /// # Safety
+    ///
+    /// If the specified state type is not part of the schema.
+    pub unsafe fn global_at_height_unchecked(
+        &self,
+        state_type: GlobalStateType,
+        height: u32,
+    ) -> SmallVec<&RevealedData> {
+        let schema = self
+            .schema
+            .global_types
+            .get(&state_type)
+            .expect("global type is not in the schema");
+        let iter = self
+            .global_by_height(state_type, ..=height)
+            .map(|(_, data)| data)
+            .take(schema.max_items as usize);
+        SmallVec::try_from_iter(iter).expect("same size as previous confined collection")
+    }
+}
+
+/// Deserialization of [`ContractHistory`] rebuilding its lookup indexes.
+#[cfg(feature = "serde")]
+mod serde_history {
+    use super::*;
+
+    /// Serialized part of [`ContractHistory`].
+    #[derive(Deserialize)]
+    #[serde(crate = "serde_crate", rename_all = "camelCase")]
+    pub struct ContractHistoryData {
+        schema_id: SchemaId,
+        root_schema_id: Option<SchemaId>,
+        contract_id: ContractId,
+        chain_net: ChainNet,
+        #[serde(with = "serde_map_seq::nested")]
+        global: TinyOrdMap<GlobalStateType, LargeOrdMap<GlobalOrd, RevealedData>>,
+        rights: LargeOrdSet<RightsOutput>,
+        fungibles: LargeOrdSet<FungibleOutput>,
+        data: LargeOrdSet<DataOutput>,
+        attach: LargeOrdSet<AttachOutput>,
+        #[serde(with = "serde_map_seq")]
+        seals: LargeOrdMap<Opout, SecretSeal>,
+        #[serde(with = "serde_map_seq")]
+        spent_index: LargeOrdMap<Opout, Spenders>,
+        #[serde(with = "serde_map_seq")]
+        rbf_index: LargeOrdMap<WitnessId, TinyOrdSet<WitnessId>>,
+        #[serde(with = "serde_map_seq")]
+        witness_ords: LargeOrdMap<WitnessId, WitnessOrd>,
+    }
+
+    impl TryFrom<ContractHistoryData> for ContractHistory {
+        type Error = String;
+
+        fn try_from(data: ContractHistoryData) -> Result<Self, Self::Error> {
+            let mut history = ContractHistory {
+                schema_id: data.schema_id,
+                root_schema_id: data.root_schema_id,
+                contract_id: data.contract_id,
+                chain_net: data.chain_net,
+                global: data.global,
+                rights: data.rights,
+                fungibles: data.fungibles,
+                data: data.data,
+                attach: data.attach,
+                seals: data.seals,
+                spent_index: data.spent_index,
+                rbf_index: data.rbf_index,
+                witness_ords: data.witness_ords,
+                type_index: empty!(),
+                seal_index: empty!(),
+                witness_index: empty!(),
+                operations: OpStore::default(),
+            };
+            history.rebuild_indexes()?;
+            Ok(history)
+        }
+    }
+}
+
+/// Serde helpers for maps with non-string keys.
+///
+/// Human-readable formats like JSON require map keys to be strings; thus maps
+/// keyed by structures and enums are serialized as sequences of key-value
+/// pairs.
+#[cfg(feature = "serde")]
+mod serde_map_seq {
+    use std::collections::BTreeMap;
+    use std::hash::Hash;
+
+    use amplify::confinement::Confined;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S, const MIN: usize, const MAX: usize>(
+        map: &Confined<BTreeMap<K, V>, MIN, MAX>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Ord + Hash,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, D, const MIN: usize, const MAX: usize>(
+        deserializer: D,
+    ) -> Result<Confined<BTreeMap<K, V>, MIN, MAX>, D::Error>
+    where
+        K: Deserialize<'de> + Ord + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let pairs = Vec::<(K, V)>::deserialize(deserializer)?;
+        Confined::try_from_iter(pairs).map_err(D::Error::custom)
+    }
+
+    /// Maps with nested maps as values, where both levels are serialized as
+    /// sequences of key-value pairs.
+    pub mod nested {
+        use super::*;
+
+        type Nested<K1, K2, V, const MAX1: usize, const MAX2: usize> =
+            Confined<BTreeMap<K1, Confined<BTreeMap<K2, V>, 0, MAX2>>, 0, MAX1>;
+
+        pub fn serialize<K1, K2, V, S, const MAX1: usize, const MAX2: usize>(
+            map: &Nested<K1, K2, V, MAX1, MAX2>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            K1: Serialize + Ord + Hash,
+            K2: Serialize + Ord + Hash,
+            V: Serialize,
+            S: Serializer,
+        {
+            serializer.collect_seq(
+                map.iter()
+                    .map(|(key, inner)| (key, inner.iter().collect::<Vec<_>>())),
+            )
+        }
+
+        pub fn deserialize<'de, K1, K2, V, D, const MAX1: usize, const MAX2: usize>(
+            deserializer: D,
+        ) -> Result<Nested<K1, K2, V, MAX1, MAX2>, D::Error>
+        where
+            K1: Deserialize<'de> + Ord + Hash,
+            K2: Deserialize<'de> + Ord + Hash,
+            V: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            let pairs = Vec::<(K1, Vec<(K2, V)>)>::deserialize(deserializer)?;
+            let mut map = BTreeMap::new();
+            for (key, inner) in pairs {
+                map.insert(key, Confined::try_from_iter(inner).map_err(D::Error::custom)?);
+            }
+            Confined::try_from(map).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use strict_encoding::DeserializeError;
+    use strict_types::{SemId, Ty};
+
+    use super::*;
+    use crate::{
+        AssetTag, BlindingFactor, GenesisSeal, GlobalStateSchema, GraphSeal, Input, Inputs,
+        WitnessPos,
+    };
+
+    #[test]
+    fn spent_index() {
+        let genesis = Genesis::strict_dumb();
+        let genesis_id = genesis.id();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let opout = Opout::new(genesis_id, AssignmentType::with(1), 0);
+        assert!(!history.is_spent(opout));
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+        let mut conflicting = transition.clone();
+        conflicting.metadata = Confined::try_from(vec![1]).unwrap();
+
+        history
+            .add_shared(Arc::new(transition.clone()).into(), None)
+            .unwrap();
+        assert!(history.is_spent(opout));
+        assert_eq!(history.spent_by(opout), Some(transition.id()));
+        assert!(history.conflicting_spends(&transition).is_empty());
+        assert_eq!(history.conflicting_spends(&conflicting), vec![(opout, transition.id())]);
+    }
+
+    #[test]
+    fn output_indexes() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([
+                        Assign::revealed(seal(0), value(100)),
+                        Assign::revealed(seal(1), value(50)),
+                    ])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let mut state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(
+            Confined::try_from_iter([Input::with(Opout::new(genesis.id(), ty, 0))]).unwrap(),
+        );
+        let graph_seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            2,
+        ));
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([Assign::revealed(graph_seal, value(100))]).unwrap(),
+                ),
+            )
+            .unwrap();
+        let witness_id = WitnessId::Bitcoin(Txid::from([2; 32]));
+        state
+            .add_transition(&transition, WitnessAnchor::from_mempool(witness_id))
+            .unwrap();
+
+        let check = |state: &ContractState| {
+            assert_eq!(state.fungible_outputs(ty, StateView::Pending).count(), 3);
+            assert_eq!(state.fungible_outputs(ty, StateView::Confirmed).count(), 2);
+            assert_eq!(
+                state
+                    .fungible_outputs(AssignmentType::with(1), StateView::Pending)
+                    .count(),
+                0
+            );
+            let outputs = state
+                .outputs_by_seal(seal(1).conceal(), StateView::Pending)
+                .collect::<Vec<_>>();
+            assert_eq!(outputs.len(), 1);
+            assert_eq!(outputs[0].opout, Opout::new(genesis.id(), ty, 1));
+            assert_eq!(outputs[0].state, StateData::Fungible(value(50)));
+            assert_eq!(
+                state
+                    .outputs_by_seal(graph_seal.conceal(), StateView::Confirmed)
+                    .count(),
+                0
+            );
+            let outputs = state.outputs_by_witness(witness_id).collect::<Vec<_>>();
+            assert_eq!(outputs.len(), 1);
+            assert_eq!(outputs[0].opout, Opout::new(transition.id(), ty, 0));
+            assert_eq!(outputs[0].output, Output::Bitcoin(Outpoint::new(Txid::from([2; 32]), 0)));
+        };
+        check(&state);
+
+        // Indexes are not serialized, but rebuilt on decoding
+        let data = state.to_strict_serialized::<U32>().unwrap();
+        let decoded = ContractState::from_strict_serialized::<U32>(data).unwrap();
+        assert_eq!(decoded.history.type_index, state.history.type_index);
+        assert_eq!(decoded.history.seal_index, state.history.seal_index);
+        assert_eq!(decoded.history.witness_index, state.history.witness_index);
+        check(&decoded);
+    }
+
+    #[test]
+    fn inconsistent_history() {
+        let genesis = Genesis::strict_dumb();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+        let decode = |state: &ContractState| {
+            let data = state.to_strict_serialized::<U32>().unwrap();
+            match ContractState::from_strict_serialized::<U32>(data) {
+                Ok(_) => None,
+                Err(DeserializeError::Decode(DecodeError::DataIntegrityError(msg))) => Some(msg),
+                Err(err) => panic!("unexpected error {err}"),
+            }
+        };
+        assert_eq!(decode(&state), None);
+
+        let mut corrupted = state.clone();
+        corrupted
+            .history
+            .seals
+            .insert(Opout::new(genesis.id(), AssignmentType::with(1), 0), strict_dumb!())
+            .unwrap();
+        assert_eq!(
+            decode(&corrupted).as_deref(),
+            Some("number of output seals doesn't match the number of outputs")
+        );
+
+        let mut corrupted = state;
+        let witness_id = WitnessId::Bitcoin(Txid::from([1; 32]));
+        corrupted
+            .history
+            .rbf_index
+            .insert(witness_id, empty!())
+            .unwrap();
+        assert_eq!(
+            decode(&corrupted),
+            Some(format!("witness {witness_id} is not known to the history"))
+        );
+    }
+
+    #[test]
+    fn global_ord() {
+        let pos = WitnessPos::new(800_000, 1_700_000_000).unwrap();
+        let first = WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(pos.with_tx_pos(2)),
+            witness_id: WitnessId::Bitcoin(Txid::from([0xFF; 32])),
+        };
+        let second = WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(pos.with_tx_pos(5)),
+            witness_id: WitnessId::Bitcoin(Txid::from([0x00; 32])),
+        };
+        assert!(first < second);
+
+        let opid1 = OpId::from([0x01; 32]);
+        let opid2 = OpId::from([0x02; 32]);
+        assert!(GlobalOrd::with_anchor(first, opid2, 1) < GlobalOrd::with_anchor(second, opid1, 0));
+        assert!(GlobalOrd::with_anchor(first, opid1, 1) < GlobalOrd::with_anchor(first, opid2, 0));
+        assert!(GlobalOrd::with_anchor(first, opid1, 0) < GlobalOrd::with_anchor(first, opid1, 1));
+        assert!(GlobalOrd::genesis(opid2, 0) < GlobalOrd::with_anchor(first, opid1, 0));
+        assert_ne!(GlobalOrd::genesis(opid1, 0), GlobalOrd::genesis(opid2, 0));
+
+        // Block timestamps are not monotonic and don't affect the ordering
+        let later = WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(800_001, 1_600_000_000).unwrap()),
+            witness_id: WitnessId::Bitcoin(Txid::from([0x00; 32])),
+        };
+        assert!(second < later);
+
+        // Witnesses mined at the same position (i.e. with unknown positions within
+        // the block) order operations by their ids
+        let other = WitnessAnchor {
+            witness_ord: first.witness_ord,
+            witness_id: WitnessId::Bitcoin(Txid::from([0x00; 32])),
+        };
+        assert!(GlobalOrd::with_anchor(first, opid1, 0) < GlobalOrd::with_anchor(other, opid2, 0));
+        assert!(GlobalOrd::with_anchor(other, opid1, 0) < GlobalOrd::with_anchor(first, opid2, 0));
+    }
+
+    #[test]
+    fn extension_ordering() {
+        let genesis = Genesis::strict_dumb();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let ty = GlobalStateType::with(1);
+        let mut extension = Extension::strict_dumb();
+        extension
+            .globals
+            .add_state(ty, RevealedData::from_typed(&1u8).unwrap())
+            .unwrap();
+        let ext_id = extension.id();
+        history
+            .add_shared(Arc::new(extension).into(), None)
+            .unwrap();
+        let ords =
+            |history: &ContractHistory| history.global[&ty].keys().copied().collect::<Vec<_>>();
+        assert_eq!(ords(&history), vec![GlobalOrd::genesis(ext_id, 0)]);
+
+        let anchor = |byte: u8, height: u32| WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(height, 1_700_000_000).unwrap()),
+            witness_id: WitnessId::Bitcoin(Txid::from([byte; 32])),
+        };
+        let opout = Opout::new(ext_id, AssignmentType::with(1), 0);
+        let mut late = Transition::strict_dumb();
+        late.inputs = Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+        let mut early = late.clone();
+        early.metadata = Confined::try_from(vec![1]).unwrap();
+
+        history
+            .add_shared(Arc::new(late).into(), Some(anchor(2, 800_001)))
+            .unwrap();
+        assert_eq!(ords(&history), vec![GlobalOrd::with_anchor(anchor(2, 800_001), ext_id, 0)]);
+        history
+            .add_shared(Arc::new(early).into(), Some(anchor(1, 800_000)))
+            .unwrap();
+        assert_eq!(ords(&history), vec![GlobalOrd::with_anchor(anchor(1, 800_000), ext_id, 0)]);
+
+        // Once the first descendant gets unmined, the next one defines the order
+        history
+            .confirm_witness(anchor(1, 800_000).witness_id, WitnessOrd::OffChain)
+            .unwrap();
+        assert_eq!(ords(&history), vec![GlobalOrd::with_anchor(anchor(2, 800_001), ext_id, 0)]);
+    }
+
+    #[test]
+    fn too_many_spenders() {
+        let genesis = Genesis::strict_dumb();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let opout = Opout::new(genesis.id(), AssignmentType::with(1), 0);
+        let mempool = WitnessAnchor::from_mempool(WitnessId::Bitcoin(strict_dumb!()));
+        let transition = |no: u8| {
+            let mut transition = Transition::strict_dumb();
+            transition.inputs =
+                Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+            transition.metadata = Confined::try_from(vec![no]).unwrap();
+            transition
+        };
+        for no in 0..u8::MAX {
+            history.add_transition(&transition(no), mempool).unwrap();
+        }
+        assert_eq!(history.spenders(opout).len(), u8::MAX as usize);
+
+        // Re-adding a known spender doesn't grow the index
+        history.add_transition(&transition(0), mempool).unwrap();
+
+        let excess = transition(u8::MAX);
+        assert_eq!(
+            history.add_transition(&excess, mempool),
+            Err(HistoryUpdateError::TooManySpenders(opout))
+        );
+        assert_eq!(history.spenders(opout).len(), u8::MAX as usize);
+        assert!(history.operation(excess.id()).is_none());
+    }
+
+    #[test]
+    fn branches() {
+        let genesis = Genesis::strict_dumb();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let mut state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+        let opout = Opout::new(genesis.id(), AssignmentType::with(1), 0);
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+        let mut conflicting = transition.clone();
+        conflicting.metadata = Confined::try_from(vec![1]).unwrap();
+        let mut child = Transition::strict_dumb();
+        child.inputs = Inputs::from(
+            Confined::try_from_iter([Input::with(Opout::new(
+                conflicting.id(),
+                AssignmentType::with(1),
+                0,
+            ))])
+            .unwrap(),
+        );
+
+        let mempool = WitnessAnchor::from_mempool(WitnessId::Bitcoin(strict_dumb!()));
+        let mined = WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(800_000, 1_700_000_000).unwrap()),
+            witness_id: WitnessId::Liquid(strict_dumb!()),
+        };
+        state.add_transition(&transition, mempool).unwrap();
+        state.add_transition(&conflicting, mempool).unwrap();
+        state.add_transition(&child, mempool).unwrap();
+        assert_eq!(state.conflicting_outputs().collect::<Vec<_>>(), vec![opout]);
+        assert_eq!(state.spenders(opout).len(), 2);
+
+        state.add_transition(&conflicting, mined).unwrap();
+        assert_eq!(state.spent_by(opout), Some(conflicting.id()));
+        assert_eq!(state.suspended_operations(), bset![transition.id()]);
+
+        let earlier = WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(799_999, 1_699_999_000).unwrap()),
+            witness_id: WitnessId::Liquid(strict_dumb!()),
+        };
+        state.add_transition(&transition, earlier).unwrap();
+        assert_eq!(state.spent_by(opout), Some(transition.id()));
+        assert_eq!(state.suspended_operations(), bset![conflicting.id(), child.id()]);
+        assert!(state
+            .suspended_outputs()
+            .iter()
+            .all(|output| output.opout.op != transition.id()));
+    }
+
+    #[test]
+    fn rbf() {
+        let genesis = Genesis::strict_dumb();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let opout = Opout::new(genesis.id(), AssignmentType::with(1), 0);
+        let ty = AssignmentType::with(2);
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            1,
+        ));
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([Assign::revealed(seal, VoidState::default())])
+                        .unwrap(),
+                ),
+            )
+            .unwrap();
+
+        let original = WitnessId::Bitcoin(Txid::from([1; 32]));
+        let replacement = WitnessId::Bitcoin(Txid::from([2; 32]));
+        let unknown = WitnessId::Bitcoin(Txid::from([3; 32]));
+        history
+            .add_transition(&transition, WitnessAnchor::from_mempool(original))
+            .unwrap();
+        let rights = |history: &ContractHistory, view| history.rights_outputs(ty, view).count();
+        assert_eq!(rights(&history, StateView::Confirmed), 0);
+        assert_eq!(rights(&history, StateView::Pending), 1);
+        assert_eq!(
+            history.add_witness_candidate(unknown, replacement),
+            Err(WitnessUpdateError::UnknownWitness(unknown))
+        );
+        history
+            .add_witness_candidate(original, replacement)
+            .unwrap();
+        assert_eq!(history.witness_candidates(original).collect::<Vec<_>>(), vec![replacement]);
+        for byte in 4..=u8::MAX {
+            history
+                .add_witness_candidate(original, WitnessId::Bitcoin(Txid::from([byte; 32])))
+                .unwrap();
+        }
+        for byte in [0, 1] {
+            history
+                .add_witness_candidate(original, WitnessId::Liquid(Txid::from([byte; 32])))
+                .unwrap();
+        }
+        assert_eq!(history.witness_candidates(original).count(), u8::MAX as usize);
+        history
+            .add_witness_candidate(original, replacement)
+            .unwrap();
+        assert_eq!(
+            history.add_witness_candidate(original, unknown),
+            Err(WitnessUpdateError::TooManyCandidates(original))
+        );
+        assert_eq!(history.witness_candidates(original).count(), u8::MAX as usize);
+
+        let mined = WitnessOrd::OnChain(WitnessPos::new(800_000, 1_700_000_000).unwrap());
+        history.confirm_witness(replacement, mined).unwrap();
+        assert_eq!(rights(&history, StateView::Confirmed), 1);
+        assert_eq!(history.witness_candidates(original).count(), 0);
+        assert_eq!(history.outputs_by_witness(original).count(), 0);
+        let outputs = history.outputs_by_witness(replacement).collect::<Vec<_>>();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].output, Output::Bitcoin(Outpoint::new(Txid::from([2; 32]), 0)));
+        assert_eq!(history.spenders(opout), vec![(
+            transition.id(),
+            Some(WitnessAnchor {
+                witness_ord: mined,
+                witness_id: replacement,
+            })
+        )]);
+        assert_eq!(
+            history.confirm_witness(original, mined),
+            Err(WitnessUpdateError::UnknownWitness(original))
+        );
+    }
+
+    #[test]
+    fn retention() {
+        let ty = GlobalStateType::with(1);
+        let data = |value: u8| RevealedData::from_typed(&value).unwrap();
+        let mut genesis = Genesis::strict_dumb();
+        genesis.globals.add_state(ty, data(0)).unwrap();
+        let genesis_id = genesis.id();
+        let mut schema = SubSchema::strict_dumb();
+        schema
+            .global_types
+            .insert(ty, GlobalStateSchema::keep_last(Ty::<SemId>::UNIT.id(None), 1))
+            .unwrap();
+        let mut state = ContractState {
+            schema,
+            history: ContractHistory::with(
+                genesis.schema_id,
+                None,
+                genesis.contract_id(),
+                &genesis,
+            ),
+        };
+        let values = |state: &ContractState| {
+            state.global[&ty]
+                .values()
+                .map(|data| data.as_ref()[0])
+                .collect::<BTreeSet<_>>()
+        };
+        let transition = |value: u8| {
+            let mut transition = Transition::strict_dumb();
+            let opout = Opout::new(genesis_id, AssignmentType::with(1), value as u16);
+            transition.inputs =
+                Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+            transition.globals.add_state(ty, data(value)).unwrap();
+            transition
+        };
+        let mined = |height| WitnessOrd::OnChain(WitnessPos::new(height, 1_700_000_000).unwrap());
+
+        // Unconfirmed state neither gets pruned nor evicts confirmed state
+        let pending1 = WitnessId::Bitcoin(Txid::from([1; 32]));
+        let pending2 = WitnessId::Bitcoin(Txid::from([2; 32]));
+        state
+            .add_transition(&transition(1), WitnessAnchor::from_mempool(pending1))
+            .unwrap();
+        state
+            .add_transition(&transition(2), WitnessAnchor::from_mempool(pending2))
+            .unwrap();
+        assert_eq!(values(&state), bset![0, 1, 2]);
+
+        // Confirmation prunes the older confirmed state
+        state.confirm_witness(pending1, mined(800_000)).unwrap();
+        assert_eq!(values(&state), bset![1, 2]);
+        assert_eq!(
+            state
+                .global_by_height(ty, ..)
+                .map(|(_, data)| data.as_ref()[0])
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+
+        let witness_id = WitnessId::Bitcoin(Txid::from([3; 32]));
+        state
+            .add_transition(&transition(3), WitnessAnchor {
+                witness_ord: mined(800_001),
+                witness_id,
+            })
+            .unwrap();
+        assert_eq!(values(&state), bset![2, 3]);
+        state.confirm_witness(pending2, mined(800_002)).unwrap();
+        assert_eq!(values(&state), bset![2]);
+    }
+
+    #[test]
+    fn global_by_height() {
+        fn values<'a>(iter: impl Iterator<Item = (GlobalOrd, &'a RevealedData)>) -> Vec<u8> {
+            iter.map(|(_, data)| data.as_ref()[0]).collect()
+        }
+
+        let ty = GlobalStateType::with(1);
+        let data = |value: u8| RevealedData::from_typed(&value).unwrap();
+        let mut genesis = Genesis::strict_dumb();
+        genesis.globals.add_state(ty, data(0)).unwrap();
+        let genesis_id = genesis.id();
+        let mut schema = SubSchema::strict_dumb();
+        schema
+            .global_types
+            .insert(ty, GlobalStateSchema::many(Ty::<SemId>::UNIT.id(None)))
+            .unwrap();
+        let mut state = ContractState {
+            schema,
+            history: ContractHistory::with(
+                genesis.schema_id,
+                None,
+                genesis.contract_id(),
+                &genesis,
+            ),
+        };
+        let transition = |value: u8| {
+            let mut transition = Transition::strict_dumb();
+            let opout = Opout::new(genesis_id, AssignmentType::with(1), value as u16);
+            transition.inputs =
+                Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+            transition.globals.add_state(ty, data(value)).unwrap();
+            transition
+        };
+        let mined = |no: u8, height| WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(height, 1_700_000_000).unwrap()),
+            witness_id: WitnessId::Bitcoin(Txid::from([no; 32])),
+        };
+        state.add_transition(&transition(1), mined(1, 100)).unwrap();
+        state.add_transition(&transition(2), mined(2, 200)).unwrap();
+        state
+            .add_transition(
+                &transition(3),
+                WitnessAnchor::from_mempool(WitnessId::Bitcoin(Txid::from([3; 32]))),
+            )
+            .unwrap();
+
+        // Genesis state is defined at height zero; unmined state is never returned
+        assert_eq!(values(state.global_by_height(ty, ..)), vec![0, 1, 2]);
+        assert_eq!(values(state.global_by_height(ty, 0..=0)), vec![0]);
+        assert_eq!(values(state.global_by_height(ty, 1..100)), Vec::<u8>::new());
+        assert_eq!(values(state.global_by_height(ty, 100..=100)), vec![1]);
+        assert_eq!(values(state.global_by_height(ty, 100..200)), vec![1]);
+        assert_eq!(values(state.global_by_height(ty, 101..=200)), vec![2]);
+        assert_eq!(values(state.global_by_height(ty, 201..)), Vec::<u8>::new());
+
+        let at_height = |height| unsafe {
+            state
+                .global_at_height_unchecked(ty, height)
+                .into_iter()
+                .map(|data| data.as_ref()[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(at_height(0), vec![0]);
+        assert_eq!(at_height(99), vec![0]);
+        assert_eq!(at_height(100), vec![0, 1]);
+        assert_eq!(at_height(199), vec![0, 1]);
+        assert_eq!(at_height(u32::MAX), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn watch_only() {
+        let genesis = Genesis::strict_dumb();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let mut state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+        let opout = Opout::new(genesis.id(), AssignmentType::with(1), 0);
+        let ty = AssignmentType::with(2);
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(Confined::try_from_iter([Input::with(opout)]).unwrap());
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            1,
+        ));
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Declarative(
+                    Confined::try_from_iter([Assign::revealed(seal, VoidState::default())])
+                        .unwrap(),
+                ),
+            )
+            .unwrap();
+
+        let original = WitnessId::Bitcoin(Txid::from([1; 32]));
+        let replacement = WitnessId::Bitcoin(Txid::from([2; 32]));
+        state
+            .add_transition(&transition, WitnessAnchor::from_mempool(original))
+            .unwrap();
+        state.add_witness_candidate(original, replacement).unwrap();
+        assert_eq!(state.relevant_witnesses(), bset![original, replacement]);
+
+        let expected = AffectedOutputs {
+            assigned: bset![Opout::new(transition.id(), ty, 0)],
+            spent: bset![opout],
+        };
+        assert_eq!(state.affected_outputs([original.txid()]), expected);
+        assert_eq!(state.affected_outputs([replacement.txid()]), expected);
+        assert!(state.affected_outputs([Txid::from([3; 32])]).is_empty());
+    }
+
+    #[test]
+    fn supply_audit() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let mut genesis = Genesis::strict_dumb();
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([
+                        Assign::revealed(seal(0), value(100)),
+                        Assign::revealed(seal(1), value(50)),
+                    ])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let mut state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(
+            Confined::try_from_iter([Input::with(Opout::new(genesis.id(), ty, 0))]).unwrap(),
+        );
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            1,
+        ));
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([Assign::revealed(seal, value(60))]).unwrap(),
+                ),
+            )
+            .unwrap();
+        state
+            .add_transition(
+                &transition,
+                WitnessAnchor::from_mempool(WitnessId::Bitcoin(strict_dumb!())),
+            )
+            .unwrap();
+
+        let audit = state.supply_audit().remove(&ty).unwrap();
+        assert_eq!(audit.issued.amount, 150);
+        assert_eq!(audit.issued.opids, bset![genesis.id()]);
+        assert_eq!(audit.burned.amount, 40);
+        assert_eq!(audit.burned.opids, bset![transition.id()]);
+        assert_eq!(audit.replaced.amount, 60);
+        assert_eq!(audit.circulating.amount, 110);
+        assert_eq!(audit.circulating.opids, bset![genesis.id(), transition.id()]);
+    }
+
+    #[test]
+    fn filter_owned() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([
+                        Assign::revealed(seal(0), value(100)),
+                        Assign::revealed(seal(1), value(50)),
+                    ])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let state = ContractState {
+            schema: SubSchema::strict_dumb(),
+            history,
+        };
+
+        let export = state.filter_owned([seal(1).conceal()]);
+        assert_eq!(export.contract_id, genesis.contract_id());
+        assert_eq!(export.fungibles.len(), 1);
+        let output = export.fungibles.first().unwrap();
+        assert_eq!(output.opout, Opout::new(genesis.id(), ty, 1));
+        assert!(export.rights.is_empty() && export.data.is_empty() && export.attach.is_empty());
+
+        let data = export.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(OwnedStateExport::from_strict_serialized::<U32>(data).unwrap(), export);
+        assert!(state.filter_owned([]).fungibles.is_empty());
+    }
+
+    #[test]
+    fn seal_filter() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([
+                        Assign::revealed(seal(0), value(100)),
+                        Assign::revealed(seal(1), value(50)),
+                    ])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let filter = history.seal_filter(StateView::Pending);
+        assert_eq!(filter.contract_id, genesis.contract_id());
+        assert_eq!(filter.len(), 2);
+        assert!(filter.contains(seal(0).conceal()) && filter.contains(seal(1).conceal()));
+        assert!(!filter.contains(seal(2).conceal()));
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(
+            Confined::try_from_iter([Input::with(Opout::new(genesis.id(), ty, 0))]).unwrap(),
+        );
+        history
+            .add_shared(Arc::new(transition).into(), None)
+            .unwrap();
+        let filter = history.seal_filter(StateView::Pending);
+        assert_eq!(filter.len(), 1);
+        assert!(!filter.contains(seal(0).conceal()));
+        assert!(filter.contains_any([seal(0).conceal(), seal(1).conceal()]));
+    }
+
+    #[test]
+    fn ancestry() {
+        let ty = AssignmentType::with(2);
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GraphSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                TxPtr::WitnessTx,
+                vout,
+                1,
+            ))
+        };
+        let transition = |prev_out: Opout| {
+            let mut transition = Transition::strict_dumb();
+            transition.inputs =
+                Inputs::from(Confined::try_from_iter([Input::with(prev_out)]).unwrap());
+            transition
+                .assignments
+                .insert(
+                    ty,
+                    TypedAssigns::Fungible(
+                        Confined::try_from_iter([Assign::revealed(seal(0), value(10))]).unwrap(),
+                    ),
+                )
+                .unwrap();
+            transition
+        };
+        let anchor = |no: u8| WitnessAnchor::from_mempool(WitnessId::Bitcoin(Txid::from([no; 32])));
+
+        let genesis = Genesis::strict_dumb();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        let parent = transition(Opout::new(genesis.id(), ty, 0));
+        let child = transition(Opout::new(parent.id(), ty, 0));
+        let unrelated = transition(Opout::new(genesis.id(), ty, 1));
+        history.add_transition(&parent, anchor(1)).unwrap();
+        history.add_transition(&child, anchor(2)).unwrap();
+        history.add_transition(&unrelated, anchor(3)).unwrap();
+
+        let path = history
+            .output_ancestry(Opout::new(child.id(), ty, 0))
+            .unwrap();
+        assert_eq!(path.transitions, bset![parent.id(), child.id()]);
+        assert!(path.extensions.is_empty());
+        assert_eq!(path.witnesses, bset![anchor(1).witness_id, anchor(2).witness_id]);
+        assert_eq!(history.ancestry(genesis.id()).unwrap(), OperationPath::default());
+
+        let opout = Opout::new(child.id(), ty, 1);
+        assert_eq!(history.output_ancestry(opout), Err(AncestryError::UnknownOutput(opout)));
+        let opid = Transition::strict_dumb().id();
+        assert_eq!(history.ancestry(opid), Err(AncestryError::UnknownOperation(opid)));
+    }
+
+    #[test]
+    fn disclosure() {
+        let ty = AssignmentType::with(2);
+        let genesis = Genesis::strict_dumb();
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(
+            Confined::try_from_iter([Input::with(Opout::new(genesis.id(), ty, 0))]).unwrap(),
+        );
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            0,
+            1,
+        ));
+        let value =
+            RevealedValue::with_blinding(10, BlindingFactor::EMPTY, AssetTag::from([1; 32]));
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([Assign::revealed(seal, value)]).unwrap(),
+                ),
+            )
+            .unwrap();
+        let opout = Opout::new(transition.id(), ty, 0);
+        history
+            .add_transition(
+                &transition.conceal(),
+                WitnessAnchor::from_mempool(WitnessId::Bitcoin(strict_dumb!())),
+            )
+            .unwrap();
+        assert!(history.fungibles().is_empty());
+
+        let mut disclosure = Disclosure::new(genesis.contract_id());
+        disclosure.reveal_transition(&transition).unwrap();
+        assert_eq!(disclosure.reveals.len(), 1);
+
+        let mut forged = disclosure.clone();
+        let mut reveal = forged.reveals.first().unwrap().clone();
+        reveal.state = StateData::Fungible(RevealedValue::with_blinding(
+            20,
+            BlindingFactor::EMPTY,
+            AssetTag::from([1; 32]),
+        ));
+        forged.reveals = Confined::try_from_iter([reveal]).unwrap();
+        assert_eq!(history.add_disclosure(&forged), Err(DisclosureError::StateMismatch(opout)));
+        assert!(history.fungibles().is_empty());
+
+        history.add_disclosure(&disclosure).unwrap();
+        let output = history.fungibles().first().unwrap();
+        assert_eq!(output.opout, opout);
+        assert_eq!(output.state, value);
+        assert_eq!(
+            history
+                .outputs_by_seal(seal.conceal(), StateView::Pending)
+                .count(),
+            1
+        );
     }
 }
@@ -21,25 +21,119 @@
 // limitations under the License.
 
 use core::fmt::{self, Debug, Display, Formatter};
+use std::io;
 
-use amplify::confinement::SmallVec;
+use amplify::confinement::{SmallVec, U16};
 use amplify::hex::ToHex;
 use amplify::{Bytes32, Wrapper};
-use commit_verify::{CommitVerify, Conceal, StrictEncodedProtocol};
-use strict_encoding::{StrictSerialize, StrictType};
+use commit_verify::{
+    CommitEncode, CommitVerify, Conceal, DigestExt, Sha256, StrictEncodedProtocol,
+};
+use strict_encoding::{
+    DecodeError, SerializeError, StrictEncode, StrictSerialize, StrictType, StrictWriter,
+};
+use strict_types::typify::TypedVal;
+use strict_types::{decode, SemId, TypeSystem};
 
 use super::{ConfidentialState, ExposedState};
 use crate::{StateCommitment, StateData, StateType, LIB_NAME_RGB};
 
-/// Struct using for storing Void (i.e. absent) state
-#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Display, Default)]
-#[display("void")]
+/// Bitmask of capabilities granted by a declarative (rights) assignment to the
+/// owner of the right, which schema may require when the right is exercised.
+#[derive(Wrapper, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Default, From)]
+#[wrapper(BitOps)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    /// Right without any additional capabilities.
+    pub const NONE: Self = Capabilities(0);
+    /// Right may be delegated, i.e. assigned with its capabilities to more
+    /// owners than were spent.
+    pub const DELEGATE: Self = Capabilities(0x01);
+    /// Right allows inflation of the contract supply.
+    pub const INFLATE: Self = Capabilities(0x02);
+    /// Right allows updating contract metadata.
+    pub const UPDATE_METADATA: Self = Capabilities(0x04);
+
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self { Capabilities(bits) }
+
+    #[inline]
+    pub const fn bits(self) -> u8 { self.0 }
+
+    #[inline]
+    pub const fn is_empty(self) -> bool { self.0 == 0 }
+
+    /// Checks whether all capabilities from `other` are present.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool { self.0 & other.0 == other.0 }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        let mut names = vec![];
+        if self.contains(Self::DELEGATE) {
+            names.push("delegate".to_owned());
+        }
+        if self.contains(Self::INFLATE) {
+            names.push("inflate".to_owned());
+        }
+        if self.contains(Self::UPDATE_METADATA) {
+            names.push("update-metadata".to_owned());
+        }
+        let unknown = self.0 & !(Self::DELEGATE | Self::INFLATE | Self::UPDATE_METADATA).0;
+        if unknown != 0 {
+            names.push(format!("{unknown:#04x}"));
+        }
+        f.write_str(&names.join("|"))
+    }
+}
+
+/// Struct using for storing Void (i.e. absent) state, which may carry
+/// [`Capabilities`] of the assigned right.
+#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Default)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
-#[derive(CommitEncode)]
-#[commit_encode(strategy = strict)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(crate = "serde_crate"))]
-pub struct VoidState(());
+pub struct VoidState(Capabilities);
+
+impl VoidState {
+    #[inline]
+    pub fn with(capabilities: Capabilities) -> Self { VoidState(capabilities) }
+
+    #[inline]
+    pub fn capabilities(&self) -> Capabilities { self.0 }
+}
+
+impl Display for VoidState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("void")
+        } else {
+            write!(f, "void:{}", self.0)
+        }
+    }
+}
+
+// Rights without capabilities keep the commitment of the rights created before
+// the capabilities were introduced
+impl CommitEncode for VoidState {
+    fn commit_encode(&self, e: &mut impl io::Write) {
+        if !self.0.is_empty() {
+            e.write_all(&[self.0.bits()]).ok();
+        }
+    }
+}
 
 impl ConfidentialState for VoidState {
     fn state_type(&self) -> StateType { StateType::Void }
@@ -78,6 +172,27 @@ impl Conceal for RevealedData {
 
 impl StrictSerialize for RevealedData {}
 
+impl RevealedData {
+    /// Constructs data state from the strict serialization of a value, which
+    /// must match the semantic type declared for the state by the schema.
+    pub fn from_typed(value: &impl StrictEncode) -> Result<Self, SerializeError> {
+        let data = value.strict_encode(StrictWriter::in_memory(U16))?.unbox();
+        SmallVec::try_from(data)
+            .map(RevealedData)
+            .map_err(SerializeError::from)
+    }
+
+    /// Deserializes the data using a semantic type from the type system,
+    /// failing if the data don't match the type or are not entirely consumed.
+    pub fn to_typed(
+        &self,
+        type_system: &TypeSystem,
+        sem_id: SemId,
+    ) -> Result<TypedVal, decode::Error> {
+        type_system.strict_deserialize_type(sem_id, self.as_ref())
+    }
+}
+
 impl Debug for RevealedData {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let val = match String::from_utf8(self.0.to_inner()) {
@@ -93,6 +208,50 @@ impl Display for RevealedData {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.as_ref().to_hex()) }
 }
 
+/// Borrowed view of a strict-serialized [`RevealedData`], allowing to access
+/// and conceal structured state without copying it out of the buffer it was
+/// received in.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct RevealedDataRef<'a>(&'a [u8]);
+
+impl<'a> RevealedDataRef<'a> {
+    /// Decodes data from the beginning of a strict-serialized buffer, returning
+    /// the view together with the rest of the buffer.
+    pub fn from_strict_slice(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        if data.len() < 2 {
+            return Err(DecodeError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let (len, rest) = data.split_at(2);
+        let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+        if rest.len() < len {
+            return Err(DecodeError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let (data, rest) = rest.split_at(len);
+        Ok((RevealedDataRef(data), rest))
+    }
+
+    pub fn as_slice(&self) -> &'a [u8] { self.0 }
+
+    /// Computes concealed data, which is equal to the concealed data of the
+    /// owned version.
+    pub fn conceal(&self) -> ConcealedData {
+        let mut engine = Sha256::from_tag(*b"urn:lnpbp:lnpbp0007:strict:v01#A");
+        engine.input_raw(&(self.0.len() as u16).to_le_bytes());
+        engine.input_raw(self.0);
+        ConcealedData::from(engine.finish())
+    }
+}
+
+impl<'a> From<RevealedDataRef<'a>> for RevealedData {
+    fn from(data: RevealedDataRef<'a>) -> Self {
+        RevealedData(SmallVec::try_from(data.0.to_vec()).expect("decoded with u16 length"))
+    }
+}
+
+impl<'a> From<&'a RevealedData> for RevealedDataRef<'a> {
+    fn from(data: &'a RevealedData) -> Self { RevealedDataRef(data.0.as_slice()) }
+}
+
 /// Confidential version of an structured state data.
 ///
 /// See also revealed version [`RevealedData`].
@@ -121,3 +280,105 @@ impl ConfidentialState for ConcealedData {
 impl CommitVerify<RevealedData, StrictEncodedProtocol> for ConcealedData {
     fn commit(revealed: &RevealedData) -> Self { Bytes32::commit(revealed).into() }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{MediumOrdMap, TinyVec, U16};
+    use strict_encoding::Sizing;
+    use strict_types::Ty;
+
+    use super::*;
+    use crate::validation::{Failure, Validity};
+    use crate::{
+        AssignmentType, FungibleType, GlobalStateSchema, GlobalStateType, StateSchema, SubSchema,
+        TransitionSchema, TransitionType,
+    };
+
+    #[test]
+    fn borrowed() {
+        let data = RevealedData::from(SmallVec::try_from(b"borrowed".to_vec()).unwrap());
+        let mut buf = data.to_strict_serialized::<U16>().unwrap().into_inner();
+        buf.push(0xFF);
+        let (view, rest) = RevealedDataRef::from_strict_slice(&buf).unwrap();
+        assert_eq!(rest, &[0xFF]);
+        assert_eq!(view.as_slice(), b"borrowed");
+        assert_eq!(view.conceal(), data.conceal());
+        assert_eq!(RevealedData::from(view), data);
+        assert!(RevealedDataRef::from_strict_slice(&buf[..4]).is_err());
+    }
+
+    #[test]
+    fn capabilities() {
+        let caps = Capabilities::DELEGATE | Capabilities::INFLATE;
+        assert!(caps.contains(Capabilities::INFLATE));
+        assert!(!caps.contains(Capabilities::UPDATE_METADATA));
+        assert_eq!(caps.to_string(), "delegate|inflate");
+        assert_eq!(Capabilities::from_bits(0x84).to_string(), "update-metadata|0x80");
+        assert_eq!(VoidState::default().to_string(), "void");
+        assert_eq!(VoidState::with(caps).to_string(), "void:delegate|inflate");
+
+        let mut buf = vec![];
+        VoidState::default().commit_encode(&mut buf);
+        assert!(buf.is_empty());
+        VoidState::with(caps).commit_encode(&mut buf);
+        assert_eq!(buf, vec![0x03]);
+
+        let mut schema = SubSchema::default();
+        schema
+            .owned_types
+            .insert(AssignmentType::with(0), StateSchema::Fungible(FungibleType::Unsigned64Bit))
+            .unwrap();
+        let mut transition = TransitionSchema::default();
+        transition
+            .capabilities
+            .insert(AssignmentType::with(0), Capabilities::INFLATE)
+            .unwrap();
+        schema
+            .transitions
+            .insert(TransitionType::with(1), transition)
+            .unwrap();
+        assert!(schema
+            .verify()
+            .failures
+            .contains(&Failure::SchemaCapabilityNotRights(
+                TransitionType::with(1),
+                AssignmentType::with(0)
+            )));
+    }
+
+    #[test]
+    fn typed() {
+        let u8_id = Ty::<SemId>::U8.id(None);
+        let list = Ty::<SemId>::list(u8_id, Sizing::new(1, 4));
+        let list_id = list.id(None);
+        let mut types = MediumOrdMap::new();
+        types.insert(list_id, list).unwrap();
+
+        let mut schema = SubSchema::default();
+        schema
+            .global_types
+            .insert(GlobalStateType::with(0), GlobalStateSchema::once(list_id))
+            .unwrap();
+        schema.type_system = TypeSystem::from(types.clone());
+        let status = schema.verify();
+        assert!(status
+            .failures
+            .contains(&Failure::SchemaSemIdIncomplete(list_id, u8_id)));
+
+        types.insert(u8_id, Ty::U8).unwrap();
+        let type_system = TypeSystem::from(types);
+        schema.type_system = type_system.clone();
+        assert!(!schema
+            .verify()
+            .failures
+            .iter()
+            .any(|f| matches!(f, Failure::SchemaSemIdIncomplete(..))));
+
+        let data = RevealedData::from_typed(&TinyVec::try_from(vec![1u8, 2, 3]).unwrap()).unwrap();
+        assert!(data.to_typed(&type_system, list_id).is_ok());
+        assert!(data.to_typed(&type_system, u8_id).is_err());
+        let truncated = RevealedData::from(SmallVec::try_from(vec![3u8, 1]).unwrap());
+        assert!(truncated.to_typed(&type_system, list_id).is_err());
+        assert_ne!(status.validity(), Validity::Valid);
+    }
+}
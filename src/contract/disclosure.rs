@@ -0,0 +1,181 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Disclosures of concealed state transition outputs.
+//!
+//! Seals and state assigned by a state transition may be kept concealed by
+//! the transition, such that only their owners know the revealed data. A
+//! [`Disclosure`] reveals such outputs after the fact, e.g. to an auditor,
+//! without creating new operations: each revealed item is checked against
+//! the concealed commitments of the already known transition.
+
+use amplify::confinement::{self, LargeOrdSet};
+use commit_verify::{CommitEncode, Conceal};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{
+    ContractId, GraphSeal, OpId, Operation, Opout, SealDefinition, StateCommitment, StateData,
+    Transition, TypedAssigns, LIB_NAME_RGB,
+};
+
+/// Errors verifying disclosure against the contract history.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum DisclosureError {
+    /// disclosure is made for a different contract {0}.
+    ContractMismatch(ContractId),
+
+    /// state transition {0} is not present in the history operation store.
+    UnknownTransition(OpId),
+
+    /// state transition doesn't define output {0}.
+    UnknownOutput(Opout),
+
+    /// seal disclosed for output {0} doesn't match the concealed seal.
+    SealMismatch(Opout),
+
+    /// state disclosed for output {0} doesn't match the concealed state.
+    StateMismatch(Opout),
+
+    /// witness transaction of state transition {0} is not known to the
+    /// contract history.
+    UnknownWitness(OpId),
+
+    /// seal disclosed for output {0} is defined on a layer 1 different from
+    /// the witness transaction.
+    Layer1Mismatch(Opout),
+}
+
+/// Revealed seal and state of a state transition output.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct DisclosedAssign {
+    pub opout: Opout,
+    pub seal: SealDefinition<GraphSeal>,
+    pub state: StateData,
+}
+
+impl DisclosedAssign {
+    /// Checks that the revealed data match the concealed seal and state of the
+    /// transition output.
+    pub fn verify(&self, transition: &Transition) -> Result<(), DisclosureError> {
+        let opout = self.opout;
+        let no = opout.no as usize;
+        let unknown = DisclosureError::UnknownOutput(opout);
+        if transition.id() != opout.op {
+            return Err(unknown);
+        }
+        let assigns = transition.assignments.get(&opout.ty).ok_or(unknown)?;
+        let state = match assigns {
+            TypedAssigns::Declarative(vec) => vec.get(no).map(|_| StateCommitment::Void),
+            TypedAssigns::Fungible(vec) => vec
+                .get(no)
+                .map(|a| StateCommitment::Fungible(a.to_confidential_state())),
+            TypedAssigns::Structured(vec) => vec
+                .get(no)
+                .map(|a| StateCommitment::Structured(a.to_confidential_state())),
+            TypedAssigns::Attachment(vec) => vec
+                .get(no)
+                .map(|a| StateCommitment::Attachment(a.to_confidential_state())),
+        }
+        .ok_or(unknown)?;
+        if assigns.to_confidential_seals()[no] != self.seal.conceal() {
+            return Err(DisclosureError::SealMismatch(opout));
+        }
+        // Range proofs are not committed to, thus only the commitments are compared
+        let (mut expected, mut actual) = (vec![], vec![]);
+        state.commit_encode(&mut expected);
+        self.state.conceal().commit_encode(&mut actual);
+        if expected != actual {
+            return Err(DisclosureError::StateMismatch(opout));
+        }
+        Ok(())
+    }
+}
+
+/// Set of revealed state transition outputs of a contract.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Disclosure {
+    pub contract_id: ContractId,
+    pub reveals: LargeOrdSet<DisclosedAssign>,
+}
+
+impl Disclosure {
+    pub fn new(contract_id: ContractId) -> Self {
+        Disclosure {
+            contract_id,
+            reveals: empty!(),
+        }
+    }
+
+    /// Adds all outputs of the transition, for which the seal and the state
+    /// are known, to the disclosure.
+    pub fn reveal_transition(&mut self, transition: &Transition) -> Result<(), confinement::Error> {
+        let opid = transition.id();
+        for (ty, assigns) in transition.assignments.iter() {
+            for no in 0..assigns.len_u16() {
+                let Ok(Some(seal)) = assigns.revealed_seal_at(no) else {
+                    continue;
+                };
+                let state = match assigns {
+                    TypedAssigns::Declarative(vec) => vec[no as usize]
+                        .as_revealed_state()
+                        .map(|_| StateData::Void),
+                    TypedAssigns::Fungible(vec) => vec[no as usize]
+                        .as_revealed_state()
+                        .map(|s| StateData::Fungible(*s)),
+                    TypedAssigns::Structured(vec) => vec[no as usize]
+                        .as_revealed_state()
+                        .map(|s| StateData::Structured(s.clone())),
+                    TypedAssigns::Attachment(vec) => vec[no as usize]
+                        .as_revealed_state()
+                        .map(|s| StateData::Attachment(s.clone())),
+                };
+                let Some(state) = state else {
+                    continue;
+                };
+                self.reveals.push(DisclosedAssign {
+                    opout: Opout::new(opid, *ty, no),
+                    seal,
+                    state,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StrictSerialize for Disclosure {}
+impl StrictDeserialize for Disclosure {}
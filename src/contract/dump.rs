@@ -0,0 +1,185 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human-readable dumps of consensus data.
+//!
+//! Serde serialization of all consensus types uses field order matching the
+//! strict encoding and ordered collections, thus producing canonical output in
+//! any of the human-readable formats (JSON, YAML etc). Dumps created with
+//! [`Dump`] additionally include commitment id of the data, which is verified
+//! to match the data when the dump is imported back.
+
+use std::fmt::{self, Display, Formatter};
+
+use commit_verify::CommitmentId;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{
+    BundleId, Consignment, ConsignmentId, ContractId, ContractState, Extension, Genesis, OpId,
+    Schema, SchemaId, SchemaRoot, Transition, TransitionBundle,
+};
+
+/// Data which can be dumped together with their commitment id.
+pub trait Dumpable: Serialize + DeserializeOwned {
+    type Id: Copy + Eq + Display + Serialize + DeserializeOwned;
+
+    /// Returns id of the data which is put into the dump.
+    fn dump_id(&self) -> Self::Id;
+
+    /// Verifies that the data match the id provided in the dump.
+    fn verify_dump(&self, id: Self::Id) -> bool { self.dump_id() == id }
+}
+
+/// Error importing dump when the data don't match the id from the dump.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DumpMismatch<Id: Display>(pub Id);
+
+impl<Id: Display> Display for DumpMismatch<Id> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "dumped data don't match the id {}", self.0)
+    }
+}
+
+impl<Id: Display + fmt::Debug> std::error::Error for DumpMismatch<Id> {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", bound = "")]
+struct DumpRaw<T: Dumpable> {
+    id: T::Id,
+    data: T,
+}
+
+/// Dump of the data together with their commitment id, which is verified
+/// during the deserialization.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(crate = "serde_crate", bound = "T: Clone", into = "DumpRaw<T>", try_from = "DumpRaw<T>")]
+pub struct Dump<T: Dumpable> {
+    id: T::Id,
+    data: T,
+}
+
+impl<T: Dumpable> From<Dump<T>> for DumpRaw<T> {
+    fn from(dump: Dump<T>) -> Self {
+        DumpRaw {
+            id: dump.id,
+            data: dump.data,
+        }
+    }
+}
+
+impl<T: Dumpable> TryFrom<DumpRaw<T>> for Dump<T> {
+    type Error = DumpMismatch<T::Id>;
+
+    fn try_from(raw: DumpRaw<T>) -> Result<Self, Self::Error> {
+        if !raw.data.verify_dump(raw.id) {
+            return Err(DumpMismatch(raw.id));
+        }
+        Ok(Dump {
+            id: raw.id,
+            data: raw.data,
+        })
+    }
+}
+
+impl<T: Dumpable> Dump<T> {
+    pub fn new(data: T) -> Self {
+        Dump {
+            id: data.dump_id(),
+            data,
+        }
+    }
+
+    pub fn id(&self) -> T::Id { self.id }
+
+    pub fn data(&self) -> &T { &self.data }
+
+    pub fn into_data(self) -> T { self.data }
+}
+
+impl<Root: SchemaRoot + Serialize + DeserializeOwned> Dumpable for Schema<Root> {
+    type Id = SchemaId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for Genesis {
+    type Id = ContractId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for Transition {
+    type Id = OpId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for Extension {
+    type Id = OpId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for TransitionBundle {
+    type Id = BundleId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for Consignment {
+    type Id = ConsignmentId;
+    fn dump_id(&self) -> Self::Id { self.commitment_id() }
+}
+
+impl Dumpable for ContractState {
+    type Id = ContractId;
+    fn dump_id(&self) -> Self::Id { self.contract_id() }
+    fn verify_dump(&self, id: Self::Id) -> bool {
+        self.contract_id() == id && self.schema.schema_id() == self.schema_id()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::TransitionType;
+
+    #[test]
+    fn json_roundtrip() {
+        let dump = Dump::new(Transition::strict_dumb());
+        let json = serde_json::to_string(&dump).unwrap();
+        assert_eq!(serde_json::from_str::<Dump<Transition>>(&json).unwrap(), dump);
+    }
+
+    #[test]
+    fn yaml_roundtrip() {
+        let dump = Dump::new(Consignment::strict_dumb());
+        let yaml = serde_yaml::to_string(&dump).unwrap();
+        assert_eq!(serde_yaml::from_str::<Dump<Consignment>>(&yaml).unwrap(), dump);
+    }
+
+    #[test]
+    fn id_mismatch() {
+        let mut dump = Dump::new(Transition::strict_dumb());
+        dump.data.transition_type = TransitionType::with(1);
+        let json = serde_json::to_string(&dump).unwrap();
+        assert!(serde_json::from_str::<Dump<Transition>>(&json).is_err());
+    }
+}
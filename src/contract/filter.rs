@@ -0,0 +1,255 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact probabilistic filters of the contract seals.
+//!
+//! A [`SealFilter`] is a Golomb-coded set in the style of BIP-158 block
+//! filters, constructed over concealed seals holding unspent contract state.
+//! Servers may provide it to light clients, which test whether any of their
+//! seals is involved into the contract without downloading its state. The
+//! filter has no false negatives, while false positives happen with
+//! probability of about `1/784931` per tested seal.
+
+use std::collections::BTreeSet;
+
+use amplify::confinement::{Confined, U32};
+use amplify::Wrapper;
+use commit_verify::{DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{ContractId, SecretSeal, LIB_NAME_RGB};
+
+const SEAL_FILTER_TAG: &str = "urn:lnpbp:rgb:seal-filter#261015";
+
+/// Golomb-Rice coding parameter of the filter (same as in BIP-158).
+pub const SEAL_FILTER_P: u8 = 19;
+/// Inverse false positive rate of the filter (same as in BIP-158).
+pub const SEAL_FILTER_M: u64 = 784_931;
+
+/// Golomb-coded set of concealed seals of a contract. Seals are hashed
+/// together with the contract id, such that filters of different contracts
+/// are not correlated.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SealFilter {
+    pub contract_id: ContractId,
+    count: u32,
+    data: Confined<Vec<u8>, 0, U32>,
+}
+
+impl StrictSerialize for SealFilter {}
+impl StrictDeserialize for SealFilter {}
+
+impl SealFilter {
+    /// Constructs filter over the given seals of a contract.
+    pub fn with(contract_id: ContractId, seals: impl IntoIterator<Item = SecretSeal>) -> Self {
+        let seals = seals.into_iter().collect::<BTreeSet<_>>();
+        let count = u32::try_from(seals.len())
+            .expect("number of contract seals exceeds 2^32, which is unrealistic");
+        let range = filter_range(count);
+        let mut values = seals
+            .into_iter()
+            .map(|seal| hash_seal(contract_id, seal, range))
+            .collect::<Vec<_>>();
+        values.sort_unstable();
+
+        let mut writer = BitWriter::default();
+        let mut last = 0;
+        for value in values {
+            writer.write_golomb(value - last);
+            last = value;
+        }
+        SealFilter {
+            contract_id,
+            count,
+            data: Confined::try_from(writer.bytes)
+                .expect("filter size exceeds 4GB, which is unrealistic"),
+        }
+    }
+
+    /// Returns number of seals in the filter.
+    #[inline]
+    pub fn len(&self) -> u32 { self.count }
+
+    /// Detects whether the filter has no seals.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.count == 0 }
+
+    /// Returns size of the encoded filter data, in bytes.
+    #[inline]
+    pub fn byte_len(&self) -> usize { self.data.len() }
+
+    /// Tests whether the seal may be in the filter.
+    pub fn contains(&self, seal: SecretSeal) -> bool { self.contains_any([seal]) }
+
+    /// Tests whether any of the seals may be in the filter, decoding the
+    /// filter only once.
+    pub fn contains_any(&self, seals: impl IntoIterator<Item = SecretSeal>) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let range = filter_range(self.count);
+        let mut queries = seals
+            .into_iter()
+            .map(|seal| hash_seal(self.contract_id, seal, range))
+            .collect::<Vec<_>>();
+        queries.sort_unstable();
+        let mut queries = queries.into_iter().peekable();
+
+        let mut reader = BitReader::new(&self.data);
+        let mut value = 0u64;
+        for _ in 0..self.count {
+            let Some(next) = reader
+                .read_golomb()
+                .and_then(|delta| value.checked_add(delta))
+            else {
+                return false;
+            };
+            value = next;
+            while queries.next_if(|query| *query < value).is_some() {}
+            match queries.peek() {
+                None => return false,
+                Some(query) if *query == value => return true,
+                Some(_) => {}
+            }
+        }
+        false
+    }
+}
+
+fn filter_range(count: u32) -> u64 { count as u64 * SEAL_FILTER_M }
+
+/// Maps the seal hash uniformly into `[0, range)`.
+fn hash_seal(contract_id: ContractId, seal: SecretSeal, range: u64) -> u64 {
+    let mut engine = Sha256::from_tag(SEAL_FILTER_TAG);
+    engine.input_raw(contract_id.as_slice());
+    engine.input_raw(seal.as_inner().as_slice());
+    let hash = engine.finish();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&hash[..8]);
+    ((u64::from_le_bytes(head) as u128 * range as u128) >> 64) as u64
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.bits == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("byte is pushed above") |= 0x80 >> self.bits;
+        }
+        self.bits = (self.bits + 1) % 8;
+    }
+
+    fn write_golomb(&mut self, value: u64) {
+        for _ in 0..(value >> SEAL_FILTER_P) {
+            self.write_bit(true);
+        }
+        self.write_bit(false);
+        for i in (0..SEAL_FILTER_P).rev() {
+            self.write_bit(value & (1 << i) != 0);
+        }
+    }
+}
+
+struct BitReader<'data> {
+    bytes: &'data [u8],
+    pos: usize,
+}
+
+impl<'data> BitReader<'data> {
+    fn new(bytes: &'data [u8]) -> Self { BitReader { bytes, pos: 0 } }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.pos / 8)?;
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    fn read_golomb(&mut self) -> Option<u64> {
+        let mut quotient = 0u64;
+        while self.read_bit()? {
+            quotient = quotient.checked_add(1)?;
+        }
+        let mut value = quotient.checked_shl(SEAL_FILTER_P as u32)?;
+        for i in (0..SEAL_FILTER_P).rev() {
+            if self.read_bit()? {
+                value |= 1 << i;
+            }
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn seal(no: u16) -> SecretSeal {
+        let mut bytes = [0u8; 32];
+        bytes[..2].copy_from_slice(&no.to_le_bytes());
+        SecretSeal::from(bytes)
+    }
+
+    #[test]
+    fn membership() {
+        let contract_id = ContractId::from([0x42; 32]);
+        let filter = SealFilter::with(contract_id, (0..500).map(seal));
+        assert_eq!(filter.len(), 500);
+        // About 21.5 bits per seal
+        assert!(filter.byte_len() < 500 * 22 / 8);
+        assert!((0..500).map(seal).all(|seal| filter.contains(seal)));
+        assert!(!(500..5_000).map(seal).any(|seal| filter.contains(seal)));
+        assert!(filter.contains_any((499..5_000).map(seal)));
+        assert!(!filter.contains_any((500..5_000).map(seal)));
+
+        let other = SealFilter::with(ContractId::from([0x11; 32]), (0..500).map(seal));
+        assert_ne!(filter, other);
+
+        let data = filter
+            .to_strict_serialized::<{ u32::MAX as usize }>()
+            .unwrap();
+        let decoded = SealFilter::from_strict_serialized::<{ u32::MAX as usize }>(data).unwrap();
+        assert_eq!(decoded, filter);
+    }
+
+    #[test]
+    fn empty() {
+        let filter = SealFilter::with(ContractId::from([0x42; 32]), []);
+        assert!(filter.is_empty());
+        assert_eq!(filter.byte_len(), 0);
+        assert!(!filter.contains(seal(0)));
+    }
+}
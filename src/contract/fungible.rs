@@ -31,12 +31,15 @@
 //! using elliptic curve homomorphic cryptography such as Pedesen commitments.
 
 use core::cmp::Ordering;
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Display, Formatter};
 use core::num::ParseIntError;
 use core::ops::Deref;
 use core::str::FromStr;
+use std::collections::BTreeMap;
 use std::io;
 use std::io::Write;
+use std::sync::{PoisonError, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
 use std::time::SystemTime;
 
 use amplify::confinement::U8;
@@ -73,19 +76,121 @@ pub struct AssetTag(
     Bytes32,
 );
 
+/// Current time used as an additional source of entropy. On `wasm32` targets
+/// there is no system time available and the time is taken from JavaScript
+/// runtime if `wasm` feature is enabled; otherwise zero is returned.
+fn timestamp_nanos() -> u128 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .expect("system time error")
+            .as_nanos()
+    }
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        (js_sys::Date::now() * 1_000_000.0) as u128
+    }
+    #[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+    {
+        0
+    }
+}
+
 impl AssetTag {
     pub fn new_random(contract_domain: impl AsRef<str>, assignment_type: AssignmentType) -> Self {
         let rand = thread_rng().next_u64();
-        let timestamp = SystemTime::now().elapsed().expect("system time error");
+        let timestamp = timestamp_nanos();
         let mut hasher = Sha256::default();
         hasher.input_with_len::<U8>(contract_domain.as_ref().as_bytes());
         hasher.input_raw(&assignment_type.to_le_bytes());
-        hasher.input_raw(&timestamp.as_nanos().to_le_bytes());
+        hasher.input_raw(&timestamp.to_le_bytes());
         hasher.input_raw(&rand.to_le_bytes());
         AssetTag::from(hasher.finish())
     }
 }
 
+/// Fixed-point decimal value, equal to `mantissa * 10^-exponent`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct FixedDecimal {
+    /// Value in the smallest units defined by the exponent.
+    pub mantissa: u128,
+    /// Number of decimal digits after the decimal point.
+    pub exponent: u8,
+}
+
+impl FixedDecimal {
+    pub fn new(mantissa: u128, exponent: u8) -> Self { FixedDecimal { mantissa, exponent } }
+
+    /// Converts the value to a representation with a larger exponent. Returns
+    /// `None` if the exponent is smaller than the current one or the mantissa
+    /// overflows.
+    pub fn rescale(self, exponent: u8) -> Option<Self> {
+        let scale = 10u128.checked_pow(exponent.checked_sub(self.exponent)? as u32)?;
+        Some(FixedDecimal {
+            mantissa: self.mantissa.checked_mul(scale)?,
+            exponent,
+        })
+    }
+
+    fn aligned(self, other: Self) -> Option<(u128, u128, u8)> {
+        let exponent = self.exponent.max(other.exponent);
+        Some((self.rescale(exponent)?.mantissa, other.rescale(exponent)?.mantissa, exponent))
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (a, b, exponent) = self.aligned(other)?;
+        Some(FixedDecimal::new(a.checked_add(b)?, exponent))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (a, b, exponent) = self.aligned(other)?;
+        Some(FixedDecimal::new(a.checked_sub(b)?, exponent))
+    }
+}
+
+impl Display for FixedDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let exponent = self.exponent as usize;
+        if exponent == 0 {
+            return write!(f, "{}", self.mantissa);
+        }
+        let digits = format!("{:0>width$}", self.mantissa, width = exponent + 1);
+        let (int, frac) = digits.split_at(digits.len() - exponent);
+        write!(f, "{int}.{frac}")
+    }
+}
+
+impl FromStr for FixedDecimal {
+    type Err = FungibleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let exponent = u8::try_from(frac.len()).map_err(|_| FungibleParseError::Exponent)?;
+        let mantissa = format!("{int}{frac}").parse()?;
+        Ok(FixedDecimal { mantissa, exponent })
+    }
+}
+
+/// Errors parsing fungible state.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum FungibleParseError {
+    /// invalid fungible value - {0}
+    #[from]
+    Value(ParseIntError),
+
+    /// decimal value has more than 255 fractional digits.
+    Exponent,
+}
+
 /// An atom of an additive state, which thus can be monomorphically encrypted.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
 #[display(inner)]
@@ -101,6 +206,15 @@ pub enum FungibleState {
     #[from]
     #[strict_type(tag = 8)] // Matches strict types U64 primitive value
     Bits64(u64),
+
+    /// 128-bit value.
+    #[strict_type(tag = 16)] // Matches strict types U128 primitive value
+    Bits128(u128),
+
+    /// Fixed-point decimal value.
+    #[from]
+    #[strict_type(tag = 0x80)]
+    Decimal(FixedDecimal),
     // When/if adding more variants do not forget to re-write FromStr impl
 }
 
@@ -113,34 +227,255 @@ impl From<RevealedValue> for FungibleState {
 }
 
 impl FromStr for FungibleState {
-    type Err = ParseIntError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> { s.parse().map(FungibleState::Bits64) }
-}
-
-impl From<FungibleState> for u64 {
-    fn from(value: FungibleState) -> Self {
-        match value {
-            FungibleState::Bits64(val) => val,
+    type Err = FungibleParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('.') {
+            return s.parse().map(FungibleState::Decimal);
         }
+        let value = s.parse::<u128>()?;
+        Ok(match u64::try_from(value) {
+            Ok(value) => FungibleState::Bits64(value),
+            Err(_) => FungibleState::Bits128(value),
+        })
     }
 }
 
+impl TryFrom<FungibleState> for u64 {
+    type Error = FungibleState;
+    fn try_from(value: FungibleState) -> Result<Self, Self::Error> { value.as_u64().ok_or(value) }
+}
+
 impl FungibleState {
     pub fn fungible_type(&self) -> schema::FungibleType {
         match self {
             FungibleState::Bits64(_) => schema::FungibleType::Unsigned64Bit,
+            FungibleState::Bits128(_) => schema::FungibleType::Unsigned128Bit,
+            FungibleState::Decimal(_) => schema::FungibleType::Decimal,
+        }
+    }
+
+    /// Returns value in the smallest indivisible units, which is used in
+    /// Pedersen commitments. For decimal values this is the mantissa.
+    pub fn atoms(&self) -> u128 {
+        match *self {
+            FungibleState::Bits64(value) => value as u128,
+            FungibleState::Bits128(value) => value,
+            FungibleState::Decimal(decimal) => decimal.mantissa,
+        }
+    }
+
+    /// Returns value in the smallest indivisible units if it fits into 64
+    /// bits.
+    pub fn as_u64(&self) -> Option<u64> { u64::try_from(self.atoms()).ok() }
+
+    fn as_decimal(&self) -> FixedDecimal {
+        match *self {
+            FungibleState::Decimal(decimal) => decimal,
+            _ => FixedDecimal::new(self.atoms(), 0),
+        }
+    }
+
+    /// Adds two values, returning `None` on overflow. Sum of 64-bit values is
+    /// a 64-bit value; sum involving a decimal value is a decimal value with
+    /// the largest of the exponents; otherwise the sum is a 128-bit value.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.checked_op(other, u64::checked_add, u128::checked_add, FixedDecimal::checked_add)
+    }
+
+    /// Subtracts values, returning `None` on overflow. The type of the result
+    /// is defined in the same way as for [`FungibleState::checked_add`].
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_op(other, u64::checked_sub, u128::checked_sub, FixedDecimal::checked_sub)
+    }
+
+    fn checked_op(
+        self,
+        other: Self,
+        op64: impl FnOnce(u64, u64) -> Option<u64>,
+        op128: impl FnOnce(u128, u128) -> Option<u128>,
+        op_decimal: impl FnOnce(FixedDecimal, FixedDecimal) -> Option<FixedDecimal>,
+    ) -> Option<Self> {
+        match (self, other) {
+            (FungibleState::Bits64(a), FungibleState::Bits64(b)) => {
+                op64(a, b).map(FungibleState::Bits64)
+            }
+            (FungibleState::Decimal(_), _) | (_, FungibleState::Decimal(_)) => {
+                op_decimal(self.as_decimal(), other.as_decimal()).map(FungibleState::Decimal)
+            }
+            _ => op128(self.atoms(), other.atoms()).map(FungibleState::Bits128),
+        }
+    }
+}
+
+/// Amount of a fungible asset in its smallest indivisible units, providing
+/// overflow-checked arithmetic.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
+#[wrapper(Display, FromStr)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct Amount(u64);
+
+impl From<Amount> for FungibleState {
+    fn from(amount: Amount) -> Self { FungibleState::Bits64(amount.0) }
+}
+
+impl TryFrom<FungibleState> for Amount {
+    type Error = FungibleState;
+    fn try_from(state: FungibleState) -> Result<Self, Self::Error> {
+        match state {
+            FungibleState::Decimal(_) => Err(state),
+            _ => state.as_u64().map(Amount).ok_or(state),
+        }
+    }
+}
+
+impl Amount {
+    pub const ZERO: Self = Amount(0);
+    pub const MAX: Self = Amount(u64::MAX);
+
+    pub fn checked_add(self, other: impl Into<Self>) -> Option<Self> {
+        self.0.checked_add(other.into().0).map(Amount)
+    }
+    pub fn checked_sub(self, other: impl Into<Self>) -> Option<Self> {
+        self.0.checked_sub(other.into().0).map(Amount)
+    }
+    pub fn checked_mul(self, other: impl Into<Self>) -> Option<Self> {
+        self.0.checked_mul(other.into().0).map(Amount)
+    }
+
+    pub fn saturating_add(self, other: impl Into<Self>) -> Self {
+        Amount(self.0.saturating_add(other.into().0))
+    }
+    pub fn saturating_sub(self, other: impl Into<Self>) -> Self {
+        Amount(self.0.saturating_sub(other.into().0))
+    }
+    pub fn saturating_mul(self, other: impl Into<Self>) -> Self {
+        Amount(self.0.saturating_mul(other.into().0))
+    }
+
+    /// Adds amounts, returning wrapped result together with a flag indicating
+    /// whether an overflow has happened.
+    pub fn overflowing_add(self, other: impl Into<Self>) -> (Self, bool) {
+        let (res, overflow) = self.0.overflowing_add(other.into().0);
+        (Amount(res), overflow)
+    }
+    pub fn overflowing_sub(self, other: impl Into<Self>) -> (Self, bool) {
+        let (res, overflow) = self.0.overflowing_sub(other.into().0);
+        (Amount(res), overflow)
+    }
+    pub fn overflowing_mul(self, other: impl Into<Self>) -> (Self, bool) {
+        let (res, overflow) = self.0.overflowing_mul(other.into().0);
+        (Amount(res), overflow)
+    }
+
+    /// Sums amounts, returning `None` on overflow.
+    pub fn checked_sum(iter: impl IntoIterator<Item = impl Into<Self>>) -> Option<Self> {
+        iter.into_iter()
+            .try_fold(Amount::ZERO, |sum, amount| sum.checked_add(amount))
+    }
+
+    /// Formats amount with a given number of decimal digits after the decimal
+    /// point, grouping fractional digits by three, like `1.234 567 89`.
+    /// Trailing zeros of the fractional part are omitted.
+    pub fn to_string_with_precision(self, precision: u8) -> String {
+        let precision = precision as usize;
+        let digits = format!("{:0>width$}", self.0, width = precision + 1);
+        let (int, fract) = digits.split_at(digits.len() - precision);
+        let fract = fract.trim_end_matches('0');
+        if fract.is_empty() {
+            return int.to_owned();
         }
+        let fract = fract
+            .as_bytes()
+            .chunks(3)
+            .map(|chunk| String::from_utf8_lossy(chunk))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{int}.{fract}")
     }
 
-    pub fn as_u64(&self) -> u64 { (*self).into() }
+    /// Parses amount with a given number of decimal digits after the decimal
+    /// point. Spaces grouping the digits and trailing zeros of the fractional
+    /// part are ignored.
+    pub fn from_str_with_precision(s: &str, precision: u8) -> Result<Self, AmountParseError> {
+        let s = s.replace(' ', "");
+        let (int, fract) = s.split_once('.').unwrap_or((&s, ""));
+        if let Some(c) = int
+            .chars()
+            .chain(fract.chars())
+            .find(|c| !c.is_ascii_digit())
+        {
+            return Err(AmountParseError::InvalidChar(c));
+        }
+        if int.is_empty() && fract.is_empty() {
+            return Err(AmountParseError::Empty);
+        }
+        let fract = fract.trim_end_matches('0');
+        if fract.len() > precision as usize {
+            return Err(AmountParseError::Precision(precision));
+        }
+        let int = if int.is_empty() {
+            0
+        } else {
+            int.parse::<u64>()?
+        };
+        let fract = format!("{fract:0<width$}", width = precision as usize);
+        let fract = if fract.is_empty() {
+            0
+        } else {
+            fract.parse::<u64>()?
+        };
+        10u64
+            .checked_pow(precision as u32)
+            .and_then(|scale| int.checked_mul(scale))
+            .and_then(|int| int.checked_add(fract))
+            .map(Amount)
+            .ok_or(AmountParseError::Overflow)
+    }
+}
+
+/// Errors parsing fungible amount.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum AmountParseError {
+    /// invalid amount - {0}
+    #[from]
+    Int(ParseIntError),
+
+    /// amount has more fractional digits than allowed by the precision {0}.
+    Precision(u8),
+
+    /// amount contains invalid character '{0}'.
+    InvalidChar(char),
+
+    /// amount contains no digits.
+    Empty,
+
+    /// amount exceeds maximum value.
+    Overflow,
 }
 
-/// value provided for a blinding factor overflows prime field order for
-/// Secp256k1 curve.
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error, From)]
+/// Errors constructing blinding factor, which must be a non-zero element of
+/// the Secp256k1 curve scalar field.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
 #[display(doc_comments)]
-#[from(secp256k1_zkp::UpstreamError)]
-pub struct InvalidFieldElement;
+pub enum InvalidFieldElement {
+    /// value provided for a blinding factor is zero.
+    Zero,
+
+    /// value provided for a blinding factor overflows prime field order for
+    /// Secp256k1 curve.
+    FieldOrderOverflow,
+}
+
+// Arithmetic operations on valid field elements fail only when their result
+// is zero.
+impl From<secp256k1_zkp::UpstreamError> for InvalidFieldElement {
+    fn from(_: secp256k1_zkp::UpstreamError) -> Self { InvalidFieldElement::Zero }
+}
 
 /// Errors parsing string representation of a blinding factor.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error, From)]
@@ -150,10 +485,9 @@ pub enum BlindingParseError {
     #[from]
     Hex(hex::Error),
 
-    /// blinding factor value is invalid and does not belong to the Secp256k1
-    /// curve field.
-    #[from(InvalidFieldElement)]
-    InvalidFieldElement,
+    /// invalid blinding factor value - {0}
+    #[from]
+    InvalidFieldElement(InvalidFieldElement),
 }
 
 /// Blinding factor used in creating Pedersen commitment to an [`AtomicValue`].
@@ -162,8 +496,8 @@ pub enum BlindingParseError {
 /// process if the original value is kept.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 #[display(Self::to_hex)]
-#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
-#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(StrictType, StrictDumb, StrictEncode)]
+#[strict_type(lib = LIB_NAME_RGB, dumb = Self::EMPTY)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -171,8 +505,57 @@ pub enum BlindingParseError {
 )]
 pub struct BlindingFactor(Bytes32);
 
+// Blinding factor must be a valid field element, otherwise operations on it
+// (like creating Pedersen commitment) will panic.
+impl StrictDecode for BlindingFactor {
+    fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+        reader.read_tuple(|r| {
+            let bytes = r.read_field::<Bytes32>()?;
+            BlindingFactor::try_from(bytes)
+                .map_err(|_| DecodeError::DataIntegrityError(s!("invalid blinding factor")))
+        })
+    }
+}
+
 impl BlindingFactor {
     pub const EMPTY: Self = BlindingFactor(Bytes32::from_array([0x7E; 32]));
+
+    /// Order of the Secp256k1 curve scalar field, big-endian.
+    const FIELD_ORDER: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFE, 0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B, 0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36,
+        0x41, 0x41,
+    ];
+
+    /// Constructs blinding factor from a big-endian scalar, which must be a
+    /// non-zero value below the Secp256k1 field order.
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Result<Self, InvalidFieldElement> {
+        if bytes == [0u8; 32] {
+            return Err(InvalidFieldElement::Zero);
+        }
+        if bytes >= Self::FIELD_ORDER {
+            return Err(InvalidFieldElement::FieldOrderOverflow);
+        }
+        Ok(BlindingFactor(Bytes32::from_array(bytes)))
+    }
+
+    /// Constructs blinding factor from a big-endian scalar, reducing it modulo
+    /// the Secp256k1 field order. Fails only if the reduced value is zero.
+    pub fn from_be_bytes_reduced(mut bytes: [u8; 32]) -> Result<Self, InvalidFieldElement> {
+        // Since 2^256 < 2·n, a single subtraction of the field order is enough
+        if bytes >= Self::FIELD_ORDER {
+            let mut borrow = 0u16;
+            for (byte, order) in bytes.iter_mut().zip(Self::FIELD_ORDER).rev() {
+                let diff = 0x100 + *byte as u16 - order as u16 - borrow;
+                *byte = diff as u8;
+                borrow = (diff < 0x100) as u16;
+            }
+        }
+        Self::from_be_bytes(bytes)
+    }
+
+    /// Returns big-endian representation of the blinding factor scalar.
+    pub fn to_be_bytes(self) -> [u8; 32] { self.0.to_byte_array() }
 }
 
 impl Deref for BlindingFactor {
@@ -239,20 +622,17 @@ impl BlindingFactor {
         Ok(blinding_correction.into())
     }
 
-    fn to_secret_key(self) -> secp256k1_zkp::SecretKey {
+    /// Converts blinding factor into Secp256k1 secret key.
+    pub fn to_secret_key(self) -> secp256k1_zkp::SecretKey {
         secp256k1_zkp::SecretKey::from_slice(self.0.as_slice())
-            .expect("blinding factor is an invalid secret key")
+            .expect("blinding factor is guaranteed to be a non-zero field element")
     }
 }
 
 impl TryFrom<[u8; 32]> for BlindingFactor {
     type Error = InvalidFieldElement;
 
-    fn try_from(array: [u8; 32]) -> Result<Self, Self::Error> {
-        secp256k1_zkp::SecretKey::from_slice(&array)
-            .map_err(|_| InvalidFieldElement)
-            .map(Self::from)
-    }
+    fn try_from(array: [u8; 32]) -> Result<Self, Self::Error> { Self::from_be_bytes(array) }
 }
 
 impl TryFrom<Bytes32> for BlindingFactor {
@@ -389,20 +769,239 @@ impl StrictDecode for PedersenCommitment {
 
 impl CommitVerify<RevealedValue, UntaggedProtocol> for PedersenCommitment {
     fn commit(revealed: &RevealedValue) -> Self {
-        use secp256k1_zkp::{Generator, Tag, Tweak};
+        PedersenCommitment::with_generator(revealed, asset_generator(revealed.tag))
+    }
+}
+
+impl PedersenCommitment {
+    fn with_generator(revealed: &RevealedValue, generator: secp256k1_zkp::Generator) -> Self {
+        use secp256k1_zkp::Tweak;
 
         let blinding = Tweak::from_inner(revealed.blinding.0.into_inner())
             .expect("type guarantees of BlindingFactor are broken");
-        let FungibleState::Bits64(value) = revealed.value;
+        let value = revealed.value.atoms();
+
+        match u64::try_from(value) {
+            Ok(value) => {
+                secp256k1_zkp::PedersenCommitment::new(SECP256K1, value, blinding, generator)
+            }
+            Err(_) => commit_u128(value, &revealed.blinding, generator),
+        }
+        .into()
+    }
+
+    /// Verifies that the commitment was produced for the given amount of the
+    /// asset with the provided blinding factor.
+    pub fn verify_reveal(
+        &self,
+        amount: impl Into<FungibleState>,
+        blinding: BlindingFactor,
+        tag: AssetTag,
+    ) -> Result<(), CommitmentError> {
+        let revealed = RevealedValue::with_blinding(amount, blinding, tag);
+        if PedersenCommitment::commit(&revealed) != *self {
+            return Err(CommitmentError::RevealMismatch(*self));
+        }
+        Ok(())
+    }
+}
+
+/// Errors verifying Pedersen commitments against the revealed data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CommitmentError {
+    /// Pedersen commitment {0} doesn't match the revealed amount, blinding
+    /// factor and asset tag.
+    RevealMismatch(PedersenCommitment),
+}
 
-        let tag = Tag::from(revealed.tag.to_byte_array());
-        let generator = Generator::new_unblinded(SECP256K1, tag);
+/// Derives generator of the Pedersen commitment value component for an asset.
+fn asset_generator(tag: AssetTag) -> secp256k1_zkp::Generator {
+    let tag = secp256k1_zkp::Tag::from(tag.to_byte_array());
+    secp256k1_zkp::Generator::new_unblinded(SECP256K1, tag)
+}
+
+/// Context for producing and verifying Pedersen commitments, shared by all
+/// operations of a validation run.
+///
+/// All computations use the global preallocated secp256k1 context, while
+/// generators derived from asset tags are computed once and cached. The
+/// context is [`Sync`], so a single instance may be shared between the
+/// threads validating a consignment in parallel.
+#[derive(Debug, Default)]
+pub struct PedersenContext {
+    generators: RwLock<BTreeMap<AssetTag, secp256k1_zkp::Generator>>,
+}
+
+impl PedersenContext {
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns generator for the asset, computing it only on the first use.
+    pub fn generator(&self, tag: AssetTag) -> secp256k1_zkp::Generator {
+        let cached = self
+            .generators
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&tag)
+            .copied();
+        if let Some(generator) = cached {
+            return generator;
+        }
+        let generator = asset_generator(tag);
+        self.generators
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(tag, generator);
+        generator
+    }
+
+    /// Produces commitment to the revealed value, equal to the one produced
+    /// by [`PedersenCommitment::commit`].
+    pub fn commit(&self, revealed: &RevealedValue) -> PedersenCommitment {
+        PedersenCommitment::with_generator(revealed, self.generator(revealed.tag))
+    }
 
-        secp256k1_zkp::PedersenCommitment::new(SECP256K1, value, blinding, generator).into()
+    /// Checks that the sum of input commitments is equal to the sum of output
+    /// commitments.
+    pub fn verify_sum(
+        &self,
+        inputs: &[PedersenCommitment],
+        outputs: &[PedersenCommitment],
+    ) -> bool {
+        let inputs = inputs.iter().map(|c| c.0).collect::<Vec<_>>();
+        let outputs = outputs.iter().map(|c| c.0).collect::<Vec<_>>();
+        secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &inputs, &outputs)
     }
 }
 
+/// Constructs Pedersen commitment `v·H + r·G` to a value exceeding 64 bits,
+/// which is not supported by `secp256k1-zkp`. Values fitting into 64 bits
+/// produce the same commitment as `secp256k1_zkp::PedersenCommitment::new`.
+fn commit_u128(
+    value: u128,
+    blinding: &BlindingFactor,
+    generator: secp256k1_zkp::Generator,
+) -> secp256k1_zkp::PedersenCommitment {
+    use secp256k1_zkp::{PublicKey, Scalar, SecretKey};
+
+    let mut scalar = [0u8; 32];
+    scalar[16..].copy_from_slice(&value.to_be_bytes());
+    let scalar = Scalar::from_be_bytes(scalar).expect("128-bit value is always below curve order");
+    let blinding = SecretKey::from_slice(blinding.as_slice())
+        .expect("type guarantees of BlindingFactor are broken");
+
+    let point = secp_field::decode_point(&generator.serialize(), secp_field::GENERATOR_PREFIX);
+    let point = point
+        .mul_tweak(SECP256K1, &scalar)
+        .and_then(|point| point.combine(&PublicKey::from_secret_key(SECP256K1, &blinding)))
+        .expect("negligible probability");
+    let data = secp_field::encode_point(&point, secp_field::COMMITMENT_PREFIX);
+    secp256k1_zkp::PedersenCommitment::from_slice(&data).expect("valid curve point")
+}
+
+/// Conversion between `secp256k1-zkp` serialization of curve points, which
+/// encodes quadratic residuosity of the y coordinate, and public keys, which
+/// encode its parity.
+mod secp_field {
+    use secp256k1_zkp::PublicKey;
+
+    pub const COMMITMENT_PREFIX: u8 = 0x08;
+    pub const GENERATOR_PREFIX: u8 = 0x0A;
+
+    type Fe = [u64; 4];
+
+    const P: Fe = [0xFFFF_FFFE_FFFF_FC2F, u64::MAX, u64::MAX, u64::MAX];
+    /// `(p - 1) / 2`
+    const HALF_P: Fe = [0xFFFF_FFFF_7FFF_FE17, u64::MAX, u64::MAX, u64::MAX >> 1];
+    /// `2^256 mod p`
+    const C: u128 = 0x1_0000_03D1;
+
+    fn from_be_bytes(data: &[u8]) -> Fe {
+        let mut fe = [0u64; 4];
+        for (limb, chunk) in fe.iter_mut().zip(data.rchunks(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().expect("fixed size"));
+        }
+        fe
+    }
+
+    fn mul(a: &Fe, b: &Fe) -> Fe {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let t = wide[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+                wide[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+
+        let mut r = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let t = wide[i] as u128 + wide[i + 4] as u128 * C + carry;
+            r[i] = t as u64;
+            carry = t >> 64;
+        }
+        while carry > 0 {
+            let mut t = carry * C;
+            for limb in &mut r {
+                let s = *limb as u128 + t;
+                *limb = s as u64;
+                t = s >> 64;
+            }
+            carry = t;
+        }
+        if r[1..] == P[1..] && r[0] >= P[0] {
+            r = [r[0] - P[0], 0, 0, 0];
+        }
+        r
+    }
+
+    /// Euler criterion: `y^((p-1)/2) == 1`.
+    fn is_quad(y: &Fe) -> bool {
+        let mut acc: Fe = [1, 0, 0, 0];
+        for limb in HALF_P.iter().rev() {
+            for bit in (0..64).rev() {
+                acc = mul(&acc, &acc);
+                if limb >> bit & 1 == 1 {
+                    acc = mul(&acc, y);
+                }
+            }
+        }
+        acc == [1, 0, 0, 0]
+    }
+
+    fn y_is_quad(point: &PublicKey) -> bool {
+        is_quad(&from_be_bytes(&point.serialize_uncompressed()[33..]))
+    }
+
+    pub fn decode_point(data: &[u8; 33], prefix: u8) -> PublicKey {
+        let mut key = *data;
+        key[0] = 0x02;
+        let point = PublicKey::from_slice(&key).expect("valid curve point");
+        let quad = data[0] == prefix;
+        if y_is_quad(&point) == quad {
+            point
+        } else {
+            point.negate(secp256k1_zkp::SECP256K1)
+        }
+    }
+
+    pub fn encode_point(point: &PublicKey, prefix: u8) -> [u8; 33] {
+        let mut data = point.serialize();
+        data[0] = prefix | !y_is_quad(point) as u8;
+        data
+    }
+}
+
+const NOISE_DUMB_TAG: &str = "urn:lnpbp:rgb:noise-dumb#240215";
+
 /// A dumb placeholder for a future bulletproofs.
+///
+/// The placeholder data are derived deterministically from the blinding factor
+/// of the concealed value (see [`NoiseDumb::with_blinding`]), such that
+/// confidential state is reproducible from the revealed one.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[derive(StrictType, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
@@ -414,9 +1013,20 @@ impl CommitVerify<RevealedValue, UntaggedProtocol> for PedersenCommitment {
 pub struct NoiseDumb(Array<u8, 512>);
 
 impl Default for NoiseDumb {
-    fn default() -> Self {
+    fn default() -> Self { NoiseDumb::with_blinding(BlindingFactor::EMPTY) }
+}
+
+impl NoiseDumb {
+    /// Derives placeholder data from the blinding factor, using tagged SHA256
+    /// in a counter mode as a pseudo-random function.
+    pub fn with_blinding(blinding: BlindingFactor) -> Self {
         let mut dumb = [0u8; 512];
-        thread_rng().fill(&mut dumb);
+        for (counter, chunk) in dumb.chunks_exact_mut(32).enumerate() {
+            let mut engine = Sha256::from_tag(NOISE_DUMB_TAG);
+            engine.input_raw(blinding.as_slice());
+            engine.input_raw(&(counter as u32).to_le_bytes());
+            chunk.copy_from_slice(&engine.finish());
+        }
         NoiseDumb(dumb.into())
     }
 }
@@ -445,6 +1055,22 @@ impl Default for RangeProof {
     fn default() -> Self { RangeProof::Placeholder(default!()) }
 }
 
+impl RangeProof {
+    /// Constructs placeholder range proof for a value with the given blinding
+    /// factor.
+    pub fn placeholder(blinding: BlindingFactor) -> Self {
+        RangeProof::Placeholder(NoiseDumb::with_blinding(blinding))
+    }
+
+    /// Verifies that the range proof is valid for the given commitment, i.e.
+    /// the committed value doesn't exceed the type boundaries.
+    pub fn verify(&self, _commitment: &PedersenCommitment) -> Result<(), RangeProofError> {
+        match self {
+            RangeProof::Placeholder(_) => Err(RangeProofError::BulletproofsAbsent),
+        }
+    }
+}
+
 pub struct PedersenProtocol;
 
 impl CommitmentProtocol for PedersenProtocol {}
@@ -482,7 +1108,7 @@ impl CommitVerify<RevealedValue, PedersenProtocol> for ConcealedValue {
         );
         let commitment = PedersenCommitment::commit(revealed);
         // TODO: Do actual conceal upon integration of bulletproofs library
-        let range_proof = RangeProof::default();
+        let range_proof = RangeProof::placeholder(revealed.blinding);
         ConcealedValue {
             commitment,
             range_proof,
@@ -515,7 +1141,7 @@ pub enum RangeProofError {
 impl ConcealedValue {
     /// Verifies validity of the range proof.
     pub fn verify_range_proof(&self) -> Result<bool, RangeProofError> {
-        Err(RangeProofError::BulletproofsAbsent)
+        self.range_proof.verify(&self.commitment).map(|_| true)
     }
 }
 
@@ -543,6 +1169,91 @@ mod test {
         assert_eq!(generators.len(), 1);
     }
 
+    #[test]
+    fn conceal_determinism() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let value = RevealedValue::with_random_blinding(15, &mut thread_rng(), tag);
+        let other = RevealedValue::with_random_blinding(15, &mut thread_rng(), tag);
+
+        assert_eq!(value.conceal(), value.conceal());
+        assert_eq!(
+            value.conceal().range_proof,
+            RangeProof::Placeholder(NoiseDumb::with_blinding(value.blinding))
+        );
+        assert_ne!(value.conceal().range_proof, other.conceal().range_proof);
+        assert_eq!(NoiseDumb::default(), NoiseDumb::default());
+    }
+
+    #[test]
+    fn verify_reveal() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let value = RevealedValue::with_random_blinding(15, &mut thread_rng(), tag);
+        let commitment = PedersenCommitment::commit(&value);
+
+        assert_eq!(commitment.verify_reveal(15u64, value.blinding, tag), Ok(()));
+        assert_eq!(
+            commitment.verify_reveal(16u64, value.blinding, tag),
+            Err(CommitmentError::RevealMismatch(commitment))
+        );
+        assert_eq!(
+            commitment.verify_reveal(15u64, BlindingFactor::EMPTY, tag),
+            Err(CommitmentError::RevealMismatch(commitment))
+        );
+        assert_eq!(
+            commitment.verify_reveal(15u64, value.blinding, AssetTag::from_byte_array([2u8; 32])),
+            Err(CommitmentError::RevealMismatch(commitment))
+        );
+        assert_eq!(
+            value.conceal().range_proof.verify(&commitment),
+            Err(RangeProofError::BulletproofsAbsent)
+        );
+    }
+
+    #[test]
+    fn blinding_field_element() {
+        let order = BlindingFactor::FIELD_ORDER;
+        let mut below = order;
+        below[31] -= 1;
+        let mut above = order;
+        above[31] += 1;
+
+        assert_eq!(BlindingFactor::from_be_bytes([0u8; 32]), Err(InvalidFieldElement::Zero));
+        assert_eq!(
+            BlindingFactor::from_be_bytes(order),
+            Err(InvalidFieldElement::FieldOrderOverflow)
+        );
+        assert_eq!(
+            BlindingFactor::try_from([0xFFu8; 32]),
+            Err(InvalidFieldElement::FieldOrderOverflow)
+        );
+        assert_eq!(BlindingFactor::from_be_bytes(below).unwrap().to_be_bytes(), below);
+
+        assert_eq!(BlindingFactor::from_be_bytes_reduced(order), Err(InvalidFieldElement::Zero));
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(
+            BlindingFactor::from_be_bytes_reduced(above)
+                .unwrap()
+                .to_be_bytes(),
+            one
+        );
+        let reduced = BlindingFactor::from_be_bytes_reduced([0xFFu8; 32]).unwrap();
+        assert_eq!(
+            reduced.to_hex(),
+            "000000000000000000000000000000014551231950b75fc4402da1732fc9bebe"
+        );
+        assert_eq!(BlindingFactor::from(reduced.to_secret_key()), reduced);
+        assert_eq!(
+            BlindingFactor::from_be_bytes_reduced(below).unwrap(),
+            BlindingFactor::from_be_bytes(below).unwrap()
+        );
+
+        assert_eq!(
+            BlindingFactor::from_str(&[0u8; 32].to_hex()),
+            Err(BlindingParseError::InvalidFieldElement(InvalidFieldElement::Zero))
+        );
+    }
+
     #[test]
     fn pedersen_blinding_mismatch() {
         let mut r = thread_rng();
@@ -650,4 +1361,146 @@ mod test {
 
         assert!(secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]))
     }
+
+    #[test]
+    fn pedersen_u128() {
+        let blinding = BlindingFactor::random();
+        let generator =
+            secp256k1_zkp::Generator::new_unblinded(SECP256K1, secp256k1_zkp::Tag::from([1u8; 32]));
+        let tweak = secp256k1_zkp::Tweak::from_inner(blinding.0.into_inner()).unwrap();
+        for value in [1u64, 15, u64::MAX] {
+            assert_eq!(
+                commit_u128(value as u128, &blinding, generator),
+                secp256k1_zkp::PedersenCommitment::new(SECP256K1, value, tweak, generator)
+            );
+        }
+
+        let blinding =
+            BlindingFactor::from(secp256k1_zkp::SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let commit = |value: FungibleState| {
+            PedersenCommitment::commit(&RevealedValue::with_blinding(value, blinding, tag))
+                .into_inner()
+        };
+
+        let big = u64::MAX as u128 * 3;
+        let a = commit(FungibleState::Bits128(big));
+        let b = commit(FungibleState::Bits64(7));
+        let c = commit(FungibleState::Bits128(big - 2));
+        let d = commit(FungibleState::Bits64(9));
+        assert!(secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, d]));
+        assert!(!secp256k1_zkp::verify_commitments_sum_to_equal(SECP256K1, &[a, b], &[c, b]));
+    }
+
+    #[test]
+    fn checked_arithmetics() {
+        let max = FungibleState::Bits64(u64::MAX);
+        let one = FungibleState::Bits64(1);
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(
+            FungibleState::Bits128(u64::MAX as u128).checked_add(one),
+            Some(FungibleState::Bits128(u64::MAX as u128 + 1))
+        );
+        assert_eq!(one.checked_sub(max), None);
+
+        let decimal = FungibleState::Decimal(FixedDecimal::new(150, 2));
+        assert_eq!(
+            decimal.checked_add(one),
+            Some(FungibleState::Decimal(FixedDecimal::new(250, 2)))
+        );
+        assert_eq!(
+            decimal.checked_sub(FungibleState::Decimal(FixedDecimal::new(5, 1))),
+            Some(FungibleState::Decimal(FixedDecimal::new(100, 2)))
+        );
+        assert_eq!(FungibleState::Decimal(FixedDecimal::new(1, 40)).checked_add(one), None);
+    }
+
+    #[test]
+    fn parse_display() {
+        for s in ["0", "42", "18446744073709551616", "0.001", "12.50"] {
+            assert_eq!(FungibleState::from_str(s).unwrap().to_string(), s);
+        }
+        assert_eq!(FungibleState::from_str("42").unwrap(), FungibleState::Bits64(42));
+        assert_eq!(
+            FungibleState::from_str("18446744073709551616")
+                .unwrap()
+                .fungible_type(),
+            schema::FungibleType::Unsigned128Bit
+        );
+        assert_eq!(
+            FungibleState::from_str(".5").unwrap(),
+            FungibleState::Decimal(FixedDecimal::new(5, 1))
+        );
+        assert!(FungibleState::from_str("1.2.3").is_err());
+        assert_eq!(FungibleState::Bits128(u64::MAX as u128 + 1).as_u64(), None);
+    }
+
+    #[test]
+    fn amount_arithmetics() {
+        let max = Amount::MAX;
+        assert_eq!(max.checked_add(1), None);
+        assert_eq!(max.saturating_add(1), max);
+        assert_eq!(max.overflowing_add(1), (Amount::ZERO, true));
+        assert_eq!(Amount::ZERO.checked_sub(1), None);
+        assert_eq!(Amount::ZERO.saturating_sub(1), Amount::ZERO);
+        assert_eq!(Amount::from(3).checked_mul(5), Some(Amount::from(15)));
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(Amount::checked_sum([1u64, 2, 3]), Some(Amount::from(6)));
+        assert_eq!(Amount::checked_sum([u64::MAX, 1]), None);
+        assert_eq!(Amount::try_from(FungibleState::Bits128(u64::MAX as u128 + 1)).ok(), None);
+    }
+
+    #[test]
+    fn amount_precision() {
+        let amount = Amount::from(123_456_789);
+        assert_eq!(amount.to_string_with_precision(8), "1.234 567 89");
+        assert_eq!(amount.to_string_with_precision(0), "123456789");
+        assert_eq!(Amount::from(100_000_000).to_string_with_precision(8), "1");
+        assert_eq!(Amount::from(5).to_string_with_precision(2), "0.05");
+
+        assert_eq!(Amount::from_str_with_precision("1.234 567 89", 8), Ok(amount));
+        assert_eq!(Amount::from_str_with_precision("1", 8), Ok(Amount::from(100_000_000)));
+        assert_eq!(Amount::from_str_with_precision(".05", 2), Ok(Amount::from(5)));
+        assert_eq!(
+            Amount::from_str_with_precision("0.001", 2),
+            Err(AmountParseError::Precision(2))
+        );
+        assert_eq!(
+            Amount::from_str_with_precision("184467440737.09551616", 8),
+            Err(AmountParseError::Overflow)
+        );
+        assert!(Amount::from_str_with_precision("1.2x", 8).is_err());
+        assert_eq!(Amount::from_str_with_precision("2.100", 1), Ok(Amount::from(21)));
+        assert_eq!(
+            Amount::from_str_with_precision("1.+5", 2),
+            Err(AmountParseError::InvalidChar('+'))
+        );
+        assert_eq!(Amount::from_str_with_precision(".", 2), Err(AmountParseError::Empty));
+    }
+
+    #[test]
+    fn shared_context() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let context = PedersenContext::new();
+
+        std::thread::scope(|scope| {
+            for value in [FungibleState::Bits64(1), FungibleState::Bits128(u64::MAX as u128 + 3)] {
+                let context = &context;
+                scope.spawn(move || {
+                    let revealed =
+                        RevealedValue::with_random_blinding(value, &mut thread_rng(), tag);
+                    assert_eq!(context.commit(&revealed), PedersenCommitment::commit(&revealed));
+                });
+            }
+        });
+        assert_eq!(context.generators.read().unwrap().len(), 1);
+
+        let blinding = BlindingFactor::EMPTY;
+        let a = context.commit(&RevealedValue::with_blinding(15, blinding, tag));
+        let b = context.commit(&RevealedValue::with_blinding(7, blinding, tag));
+        let c = context.commit(&RevealedValue::with_blinding(13, blinding, tag));
+        let d = context.commit(&RevealedValue::with_blinding(9, blinding, tag));
+        assert!(context.verify_sum(&[a, b], &[c, d]));
+        assert!(!context.verify_sum(&[a, b], &[c, c]));
+    }
 }
@@ -0,0 +1,156 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Confidential seals which can be revealed by the counterparty.
+//!
+//! A sender, knowing the public key of the receiver, may define a seal on
+//! behalf of the receiver without an invoice, providing the receiver with an
+//! ECDH hint. The hint contains the seal definition encrypted with a key
+//! derived from the shared secret between an ephemeral sender key and the
+//! receiver key. Only the holder of the receiver private key can decrypt it;
+//! for third parties the hint is indistinguishable from random data. The
+//! decrypted seal definition is authenticated by checking that it conceals to
+//! the hinted [`SecretSeal`].
+
+use amplify::confinement::TinyBlob;
+use bp::secp256k1::ecdh::SharedSecret;
+use bp::secp256k1::rand::thread_rng;
+use bp::secp256k1::{PublicKey, SecretKey, SECP256K1};
+use bp::CompressedPk;
+use commit_verify::{Conceal, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::{GraphSeal, SealDefinition, SecretSeal, LIB_NAME_RGB};
+
+const SEAL_HINT_TAG: &str = "urn:lnpbp:rgb:seal-hint#240110";
+
+/// Errors revealing seal from a [`HintedSeal`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SealHintError {
+    /// seal hint is not addressed to the provided key or is corrupted.
+    Undecryptable,
+}
+
+/// Confidential seal accompanied by an ECDH hint allowing the counterparty to
+/// reveal the seal definition.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct HintedSeal {
+    pub seal: SecretSeal,
+    /// Ephemeral public key of the sender.
+    pub ephemeral: CompressedPk,
+    /// Seal definition encrypted to the counterparty.
+    pub payload: TinyBlob,
+}
+
+impl StrictSerialize for HintedSeal {}
+impl StrictDeserialize for HintedSeal {}
+
+impl HintedSeal {
+    /// Conceals the seal, encrypting its definition to the `counterparty` key
+    /// using a random ephemeral key.
+    pub fn new(seal: SealDefinition<GraphSeal>, counterparty: PublicKey) -> Self {
+        Self::with_ephemeral(seal, counterparty, SecretKey::new(&mut thread_rng()))
+    }
+
+    /// Conceals the seal, encrypting its definition to the `counterparty` key
+    /// using the provided ephemeral key.
+    pub fn with_ephemeral(
+        seal: SealDefinition<GraphSeal>,
+        counterparty: PublicKey,
+        ephemeral: SecretKey,
+    ) -> Self {
+        let secret_seal = seal.conceal();
+        let mut payload = seal
+            .to_strict_serialized::<{ u8::MAX as usize }>()
+            .expect("seal definition always fits 255 bytes")
+            .into_inner();
+        let shared = SharedSecret::new(&counterparty, &ephemeral);
+        apply_keystream(&shared, secret_seal, &mut payload);
+        HintedSeal {
+            seal: secret_seal,
+            ephemeral: ephemeral.public_key(SECP256K1).into(),
+            payload: TinyBlob::try_from(payload).expect("seal definition always fits 255 bytes"),
+        }
+    }
+
+    /// Reveals the seal definition using counterparty private key.
+    pub fn reveal(&self, key: &SecretKey) -> Result<SealDefinition<GraphSeal>, SealHintError> {
+        let shared = SharedSecret::new(&self.ephemeral, key);
+        let mut payload = self.payload.to_vec();
+        apply_keystream(&shared, self.seal, &mut payload);
+        let seal = SealDefinition::<GraphSeal>::from_strict_serialized::<{ u8::MAX as usize }>(
+            TinyBlob::try_from(payload).expect("same length as the payload"),
+        )
+        .map_err(|_| SealHintError::Undecryptable)?;
+        if seal.conceal() != self.seal {
+            return Err(SealHintError::Undecryptable);
+        }
+        Ok(seal)
+    }
+}
+
+/// Encrypts or decrypts data with a keystream derived from the ECDH shared
+/// secret and the concealed seal.
+fn apply_keystream(shared: &SharedSecret, seal: SecretSeal, data: &mut [u8]) {
+    for (counter, chunk) in data.chunks_mut(32).enumerate() {
+        let mut engine = Sha256::from_tag(SEAL_HINT_TAG);
+        engine.input_raw(&shared.secret_bytes());
+        engine.input_raw(&seal[..]);
+        engine.input_raw(&(counter as u32).to_le_bytes());
+        let keystream = engine.finish();
+        for (byte, key) in chunk.iter_mut().zip(keystream) {
+            *byte ^= key;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+
+    use super::*;
+
+    #[test]
+    fn reveal() {
+        let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+            CloseMethod::TapretFirst,
+            TxPtr::WitnessTx,
+            2,
+            0x1234,
+        ));
+        let key = SecretKey::new(&mut thread_rng());
+        let hinted = HintedSeal::new(seal, key.public_key(SECP256K1));
+        assert_eq!(hinted.seal, seal.conceal());
+        assert_eq!(hinted.reveal(&key), Ok(seal));
+
+        let other = SecretKey::new(&mut thread_rng());
+        assert_eq!(hinted.reveal(&other), Err(SealHintError::Undecryptable));
+    }
+}
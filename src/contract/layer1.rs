@@ -0,0 +1,182 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Abstraction over layer 1 blockchains used for anchoring.
+
+use bp::{dbc, Outpoint, Txid};
+use commit_verify::mpc;
+use strict_encoding::StrictDumb;
+
+use crate::{Anchor, ExposedSeal, Layer1, Output, SealDefinition, WitnessId};
+
+/// Data bound to a specific layer 1, made of a layer 1 identifier and a
+/// layer-independent part, like transaction id, outpoint, seal or anchor.
+///
+/// Code generic over this trait doesn't have to match on the layer 1 variants
+/// and keeps working when new layers are added. Bitcoin is the default layer
+/// 1.
+pub trait OnLayer1: Sized {
+    /// Layer-independent part of the data.
+    type Inner;
+
+    fn layer1(&self) -> Layer1;
+
+    fn as_inner(&self) -> &Self::Inner;
+
+    fn into_parts(self) -> (Layer1, Self::Inner);
+
+    fn with_layer1(layer1: Layer1, inner: Self::Inner) -> Self;
+
+    #[inline]
+    fn bitcoin(inner: Self::Inner) -> Self { Self::with_layer1(Layer1::Bitcoin, inner) }
+
+    /// Checks whether the data belong to the same layer 1 as `other`.
+    #[inline]
+    fn is_same_layer1(&self, other: &impl OnLayer1) -> bool { self.layer1() == other.layer1() }
+}
+
+impl OnLayer1 for WitnessId {
+    type Inner = Txid;
+
+    fn layer1(&self) -> Layer1 {
+        match self {
+            WitnessId::Bitcoin(_) => Layer1::Bitcoin,
+            WitnessId::Liquid(_) => Layer1::Liquid,
+        }
+    }
+
+    fn as_inner(&self) -> &Txid {
+        match self {
+            WitnessId::Bitcoin(txid) | WitnessId::Liquid(txid) => txid,
+        }
+    }
+
+    fn into_parts(self) -> (Layer1, Txid) { (self.layer1(), *self.as_inner()) }
+
+    fn with_layer1(layer1: Layer1, txid: Txid) -> Self {
+        match layer1 {
+            Layer1::Bitcoin => WitnessId::Bitcoin(txid),
+            Layer1::Liquid => WitnessId::Liquid(txid),
+        }
+    }
+}
+
+impl OnLayer1 for Output {
+    type Inner = Outpoint;
+
+    fn layer1(&self) -> Layer1 {
+        match self {
+            Output::Bitcoin(_) => Layer1::Bitcoin,
+            Output::Liquid(_) => Layer1::Liquid,
+        }
+    }
+
+    fn as_inner(&self) -> &Outpoint {
+        match self {
+            Output::Bitcoin(outpoint) | Output::Liquid(outpoint) => outpoint,
+        }
+    }
+
+    fn into_parts(self) -> (Layer1, Outpoint) { (self.layer1(), *self.as_inner()) }
+
+    fn with_layer1(layer1: Layer1, outpoint: Outpoint) -> Self {
+        match layer1 {
+            Layer1::Bitcoin => Output::Bitcoin(outpoint),
+            Layer1::Liquid => Output::Liquid(outpoint),
+        }
+    }
+}
+
+impl<U: ExposedSeal> OnLayer1 for SealDefinition<U> {
+    type Inner = U;
+
+    #[inline]
+    fn layer1(&self) -> Layer1 { SealDefinition::layer1(*self) }
+
+    fn as_inner(&self) -> &U {
+        match self {
+            SealDefinition::Bitcoin(seal) | SealDefinition::Liquid(seal) => seal,
+        }
+    }
+
+    fn into_parts(self) -> (Layer1, U) { (SealDefinition::layer1(self), *self.as_inner()) }
+
+    fn with_layer1(layer1: Layer1, seal: U) -> Self {
+        match layer1 {
+            Layer1::Bitcoin => SealDefinition::Bitcoin(seal),
+            Layer1::Liquid => SealDefinition::Liquid(seal),
+        }
+    }
+}
+
+impl<P: mpc::Proof + StrictDumb> OnLayer1 for Anchor<P> {
+    type Inner = dbc::Anchor<P>;
+
+    #[inline]
+    fn layer1(&self) -> Layer1 { Anchor::layer1(self) }
+
+    #[inline]
+    fn as_inner(&self) -> &dbc::Anchor<P> { self }
+
+    fn into_parts(self) -> (Layer1, dbc::Anchor<P>) {
+        match self {
+            Anchor::Bitcoin(anchor) => (Layer1::Bitcoin, anchor),
+            Anchor::Liquid(anchor) => (Layer1::Liquid, anchor),
+        }
+    }
+
+    fn with_layer1(layer1: Layer1, anchor: dbc::Anchor<P>) -> Self {
+        match layer1 {
+            Layer1::Bitcoin => Anchor::Bitcoin(anchor),
+            Layer1::Liquid => Anchor::Liquid(anchor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+
+    use super::*;
+    use crate::GraphSeal;
+
+    #[test]
+    fn layer1_parts() {
+        assert_eq!(Layer1::default(), Layer1::Bitcoin);
+
+        let txid = Txid::from([1; 32]);
+        let outpoint = Outpoint::new(txid, 2);
+        let seal = GraphSeal::with_blinding(CloseMethod::TapretFirst, TxPtr::WitnessTx, 2, 1);
+        for layer1 in [Layer1::Bitcoin, Layer1::Liquid] {
+            let witness_id = WitnessId::with_layer1(layer1, txid);
+            assert_eq!(witness_id.into_parts(), (layer1, txid));
+            let output = Output::with_layer1(layer1, outpoint);
+            assert_eq!(output.into_parts(), (layer1, outpoint));
+            assert!(output.is_same_layer1(&witness_id));
+            let seal = SealDefinition::with_layer1(layer1, seal);
+            assert_eq!(seal.as_inner(), &seal.into_parts().1);
+            assert_eq!(seal.output_or_witness(witness_id), Ok(output));
+        }
+        assert_eq!(WitnessId::bitcoin(txid), WitnessId::Bitcoin(txid));
+        assert!(!Output::bitcoin(outpoint).is_same_layer1(&WitnessId::Liquid(txid)));
+    }
+}
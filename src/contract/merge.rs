@@ -0,0 +1,263 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merging of copies of the same data with different reveal levels, which may
+//! come from overlapping consignments provided by multiple senders.
+
+use amplify::confinement::{Confined, SmallVec};
+use amplify::Wrapper;
+use commit_verify::merkle::MerkleNode;
+use commit_verify::{CommitmentId, Conceal};
+
+use crate::{
+    Assign, AssignPaymentTag, AssignmentType, Assignments, ExposedSeal, ExposedState, Extension,
+    Genesis, OpId, Operation, RevealError, SealDefinition, SecretSeal, StateType, Transition,
+    TypedAssigns,
+};
+
+/// Errors merging two copies of the same data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum MergeRevealError {
+    /// merged operations have different ids {0} and {1}.
+    OperationMismatch(OpId, OpId),
+
+    /// merged seals {0} and {1} are different.
+    SealMismatch(SecretSeal, SecretSeal),
+
+    /// merged assignments commit to different data ({0} and {1}).
+    AssignMismatch(MerkleNode, MerkleNode),
+
+    /// merged assignments have different state types {0} and {1}.
+    StateTypeMismatch(StateType, StateType),
+
+    /// merged assignments have different number of items ({0} and {1}).
+    CountMismatch(u16, u16),
+
+    /// merged assignments of type {0} are present only in one of the copies.
+    AssignmentTypeMismatch(AssignmentType),
+
+    /// merged assignment reveals data not matching its confidential form: {0}
+    #[from]
+    Reveal(RevealError),
+}
+
+/// Merging two copies of the same object with different reveal levels into
+/// the object with all the data revealed by any of the copies.
+pub trait MergeReveal: Sized {
+    /// Merges the copies, failing if they are not copies of the same object.
+    fn merge_reveal(self, other: Self) -> Result<Self, MergeRevealError>;
+}
+
+impl<Seal: ExposedSeal> MergeReveal for SealDefinition<Seal> {
+    fn merge_reveal(self, other: Self) -> Result<Self, MergeRevealError> {
+        if self != other {
+            return Err(MergeRevealError::SealMismatch(self.conceal(), other.conceal()));
+        }
+        Ok(self)
+    }
+}
+
+impl<State: ExposedState, Seal: ExposedSeal> MergeReveal for Assign<State, Seal> {
+    fn merge_reveal(self, other: Self) -> Result<Self, MergeRevealError> {
+        let (id, other_id) = (self.commitment_id(), other.commitment_id());
+        if id != other_id {
+            return Err(MergeRevealError::AssignMismatch(id, other_id));
+        }
+        let merged = self.reveal_with(other.revealed_seal(), other.as_revealed_state().cloned())?;
+        Ok(match (self.payment_tag(), other.payment_tag()) {
+            (Some(AssignPaymentTag::Concealed(_)), Some(tag @ AssignPaymentTag::Revealed(_))) => {
+                merged.with_payment_tag(*tag)
+            }
+            _ => merged,
+        })
+    }
+}
+
+impl<Seal: ExposedSeal> MergeReveal for TypedAssigns<Seal> {
+    fn merge_reveal(self, other: Self) -> Result<Self, MergeRevealError> {
+        fn merge<State: ExposedState, Seal: ExposedSeal>(
+            assigns: SmallVec<Assign<State, Seal>>,
+            other: SmallVec<Assign<State, Seal>>,
+        ) -> Result<SmallVec<Assign<State, Seal>>, MergeRevealError> {
+            if assigns.len() != other.len() {
+                return Err(MergeRevealError::CountMismatch(
+                    assigns.len() as u16,
+                    other.len() as u16,
+                ));
+            }
+            let merged = assigns
+                .into_iter()
+                .zip(other)
+                .map(|(assign, other)| assign.merge_reveal(other))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Confined::try_from(merged).expect("the same number of items as in the source"))
+        }
+
+        match (self, other) {
+            (TypedAssigns::Declarative(a), TypedAssigns::Declarative(b)) => {
+                merge(a, b).map(TypedAssigns::Declarative)
+            }
+            (TypedAssigns::Fungible(a), TypedAssigns::Fungible(b)) => {
+                merge(a, b).map(TypedAssigns::Fungible)
+            }
+            (TypedAssigns::Structured(a), TypedAssigns::Structured(b)) => {
+                merge(a, b).map(TypedAssigns::Structured)
+            }
+            (TypedAssigns::Attachment(a), TypedAssigns::Attachment(b)) => {
+                merge(a, b).map(TypedAssigns::Attachment)
+            }
+            (a, b) => Err(MergeRevealError::StateTypeMismatch(a.state_type(), b.state_type())),
+        }
+    }
+}
+
+impl<Seal: ExposedSeal> MergeReveal for Assignments<Seal> {
+    fn merge_reveal(self, other: Self) -> Result<Self, MergeRevealError> {
+        let mut other = other.into_inner().into_inner();
+        let mut merged = Assignments::default();
+        for (ty, assigns) in self.into_inner() {
+            let other = other
+                .remove(&ty)
+                .ok_or(MergeRevealError::AssignmentTypeMismatch(ty))?;
+            merged
+                .insert(ty, assigns.merge_reveal(other)?)
+                .expect("the same number of types as in the source");
+        }
+        if let Some(ty) = other.into_keys().next() {
+            return Err(MergeRevealError::AssignmentTypeMismatch(ty));
+        }
+        Ok(merged)
+    }
+}
+
+impl MergeReveal for Genesis {
+    fn merge_reveal(mut self, other: Self) -> Result<Self, MergeRevealError> {
+        check_ids(&self, &other)?;
+        self.assignments = self.assignments.merge_reveal(other.assignments)?;
+        Ok(self)
+    }
+}
+
+impl MergeReveal for Extension {
+    fn merge_reveal(mut self, other: Self) -> Result<Self, MergeRevealError> {
+        check_ids(&self, &other)?;
+        self.assignments = self.assignments.merge_reveal(other.assignments)?;
+        Ok(self)
+    }
+}
+
+impl MergeReveal for Transition {
+    /// Merges copies of the state transition. The data not committed to by
+    /// the operation id (proof and signature) are taken from the first copy,
+    /// if present, and from the second one otherwise.
+    fn merge_reveal(mut self, other: Self) -> Result<Self, MergeRevealError> {
+        check_ids(&self, &other)?;
+        self.assignments = self.assignments.merge_reveal(other.assignments)?;
+        self.proof = self.proof.or(other.proof);
+        self.signature = self.signature.or(other.signature);
+        Ok(self)
+    }
+}
+
+fn check_ids(op: &impl Operation, other: &impl Operation) -> Result<(), MergeRevealError> {
+    let (id, other_id) = (op.id(), other.id());
+    if id != other_id {
+        return Err(MergeRevealError::OperationMismatch(id, other_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssetTag, BlindingFactor, GraphSeal, RevealedValue};
+
+    #[test]
+    fn merge_reveal() {
+        let ty = AssignmentType::with(1);
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GraphSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                TxPtr::WitnessTx,
+                vout,
+                1,
+            ))
+        };
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let transition = |assigns: Vec<Assign<RevealedValue, GraphSeal>>| {
+            let mut transition = Transition::strict_dumb();
+            transition
+                .assignments
+                .insert(ty, TypedAssigns::Fungible(Confined::try_from(assigns).unwrap()))
+                .unwrap();
+            transition
+        };
+        let first = Assign::revealed(seal(0), value(10));
+        let second = Assign::revealed(seal(1), value(5));
+        let concealed = first.conceal();
+
+        let revealed = transition(vec![first.clone(), second.clone()]);
+        let no_seal =
+            transition(vec![concealed.reveal_with(None, Some(value(10))).unwrap(), second.clone()]);
+        let no_state =
+            transition(vec![concealed.reveal_with(Some(seal(0)), None).unwrap(), second]);
+        assert_eq!(no_seal.id(), revealed.id());
+        assert_eq!(no_state.id(), revealed.id());
+
+        let merged = no_seal.clone().merge_reveal(no_state).unwrap();
+        assert_eq!(merged.id(), revealed.id());
+        assert_eq!(
+            merged.assignments.get(&ty).unwrap().as_fungible()[0].to_revealed(),
+            Some((seal(0), value(10)))
+        );
+        let merged = merged.merge_reveal(revealed.clone()).unwrap();
+        assert_eq!(merged.assignments, revealed.assignments);
+
+        let mut other = revealed.clone();
+        other.metadata = Confined::try_from(vec![1]).unwrap();
+        assert_eq!(
+            no_seal.merge_reveal(other.clone()).unwrap_err(),
+            MergeRevealError::OperationMismatch(revealed.id(), other.id())
+        );
+        assert_eq!(
+            seal(0).merge_reveal(seal(1)).unwrap_err(),
+            MergeRevealError::SealMismatch(seal(0).conceal(), seal(1).conceal())
+        );
+        assert!(matches!(
+            first.merge_reveal(Assign::revealed(seal(0), value(11))),
+            Err(MergeRevealError::AssignMismatch(..))
+        ));
+        assert_eq!(
+            transition(vec![concealed])
+                .assignments
+                .merge_reveal(revealed.assignments)
+                .unwrap_err(),
+            MergeRevealError::CountMismatch(1, 2)
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle commitment to the contract state for light clients.
+//!
+//! Each global state item and each owned output known to the contract state is
+//! a leaf of the Merkle tree constructed according to LNPBP-81 merklization
+//! rules. A server may provide a light client with a [`StateProof`] for a
+//! single leaf, which the client verifies against the state commitment
+//! obtained from a trusted source.
+
+use amplify::confinement::TinyVec;
+use commit_verify::merkle::MerkleNode;
+use commit_verify::{CommitStrategy, CommitmentId};
+
+use crate::{GlobalOrd, GlobalStateType, OutputAssignment, RevealedData, StateData, LIB_NAME_RGB};
+
+/// Merkle tree tag used for the contract state commitment.
+pub const STATE_MERKLE_TAG: [u8; 16] = *b"urn:rgb:state#24";
+
+/// Leaf of the contract state commitment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Owned(strict_dumb!()))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum StateLeaf {
+    #[strict_type(tag = 0x00)]
+    Global {
+        ty: GlobalStateType,
+        ord: GlobalOrd,
+        data: RevealedData,
+    },
+    #[strict_type(tag = 0x01)]
+    Owned(OutputAssignment<StateData>),
+}
+
+impl CommitStrategy for StateLeaf {
+    type Strategy = commit_verify::strategies::Strict;
+}
+
+impl CommitmentId for StateLeaf {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:state-leaf#240116A";
+    type Id = MerkleNode;
+}
+
+/// Proof of a leaf inclusion into the contract state commitment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct StateProof {
+    pub leaf: StateLeaf,
+    /// Position of the leaf in the tree.
+    pub pos: u32,
+    /// Number of leaves in the tree.
+    pub width: u32,
+    /// Roots of sibling subtrees, starting from the leaf.
+    pub path: TinyVec<MerkleNode>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Step {
+    Single,
+    Left,
+    Right,
+}
+
+/// Returns steps from the root of the tree of `width` leaves towards the leaf
+/// at `pos`, each with its depth. The steps follow the tree splitting used by
+/// LNPBP-81 merklization.
+fn steps(width: u32, pos: u32) -> Vec<(u8, Step)> {
+    let mut steps = vec![];
+    if width <= 1 {
+        return steps;
+    }
+    let (mut lo, mut len, mut depth) = (0u32, width, 0u8);
+    loop {
+        match len {
+            1 => {
+                steps.push((depth, Step::Single));
+                break;
+            }
+            2 => {
+                steps.push((depth, if pos == lo { Step::Left } else { Step::Right }));
+                break;
+            }
+            _ => {
+                let div = len / 2 + len % 2;
+                if pos < lo + div {
+                    steps.push((depth, Step::Left));
+                    len = div;
+                } else {
+                    steps.push((depth, Step::Right));
+                    lo += div;
+                    len -= div;
+                }
+                depth += 1;
+            }
+        }
+    }
+    steps
+}
+
+fn merklize_subtree(leaves: &[MerkleNode], depth: u8, width: u32) -> MerkleNode {
+    let tag = STATE_MERKLE_TAG;
+    match leaves {
+        [] => MerkleNode::void(tag, depth, width),
+        [leaf] => MerkleNode::single(tag, depth, width, *leaf),
+        [leaf1, leaf2] => MerkleNode::branches(tag, depth, width, *leaf1, *leaf2),
+        _ => {
+            let div = leaves.len() / 2 + leaves.len() % 2;
+            let branch1 = merklize_subtree(&leaves[..div], depth + 1, width);
+            let branch2 = merklize_subtree(&leaves[div..], depth + 1, width);
+            MerkleNode::branches(tag, depth, width, branch1, branch2)
+        }
+    }
+}
+
+/// Computes Merkle root over the leaves.
+pub(crate) fn merklize(leaves: &[MerkleNode]) -> MerkleNode {
+    match leaves {
+        [leaf] => *leaf,
+        _ => merklize_subtree(leaves, 0, leaves.len() as u32),
+    }
+}
+
+/// Constructs inclusion proof for the leaf at position `pos`.
+pub(crate) fn prove(leaf: StateLeaf, leaves: &[MerkleNode], pos: u32) -> StateProof {
+    let width = leaves.len() as u32;
+    let mut path = vec![];
+    let mut lo = 0usize;
+    let mut len = leaves.len();
+    for (depth, step) in steps(width, pos) {
+        let div = if len == 2 { 1 } else { len / 2 + len % 2 };
+        let subtree = |range: &[MerkleNode]| match range {
+            [leaf] if len == 2 => *leaf,
+            _ => merklize_subtree(range, depth + 1, width),
+        };
+        match step {
+            Step::Single => {}
+            Step::Left => {
+                path.push(subtree(&leaves[lo + div..lo + len]));
+                len = div;
+            }
+            Step::Right => {
+                path.push(subtree(&leaves[lo..lo + div]));
+                lo += div;
+                len -= div;
+            }
+        }
+    }
+    path.reverse();
+    StateProof {
+        leaf,
+        pos,
+        width,
+        path: TinyVec::try_from(path).expect("tree depth never exceeds 32"),
+    }
+}
+
+impl StateProof {
+    /// Computes Merkle root the proof commits to.
+    ///
+    /// Returns `None` if the proof structure doesn't match the tree width.
+    pub fn root(&self) -> Option<MerkleNode> {
+        if self.pos >= self.width {
+            return None;
+        }
+        let tag = STATE_MERKLE_TAG;
+        let mut node = self.leaf.commitment_id();
+        let mut path = self.path.iter();
+        for (depth, step) in steps(self.width, self.pos).into_iter().rev() {
+            node = match step {
+                Step::Single => MerkleNode::single(tag, depth, self.width, node),
+                Step::Left => MerkleNode::branches(tag, depth, self.width, node, *path.next()?),
+                Step::Right => MerkleNode::branches(tag, depth, self.width, *path.next()?, node),
+            };
+        }
+        if path.next().is_some() {
+            return None;
+        }
+        Some(node)
+    }
+
+    /// Verifies that the proof leaf is included into the state with the given
+    /// commitment.
+    pub fn verify(&self, commitment: MerkleNode) -> bool { self.root() == Some(commitment) }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::SmallVec;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssignmentType, OpId, Opout};
+
+    fn leaf(no: u16) -> StateLeaf {
+        let mut output = OutputAssignment::<StateData>::strict_dumb();
+        output.opout = Opout::new(OpId::from([0x11; 32]), AssignmentType::with(1), no);
+        StateLeaf::Owned(output)
+    }
+
+    #[test]
+    fn lnpbp81_compatibility() {
+        for width in 0..12u16 {
+            let nodes = (0..width)
+                .map(|no| leaf(no).commitment_id())
+                .collect::<Vec<_>>();
+            let ids = nodes
+                .iter()
+                .map(MerkleNode::commitment_id)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                merklize(&ids),
+                MerkleNode::merklize(STATE_MERKLE_TAG, &SmallVec::try_from(nodes).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn inclusion() {
+        for width in 1..12u16 {
+            let leaves = (0..width).map(leaf).collect::<Vec<_>>();
+            let ids = leaves
+                .iter()
+                .map(StateLeaf::commitment_id)
+                .collect::<Vec<_>>();
+            let root = merklize(&ids);
+            for pos in 0..width {
+                let proof = prove(leaves[pos as usize].clone(), &ids, pos as u32);
+                assert!(proof.verify(root));
+
+                let mut forged = proof.clone();
+                forged.leaf = leaf(width);
+                assert!(!forged.verify(root));
+            }
+        }
+    }
+}
@@ -21,48 +21,176 @@
 // limitations under the License.
 
 mod global;
+mod action;
 mod data;
 mod fungible;
 mod attachment;
 mod state;
 mod anchor;
+#[cfg(feature = "armor")]
+mod armor;
 pub mod seal;
+mod hint;
+mod layer1;
+mod stealth;
+mod payment;
+mod reserve;
+mod chain_proof;
+mod ticker;
+mod filter;
+mod precision;
+mod witness;
+mod sanity;
 pub mod assignments;
 mod operations;
+mod proof;
+mod checkpoint;
+mod channel;
+mod spec;
+mod disclosure;
+mod merkle;
 mod bundle;
+mod blank;
+mod cached;
+mod preimage;
+mod canonical;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod consignment;
+#[cfg(feature = "serde")]
+mod dump;
+#[cfg(feature = "compression")]
+mod compressed;
 #[allow(clippy::module_inception)]
 mod contract;
+mod store;
+mod merge;
+mod portfolio;
+mod simulate;
 
 use std::io::Write;
 
+pub use action::{ActionBuilder, ActionError};
 use amplify::confinement::TinyOrdSet;
-pub use anchor::{Anchor, AnchoredBundle, Layer1, WitnessAnchor};
+pub use anchor::{Anchor, AnchorSet, AnchorSetError, AnchoredBundle, Layer1, WitnessAnchor};
+#[cfg(feature = "armor")]
+pub use armor::{Armor, ArmorParseError, ARMOR_VERSION};
 pub use assignments::{
     Assign, AssignAttach, AssignData, AssignFungible, AssignRights, Assignments, AssignmentsRef,
-    TypedAssigns,
+    RevealError, TypedAssigns,
 };
 pub use attachment::{AttachId, ConcealedAttach, RevealedAttach};
-pub use bundle::{BundleId, BundleItem, TransitionBundle};
+pub use blank::{blank_bundles, BlankError};
+pub use bundle::{BundleError, BundleId, BundleItem, BundleWeight, TransitionBundle};
+pub use cached::CachedTransition;
+pub use canonical::{CanonicalOrd, CanonicalOrderError, CanonicalVec};
+#[cfg(feature = "cbor")]
+pub use cbor::{CborError, CborSerialize};
+pub use chain_proof::ChainProof;
+pub use channel::{Channel, ChannelError, ChannelId, ChannelUpdate};
+pub use checkpoint::{CheckpointId, HistoryCheckpoint};
 use commit_verify::CommitEncode;
+#[cfg(feature = "compression")]
+pub use compressed::{CompressedSerialize, CompressionError, COMPRESSED_FORMAT_V1};
+pub use consignment::{Consignment, ConsignmentId, ConsignmentReader, Terminal};
 pub use contract::{
-    AttachOutput, ContractHistory, ContractState, DataOutput, FungibleOutput, GlobalOrd, Opout,
-    OpoutParseError, Output, OutputAssignment, RightsOutput,
+    AffectedOutputs, AncestryError, AttachOutput, ContractHistory, ContractState, DataOutput,
+    FungibleOutput, GlobalOrd, HistoryUpdateError, OpStore, OperationPath, Opout, OpoutParseError,
+    Output, OutputAssignment, OwnedStateExport, RightsOutput, SharedOp, Spenders, StateView,
+    SupplyAudit, SupplyFigure, WitnessUpdateError,
 };
-pub use data::{ConcealedData, RevealedData, VoidState};
+pub use data::{Capabilities, ConcealedData, RevealedData, RevealedDataRef, VoidState};
+pub use disclosure::{DisclosedAssign, Disclosure, DisclosureError};
+#[cfg(feature = "serde")]
+pub use dump::{Dump, DumpMismatch, Dumpable};
+pub use filter::{SealFilter, SEAL_FILTER_M, SEAL_FILTER_P};
 pub use fungible::{
-    AssetTag, BlindingFactor, BlindingParseError, ConcealedValue, FungibleState,
-    InvalidFieldElement, NoiseDumb, PedersenCommitment, RangeProof, RangeProofError, RevealedValue,
+    Amount, AmountParseError, AssetTag, BlindingFactor, BlindingParseError, CommitmentError,
+    ConcealedValue, FixedDecimal, FungibleParseError, FungibleState, InvalidFieldElement,
+    NoiseDumb, PedersenCommitment, PedersenContext, RangeProof, RangeProofError, RevealedValue,
 };
 pub use global::{GlobalState, GlobalValues};
+pub use hint::{HintedSeal, SealHintError};
+pub use layer1::OnLayer1;
+pub use merge::{MergeReveal, MergeRevealError};
+pub use merkle::{StateLeaf, StateProof, STATE_MERKLE_TAG};
 pub use operations::{
     ContractId, Extension, Genesis, Input, Inputs, OpId, OpRef, Operation, Redeemed, Transition,
     Valencies,
 };
+pub use payment::{
+    AssignPaymentTag, ConcealedPaymentTag, PaymentProof, PaymentProofError, PaymentTag,
+    RevealedPaymentTag,
+};
+pub use portfolio::{OutputOwnership, Portfolio, PortfolioOutputs};
+pub use precision::Precision;
+pub use preimage::{OpPreimage, PreimageField, PreimageSection};
+pub use proof::{IdentitySig, VerifyingKeyId, ZkProof};
+pub use reserve::IssueReserveProof;
+pub use sanity::{dust_limit, WitnessTemplate, WitnessWarning, DUST_RELAY_FEE_RATE};
 pub use seal::{
-    ExposedSeal, GenesisSeal, GraphSeal, SealDefinition, SecretSeal, TxoSeal, WitnessId,
-    WitnessOrd, WitnessPos,
+    ExposedSeal, GenesisSeal, GraphSeal, InvoiceSeal, InvoiceSealParseError, SealDefinition,
+    SecretSeal, TxoSeal, WitnessId, WitnessOrd, WitnessPos,
+};
+pub use simulate::SimulationError;
+pub use spec::ContractSpecId;
+pub use state::{
+    ConfidentialState, ExposedState, FromStateData, StateCommitment, StateData, StateType,
+    StateTypeMismatch,
 };
-pub use state::{ConfidentialState, ExposedState, StateCommitment, StateData, StateType};
+pub use stealth::{PaymentCode, ScanKey, StealthSeal};
+pub use store::{HistoryStore, MemStateStore, StateStore};
+#[cfg(all(unix, feature = "mmap"))]
+pub use store::{MmapStore, MmapStoreError, MMAP_STORE_MAGIC};
+pub use ticker::{Name, NameError, Ticker};
+pub use witness::{check_pow, compact_target, header_hash, EmbeddedWitness, SpvError, SpvProof};
+
+/// Blockchain network on which the contract is issued.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = super::LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum ChainNet {
+    #[strict_type(dumb)]
+    #[display("bitcoin-mainnet")]
+    BitcoinMainnet = 0,
+    #[display("bitcoin-testnet")]
+    BitcoinTestnet = 1,
+    #[display("bitcoin-signet")]
+    BitcoinSignet = 2,
+    #[display("bitcoin-regtest")]
+    BitcoinRegtest = 3,
+    #[display("liquid-mainnet")]
+    LiquidMainnet = 4,
+    #[display("liquid-testnet")]
+    LiquidTestnet = 5,
+}
+
+impl ChainNet {
+    /// Layer 1 which is used by the contract unless an alternative layer 1 is
+    /// specified.
+    pub fn layer1(&self) -> Layer1 {
+        match self {
+            ChainNet::BitcoinMainnet |
+            ChainNet::BitcoinTestnet |
+            ChainNet::BitcoinSignet |
+            ChainNet::BitcoinRegtest => Layer1::Bitcoin,
+            ChainNet::LiquidMainnet | ChainNet::LiquidTestnet => Layer1::Liquid,
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        !matches!(self, ChainNet::BitcoinMainnet | ChainNet::LiquidMainnet)
+    }
+}
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
 #[display(lowercase)]
@@ -20,22 +20,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{btree_map, btree_set};
+use std::collections::{btree_map, btree_set, BTreeSet};
 use std::fmt::{self, Display, Formatter};
 use std::iter;
 use std::str::FromStr;
 
 use amplify::confinement::{SmallBlob, TinyOrdMap, TinyOrdSet};
-use amplify::hex::{FromHex, ToHex};
-use amplify::{hex, ByteArray, Bytes32, FromSliceError, Wrapper};
+use amplify::hex::FromHex;
+use amplify::{ByteArray, Bytes32, FromSliceError, Wrapper};
 use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32CHECKSUM};
+use bp::secp256k1::schnorr::Signature;
+use bp::secp256k1::{Keypair, Message, SecretKey, SECP256K1};
+use bp::XOnlyPk;
 use commit_verify::{mpc, CommitmentId, Conceal};
-use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize};
+use strict_encoding::{StrictDeserialize, StrictEncode, StrictSerialize, StrictWriter};
 
 use crate::schema::{self, ExtensionType, OpFullType, OpType, SchemaId, TransitionType};
 use crate::{
-    AltLayer1Set, AssignmentType, Assignments, AssignmentsRef, Ffv, GenesisSeal, GlobalState,
-    GraphSeal, Opout, ReservedByte, TypedAssigns, LIB_NAME_RGB,
+    AltLayer1Set, AssignmentType, Assignments, AssignmentsRef, ChainNet, ChainProof, Ffv,
+    GenesisSeal, GlobalState, GraphSeal, IdentitySig, IssueReserveProof, Opout, ReservedByte,
+    TypedAssigns, ZkProof, LIB_NAME_RGB,
 };
 
 #[derive(Wrapper, WrapperMut, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, From)]
@@ -126,9 +130,8 @@ impl Input {
 
 /// Unique operation (genesis, extensions & state transition) identifier
 /// equivalent to the commitment hash
-#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
-#[display(Self::to_hex)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB)]
 #[cfg_attr(
@@ -142,9 +145,32 @@ pub struct OpId(
     Bytes32,
 );
 
+impl ToBaid58<32> for OpId {
+    const HRI: &'static str = "op";
+    const CHUNKING: Option<Chunking> = CHUNKING_32CHECKSUM;
+    fn to_baid58_payload(&self) -> [u8; 32] { self.to_byte_array() }
+    fn to_baid58_string(&self) -> String { self.to_string() }
+}
+impl FromBaid58<32> for OpId {}
+impl Display for OpId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{::^}", self.to_baid58())
+        } else {
+            write!(f, "{::^.3}", self.to_baid58())
+        }
+    }
+}
 impl FromStr for OpId {
-    type Err = hex::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+    type Err = Baid58ParseError;
+    /// Parses checksummed Baid58 representation of the id; for backward
+    /// compatibility also accepts a plain hexadecimal string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 64 && s.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Self::from_hex(s).map_err(|err| Baid58ParseError::Unparsable(err.to_string()));
+        }
+        Self::from_baid58_maybe_chunked_str(s, ':', '#')
+    }
 }
 
 impl OpId {
@@ -153,7 +179,19 @@ impl OpId {
     }
 }
 
-/// Unique contract identifier equivalent to the contract genesis commitment
+/// Unique contract identifier equivalent to the contract genesis commitment.
+///
+/// Genesis commits to its [`ChainNet`] and the consensus version ([`Ffv`]),
+/// thus identical genesis data issued on different chains or networks produce
+/// different contract ids.
+///
+/// The genesis commitment tag has never changed, so existing contract ids
+/// stay stable only because the genesis commitment layout is kept backward
+/// compatible: [`ChainNet`] replaced the former `testnet` flag encoding bitcoin
+/// mainnet and testnet the same way, and the fields added later (issue reserves
+/// and chain proof) commit to nothing unless they are set. Golden tests check
+/// this against ids of a genesis in the original layout. Any change breaking
+/// this compatibility must change the genesis commitment tag.
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
 #[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
@@ -236,6 +274,10 @@ pub trait Operation {
     /// Returns [`ContractId`] this operation belongs to.
     fn contract_id(&self) -> ContractId;
 
+    /// Returns [`SchemaId`] of the contract schema, to which the operation
+    /// commits.
+    fn schema_id(&self) -> SchemaId;
+
     /// Returns [`Option::Some`]`(`[`TransitionType`]`)` for transitions or
     /// [`Option::None`] for genesis and extension operation types
     fn transition_type(&self) -> Option<TransitionType>;
@@ -252,7 +294,7 @@ pub trait Operation {
     fn globals(&self) -> &GlobalState;
     fn valencies(&self) -> &Valencies;
 
-    fn assignments(&self) -> AssignmentsRef;
+    fn assignments(&self) -> AssignmentsRef<'_>;
 
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>>;
 
@@ -260,6 +302,36 @@ pub trait Operation {
     /// While public state extension do have parent nodes, they do not contain
     /// indexed rights.
     fn inputs(&self) -> Inputs;
+
+    /// Returns information about the public rights (in form of [`Redeemed`]
+    /// wrapper structure), defined with "parent" operations, which this
+    /// state extension redeems.
+    ///
+    /// For genesis and state transitions always returns an empty list.
+    fn redeemed(&self) -> Redeemed;
+
+    /// Returns ids of operations this operation directly depends on: the ones
+    /// spent by the transition inputs and the ones which valencies are
+    /// redeemed by the state extension.
+    fn prev_ops(&self) -> BTreeSet<OpId> {
+        self.inputs()
+            .into_iter()
+            .map(|input| input.prev_out.op)
+            .chain(self.redeemed().values().copied())
+            .collect()
+    }
+
+    /// Returns length of the operation strict serialization in bytes. The
+    /// length is computed without allocating a serialization buffer.
+    fn encoded_len(&self) -> usize;
+}
+
+/// Computes length of the strict serialization of the data without allocating
+/// serialization buffer.
+pub(crate) fn strict_len(data: &impl StrictEncode) -> usize {
+    data.strict_encode(StrictWriter::counter())
+        .expect("counting writer doesn't fail")
+        .count()
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -274,12 +346,19 @@ pub trait Operation {
 pub struct Genesis {
     pub ffv: Ffv,
     pub schema_id: SchemaId,
-    pub testnet: bool,
+    pub chain_net: ChainNet,
     pub alt_layers1: AltLayer1Set,
     pub metadata: SmallBlob,
     pub globals: GlobalState,
     pub assignments: Assignments<GenesisSeal>,
     pub valencies: Valencies,
+    /// Proof of bitcoin reserves backing the issuance, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reserves: IssueReserveProof,
+    /// Commitment to a recent block proving the contract wasn't created
+    /// before it, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub chain_proof: ChainProof,
 }
 
 impl StrictSerialize for Genesis {}
@@ -297,6 +376,8 @@ impl StrictDeserialize for Genesis {}
 pub struct Extension {
     pub ffv: Ffv,
     pub contract_id: ContractId,
+    /// Schema of the contract, which must declare the extension type.
+    pub schema_id: SchemaId,
     pub extension_type: ExtensionType,
     pub metadata: SmallBlob,
     pub globals: GlobalState,
@@ -320,12 +401,20 @@ impl StrictDeserialize for Extension {}
 pub struct Transition {
     pub ffv: Ffv,
     pub contract_id: ContractId,
+    /// Schema of the contract, which must declare the transition type.
+    pub schema_id: SchemaId,
     pub transition_type: TransitionType,
     pub metadata: SmallBlob,
     pub globals: GlobalState,
     pub inputs: Inputs,
     pub assignments: Assignments<GraphSeal>,
     pub valencies: Valencies,
+    #[commit_encode(skip)]
+    pub proof: Option<ZkProof>,
+    /// BIP-340 signature over the operation id made with the identity key
+    /// required by the schema.
+    #[commit_encode(skip)]
+    pub signature: Option<IdentitySig>,
 }
 
 impl StrictSerialize for Transition {}
@@ -360,8 +449,10 @@ impl Conceal for Transition {
     }
 }
 
+// The tag was bumped from `v02#23B` when transitions started to commit to the
+// schema id, separating their ids from the ids of the older layout.
 impl CommitmentId for Transition {
-    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:transition:v02#23B";
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:transition:v03#26A";
     type Id = OpId;
 }
 
@@ -377,25 +468,51 @@ impl Conceal for Extension {
     }
 }
 
+// The tag was bumped from `v02#2304` when extensions started to commit to the
+// schema id, separating their ids from the ids of the older layout.
 impl CommitmentId for Extension {
-    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:extension:v02#2304";
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:extension:v03#2610";
     type Id = OpId;
 }
 
+impl Genesis {
+    /// Returns identity key (BIP-340 x-only public key) from the global state
+    /// of the given type, if the state has a single value holding a valid key.
+    pub fn identity_key(&self, ty: schema::GlobalStateType) -> Option<XOnlyPk> {
+        let values = self.globals.get(&ty)?;
+        let [data] = values.as_slice() else {
+            return None;
+        };
+        let key = <[u8; 32]>::try_from(data.as_inner().as_slice()).ok()?;
+        XOnlyPk::from_byte_array(key).ok()
+    }
+}
+
 impl Transition {
     /// Returns reference to information about the owned rights in form of
     /// [`Inputs`] wrapper structure which this operation updates with
     /// state transition ("parent owned rights").
     pub fn prev_state(&self) -> &Inputs { &self.inputs }
-}
 
-impl Extension {
-    /// Returns reference to information about the public rights (in form of
-    /// [`Redeemed`] wrapper structure), defined with "parent" state
-    /// extensions (i.e. those finalized with the current state transition) or
-    /// referenced by another state extension, which this operation updates
-    /// ("parent public rights").
-    pub fn redeemed(&self) -> &Redeemed { &self.redeemed }
+    /// Signs the transition id with the identity key, replacing the previous
+    /// signature, if any.
+    pub fn sign(&mut self, secret_key: &SecretKey) {
+        let keypair = Keypair::from_secret_key(SECP256K1, secret_key);
+        let msg = Message::from_digest(self.id().to_byte_array());
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(&msg, &keypair);
+        self.signature = Some(IdentitySig::from(sig.serialize()));
+    }
+
+    /// Checks that the transition is signed with the given identity key.
+    pub fn verify_signature(&self, identity: XOnlyPk) -> bool {
+        let Some(sig) = self.signature else {
+            return false;
+        };
+        let msg = Message::from_digest(self.id().to_byte_array());
+        Signature::from_slice(sig.as_slice())
+            .and_then(|sig| SECP256K1.verify_schnorr(&sig, &msg, &identity))
+            .is_ok()
+    }
 }
 
 impl Operation for Genesis {
@@ -411,6 +528,9 @@ impl Operation for Genesis {
     #[inline]
     fn contract_id(&self) -> ContractId { ContractId::from_inner(self.id().into_inner()) }
 
+    #[inline]
+    fn schema_id(&self) -> SchemaId { self.schema_id }
+
     #[inline]
     fn transition_type(&self) -> Option<TransitionType> { None }
 
@@ -427,7 +547,7 @@ impl Operation for Genesis {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -438,6 +558,12 @@ impl Operation for Genesis {
 
     #[inline]
     fn inputs(&self) -> Inputs { empty!() }
+
+    #[inline]
+    fn redeemed(&self) -> Redeemed { empty!() }
+
+    #[inline]
+    fn encoded_len(&self) -> usize { strict_len(self) }
 }
 
 impl Operation for Extension {
@@ -453,6 +579,9 @@ impl Operation for Extension {
     #[inline]
     fn contract_id(&self) -> ContractId { self.contract_id }
 
+    #[inline]
+    fn schema_id(&self) -> SchemaId { self.schema_id }
+
     #[inline]
     fn transition_type(&self) -> Option<TransitionType> { None }
 
@@ -469,7 +598,7 @@ impl Operation for Extension {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -480,6 +609,12 @@ impl Operation for Extension {
 
     #[inline]
     fn inputs(&self) -> Inputs { empty!() }
+
+    #[inline]
+    fn redeemed(&self) -> Redeemed { self.redeemed.clone() }
+
+    #[inline]
+    fn encoded_len(&self) -> usize { strict_len(self) }
 }
 
 impl Operation for Transition {
@@ -495,6 +630,9 @@ impl Operation for Transition {
     #[inline]
     fn contract_id(&self) -> ContractId { self.contract_id }
 
+    #[inline]
+    fn schema_id(&self) -> SchemaId { self.schema_id }
+
     #[inline]
     fn transition_type(&self) -> Option<TransitionType> { Some(self.transition_type) }
 
@@ -511,7 +649,7 @@ impl Operation for Transition {
     fn valencies(&self) -> &Valencies { &self.valencies }
 
     #[inline]
-    fn assignments(&self) -> AssignmentsRef { (&self.assignments).into() }
+    fn assignments(&self) -> AssignmentsRef<'_> { (&self.assignments).into() }
 
     #[inline]
     fn assignments_by_type(&self, t: AssignmentType) -> Option<TypedAssigns<GraphSeal>> {
@@ -519,6 +657,12 @@ impl Operation for Transition {
     }
 
     fn inputs(&self) -> Inputs { self.inputs.clone() }
+
+    #[inline]
+    fn redeemed(&self) -> Redeemed { empty!() }
+
+    #[inline]
+    fn encoded_len(&self) -> usize { strict_len(self) }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, From)]
@@ -564,6 +708,14 @@ impl<'op> Operation for OpRef<'op> {
         }
     }
 
+    fn schema_id(&self) -> SchemaId {
+        match self {
+            OpRef::Genesis(op) => op.schema_id(),
+            OpRef::Transition(op) => op.schema_id(),
+            OpRef::Extension(op) => op.schema_id(),
+        }
+    }
+
     fn transition_type(&self) -> Option<TransitionType> {
         match self {
             OpRef::Genesis(op) => op.transition_type(),
@@ -627,11 +779,52 @@ impl<'op> Operation for OpRef<'op> {
             OpRef::Extension(op) => op.inputs(),
         }
     }
+
+    fn redeemed(&self) -> Redeemed {
+        match self {
+            OpRef::Genesis(op) => op.redeemed(),
+            OpRef::Transition(op) => op.redeemed(),
+            OpRef::Extension(op) => op.redeemed(),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            OpRef::Genesis(op) => op.encoded_len(),
+            OpRef::Transition(op) => op.encoded_len(),
+            OpRef::Extension(op) => op.encoded_len(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use amplify::confinement::SmallVec;
+    use amplify::hex::ToHex;
+    use strict_encoding::StrictDumb;
+
     use super::*;
+    use crate::RevealedData;
+
+    #[test]
+    fn prev_ops() {
+        let prev = OpId::from([0x11; 32]);
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(
+            TinyOrdSet::try_from_iter([Input::with(Opout::new(prev, AssignmentType::with(1), 0))])
+                .unwrap(),
+        );
+        let mut extension = Extension::strict_dumb();
+        extension.redeemed = Redeemed::from(
+            TinyOrdMap::try_from_iter([(schema::ValencyType::with(1), prev)]).unwrap(),
+        );
+
+        assert!(OpRef::from(&Genesis::strict_dumb()).prev_ops().is_empty());
+        assert_eq!(OpRef::from(&transition).prev_ops(), bset! {prev});
+        assert_eq!(OpRef::from(&extension).prev_ops(), bset! {prev});
+        assert!(transition.redeemed().is_empty());
+        assert!(extension.inputs().is_empty());
+    }
 
     #[test]
     fn contract_id_display() {
@@ -664,14 +857,52 @@ mod test {
         );
 
         // Wrong separator placement
-        assert!(
-            ContractId::from_str("rgb:pkXwpsb-aemTWhtSg-VDGF25hEi-jtTAnPjzh-B63ZwSeh-EWvfhF9")
-                .is_err()
-        );
+        assert!(ContractId::from_str("rgb:pkXwpsb-aemTWhtSg-VDGF25hEi-jtTAnPjzh-B63ZwSeh-EWvfhF9")
+            .is_err());
         // Wrong separator number
-        assert!(
-            ContractId::from_str("rgb:pkXwpsb-aemTWhtSg-VDGF25hEi-jtTAnPjzh-B63ZwSehEWvfhF9")
-                .is_err()
-        );
+        assert!(ContractId::from_str("rgb:pkXwpsb-aemTWhtSg-VDGF25hEi-jtTAnPjzh-B63ZwSehEWvfhF9")
+            .is_err());
+    }
+
+    #[test]
+    fn opid_display_from_str() {
+        let id = OpId::from_byte_array([0x6c; 32]);
+        let s = id.to_string();
+        assert!(s.starts_with("op:"));
+        assert_eq!(Ok(id), OpId::from_str(&s));
+        assert_eq!(Ok(id), OpId::from_str(&format!("{id:#}")));
+        assert_eq!(Ok(id), OpId::from_str(&id.to_hex()));
+
+        // Typo in the id is detected by the checksum
+        let mut typo = s.chars().collect::<Vec<_>>();
+        typo[5] = if typo[5] == '2' { '3' } else { '2' };
+        assert!(OpId::from_str(&typo.into_iter().collect::<String>()).is_err());
+    }
+
+    #[test]
+    fn identity_signature() {
+        let ty = schema::GlobalStateType::with(7);
+        let secret_key = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let identity = XOnlyPk::from(secret_key.x_only_public_key(SECP256K1).0);
+        let mut genesis = Genesis::strict_dumb();
+        assert_eq!(genesis.identity_key(ty), None);
+        genesis
+            .globals
+            .add_state(
+                ty,
+                RevealedData::from(SmallVec::try_from(identity.to_byte_array().to_vec()).unwrap()),
+            )
+            .unwrap();
+        assert_eq!(genesis.identity_key(ty), Some(identity));
+
+        let mut transition = Transition::strict_dumb();
+        assert!(!transition.verify_signature(identity));
+        let opid = transition.id();
+        transition.sign(&secret_key);
+        assert_eq!(transition.id(), opid);
+        assert!(transition.verify_signature(identity));
+
+        transition.transition_type = TransitionType::with(2);
+        assert!(!transition.verify_signature(identity));
     }
 }
@@ -0,0 +1,378 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Binding of individual assignments to payment requests and proofs of
+//! payments.
+//!
+//! A payer may attach a [`PaymentTag`] (for instance, an invoice id) to an
+//! assignment, so the receiver can find the transfer it was expecting in the
+//! incoming consignment. The tag is salted and can be concealed together with
+//! the rest of the assignment data.
+//!
+//! [`PaymentProof`] allows to prove to a third party that some fungible state
+//! was assigned to a seal by an operation anchored to a witness transaction,
+//! without disclosing the rest of the contract history.
+
+use std::str::FromStr;
+
+use amplify::hex::{self, FromHex, ToHex};
+use amplify::Bytes32;
+use bp::dbc::anchor;
+use bp::secp256k1::rand::{thread_rng, RngCore};
+use bp::Txid;
+use commit_verify::{mpc, CommitVerify, Conceal, StrictEncodedProtocol};
+use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+use crate::validation::{ResolveTx, TxResolverError};
+use crate::{
+    Anchor, AnchoredBundle, GraphSeal, OpId, Operation, Opout, RevealedValue, SealDefinition,
+    Transition, TransitionBundle, WitnessId, LIB_NAME_RGB,
+};
+
+/// Identifier of a payment request, like an invoice id, which can be bound to
+/// an assignment.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(Self::to_hex)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct PaymentTag(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl FromStr for PaymentTag {
+    type Err = hex::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+}
+
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct RevealedPaymentTag {
+    pub tag: PaymentTag,
+    /// Salt preventing detection of the concealed tag by guessing it.
+    pub salt: u64,
+}
+
+impl RevealedPaymentTag {
+    /// Creates new revealed payment tag. Uses `thread_rng` to initialize
+    /// [`RevealedPaymentTag::salt`].
+    pub fn new(tag: PaymentTag) -> Self {
+        Self {
+            tag,
+            salt: thread_rng().next_u64(),
+        }
+    }
+}
+
+impl Conceal for RevealedPaymentTag {
+    type Concealed = ConcealedPaymentTag;
+    fn conceal(&self) -> Self::Concealed { ConcealedPaymentTag::commit(self) }
+}
+
+/// Confidential version of a payment tag.
+///
+/// See also revealed version [`RevealedPaymentTag`].
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ConcealedPaymentTag(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitVerify<RevealedPaymentTag, StrictEncodedProtocol> for ConcealedPaymentTag {
+    fn commit(revealed: &RevealedPaymentTag) -> Self { Bytes32::commit(revealed).into() }
+}
+
+/// Payment tag of an assignment, which may be concealed.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Concealed(strict_dumb!()))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum AssignPaymentTag {
+    #[from]
+    #[strict_type(tag = 0x00)]
+    Concealed(ConcealedPaymentTag),
+    #[from]
+    #[strict_type(tag = 0x01)]
+    Revealed(RevealedPaymentTag),
+}
+
+impl Conceal for AssignPaymentTag {
+    type Concealed = ConcealedPaymentTag;
+    fn conceal(&self) -> ConcealedPaymentTag {
+        match self {
+            AssignPaymentTag::Concealed(concealed) => *concealed,
+            AssignPaymentTag::Revealed(revealed) => revealed.conceal(),
+        }
+    }
+}
+
+impl AssignPaymentTag {
+    pub fn as_revealed(&self) -> Option<&RevealedPaymentTag> {
+        match self {
+            AssignPaymentTag::Revealed(revealed) => Some(revealed),
+            AssignPaymentTag::Concealed(_) => None,
+        }
+    }
+
+    /// Detects whether the revealed tag matches the given one.
+    pub fn matches(&self, tag: PaymentTag) -> bool {
+        self.as_revealed()
+            .map(|revealed| revealed.tag == tag)
+            .unwrap_or_default()
+    }
+}
+
+/// Errors verifying [`PaymentProof`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PaymentProofError {
+    /// payment proof output {0} doesn't belong to the operation {1}.
+    OperationMismatch(Opout, OpId),
+
+    /// operation {0} is not a part of the transition bundle.
+    NotInBundle(OpId),
+
+    /// state of the output {0} is either absent, concealed or not fungible.
+    StateUnavailable(Opout),
+
+    /// unable to resolve witness transaction {0}.
+    WitnessUnresolved(Txid),
+
+    /// bundle with operation {0} is not properly anchored to the witness
+    /// transaction {1}. Details: {2}
+    AnchorInvalid(OpId, Txid, anchor::VerifyError),
+}
+
+/// Proof that fungible state was assigned to a seal by an operation anchored
+/// to a witness transaction.
+///
+/// The proof contains the operation with all its state concealed except the
+/// proven assignment, the concealed bundle the operation belongs to and the
+/// anchor of the bundle. It doesn't prove validity of the operation itself,
+/// thus it must be extracted only from a validated consignment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct PaymentProof {
+    pub opout: Opout,
+    pub transition: Transition,
+    pub bundle: TransitionBundle,
+    pub anchor: Anchor,
+}
+
+impl StrictSerialize for PaymentProof {}
+impl StrictDeserialize for PaymentProof {}
+
+impl PaymentProof {
+    /// Constructs proof for the output of the transition from the anchored
+    /// bundle. Returns `None` if the output is not a revealed fungible state
+    /// assigned by a transition from the bundle.
+    pub fn with(anchored_bundle: &AnchoredBundle, opout: Opout) -> Option<Self> {
+        let original = anchored_bundle.bundle.get(&opout.op)?.transition.as_ref()?;
+        let assign = original
+            .assignments
+            .get(&opout.ty)?
+            .as_fungible()
+            .get(opout.no as usize)?
+            .clone();
+        assign.as_revealed()?;
+
+        let mut transition = original.conceal();
+        let assigns = transition
+            .assignments
+            .get_mut(&opout.ty)
+            .and_then(|assigns| assigns.as_fungible_mut())
+            .expect("concealed transition keeps assignments");
+        assigns[opout.no as usize] = assign;
+
+        Some(PaymentProof {
+            opout,
+            transition,
+            bundle: anchored_bundle.bundle.conceal_except(&[]),
+            anchor: anchored_bundle.anchor.clone(),
+        })
+    }
+
+    pub fn opid(&self) -> OpId { self.opout.op }
+
+    pub fn witness_id(&self) -> WitnessId { self.anchor.witness_id() }
+
+    /// Returns seal and the fungible state assigned to it by the proven
+    /// assignment.
+    pub fn assignment(&self) -> Option<(SealDefinition<GraphSeal>, RevealedValue)> {
+        self.transition
+            .assignments
+            .get(&self.opout.ty)?
+            .as_fungible()
+            .get(self.opout.no as usize)?
+            .to_revealed()
+    }
+
+    /// Verifies the proof, resolving the witness transaction with the provided
+    /// resolver. Returns the proven seal and state.
+    #[allow(clippy::result_large_err)]
+    pub fn verify(
+        &self,
+        resolver: &impl ResolveTx,
+    ) -> Result<(SealDefinition<GraphSeal>, RevealedValue), PaymentProofError> {
+        let opid = self.transition.id();
+        if self.opout.op != opid {
+            return Err(PaymentProofError::OperationMismatch(self.opout, opid));
+        }
+        if !self.bundle.contains_key(&opid) {
+            return Err(PaymentProofError::NotInBundle(opid));
+        }
+        let assignment = self
+            .assignment()
+            .ok_or(PaymentProofError::StateUnavailable(self.opout))?;
+
+        let txid = self.anchor.txid;
+        let witness = resolver
+            .resolve_tx(self.anchor.layer1(), txid)
+            .map_err(|err| match err {
+                TxResolverError::Unknown(txid) | TxResolverError::Other(txid, _) => {
+                    PaymentProofError::WitnessUnresolved(txid)
+                }
+            })?;
+        let message = mpc::Message::from(self.bundle.bundle_id());
+        self.anchor
+            .verify(self.transition.contract_id, message, &witness)
+            .map_err(|err| PaymentProofError::AnchorInvalid(opid, txid, err))?;
+
+        Ok(assignment)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use bp::Tx;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, AssignmentType, BlindingFactor, Layer1, TransitionBundle, TypedAssigns,
+    };
+
+    struct NoResolver;
+    impl ResolveTx for NoResolver {
+        fn resolve_tx(&self, _: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+            Err(TxResolverError::Unknown(txid))
+        }
+    }
+
+    #[test]
+    fn payment_proof() {
+        let ty = AssignmentType::with(1);
+        let assign = |vout, amount: u64| {
+            let seal = SealDefinition::Bitcoin(GraphSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                TxPtr::WitnessTx,
+                vout,
+                1,
+            ));
+            let value = RevealedValue::with_blinding(
+                amount,
+                BlindingFactor::EMPTY,
+                AssetTag::from([1; 32]),
+            );
+            Assign::revealed(seal, value)
+        };
+        let mut transition = Transition::strict_dumb();
+        transition
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([assign(0, 10), assign(1, 20)]).unwrap(),
+                ),
+            )
+            .unwrap();
+        let opid = transition.id();
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle =
+            TransitionBundle::try_from_transitions([(0, transition.clone())]).unwrap();
+
+        let no = transition.assignments[&ty]
+            .as_fungible()
+            .iter()
+            .position(|a| a.to_confidential_seal() == assign(1, 20).to_confidential_seal())
+            .unwrap() as u16;
+        let opout = Opout::new(opid, ty, no);
+        let proof = PaymentProof::with(&anchored, opout).unwrap();
+        assert_eq!(proof.transition.id(), opid);
+        assert_eq!(proof.bundle.bundle_id(), anchored.bundle.bundle_id());
+        assert_eq!(proof.assignment(), assign(1, 20).to_revealed());
+        assert_eq!(
+            proof.transition.assignments[&ty]
+                .as_fungible()
+                .iter()
+                .filter(|a| a.as_revealed().is_some())
+                .count(),
+            1
+        );
+        assert_eq!(
+            proof.verify(&NoResolver),
+            Err(PaymentProofError::WitnessUnresolved(anchored.anchor.txid))
+        );
+
+        let mut wrong = proof.clone();
+        wrong.opout.op = OpId::from([1; 32]);
+        assert_eq!(
+            wrong.verify(&NoResolver),
+            Err(PaymentProofError::OperationMismatch(wrong.opout, opid))
+        );
+        assert!(PaymentProof::with(&anchored, Opout::new(opid, ty, 2)).is_none());
+    }
+}
@@ -0,0 +1,321 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Portfolio aggregating state of multiple contracts known to a wallet.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use amplify::confinement::LargeOrdSet;
+
+use crate::{
+    ContractId, ContractState, ExposedState, Opout, Output, OutputAssignment, SecretSeal,
+    StateData, StateView,
+};
+
+/// Unspent outputs of a contract, with their state.
+pub type PortfolioOutputs = BTreeMap<ContractId, Vec<OutputAssignment<StateData>>>;
+
+/// Contract state assigned to a layer 1 output.
+pub type OutputOwnership = (ContractId, Opout, StateData);
+
+/// Collection of contract states, providing queries over all the contracts.
+///
+/// Portfolio maintains index of the unspent state (including the state
+/// defined by operations with unconfirmed witness transactions) by the
+/// outputs it is assigned to, which is updated each time a contract state is
+/// added or updated.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Portfolio {
+    contracts: BTreeMap<ContractId, ContractState>,
+    output_index: BTreeMap<Output, Vec<OutputOwnership>>,
+    contract_outputs: BTreeMap<ContractId, BTreeSet<Output>>,
+}
+
+impl Portfolio {
+    pub fn new() -> Self { Self::default() }
+
+    /// Adds contract state, replacing and returning a previously known state
+    /// of the same contract.
+    pub fn insert(&mut self, state: ContractState) -> Option<ContractState> {
+        let contract_id = state.contract_id();
+        let prev = self.contracts.insert(contract_id, state);
+        self.reindex(contract_id);
+        prev
+    }
+
+    pub fn remove(&mut self, contract_id: ContractId) -> Option<ContractState> {
+        let state = self.contracts.remove(&contract_id);
+        self.reindex(contract_id);
+        state
+    }
+
+    pub fn contract(&self, contract_id: ContractId) -> Option<&ContractState> {
+        self.contracts.get(&contract_id)
+    }
+
+    /// Updates contract state, for instance merging operations from a
+    /// consignment, and reindexes its outputs. Returns `None` if the contract
+    /// is not known.
+    pub fn update<T>(
+        &mut self,
+        contract_id: ContractId,
+        f: impl FnOnce(&mut ContractState) -> T,
+    ) -> Option<T> {
+        let res = f(self.contracts.get_mut(&contract_id)?);
+        self.reindex(contract_id);
+        Some(res)
+    }
+
+    pub fn contract_ids(&self) -> impl Iterator<Item = ContractId> + '_ {
+        self.contracts.keys().copied()
+    }
+
+    pub fn contracts(&self) -> impl Iterator<Item = &ContractState> { self.contracts.values() }
+
+    #[inline]
+    pub fn len(&self) -> usize { self.contracts.len() }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.contracts.is_empty() }
+
+    /// Returns all unspent state of all contracts assigned to the given output
+    /// (transaction outpoint on some layer 1), including state defined by
+    /// operations with unconfirmed witnesses. Uses the portfolio output
+    /// index, thus doesn't iterate over the contract states.
+    pub fn owned_by(&self, output: Output) -> &[OutputOwnership] {
+        self.output_index
+            .get(&output)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns unspent outputs of all contracts assigned to the given output
+    /// (transaction outpoint on some layer 1).
+    pub fn outputs_on(&self, output: Output, view: StateView) -> PortfolioOutputs {
+        self.select(view, |assignment| assignment.output == output)
+    }
+
+    /// Returns unspent outputs of all contracts assigned to any of the given
+    /// seals, i.e. all the assets controlled by the seal set.
+    pub fn controlled_by(
+        &self,
+        seals: impl IntoIterator<Item = SecretSeal>,
+        view: StateView,
+    ) -> PortfolioOutputs {
+        let seals = seals.into_iter().collect::<BTreeSet<_>>();
+        let mut outputs = PortfolioOutputs::new();
+        for (contract_id, state) in &self.contracts {
+            let opouts = seals
+                .iter()
+                .flat_map(|seal| state.outputs_by_seal(*seal, view))
+                .filter(|assignment| !state.is_spent(assignment.opout))
+                .collect::<BTreeSet<_>>();
+            if !opouts.is_empty() {
+                outputs.insert(*contract_id, opouts.into_iter().collect());
+            }
+        }
+        outputs
+    }
+
+    fn select(
+        &self,
+        view: StateView,
+        filter: impl Fn(&OutputAssignment<StateData>) -> bool,
+    ) -> PortfolioOutputs {
+        let mut outputs = PortfolioOutputs::new();
+        for (contract_id, state) in &self.contracts {
+            let selected = unspent(state, view, &filter);
+            if !selected.is_empty() {
+                outputs.insert(*contract_id, selected);
+            }
+        }
+        outputs
+    }
+
+    fn reindex(&mut self, contract_id: ContractId) {
+        for output in self
+            .contract_outputs
+            .remove(&contract_id)
+            .unwrap_or_default()
+        {
+            if let Some(owned) = self.output_index.get_mut(&output) {
+                owned.retain(|(id, ..)| *id != contract_id);
+                if owned.is_empty() {
+                    self.output_index.remove(&output);
+                }
+            }
+        }
+        let Some(state) = self.contracts.get(&contract_id) else {
+            return;
+        };
+        let mut outputs = BTreeSet::new();
+        for assignment in unspent(state, StateView::Pending, &|_| true) {
+            self.output_index
+                .entry(assignment.output)
+                .or_default()
+                .push((contract_id, assignment.opout, assignment.state));
+            outputs.insert(assignment.output);
+        }
+        if !outputs.is_empty() {
+            self.contract_outputs.insert(contract_id, outputs);
+        }
+    }
+}
+
+/// Collects unspent outputs of the contract visible in the state view and
+/// matching the filter.
+fn unspent(
+    state: &ContractState,
+    view: StateView,
+    filter: &impl Fn(&OutputAssignment<StateData>) -> bool,
+) -> Vec<OutputAssignment<StateData>> {
+    fn collect<State: ExposedState>(
+        state: &ContractState,
+        outputs: &LargeOrdSet<OutputAssignment<State>>,
+        view: StateView,
+        filter: &impl Fn(&OutputAssignment<StateData>) -> bool,
+        selected: &mut Vec<OutputAssignment<StateData>>,
+    ) {
+        selected.extend(
+            outputs
+                .iter()
+                .filter(|assignment| {
+                    state.is_visible(assignment.witness, view) && !state.is_spent(assignment.opout)
+                })
+                .map(OutputAssignment::to_state_data)
+                .filter(filter),
+        );
+    }
+
+    let mut selected = vec![];
+    collect(state, state.rights(), view, filter, &mut selected);
+    collect(state, state.fungibles(), view, filter, &mut selected);
+    collect(state, state.data(), view, filter, &mut selected);
+    collect(state, state.attach(), view, filter, &mut selected);
+    selected.sort();
+    selected
+}
+
+impl FromIterator<ContractState> for Portfolio {
+    fn from_iter<T: IntoIterator<Item = ContractState>>(iter: T) -> Self {
+        let mut portfolio = Portfolio::new();
+        for state in iter {
+            portfolio.insert(state);
+        }
+        portfolio
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::seals::txout::CloseMethod;
+    use bp::{Outpoint, Txid};
+    use commit_verify::Conceal;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, AssignmentType, BlindingFactor, ContractHistory, Genesis, GenesisSeal,
+        Operation, RevealedValue, SealDefinition, SubSchema, TypedAssigns, VoidState,
+    };
+
+    #[test]
+    fn portfolio() {
+        let seal = |vout| {
+            SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                CloseMethod::TapretFirst,
+                Txid::from([1; 32]),
+                vout,
+                1,
+            ))
+        };
+        let contract = |metadata, assignments| {
+            let mut genesis = Genesis::strict_dumb();
+            genesis.metadata = Confined::try_from(vec![metadata]).unwrap();
+            genesis
+                .assignments
+                .insert(AssignmentType::with(1), assignments)
+                .unwrap();
+            ContractState {
+                schema: SubSchema::strict_dumb(),
+                history: ContractHistory::with(
+                    genesis.schema_id,
+                    None,
+                    genesis.contract_id(),
+                    &genesis,
+                ),
+            }
+        };
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let fungible = contract(
+            1,
+            TypedAssigns::Fungible(
+                Confined::try_from_iter([
+                    Assign::revealed(seal(0), value(100)),
+                    Assign::revealed(seal(1), value(50)),
+                ])
+                .unwrap(),
+            ),
+        );
+        let rights = contract(
+            2,
+            TypedAssigns::Declarative(
+                Confined::try_from_iter([Assign::revealed(seal(0), VoidState::default())]).unwrap(),
+            ),
+        );
+        let mut portfolio = Portfolio::from_iter([fungible.clone(), rights.clone()]);
+        assert_eq!(portfolio.len(), 2);
+
+        let outputs =
+            portfolio.outputs_on(Output::Bitcoin(Outpoint::new(Txid::from([1; 32]), 0)), none!());
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[&fungible.contract_id()].len(), 1);
+        assert_eq!(outputs[&rights.contract_id()][0].state, StateData::Void);
+        assert!(portfolio
+            .outputs_on(Output::Liquid(Outpoint::new(Txid::from([1; 32]), 0)), none!())
+            .is_empty());
+
+        let outpoint = |vout| Output::Bitcoin(Outpoint::new(Txid::from([1; 32]), vout));
+        let owned = portfolio.owned_by(outpoint(0));
+        assert_eq!(owned.len(), 2);
+        assert!(owned.iter().any(|(id, opout, state)| {
+            *id == rights.contract_id() &&
+                opout.ty == AssignmentType::with(1) &&
+                *state == StateData::Void
+        }));
+        assert_eq!(portfolio.owned_by(outpoint(1)).len(), 1);
+        assert!(portfolio.owned_by(outpoint(2)).is_empty());
+
+        let controlled = portfolio.controlled_by([seal(1).conceal()], none!());
+        assert_eq!(controlled.keys().copied().collect::<Vec<_>>(), vec![fungible.contract_id()]);
+        assert_eq!(controlled[&fungible.contract_id()][0].state, StateData::Fungible(value(50)));
+
+        portfolio.remove(rights.contract_id());
+        assert_eq!(portfolio.owned_by(outpoint(0)).len(), 1);
+        portfolio.remove(fungible.contract_id());
+        assert!(portfolio.owned_by(outpoint(0)).is_empty());
+        assert_eq!(portfolio, Portfolio::new());
+    }
+}
@@ -0,0 +1,110 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Precision of fungible assets.
+//!
+//! Fungible state is always represented in indivisible atomic units. Contract
+//! global state of [`Precision`] type defines the number of decimal digits
+//! used to display the amount to the user; the conversion between the two
+//! representations is performed with integer arithmetic only, without any
+//! rounding errors.
+
+use crate::{Amount, AmountParseError, LIB_NAME_RGB};
+
+/// Number of decimal digits used to display amounts of a fungible asset.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = repr, into_u8, try_from_u8)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u8)]
+pub enum Precision {
+    Indivisible = 0,
+    Deci = 1,
+    Centi = 2,
+    Milli = 3,
+    DeciMilli = 4,
+    CentiMilli = 5,
+    Micro = 6,
+    DeciMicro = 7,
+    #[default]
+    CentiMicro = 8,
+    Nano = 9,
+    DeciNano = 10,
+    CentiNano = 11,
+    Pico = 12,
+    DeciPico = 13,
+    CentiPico = 14,
+    Femto = 15,
+    DeciFemto = 16,
+    CentiFemto = 17,
+    Atto = 18,
+}
+
+impl Precision {
+    /// Number of decimal digits after the point.
+    #[inline]
+    pub const fn decimals(self) -> u8 { self as u8 }
+
+    /// Number of atomic units in a single displayed unit.
+    pub const fn multiplier(self) -> u64 { 10u64.pow(self.decimals() as u32) }
+
+    /// Formats amount of atomic units as a decimal number (see
+    /// [`Amount::to_string_with_precision`]).
+    pub fn format(self, amount: impl Into<Amount>) -> String {
+        amount.into().to_string_with_precision(self.decimals())
+    }
+
+    /// Parses decimal number into amount of atomic units (see
+    /// [`Amount::from_str_with_precision`]).
+    pub fn parse(self, s: &str) -> Result<Amount, AmountParseError> {
+        Amount::from_str_with_precision(s, self.decimals())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn format_parse() {
+        let precision = Precision::default();
+        assert_eq!(precision, Precision::CentiMicro);
+        assert_eq!(precision.multiplier(), 100_000_000);
+        assert_eq!(Precision::Atto.multiplier(), 10u64.pow(18));
+        assert_eq!(precision.format(123_450_000u64), "1.234 5");
+        assert_eq!(Precision::Indivisible.format(u64::MAX), u64::MAX.to_string());
+        assert_eq!(Precision::Atto.format(u64::MAX), "18.446 744 073 709 551 615");
+        assert_eq!(Precision::Atto.parse("18.446744073709551615"), Ok(Amount::from(u64::MAX)));
+
+        for precision in (0..=18u8).map(|decimals| Precision::try_from(decimals).unwrap()) {
+            for atoms in [0, 1, 10, 99_999_999, 100_000_001, u64::MAX] {
+                let amount = Amount::from(atoms);
+                assert_eq!(precision.parse(&precision.format(amount)), Ok(amount));
+            }
+        }
+        assert!(Precision::try_from(19).is_err());
+    }
+}
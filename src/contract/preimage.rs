@@ -0,0 +1,295 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured pre-images of operation ids.
+//!
+//! Operation id is a tagged hash of the commit-encoded operation. Signing
+//! devices shouldn't trust the host in displaying what is being signed, thus
+//! [`OpPreimage`] exposes the pre-image of the id split into the sections,
+//! each corresponding to an operation field and located at a known offset.
+//! The device may parse the sections it displays, and recompute the id from
+//! the complete pre-image with [`OpPreimage::op_id`].
+
+use std::io::Write;
+
+use commit_verify::{CommitEncode, CommitmentId, DigestExt, Sha256};
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::{
+    AssignmentType, Assignments, ExposedSeal, Extension, Genesis, OpId, OpRef, Transition,
+};
+
+/// Operation field committed to by a section of the operation id pre-image.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum PreimageField {
+    #[display("ffv")]
+    Ffv,
+    #[display("contractId")]
+    ContractId,
+    #[display("schemaId")]
+    SchemaId,
+    #[display("chainNet")]
+    ChainNet,
+    #[display("altLayers1")]
+    AltLayers1,
+    #[display("transitionType")]
+    TransitionType,
+    #[display("extensionType")]
+    ExtensionType,
+    #[display("metadata")]
+    Metadata,
+    #[display("globals")]
+    Globals,
+    #[display("inputs")]
+    Inputs,
+    /// Number of the assignment types, preceding the assignments.
+    #[display("assignmentCount")]
+    AssignmentCount,
+    /// Assignments of a given type, prefixed with the type.
+    #[display("assignments({0})")]
+    Assignments(AssignmentType),
+    #[display("redeemed")]
+    Redeemed,
+    #[display("valencies")]
+    Valencies,
+    #[display("reserves")]
+    Reserves,
+    #[display("chainProof")]
+    ChainProof,
+}
+
+/// Section of the operation id pre-image.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct PreimageSection {
+    pub field: PreimageField,
+    /// Offset of the section data from the start of the pre-image.
+    pub offset: usize,
+    /// Commit-encoded field data.
+    pub data: Vec<u8>,
+}
+
+/// Pre-image of an operation id, split into sections corresponding to the
+/// operation fields, in the order they are committed to.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct OpPreimage {
+    /// Tag of the hash function used by the operation type.
+    pub tag: [u8; 32],
+    pub sections: Vec<PreimageSection>,
+}
+
+impl OpPreimage {
+    fn with(tag: [u8; 32]) -> Self {
+        OpPreimage {
+            tag,
+            sections: vec![],
+        }
+    }
+
+    fn push(&mut self, field: PreimageField, value: &impl CommitEncode) {
+        let mut data = vec![];
+        value.commit_encode(&mut data);
+        self.push_data(field, data);
+    }
+
+    fn push_data(&mut self, field: PreimageField, data: Vec<u8>) {
+        let offset = self.len();
+        self.sections.push(PreimageSection {
+            field,
+            offset,
+            data,
+        });
+    }
+
+    fn push_assignments<Seal: ExposedSeal>(&mut self, assignments: &Assignments<Seal>) {
+        let mut data = vec![];
+        assignments
+            .len_u8()
+            .strict_encode(StrictWriter::with(u32::MAX as usize, &mut data))
+            .ok();
+        self.push_data(PreimageField::AssignmentCount, data);
+        for (ty, assigns) in assignments.iter() {
+            let mut data = vec![];
+            ty.strict_encode(StrictWriter::with(u32::MAX as usize, &mut data))
+                .ok();
+            assigns.commit_encode(&mut data);
+            self.push_data(PreimageField::Assignments(*ty), data);
+        }
+    }
+
+    /// Returns length of the complete pre-image in bytes.
+    pub fn len(&self) -> usize {
+        self.sections
+            .last()
+            .map(|section| section.offset + section.data.len())
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns the first section committing to the given field.
+    pub fn section(&self, field: PreimageField) -> Option<&PreimageSection> {
+        self.sections.iter().find(|section| section.field == field)
+    }
+
+    /// Returns the complete pre-image data.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len());
+        for section in &self.sections {
+            data.extend(&section.data);
+        }
+        data
+    }
+
+    /// Computes operation id from the pre-image.
+    pub fn op_id(&self) -> OpId {
+        let mut engine = Sha256::from_tag(self.tag);
+        for section in &self.sections {
+            engine.write_all(&section.data).ok();
+        }
+        engine.finish().into()
+    }
+}
+
+impl Genesis {
+    /// Constructs structured pre-image of the genesis operation id.
+    pub fn preimage(&self) -> OpPreimage {
+        let mut preimage = OpPreimage::with(Self::TAG);
+        preimage.push(PreimageField::Ffv, &self.ffv);
+        preimage.push(PreimageField::SchemaId, &*self.schema_id);
+        preimage.push(PreimageField::ChainNet, &self.chain_net);
+        preimage.push(PreimageField::AltLayers1, &self.alt_layers1);
+        preimage.push(PreimageField::Metadata, &self.metadata);
+        preimage.push(PreimageField::Globals, &self.globals);
+        preimage.push_assignments(&self.assignments);
+        preimage.push(PreimageField::Valencies, &self.valencies);
+        preimage.push(PreimageField::Reserves, &self.reserves);
+        preimage.push(PreimageField::ChainProof, &self.chain_proof);
+        preimage
+    }
+}
+
+impl Transition {
+    /// Constructs structured pre-image of the state transition operation id.
+    pub fn preimage(&self) -> OpPreimage {
+        let mut preimage = OpPreimage::with(Self::TAG);
+        preimage.push(PreimageField::Ffv, &self.ffv);
+        preimage.push(PreimageField::ContractId, &*self.contract_id);
+        preimage.push(PreimageField::SchemaId, &*self.schema_id);
+        preimage.push(PreimageField::TransitionType, &self.transition_type);
+        preimage.push(PreimageField::Metadata, &self.metadata);
+        preimage.push(PreimageField::Globals, &self.globals);
+        preimage.push(PreimageField::Inputs, &self.inputs);
+        preimage.push_assignments(&self.assignments);
+        preimage.push(PreimageField::Valencies, &self.valencies);
+        preimage
+    }
+}
+
+impl Extension {
+    /// Constructs structured pre-image of the state extension operation id.
+    pub fn preimage(&self) -> OpPreimage {
+        let mut preimage = OpPreimage::with(Self::TAG);
+        preimage.push(PreimageField::Ffv, &self.ffv);
+        preimage.push(PreimageField::ContractId, &*self.contract_id);
+        preimage.push(PreimageField::SchemaId, &*self.schema_id);
+        preimage.push(PreimageField::ExtensionType, &self.extension_type);
+        preimage.push(PreimageField::Metadata, &self.metadata);
+        preimage.push(PreimageField::Globals, &self.globals);
+        preimage.push_assignments(&self.assignments);
+        preimage.push(PreimageField::Redeemed, &self.redeemed);
+        preimage.push(PreimageField::Valencies, &self.valencies);
+        preimage
+    }
+}
+
+impl OpRef<'_> {
+    /// Constructs structured pre-image of the operation id.
+    pub fn preimage(&self) -> OpPreimage {
+        match self {
+            OpRef::Genesis(op) => op.preimage(),
+            OpRef::Transition(op) => op.preimage(),
+            OpRef::Extension(op) => op.preimage(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, Assign, BlindingFactor, GraphSeal, Operation, RevealedValue, SealDefinition,
+        TypedAssigns,
+    };
+
+    #[test]
+    fn op_preimage() {
+        let mut transition = Transition::strict_dumb();
+        let seal = SealDefinition::Bitcoin(GraphSeal::strict_dumb());
+        let value =
+            RevealedValue::with_blinding(100, BlindingFactor::EMPTY, AssetTag::from([1; 32]));
+        transition
+            .assignments
+            .insert(
+                AssignmentType::with(2),
+                TypedAssigns::Fungible(
+                    Confined::try_from(vec![Assign::revealed(seal, value)]).unwrap(),
+                ),
+            )
+            .unwrap();
+        transition.metadata = Confined::try_from(b"memo".to_vec()).unwrap();
+
+        let preimage = transition.preimage();
+        assert_eq!(preimage.op_id(), transition.id());
+        assert_eq!(preimage.len(), preimage.to_vec().len());
+        let metadata = preimage.section(PreimageField::Metadata).unwrap();
+        assert_eq!(
+            &preimage.to_vec()[metadata.offset..metadata.offset + metadata.data.len()],
+            &metadata.data[..]
+        );
+        assert!(preimage
+            .section(PreimageField::Assignments(AssignmentType::with(2)))
+            .is_some());
+
+        let genesis = Genesis::strict_dumb();
+        assert_eq!(OpRef::from(&genesis).preimage().op_id(), genesis.id());
+        let extension = Extension::strict_dumb();
+        assert_eq!(extension.preimage().op_id(), extension.id());
+    }
+}
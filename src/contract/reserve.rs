@@ -0,0 +1,116 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proofs of reserves backing contract issuance.
+//!
+//! An issuer may attach to the contract genesis an [`IssueReserveProof`],
+//! committing to a set of bitcoin transaction outputs which back the issued
+//! assets, the total amount of satoshis held by them and the block height at
+//! which the reserves are claimed. The validator checks the proof against the
+//! blockchain using the transaction resolver.
+
+use std::io;
+
+use amplify::confinement::TinyOrdSet;
+use bp::Outpoint;
+use commit_verify::CommitEncode;
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::LIB_NAME_RGB;
+
+/// Proof of bitcoin reserves backing an issuance.
+///
+/// An empty proof doesn't contribute to the genesis commitment, such that
+/// contracts issued without reserves keep their ids.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct IssueReserveProof {
+    /// Transaction outputs holding the reserves.
+    pub utxos: TinyOrdSet<Outpoint>,
+    /// Total amount of satoshis claimed to be held by the outputs.
+    pub amount: u64,
+    /// Block height at which the reserves are claimed; all of the outputs
+    /// must be mined at or below this height.
+    pub height: u32,
+}
+
+impl CommitEncode for IssueReserveProof {
+    fn commit_encode(&self, mut e: &mut impl io::Write) {
+        if self.is_empty() {
+            return;
+        }
+        let w = StrictWriter::with(u32::MAX as usize, &mut e);
+        self.utxos
+            .strict_encode(w)
+            .and_then(|w| self.amount.strict_encode(w))
+            .and_then(|w| self.height.strict_encode(w))
+            .ok();
+    }
+}
+
+impl IssueReserveProof {
+    pub fn with(utxos: TinyOrdSet<Outpoint>, amount: u64, height: u32) -> Self {
+        IssueReserveProof {
+            utxos,
+            amount,
+            height,
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.utxos.is_empty() && self.amount == 0 && self.height == 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::{Txid, Vout};
+    use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::{Genesis, Operation};
+
+    #[test]
+    fn genesis_commitment() {
+        let mut genesis = Genesis::strict_dumb();
+        let contract_id = genesis.contract_id();
+        assert!(genesis.reserves.is_empty());
+
+        let outpoint = Outpoint::new(Txid::from_byte_array([0x11; 32]), Vout::from_u32(1));
+        genesis.reserves = IssueReserveProof::with(
+            TinyOrdSet::try_from_iter([outpoint]).unwrap(),
+            100_000,
+            800_000,
+        );
+        assert!(!genesis.reserves.is_empty());
+        assert_ne!(genesis.contract_id(), contract_id);
+
+        let data = genesis.to_strict_serialized::<{ usize::MAX }>().unwrap();
+        assert_eq!(Genesis::from_strict_serialized::<{ usize::MAX }>(data).unwrap(), genesis);
+    }
+}
@@ -0,0 +1,288 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sanity checks of witness transaction templates, performed before the
+//! witness transaction is signed and published.
+
+use bp::seals::txout::{CloseMethod, TxoSeal};
+use bp::{InternalPk, Sats, ScriptPubkey, Tx, VarInt};
+
+use crate::{SealDefinition, Transition};
+
+/// Minimal relay fee rate in sats per virtual byte used by bitcoin nodes to
+/// compute dust limits.
+pub const DUST_RELAY_FEE_RATE: u64 = 3;
+
+/// Returns minimal value of an output with the given script which is not
+/// considered dust by bitcoin nodes. OP_RETURN outputs have zero dust limit,
+/// since they are never spent.
+pub fn dust_limit(script: &ScriptPubkey) -> Sats {
+    if script.is_op_return() {
+        return Sats::ZERO;
+    }
+    let output_len = 8 + VarInt::with(script.len()).len() + script.len();
+    let input_len = if script.is_witness_program() { 67 } else { 148 };
+    Sats::from_sats((output_len + input_len) as u64 * DUST_RELAY_FEE_RATE)
+}
+
+/// Issues in the witness transaction template, which may prevent the
+/// transaction from being relayed or may lead to the loss of funds or RGB
+/// state.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display(doc_comments)]
+pub enum WitnessWarning {
+    /// witness transaction has no output which can host {0} commitment.
+    NoHostOutput(CloseMethod),
+
+    /// output #{vout} hosting the commitment has value of {value} sats, which
+    /// is below the dust limit of {limit} sats.
+    HostDust { vout: u32, value: Sats, limit: Sats },
+
+    /// OP_RETURN output #{0} already contains data, so it can't host the
+    /// commitment.
+    OpretNotEmpty(u32),
+
+    /// OP_RETURN output #{vout} hosting the commitment burns {value} sats.
+    OpretBurnsValue { vout: u32, value: Sats },
+
+    /// internal key of taproot output #{0} hosting the commitment is not
+    /// known, so it can't be checked that the tapret commitment preserves its
+    /// script paths.
+    TapretUnknownKey(u32),
+
+    /// taproot output #{0} hosting the commitment is not a key-only output for
+    /// the provided internal key; tapret commitment will make its existing
+    /// script paths unspendable.
+    TapretScriptPaths(u32),
+
+    /// seal points to the witness output #{0}, which is absent from the
+    /// witness transaction.
+    SealOutputAbsent(u32),
+
+    /// seal points to the witness output #{0}, which is OP_RETURN output and
+    /// can't be spent.
+    SealUnspendable(u32),
+
+    /// seal points to the witness output #{vout} with value of {value} sats,
+    /// which is below the dust limit of {limit} sats.
+    SealDust { vout: u32, value: Sats, limit: Sats },
+}
+
+/// Template of the witness transaction, which is going to carry the
+/// commitment to the operations.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct WitnessTemplate {
+    pub tx: Tx,
+    pub method: CloseMethod,
+    /// Internal key of the taproot output hosting tapret commitment, which
+    /// can't be recovered from the transaction itself.
+    pub internal_pk: Option<InternalPk>,
+}
+
+impl WitnessTemplate {
+    pub fn new(tx: Tx, method: CloseMethod) -> Self {
+        WitnessTemplate {
+            tx,
+            method,
+            internal_pk: None,
+        }
+    }
+
+    pub fn with_internal_pk(mut self, internal_pk: InternalPk) -> Self {
+        self.internal_pk = Some(internal_pk);
+        self
+    }
+
+    /// Returns number of the output which will host the commitment: the first
+    /// OP_RETURN output for opret and the first taproot output for tapret.
+    pub fn host(&self) -> Option<u32> {
+        self.tx
+            .outputs()
+            .position(|txout| match self.method {
+                CloseMethod::OpretFirst => txout.script_pubkey.is_op_return(),
+                CloseMethod::TapretFirst => txout.script_pubkey.is_p2tr(),
+                _ => false,
+            })
+            .map(|vout| vout as u32)
+    }
+
+    /// Checks the output which will host the commitment.
+    pub fn check(&self) -> Vec<WitnessWarning> {
+        let Some(vout) = self.host() else {
+            return vec![WitnessWarning::NoHostOutput(self.method)];
+        };
+        let txout = &self.tx.outputs[vout as usize];
+        let script = &txout.script_pubkey;
+        let mut warnings = vec![];
+        if self.method == CloseMethod::OpretFirst {
+            if *script != ScriptPubkey::op_return(&[]) {
+                warnings.push(WitnessWarning::OpretNotEmpty(vout));
+            }
+            if txout.value != Sats::ZERO {
+                warnings.push(WitnessWarning::OpretBurnsValue {
+                    vout,
+                    value: txout.value,
+                });
+            }
+            return warnings;
+        }
+        let limit = dust_limit(script);
+        if txout.value < limit {
+            warnings.push(WitnessWarning::HostDust {
+                vout,
+                value: txout.value,
+                limit,
+            });
+        }
+        match self.internal_pk {
+            None => warnings.push(WitnessWarning::TapretUnknownKey(vout)),
+            Some(internal_pk) if *script != ScriptPubkey::p2tr_key_only(internal_pk) => {
+                warnings.push(WitnessWarning::TapretScriptPaths(vout))
+            }
+            Some(_) => {}
+        }
+        warnings
+    }
+
+    /// Checks the output which will host the commitment and the witness
+    /// outputs to which the transition assigns its state.
+    pub fn check_transition(&self, transition: &Transition) -> Vec<WitnessWarning> {
+        let mut warnings = self.check();
+        for assigns in transition.assignments.values() {
+            for no in 0..assigns.len_u16() {
+                let Ok(Some(SealDefinition::Bitcoin(seal) | SealDefinition::Liquid(seal))) =
+                    assigns.revealed_seal_at(no)
+                else {
+                    continue;
+                };
+                if seal.txid().is_some() {
+                    continue;
+                }
+                let vout = seal.vout().into_u32();
+                let warning = match self.tx.outputs.get(vout as usize) {
+                    None => WitnessWarning::SealOutputAbsent(vout),
+                    Some(txout) if txout.script_pubkey.is_op_return() => {
+                        WitnessWarning::SealUnspendable(vout)
+                    }
+                    Some(txout) if txout.value < dust_limit(&txout.script_pubkey) => {
+                        WitnessWarning::SealDust {
+                            vout,
+                            value: txout.value,
+                            limit: dust_limit(&txout.script_pubkey),
+                        }
+                    }
+                    Some(_) => continue,
+                };
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::Confined;
+    use bp::seals::txout::TxPtr;
+    use bp::{LockTime, TapBranchHash, TxOut, TxVer};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{Assign, AssignmentType, GraphSeal, TypedAssigns, VoidState};
+
+    #[test]
+    fn witness_template() {
+        assert_eq!(dust_limit(&ScriptPubkey::p2pkh([1; 20])), Sats::from_sats(546u64));
+        assert_eq!(dust_limit(&ScriptPubkey::p2wpkh([1; 20])), Sats::from_sats(294u64));
+        assert_eq!(dust_limit(&ScriptPubkey::op_return(&[1])), Sats::ZERO);
+
+        let internal_pk = InternalPk::from_str(
+            "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: empty!(),
+            outputs: Confined::try_from(vec![
+                TxOut::new(ScriptPubkey::p2tr_key_only(internal_pk), 200u64),
+                TxOut::new(ScriptPubkey::op_return(&[1]), 10u64),
+                TxOut::new(ScriptPubkey::p2wpkh([1; 20]), 1000u64),
+            ])
+            .unwrap(),
+            lock_time: LockTime::default(),
+        };
+
+        let template = WitnessTemplate::new(tx.clone(), CloseMethod::TapretFirst);
+        assert_eq!(template.check(), vec![
+            WitnessWarning::HostDust {
+                vout: 0,
+                value: Sats::from_sats(200u64),
+                limit: Sats::from_sats(330u64)
+            },
+            WitnessWarning::TapretUnknownKey(0)
+        ]);
+        let template = template.with_internal_pk(internal_pk);
+        assert_eq!(template.check().len(), 1);
+        let mut tweaked = template.clone();
+        tweaked.tx.outputs[0].script_pubkey =
+            ScriptPubkey::p2tr_scripted(internal_pk, TapBranchHash::from([1; 32]));
+        assert!(tweaked
+            .check()
+            .contains(&WitnessWarning::TapretScriptPaths(0)));
+
+        let template = WitnessTemplate::new(tx, CloseMethod::OpretFirst);
+        assert_eq!(template.check(), vec![
+            WitnessWarning::OpretNotEmpty(1),
+            WitnessWarning::OpretBurnsValue {
+                vout: 1,
+                value: Sats::from_sats(10u64)
+            }
+        ]);
+
+        let seal = |vout| {
+            let seal =
+                GraphSeal::with_blinding(CloseMethod::TapretFirst, TxPtr::WitnessTx, vout, 1);
+            Assign::revealed(SealDefinition::Bitcoin(seal), VoidState::default())
+        };
+        let mut transition = Transition::strict_dumb();
+        transition
+            .assignments
+            .insert(
+                AssignmentType::with(1),
+                TypedAssigns::Declarative(small_vec![seal(0), seal(1), seal(2), seal(5), seal(5)]),
+            )
+            .unwrap();
+        assert_eq!(template.check_transition(&transition)[2..], [
+            WitnessWarning::SealDust {
+                vout: 0,
+                value: Sats::from_sats(200u64),
+                limit: Sats::from_sats(330u64)
+            },
+            WitnessWarning::SealUnspendable(1),
+            WitnessWarning::SealOutputAbsent(5),
+        ]);
+    }
+}
@@ -22,19 +22,24 @@
 
 use core::fmt::Debug;
 use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
 use std::hash::Hash;
 use std::num::NonZeroU32;
+use std::str::FromStr;
 
+use baid58::Baid58ParseError;
 pub use bp::seals::txout::blind::{
     ChainBlindSeal as GraphSeal, ParseError, SecretSeal, SingleBlindSeal as GenesisSeal,
 };
 pub use bp::seals::txout::TxoSeal;
-use bp::Txid;
+use bp::{Outpoint, OutpointParseError, Txid};
 use commit_verify::{strategies, CommitVerify, Conceal, DigestExt, Sha256, UntaggedProtocol};
-use strict_encoding::{StrictDecode, StrictDumb, StrictEncode, StrictWriter};
+use strict_encoding::{
+    StrictDecode, StrictDeserialize, StrictDumb, StrictEncode, StrictSerialize, StrictWriter,
+};
 
 use crate::contract::contract::Output;
-use crate::{Layer1, LIB_NAME_RGB};
+use crate::{Layer1, OnLayer1, LIB_NAME_RGB};
 
 pub trait ExposedSeal:
     Debug + StrictDumb + StrictEncode + StrictDecode + Eq + Ord + Copy + Hash + TxoSeal
@@ -79,6 +84,9 @@ pub enum SealDefinition<U: ExposedSeal> {
      */
 }
 
+impl<U: ExposedSeal> StrictSerialize for SealDefinition<U> {}
+impl<U: ExposedSeal> StrictDeserialize for SealDefinition<U> {}
+
 impl<U: ExposedSeal> Conceal for SealDefinition<U> {
     type Concealed = SecretSeal;
 
@@ -122,22 +130,17 @@ impl<U: ExposedSeal> SealDefinition<U> {
 
     #[inline]
     pub fn output(self) -> Option<Output> {
-        match self {
-            SealDefinition::Bitcoin(seal) => seal.outpoint().map(Output::Bitcoin),
-            SealDefinition::Liquid(seal) => seal.outpoint().map(Output::Liquid),
-        }
+        let (layer1, seal) = self.into_parts();
+        seal.outpoint()
+            .map(|outpoint| Output::with_layer1(layer1, outpoint))
     }
 
     pub fn output_or_witness(self, witness_id: WitnessId) -> Result<Output, Self> {
-        match (self, witness_id) {
-            (SealDefinition::Bitcoin(seal), WitnessId::Bitcoin(txid)) => {
-                Ok(Output::Bitcoin(seal.outpoint_or(txid)))
-            }
-            (SealDefinition::Liquid(seal), WitnessId::Liquid(txid)) => {
-                Ok(Output::Liquid(seal.outpoint_or(txid)))
-            }
-            (me, _) => Err(me),
+        if !self.is_same_layer1(&witness_id) {
+            return Err(self);
         }
+        let (layer1, seal) = self.into_parts();
+        Ok(Output::with_layer1(layer1, seal.outpoint_or(*witness_id.as_inner())))
     }
 }
 
@@ -153,6 +156,7 @@ impl<U: ExposedSeal> SealDefinition<U> {
 pub struct WitnessPos {
     height: u32,
     timestamp: i64,
+    tx_pos: u32,
 }
 
 impl WitnessPos {
@@ -160,10 +164,24 @@ impl WitnessPos {
         if height == 0 || timestamp < 1231006505 {
             return None;
         }
-        Some(WitnessPos { height, timestamp })
+        Some(WitnessPos {
+            height,
+            timestamp,
+            tx_pos: 0,
+        })
+    }
+
+    /// Sets position of the witness transaction within the block mining it,
+    /// as reported by the resolver.
+    pub fn with_tx_pos(mut self, tx_pos: u32) -> Self {
+        self.tx_pos = tx_pos;
+        self
     }
 
     pub fn height(&self) -> NonZeroU32 { NonZeroU32::new(self.height).expect("invariant") }
+
+    /// Position of the witness transaction within the block; zero if unknown.
+    pub fn tx_pos(&self) -> u32 { self.tx_pos }
 }
 
 impl PartialOrd for WitnessPos {
@@ -171,7 +189,15 @@ impl PartialOrd for WitnessPos {
 }
 
 impl Ord for WitnessPos {
-    fn cmp(&self, other: &Self) -> Ordering { self.timestamp.cmp(&other.timestamp) }
+    /// Orders positions by the block height and then by the position of the
+    /// transaction within the block. Block timestamps are not monotonic and
+    /// are compared last only to keep the ordering consistent with equality.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.height
+            .cmp(&other.height)
+            .then(self.tx_pos.cmp(&other.tx_pos))
+            .then(self.timestamp.cmp(&other.timestamp))
+    }
 }
 
 /// RGB consensus information about the current mined height of a witness
@@ -227,6 +253,15 @@ impl PartialOrd for WitnessId {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
 }
 
+impl WitnessId {
+    /// Returns id of the witness transaction on its layer 1.
+    pub fn txid(&self) -> Txid {
+        match self {
+            WitnessId::Bitcoin(txid) | WitnessId::Liquid(txid) => *txid,
+        }
+    }
+}
+
 impl Ord for WitnessId {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -239,3 +274,124 @@ impl Ord for WitnessId {
         }
     }
 }
+
+/// Errors parsing [`InvoiceSeal`] string representation.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum InvoiceSealParseError {
+    /// invalid blinded seal - {0}
+    #[from]
+    Blinded(Baid58ParseError),
+
+    /// invalid seal outpoint - {0}
+    #[from]
+    Outpoint(OutpointParseError),
+
+    /// unknown layer 1 '{0}' in the seal reference.
+    UnknownLayer1(String),
+
+    /// seal reference '{0}' is neither a blinded seal nor an explicit output.
+    Unrecognized(String),
+}
+
+/// Seal reference used in invoices.
+///
+/// The string representation is either a blinded seal in form of
+/// `utxob:<baid58>` or an explicit output in form of
+/// `<layer1>:<txid>:<vout>`, where layer 1 is `bitcoin` or `liquid`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum InvoiceSeal {
+    /// Blinded (concealed) seal definition.
+    #[from]
+    Blinded(SecretSeal),
+
+    /// Explicit transaction output.
+    #[from]
+    Explicit(Output),
+}
+
+impl InvoiceSeal {
+    const BLINDED_PREFIX: &'static str = "utxob:";
+
+    pub fn layer1(&self) -> Option<Layer1> {
+        match self {
+            InvoiceSeal::Blinded(_) => None,
+            InvoiceSeal::Explicit(output) => Some(output.layer1()),
+        }
+    }
+}
+
+impl Display for InvoiceSeal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InvoiceSeal::Blinded(seal) => Display::fmt(seal, f),
+            InvoiceSeal::Explicit(Output::Bitcoin(outpoint)) => write!(f, "bitcoin:{outpoint}"),
+            InvoiceSeal::Explicit(Output::Liquid(outpoint)) => write!(f, "liquid:{outpoint}"),
+        }
+    }
+}
+
+impl FromStr for InvoiceSeal {
+    type Err = InvoiceSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with(Self::BLINDED_PREFIX) {
+            return SecretSeal::from_str(s)
+                .map(InvoiceSeal::Blinded)
+                .map_err(InvoiceSealParseError::from);
+        }
+        let (layer1, outpoint) = s
+            .split_once(':')
+            .ok_or_else(|| InvoiceSealParseError::Unrecognized(s.to_owned()))?;
+        let outpoint = Outpoint::from_str(outpoint)?;
+        match layer1 {
+            "bitcoin" => Ok(InvoiceSeal::Explicit(Output::Bitcoin(outpoint))),
+            "liquid" => Ok(InvoiceSeal::Explicit(Output::Liquid(outpoint))),
+            _ => Err(InvoiceSealParseError::UnknownLayer1(layer1.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+
+    use super::*;
+
+    #[test]
+    fn invoice_seal() {
+        let blinded = InvoiceSeal::Blinded(SecretSeal::from_byte_array([0x6c; 32]));
+        let s = blinded.to_string();
+        assert!(s.starts_with("utxob:"));
+        assert_eq!(InvoiceSeal::from_str(&s), Ok(blinded));
+        assert_eq!(blinded.layer1(), None);
+
+        let s = "bitcoin:646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839:5";
+        let explicit = InvoiceSeal::from_str(s).unwrap();
+        assert_eq!(explicit.to_string(), s);
+        assert_eq!(explicit.layer1(), Some(Layer1::Bitcoin));
+        let liquid = s.replace("bitcoin:", "liquid:");
+        assert_eq!(InvoiceSeal::from_str(&liquid).unwrap().layer1(), Some(Layer1::Liquid));
+
+        assert_eq!(
+            InvoiceSeal::from_str(&s.replace("bitcoin:", "prime:")),
+            Err(InvoiceSealParseError::UnknownLayer1(s!("prime")))
+        );
+        assert!(matches!(
+            InvoiceSeal::from_str(&s.replace(":5", ":x")),
+            Err(InvoiceSealParseError::Outpoint(_))
+        ));
+        assert!(matches!(
+            InvoiceSeal::from_str(
+                "646ca5c1062619e2a2d60771c9dfd820551fb773e4dc8c4ed67965a8d1fae839"
+            ),
+            Err(InvoiceSealParseError::Unrecognized(_))
+        ));
+        assert!(InvoiceSeal::from_str(&format!("{s} ")).is_err());
+    }
+}
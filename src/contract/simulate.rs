@@ -0,0 +1,338 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dry-run validation of state transitions against the contract state.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use bp::Txid;
+
+use crate::schema::OccurrencesMismatch;
+use crate::{
+    Assign, AssignmentType, ContractId, ContractState, ExposedState, GraphSeal, Layer1, OpId,
+    Operation, Opout, Transition, TransitionType, TypedAssigns, WitnessAnchor, WitnessId,
+};
+
+/// Errors detected by [`ContractState::simulate`].
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum SimulationError {
+    /// state transition belongs to a different contract {0}.
+    ContractMismatch(ContractId),
+
+    /// state transition {0} is already known to the contract history.
+    AlreadyKnown(OpId),
+
+    /// state transition type {0} is not declared by the contract schema.
+    UnknownTransitionType(TransitionType),
+
+    /// input {0} is not known to the contract state.
+    InputUnknown(Opout),
+
+    /// input {0} is already spent by operation {1}.
+    InputSpent(Opout, OpId),
+
+    /// state transition schema doesn't allow inputs of type {0}.
+    InputTypeUnexpected(AssignmentType),
+
+    /// state transition schema doesn't allow assignments of type {0}.
+    AssignmentTypeUnexpected(AssignmentType),
+
+    /// number of inputs of type {0} doesn't match the state transition schema:
+    /// {1}.
+    InputOccurrences(AssignmentType, OccurrencesMismatch),
+
+    /// number of assignments of type {0} doesn't match the state transition
+    /// schema: {1}.
+    AssignmentOccurrences(AssignmentType, OccurrencesMismatch),
+
+    /// fungible state of type {0} assigned by the state transition is
+    /// concealed and can't be checked for the balance.
+    ConcealedValue(AssignmentType),
+
+    /// fungible state of type {ty} is not balanced: inputs sum up to {inputs},
+    /// while assignments sum up to {outputs}.
+    Unbalanced {
+        ty: AssignmentType,
+        inputs: u128,
+        outputs: u128,
+    },
+
+    /// state transition assigns state to seals pointing to witness
+    /// transactions on different layers 1.
+    WitnessLayerMismatch,
+}
+
+impl ContractState {
+    /// Checks a candidate state transition against the current contract state
+    /// without a witness transaction, returning the projected contract state
+    /// after the transition.
+    ///
+    /// The check verifies that the transition type and the types and numbers
+    /// of its inputs and assignments match the schema, that all inputs are
+    /// known to the contract state and are not spent, and that the amounts of
+    /// each fungible state type spent by the transition are equal to the
+    /// amounts it assigns. Scripts of the schema are not run.
+    ///
+    /// Since the witness transaction doesn't exist yet, state assigned to the
+    /// witness transaction outputs is projected with an all-zero witness txid
+    /// and is treated as unmined.
+    pub fn simulate(&self, transition: &Transition) -> Result<ContractState, SimulationError> {
+        if transition.contract_id != self.contract_id() {
+            return Err(SimulationError::ContractMismatch(transition.contract_id));
+        }
+        let opid = transition.id();
+        if self.operation(opid).is_some() {
+            return Err(SimulationError::AlreadyKnown(opid));
+        }
+
+        let ty = transition.transition_type;
+        let blank_transition = self.schema.blank_transition();
+        let schema = match self.schema.transitions.get(&ty) {
+            None if ty == TransitionType::BLANK => &blank_transition,
+            None => return Err(SimulationError::UnknownTransitionType(ty)),
+            Some(schema) => schema,
+        };
+
+        let mut inputs = BTreeMap::<AssignmentType, u16>::new();
+        let mut balance = BTreeMap::<AssignmentType, (u128, u128)>::new();
+        for input in &transition.inputs {
+            let opout = input.prev_out;
+            if !schema.inputs.contains_key(&opout.ty) {
+                return Err(SimulationError::InputTypeUnexpected(opout.ty));
+            }
+            if let Some(spender) = self.spent_by(opout) {
+                return Err(SimulationError::InputSpent(opout, spender));
+            }
+            if let Some(output) = self.fungibles().get(&opout) {
+                let (spent, _) = balance.entry(opout.ty).or_default();
+                *spent = spent.saturating_add(output.state.value.atoms());
+            } else if !self.rights().contains(&opout) &&
+                !self.data().contains(&opout) &&
+                !self.attach().contains(&opout)
+            {
+                return Err(SimulationError::InputUnknown(opout));
+            }
+            *inputs.entry(opout.ty).or_default() += 1;
+        }
+        for (ty, occ) in &schema.inputs {
+            let count = inputs.get(ty).copied().unwrap_or_default();
+            occ.check(count)
+                .map_err(|err| SimulationError::InputOccurrences(*ty, err))?;
+        }
+
+        let mut layers = BTreeSet::new();
+        for (ty, assigns) in transition.assignments.iter() {
+            if !schema.assignments.contains_key(ty) {
+                return Err(SimulationError::AssignmentTypeUnexpected(*ty));
+            }
+            match assigns {
+                TypedAssigns::Declarative(assigns) => witness_layers(assigns, &mut layers),
+                TypedAssigns::Fungible(assigns) => {
+                    witness_layers(assigns, &mut layers);
+                    let (_, assigned) = balance.entry(*ty).or_default();
+                    for assign in assigns {
+                        let value = assign
+                            .as_revealed_state()
+                            .ok_or(SimulationError::ConcealedValue(*ty))?;
+                        *assigned = assigned.saturating_add(value.value.atoms());
+                    }
+                }
+                TypedAssigns::Structured(assigns) => witness_layers(assigns, &mut layers),
+                TypedAssigns::Attachment(assigns) => witness_layers(assigns, &mut layers),
+            }
+        }
+        for (ty, occ) in &schema.assignments {
+            let count = transition
+                .assignments
+                .get(ty)
+                .map(TypedAssigns::len_u16)
+                .unwrap_or_default();
+            occ.check(count)
+                .map_err(|err| SimulationError::AssignmentOccurrences(*ty, err))?;
+        }
+
+        for (ty, (inputs, outputs)) in balance {
+            if inputs != outputs {
+                return Err(SimulationError::Unbalanced {
+                    ty,
+                    inputs,
+                    outputs,
+                });
+            }
+        }
+
+        let txid = Txid::from([0u8; 32]);
+        let witness_id = match layers.into_iter().collect::<Vec<_>>().as_slice() {
+            [] | [Layer1::Bitcoin] => WitnessId::Bitcoin(txid),
+            [Layer1::Liquid] => WitnessId::Liquid(txid),
+            _ => return Err(SimulationError::WitnessLayerMismatch),
+        };
+        let mut projected = self.clone();
+        projected
+            .add_transition(transition, WitnessAnchor::from_mempool(witness_id))
+            .expect("transition inputs are checked to be unspent");
+        Ok(projected)
+    }
+}
+
+/// Collects layers 1 of the revealed seals which point to the outputs of the
+/// witness transaction.
+fn witness_layers<State: ExposedState>(
+    assigns: &[Assign<State, GraphSeal>],
+    layers: &mut BTreeSet<Layer1>,
+) {
+    layers.extend(
+        assigns
+            .iter()
+            .filter_map(Assign::revealed_seal)
+            .filter(|seal| seal.output().is_none())
+            .map(|seal| seal.layer1()),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::seals::txout::{CloseMethod, TxPtr};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{
+        AssetTag, BlindingFactor, ContractHistory, FungibleType, Genesis, GenesisSeal, Input,
+        Inputs, Occurrences, RevealedValue, SealDefinition, StateSchema, SubSchema,
+        TransitionSchema,
+    };
+
+    #[test]
+    fn simulate() {
+        let ty = AssignmentType::with(1);
+        let transition_type = TransitionType::with(1);
+        let mut schema = SubSchema::default();
+        schema
+            .owned_types
+            .insert(ty, StateSchema::Fungible(FungibleType::Unsigned64Bit))
+            .unwrap();
+        let mut transition_schema = TransitionSchema::default();
+        transition_schema
+            .inputs
+            .insert(ty, Occurrences::OnceOrMore)
+            .unwrap();
+        transition_schema
+            .assignments
+            .insert(ty, Occurrences::OnceOrMore)
+            .unwrap();
+        schema
+            .transitions
+            .insert(transition_type, transition_schema)
+            .unwrap();
+
+        let value = |amount: u64| {
+            RevealedValue::with_blinding(amount, BlindingFactor::EMPTY, AssetTag::from([1; 32]))
+        };
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .assignments
+            .insert(
+                ty,
+                TypedAssigns::Fungible(
+                    Confined::try_from_iter([Assign::revealed(
+                        SealDefinition::Bitcoin(GenesisSeal::with_blinding(
+                            CloseMethod::TapretFirst,
+                            Txid::from([1; 32]),
+                            0,
+                            1,
+                        )),
+                        value(100),
+                    )])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let state = ContractState {
+            schema,
+            history: ContractHistory::with(
+                genesis.schema_id,
+                None,
+                genesis.contract_id(),
+                &genesis,
+            ),
+        };
+        let opout = Opout::new(genesis.id(), ty, 0);
+
+        let transfer = |input: Opout, amounts: &[u64]| {
+            let mut transition = Transition::strict_dumb();
+            transition.contract_id = genesis.contract_id();
+            transition.transition_type = transition_type;
+            transition.inputs =
+                Inputs::from(Confined::try_from_iter([Input::with(input)]).unwrap());
+            let assigns = amounts.iter().enumerate().map(|(vout, amount)| {
+                let seal = GraphSeal::with_blinding(
+                    CloseMethod::TapretFirst,
+                    TxPtr::WitnessTx,
+                    vout as u32,
+                    1,
+                );
+                Assign::revealed(SealDefinition::Bitcoin(seal), value(*amount))
+            });
+            transition
+                .assignments
+                .insert(ty, TypedAssigns::Fungible(Confined::try_from_iter(assigns).unwrap()))
+                .unwrap();
+            transition
+        };
+
+        let transition = transfer(opout, &[60, 40]);
+        let projected = state.simulate(&transition).unwrap();
+        assert!(!state.is_spent(opout));
+        assert_eq!(projected.spent_by(opout), Some(transition.id()));
+        assert_eq!(projected.fungibles().len(), 3);
+        assert_eq!(
+            projected.simulate(&transition).unwrap_err(),
+            SimulationError::AlreadyKnown(transition.id())
+        );
+        assert_eq!(
+            projected.simulate(&transfer(opout, &[100])).unwrap_err(),
+            SimulationError::InputSpent(opout, transition.id())
+        );
+
+        assert_eq!(
+            state.simulate(&transfer(opout, &[60, 50])).unwrap_err(),
+            SimulationError::Unbalanced {
+                ty,
+                inputs: 100,
+                outputs: 110
+            }
+        );
+        let unknown = Opout::new(OpId::strict_dumb(), ty, 0);
+        assert_eq!(
+            state.simulate(&transfer(unknown, &[100])).unwrap_err(),
+            SimulationError::InputUnknown(unknown)
+        );
+        let mut transition = transfer(opout, &[100]);
+        transition.transition_type = TransitionType::with(2);
+        assert_eq!(
+            state.simulate(&transition).unwrap_err(),
+            SimulationError::UnknownTransitionType(TransitionType::with(2))
+        );
+    }
+}
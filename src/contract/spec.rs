@@ -0,0 +1,112 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contract specification ids.
+//!
+//! Contract id commits to all genesis data, thus two contracts having the same
+//! name, ticker and other human-meaningful global state but issued to
+//! different seals have different ids, while being indistinguishable for a
+//! user. [`ContractSpecId`] commits only to the data defining how the contract
+//! is presented, allowing wallets to detect such contracts: different
+//! contracts sharing the same specification id are likely impersonating each
+//! other.
+
+use amplify::{Bytes32, Wrapper};
+use commit_verify::{DigestExt, Sha256};
+use strict_encoding::{StrictEncode, StrictWriter};
+
+use crate::{Genesis, LIB_NAME_RGB};
+
+const CONTRACT_SPEC_TAG: &str = "urn:lnpbp:rgb:contract-spec#240201";
+
+/// Identifier of a contract specification, committing to the schema, chain
+/// and the global state of the contract genesis.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ContractSpecId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl Genesis {
+    /// Computes id of the contract specification, which doesn't depend on
+    /// the genesis seals, owned state, metadata and valencies.
+    pub fn spec_id(&self) -> ContractSpecId {
+        let mut engine = Sha256::from_tag(CONTRACT_SPEC_TAG);
+        let w = StrictWriter::with(u32::MAX as usize, &mut engine);
+        self.schema_id
+            .strict_encode(w)
+            .and_then(|w| self.chain_net.strict_encode(w))
+            .and_then(|w| self.globals.strict_encode(w))
+            .expect("hash engine doesn't fail");
+        ContractSpecId::from_inner(engine.finish().into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{SmallBlob, SmallVec};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{ChainNet, GlobalStateType, Operation, RevealedData};
+
+    #[test]
+    fn spec_id() {
+        let mut genesis = Genesis::strict_dumb();
+        genesis
+            .globals
+            .add_state(
+                GlobalStateType::with(1),
+                RevealedData::from(SmallVec::try_from(b"TICKER".to_vec()).unwrap()),
+            )
+            .unwrap();
+        let spec_id = genesis.spec_id();
+
+        let mut impersonator = genesis.clone();
+        impersonator.metadata = SmallBlob::try_from(vec![1u8]).unwrap();
+        assert_ne!(impersonator.contract_id(), genesis.contract_id());
+        assert_eq!(impersonator.spec_id(), spec_id);
+
+        let mut other_chain = genesis.clone();
+        other_chain.chain_net = ChainNet::BitcoinTestnet;
+        assert_ne!(other_chain.spec_id(), spec_id);
+
+        let mut other_name = genesis;
+        other_name
+            .globals
+            .add_state(
+                GlobalStateType::with(1),
+                RevealedData::from(SmallVec::try_from(b"TICKER2".to_vec()).unwrap()),
+            )
+            .unwrap();
+        assert_ne!(other_name.spec_id(), spec_id);
+    }
+}
@@ -26,9 +26,10 @@ use core::hash::Hash;
 use commit_verify::{CommitEncode, Conceal};
 use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
 
+use crate::schema::FungibleType;
 use crate::{
-    ConcealedAttach, ConcealedData, ConcealedValue, RevealedAttach, RevealedData, RevealedValue,
-    LIB_NAME_RGB,
+    ConcealedAttach, ConcealedData, ConcealedValue, FixedDecimal, FungibleState, RevealedAttach,
+    RevealedData, RevealedValue, LIB_NAME_RGB,
 };
 
 /// Marker trait for types of state which are just a commitment to the actual
@@ -126,6 +127,150 @@ impl Conceal for StateData {
     }
 }
 
+impl StateData {
+    /// Accesses the state data as a specific type, failing if the state is of
+    /// a different category or can't be converted into the type without a
+    /// loss of precision.
+    pub fn downcast<T: FromStateData>(&self) -> Result<T, StateTypeMismatch> {
+        T::from_state_data(self)
+    }
+}
+
+/// Errors accessing state data as a specific type.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum StateTypeMismatch {
+    /// {found} state can't be accessed as {expected}.
+    Category {
+        expected: &'static str,
+        found: StateType,
+    },
+
+    /// fungible value of {found} type can't be losslessly converted into
+    /// {expected}.
+    Narrowing {
+        expected: &'static str,
+        found: FungibleType,
+    },
+}
+
+/// Types which can be extracted from [`StateData`] with
+/// [`StateData::downcast`].
+///
+/// Fungible values are converted only if the conversion is lossless: 64-bit
+/// values can be read as `u64`, `u128` or [`FixedDecimal`], 128-bit values
+/// as `u128` or [`FixedDecimal`] and decimal values only as [`FixedDecimal`].
+pub trait FromStateData: Sized {
+    /// Human-readable name of the type used in error messages.
+    const NAME: &'static str;
+
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch>;
+}
+
+fn mismatch<T: FromStateData>(data: &StateData) -> StateTypeMismatch {
+    match data {
+        StateData::Fungible(value) => StateTypeMismatch::Narrowing {
+            expected: T::NAME,
+            found: value.value.fungible_type(),
+        },
+        _ => StateTypeMismatch::Category {
+            expected: T::NAME,
+            found: data.state_type(),
+        },
+    }
+}
+
+impl FromStateData for () {
+    const NAME: &'static str = "void";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Void => Ok(()),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for RevealedValue {
+    const NAME: &'static str = "fungible value";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Fungible(value) => Ok(*value),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for FungibleState {
+    const NAME: &'static str = "fungible value";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        RevealedValue::from_state_data(data).map(|value| value.value)
+    }
+}
+
+impl FromStateData for u64 {
+    const NAME: &'static str = "u64";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Fungible(RevealedValue {
+                value: FungibleState::Bits64(value),
+                ..
+            }) => Ok(*value),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for u128 {
+    const NAME: &'static str = "u128";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Fungible(RevealedValue {
+                value: FungibleState::Bits64(value),
+                ..
+            }) => Ok(*value as u128),
+            StateData::Fungible(RevealedValue {
+                value: FungibleState::Bits128(value),
+                ..
+            }) => Ok(*value),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for FixedDecimal {
+    const NAME: &'static str = "decimal";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Fungible(RevealedValue {
+                value: FungibleState::Decimal(decimal),
+                ..
+            }) => Ok(*decimal),
+            StateData::Fungible(value) => Ok(FixedDecimal::new(value.value.atoms(), 0)),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for RevealedData {
+    const NAME: &'static str = "structured data";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Structured(data) => Ok(data.clone()),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
+impl FromStateData for RevealedAttach {
+    const NAME: &'static str = "attachment";
+    fn from_state_data(data: &StateData) -> Result<Self, StateTypeMismatch> {
+        match data {
+            StateData::Attachment(attach) => Ok(attach.clone()),
+            _ => Err(mismatch::<Self>(data)),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
 #[strict_type(lib = LIB_NAME_RGB, tags = custom)]
@@ -158,3 +303,44 @@ impl ConfidentialState for StateCommitment {
     }
     fn state_commitment(&self) -> StateCommitment { *self }
 }
+
+#[cfg(test)]
+mod test {
+    use amplify::ByteArray;
+    use bp::secp256k1::rand::thread_rng;
+
+    use super::*;
+    use crate::AssetTag;
+
+    #[test]
+    fn downcast() {
+        let tag = AssetTag::from_byte_array([1u8; 32]);
+        let fungible = |value: FungibleState| {
+            StateData::Fungible(RevealedValue::with_random_blinding(value, &mut thread_rng(), tag))
+        };
+
+        let small = fungible(FungibleState::Bits64(10));
+        assert_eq!(small.downcast::<u64>(), Ok(10));
+        assert_eq!(small.downcast::<u128>(), Ok(10));
+        assert_eq!(small.downcast::<FixedDecimal>(), Ok(FixedDecimal::new(10, 0)));
+
+        let large = fungible(FungibleState::Bits128(10));
+        assert_eq!(large.downcast::<u128>(), Ok(10));
+        assert_eq!(
+            large.downcast::<u64>(),
+            Err(StateTypeMismatch::Narrowing {
+                expected: "u64",
+                found: FungibleType::Unsigned128Bit
+            })
+        );
+
+        assert_eq!(StateData::Void.downcast::<()>(), Ok(()));
+        assert_eq!(
+            StateData::Void.downcast::<u64>(),
+            Err(StateTypeMismatch::Category {
+                expected: "u64",
+                found: StateType::Void
+            })
+        );
+    }
+}
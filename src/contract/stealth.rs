@@ -0,0 +1,246 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stealth (one-time) seals derived from reusable payment codes.
+//!
+//! A recipient publishes a static [`PaymentCode`] consisting of a scan and a
+//! spend public key. For each transfer the sender picks an ephemeral key and
+//! computes an ECDH shared secret with the scan key, from which it derives the
+//! seal blinding factor and a tweak of the spend key. The witness transaction
+//! output at the seal vout pays to the tweaked spend key, so neither the seal
+//! nor the output can be linked to the payment code by third parties.
+//!
+//! Given the ephemeral public key of the transfer, the recipient re-derives
+//! the seals with its [`ScanKey`] and detects the ones present in a
+//! consignment.
+
+use std::collections::BTreeSet;
+
+use bp::seals::txout::{CloseMethod, TxPtr};
+use bp::secp256k1::ecdh::SharedSecret;
+use bp::secp256k1::{PublicKey, Scalar, SecretKey, SECP256K1};
+use bp::CompressedPk;
+use commit_verify::{Conceal, DigestExt, Sha256};
+
+use crate::{
+    Consignment, GraphSeal, Layer1, OnLayer1, SealDefinition, SecretSeal, TypedAssigns,
+    LIB_NAME_RGB,
+};
+
+const STEALTH_TAG: &str = "urn:lnpbp:rgb:stealth#240112";
+
+/// Reusable recipient identifier for stealth transfers.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct PaymentCode {
+    pub scan: CompressedPk,
+    pub spend: CompressedPk,
+}
+
+/// One-time seal derived from a payment code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct StealthSeal {
+    pub seal: SealDefinition<GraphSeal>,
+    /// Key which must be used by the witness transaction output the seal
+    /// points to.
+    pub output_key: PublicKey,
+    /// Tweak which must be added to the recipient spend private key to spend
+    /// the output.
+    pub tweak: SecretKey,
+}
+
+impl PaymentCode {
+    /// Derives stealth seal for a transfer using the sender ephemeral key. The
+    /// public part of the ephemeral key must be provided to the recipient.
+    pub fn derive_seal(
+        &self,
+        ephemeral: &SecretKey,
+        layer1: Layer1,
+        method: CloseMethod,
+        vout: u32,
+    ) -> StealthSeal {
+        let shared = SharedSecret::new(&self.scan, ephemeral);
+        StealthSeal::derive(&shared, *self.spend, layer1, method, vout)
+    }
+}
+
+impl StealthSeal {
+    fn derive(
+        shared: &SharedSecret,
+        spend: PublicKey,
+        layer1: Layer1,
+        method: CloseMethod,
+        vout: u32,
+    ) -> Self {
+        let hash = |domain: u8| {
+            let mut engine = Sha256::from_tag(STEALTH_TAG);
+            engine.input_raw(&shared.secret_bytes());
+            engine.input_raw(&[domain]);
+            engine.input_raw(&vout.to_le_bytes());
+            engine.finish()
+        };
+
+        let mut blinding = [0u8; 8];
+        blinding.copy_from_slice(&hash(0)[..8]);
+        let seal =
+            GraphSeal::with_blinding(method, TxPtr::WitnessTx, vout, u64::from_le_bytes(blinding));
+        let seal = SealDefinition::with_layer1(layer1, seal);
+
+        // Probability of the hash not being a valid secret key is negligible
+        let tweak = SecretKey::from_slice(&hash(1)).expect("negligible probability");
+        let output_key = spend
+            .add_exp_tweak(SECP256K1, &Scalar::from(tweak))
+            .expect("negligible probability");
+
+        StealthSeal {
+            seal,
+            output_key,
+            tweak,
+        }
+    }
+}
+
+/// Recipient key used for detecting stealth seals.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ScanKey {
+    scan: SecretKey,
+    spend: PublicKey,
+}
+
+impl ScanKey {
+    pub fn new(scan: SecretKey, spend: PublicKey) -> Self { ScanKey { scan, spend } }
+
+    pub fn payment_code(&self) -> PaymentCode {
+        PaymentCode {
+            scan: self.scan.public_key(SECP256K1).into(),
+            spend: self.spend.into(),
+        }
+    }
+
+    /// Derives stealth seal for a transfer with the given sender ephemeral
+    /// public key.
+    pub fn derive_seal(
+        &self,
+        ephemeral: PublicKey,
+        layer1: Layer1,
+        method: CloseMethod,
+        vout: u32,
+    ) -> StealthSeal {
+        let shared = SharedSecret::new(&ephemeral, &self.scan);
+        StealthSeal::derive(&shared, self.spend, layer1, method, vout)
+    }
+
+    /// Detects seals of the consignment which were derived for the transfer
+    /// with the given sender ephemeral public key. Witness transaction outputs
+    /// with numbers above `max_vout` are not checked.
+    pub fn scan_consignment(
+        &self,
+        consignment: &Consignment,
+        ephemeral: PublicKey,
+        max_vout: u32,
+    ) -> Vec<StealthSeal> {
+        let concealed = consignment
+            .bundles
+            .iter()
+            .flat_map(|anchored| anchored.bundle.values())
+            .filter_map(|item| item.transition.as_ref())
+            .flat_map(|transition| transition.assignments.values())
+            .flat_map(TypedAssigns::to_confidential_seals)
+            .chain(consignment.terminals.iter().map(|terminal| terminal.seal))
+            .collect::<BTreeSet<SecretSeal>>();
+
+        let shared = SharedSecret::new(&ephemeral, &self.scan);
+        let mut found = vec![];
+        for layer1 in [Layer1::Bitcoin, Layer1::Liquid] {
+            for method in [CloseMethod::OpretFirst, CloseMethod::TapretFirst] {
+                for vout in 0..=max_vout {
+                    let stealth = StealthSeal::derive(&shared, self.spend, layer1, method, vout);
+                    if concealed.contains(&stealth.seal.conceal()) {
+                        found.push(stealth);
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::Confined;
+    use bp::secp256k1::rand::thread_rng;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{Assign, AssignmentType, TransitionBundle, VoidState};
+
+    #[test]
+    fn derive_scan() {
+        let scan_key = ScanKey::new(
+            SecretKey::new(&mut thread_rng()),
+            SecretKey::new(&mut thread_rng()).public_key(SECP256K1),
+        );
+        let code = scan_key.payment_code();
+        let ephemeral = SecretKey::new(&mut thread_rng());
+        let stealth = code.derive_seal(&ephemeral, Layer1::Bitcoin, CloseMethod::TapretFirst, 1);
+        assert_eq!(
+            stealth,
+            scan_key.derive_seal(
+                ephemeral.public_key(SECP256K1),
+                Layer1::Bitcoin,
+                CloseMethod::TapretFirst,
+                1
+            )
+        );
+
+        let mut transition = crate::Transition::strict_dumb();
+        transition
+            .assignments
+            .insert(
+                AssignmentType::with(1),
+                TypedAssigns::Declarative(
+                    Confined::try_from(vec![Assign::Confidential {
+                        seal: stealth.seal.conceal(),
+                        state: VoidState::default(),
+                        payment_tag: None,
+                    }])
+                    .unwrap(),
+                ),
+            )
+            .unwrap();
+        let mut consignment = Consignment::strict_dumb();
+        let mut anchored = crate::AnchoredBundle::strict_dumb();
+        anchored.bundle = TransitionBundle::try_from_transitions([(0, transition)]).unwrap();
+        consignment.bundles.push(anchored).unwrap();
+
+        let found = scan_key.scan_consignment(&consignment, ephemeral.public_key(SECP256K1), 4);
+        assert_eq!(found, vec![stealth]);
+        let other = SecretKey::new(&mut thread_rng()).public_key(SECP256K1);
+        assert!(scan_key.scan_consignment(&consignment, other, 4).is_empty());
+    }
+}
@@ -0,0 +1,507 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage backends for contract history operations.
+//!
+//! [`ContractHistory`] keeps newly added operations in memory; operations
+//! added earlier may be kept in a [`HistoryStore`] attached to the history,
+//! from which they are loaded on request. With the `mmap` feature on unix
+//! systems, the crate provides [`MmapStore`] keeping operations in a
+//! read-only memory-mapped file, which allows the operating system to page
+//! in only the operations which are actually accessed.
+//!
+//! [`ContractHistory`]: crate::ContractHistory
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::{
+    AnchoredBundle, BundleId, Consignment, ContractId, ContractState, OpId, Operation, Opout,
+    SharedOp,
+};
+
+/// Read-only store of contract history operations.
+pub trait HistoryStore: Debug + Send + Sync {
+    /// Checks whether the store contains the operation.
+    fn contains(&self, opid: OpId) -> bool;
+
+    /// Loads the operation from the store.
+    fn load(&self, opid: OpId) -> Option<SharedOp>;
+
+    /// Returns number of operations in the store.
+    fn len(&self) -> usize;
+
+    /// Checks whether the store has no operations.
+    fn is_empty(&self) -> bool { self.len() == 0 }
+}
+
+/// Persistence of contract data, allowing daemons to keep the data in a
+/// database of their choice.
+///
+/// Store keeps operations, anchored bundles and state snapshots, keyed by
+/// their ids, and the index of the operations spending each known output.
+/// [`MemStateStore`] is a reference in-memory implementation.
+pub trait StateStore {
+    /// Error of the storage backend.
+    type Error: Error;
+
+    fn operation(&self, opid: OpId) -> Result<Option<SharedOp>, Self::Error>;
+
+    fn put_operation(&mut self, op: SharedOp) -> Result<(), Self::Error>;
+
+    fn bundle(&self, bundle_id: BundleId) -> Result<Option<AnchoredBundle>, Self::Error>;
+
+    fn put_bundle(&mut self, bundle: AnchoredBundle) -> Result<(), Self::Error>;
+
+    /// Returns the latest saved snapshot of the contract state.
+    fn state(&self, contract_id: ContractId) -> Result<Option<ContractState>, Self::Error>;
+
+    /// Saves snapshot of the contract state, replacing the previous one.
+    fn put_state(&mut self, state: ContractState) -> Result<(), Self::Error>;
+
+    /// Returns operation spending the output, if known.
+    fn spender(&self, opout: Opout) -> Result<Option<OpId>, Self::Error>;
+
+    fn put_spender(&mut self, opout: Opout, spender: OpId) -> Result<(), Self::Error>;
+
+    /// Saves all operations and anchored bundles of the consignment, indexing
+    /// outputs spent by its state transitions. The consignment must be
+    /// validated before being saved.
+    fn put_consignment(&mut self, consignment: &Consignment) -> Result<(), Self::Error> {
+        self.put_operation(Arc::new(consignment.genesis.clone()).into())?;
+        for extension in &consignment.extensions {
+            self.put_operation(Arc::new(extension.clone()).into())?;
+        }
+        for anchored_bundle in &consignment.bundles {
+            for transition in anchored_bundle
+                .bundle
+                .values()
+                .filter_map(|item| item.transition.as_ref())
+            {
+                let opid = transition.id();
+                for input in &transition.inputs {
+                    self.put_spender(input.prev_out, opid)?;
+                }
+                self.put_operation(Arc::new(transition.clone()).into())?;
+            }
+            self.put_bundle(anchored_bundle.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// In-memory [`StateStore`] implementation.
+#[derive(Clone, Debug, Default)]
+pub struct MemStateStore {
+    operations: BTreeMap<OpId, SharedOp>,
+    bundles: BTreeMap<BundleId, AnchoredBundle>,
+    states: BTreeMap<ContractId, ContractState>,
+    spent: BTreeMap<Opout, OpId>,
+}
+
+impl MemStateStore {
+    pub fn new() -> Self { Self::default() }
+}
+
+impl StateStore for MemStateStore {
+    type Error = Infallible;
+
+    fn operation(&self, opid: OpId) -> Result<Option<SharedOp>, Self::Error> {
+        Ok(self.operations.get(&opid).cloned())
+    }
+
+    fn put_operation(&mut self, op: SharedOp) -> Result<(), Self::Error> {
+        self.operations.insert(op.as_op_ref().id(), op);
+        Ok(())
+    }
+
+    fn bundle(&self, bundle_id: BundleId) -> Result<Option<AnchoredBundle>, Self::Error> {
+        Ok(self.bundles.get(&bundle_id).cloned())
+    }
+
+    fn put_bundle(&mut self, bundle: AnchoredBundle) -> Result<(), Self::Error> {
+        self.bundles.insert(bundle.bundle.bundle_id(), bundle);
+        Ok(())
+    }
+
+    fn state(&self, contract_id: ContractId) -> Result<Option<ContractState>, Self::Error> {
+        Ok(self.states.get(&contract_id).cloned())
+    }
+
+    fn put_state(&mut self, state: ContractState) -> Result<(), Self::Error> {
+        self.states.insert(state.contract_id(), state);
+        Ok(())
+    }
+
+    fn spender(&self, opout: Opout) -> Result<Option<OpId>, Self::Error> {
+        Ok(self.spent.get(&opout).copied())
+    }
+
+    fn put_spender(&mut self, opout: Opout, spender: OpId) -> Result<(), Self::Error> {
+        self.spent.insert(opout, spender);
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, feature = "mmap"))]
+pub use mmap::{MmapStore, MmapStoreError, MMAP_STORE_MAGIC};
+
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap {
+    use std::fs::File;
+    use std::io::{self, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::{ptr, slice};
+
+    use amplify::confinement::{Confined, U32};
+    use amplify::IoError;
+    use strict_encoding::{DeserializeError, SerializeError, StrictDeserialize, StrictSerialize};
+
+    use super::HistoryStore;
+    use crate::{Extension, Genesis, OpId, Operation, SharedOp, Transition};
+
+    /// Magic bytes starting memory-mapped store file, including format
+    /// version.
+    pub const MMAP_STORE_MAGIC: [u8; 8] = *b"RGBOPS\x00\x01";
+
+    const HEADER_LEN: usize = 16;
+    const ENTRY_LEN: usize = 45;
+
+    const KIND_GENESIS: u8 = 0;
+    const KIND_TRANSITION: u8 = 1;
+    const KIND_EXTENSION: u8 = 2;
+
+    /// Errors of memory-mapped history store.
+    #[derive(Clone, Eq, PartialEq, Debug, Display, Error, From)]
+    #[display(doc_comments)]
+    pub enum MmapStoreError {
+        /// I/O error of the history store file. Details: {0}
+        #[from(io::Error)]
+        Io(IoError),
+
+        /// history store file has invalid header.
+        InvalidHeader,
+
+        /// history store file is truncated or has invalid index.
+        InvalidIndex,
+
+        /// operation can't be serialized. Details: {0}
+        #[from]
+        Serialize(SerializeError),
+    }
+
+    /// Read-only store of contract history operations in a memory-mapped
+    /// file.
+    ///
+    /// The file starts with a header, consisting of [`MMAP_STORE_MAGIC`] and
+    /// the number of operations, followed by the index of the operations
+    /// sorted by their ids and the strict-serialized operations. Each index
+    /// entry contains operation id, operation kind, offset of the serialized
+    /// operation in the file and its length. The index is binary searched
+    /// directly in the mapped memory, thus opening the store doesn't read the
+    /// file, and loading an operation pages in only the index pages on the
+    /// search path and the pages of the operation itself.
+    ///
+    /// Since the store reads the mapped memory directly, it can be opened only
+    /// with the unsafe [`MmapStore::open`]; see its safety requirements.
+    #[derive(Debug)]
+    pub struct MmapStore {
+        ptr: *mut libc::c_void,
+        size: usize,
+        count: usize,
+    }
+
+    // The mapped memory is never written to and is unmapped only on drop.
+    unsafe impl Send for MmapStore {}
+    unsafe impl Sync for MmapStore {}
+
+    impl Drop for MmapStore {
+        fn drop(&mut self) {
+            if self.size > 0 {
+                unsafe { libc::munmap(self.ptr, self.size) };
+            }
+        }
+    }
+
+    impl MmapStore {
+        /// Writes operations into a new store file at `path`, replacing an
+        /// existing file.
+        pub fn create<'op>(
+            path: impl AsRef<Path>,
+            ops: impl IntoIterator<Item = &'op SharedOp>,
+        ) -> Result<(), MmapStoreError> {
+            let mut entries = ops
+                .into_iter()
+                .map(|op| {
+                    let (kind, data) = match op {
+                        SharedOp::Genesis(op) => (KIND_GENESIS, op.to_strict_serialized::<U32>()?),
+                        SharedOp::Transition(op) => {
+                            (KIND_TRANSITION, op.to_strict_serialized::<U32>()?)
+                        }
+                        SharedOp::Extension(op) => {
+                            (KIND_EXTENSION, op.to_strict_serialized::<U32>()?)
+                        }
+                    };
+                    Ok((op.as_op_ref().id(), kind, data.into_inner()))
+                })
+                .collect::<Result<Vec<_>, MmapStoreError>>()?;
+            entries.sort_by_key(|(opid, ..)| *opid);
+            entries.dedup_by_key(|(opid, ..)| *opid);
+
+            let mut file = io::BufWriter::new(File::create(path)?);
+            file.write_all(&MMAP_STORE_MAGIC)?;
+            file.write_all(&(entries.len() as u64).to_le_bytes())?;
+            let mut offset = (HEADER_LEN + entries.len() * ENTRY_LEN) as u64;
+            for (opid, kind, data) in &entries {
+                file.write_all(opid.as_slice())?;
+                file.write_all(&[*kind])?;
+                file.write_all(&offset.to_le_bytes())?;
+                file.write_all(&(data.len() as u32).to_le_bytes())?;
+                offset += data.len() as u64;
+            }
+            for (_, _, data) in &entries {
+                file.write_all(data)?;
+            }
+            file.flush()?;
+            Ok(())
+        }
+
+        /// Opens store file at `path`, mapping it into memory, and checks the
+        /// consistency of the store index.
+        ///
+        /// # Safety
+        ///
+        /// The file must not be truncated or modified, neither by this nor by
+        /// other processes, until the returned store is dropped. Truncating the
+        /// file makes the accesses to the mapped memory raise `SIGBUS`, while
+        /// modifying it invalidates the index checks done when opening the
+        /// store, resulting in undefined behavior.
+        pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, MmapStoreError> {
+            let file = File::open(path)?;
+            let len = usize::try_from(file.metadata()?.len())
+                .map_err(|_| MmapStoreError::InvalidIndex)?;
+            if len < HEADER_LEN {
+                return Err(MmapStoreError::InvalidHeader);
+            }
+            let ptr = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error().into());
+            }
+            let mut store = MmapStore {
+                ptr,
+                size: len,
+                count: 0,
+            };
+
+            let data = store.data();
+            if data[..8] != MMAP_STORE_MAGIC {
+                return Err(MmapStoreError::InvalidHeader);
+            }
+            let count = u64::from_le_bytes(data[8..16].try_into().expect("fixed size"));
+            let count = usize::try_from(count).map_err(|_| MmapStoreError::InvalidIndex)?;
+            let index_len = count
+                .checked_mul(ENTRY_LEN)
+                .and_then(|index_len| index_len.checked_add(HEADER_LEN))
+                .ok_or(MmapStoreError::InvalidIndex)?;
+            if index_len > len {
+                return Err(MmapStoreError::InvalidIndex);
+            }
+            store.count = count;
+            // Index must be sorted by operation ids for the binary search
+            let mut prev_opid = None;
+            for no in 0..count {
+                let (opid, kind, offset, size) = store.entry(no);
+                let offset = usize::try_from(offset).map_err(|_| MmapStoreError::InvalidIndex)?;
+                if !matches!(kind, KIND_GENESIS | KIND_TRANSITION | KIND_EXTENSION) ||
+                    prev_opid >= Some(opid) ||
+                    offset < index_len ||
+                    offset.checked_add(size).map_or(true, |end| end > len)
+                {
+                    return Err(MmapStoreError::InvalidIndex);
+                }
+                prev_opid = Some(opid);
+            }
+            Ok(store)
+        }
+
+        fn data(&self) -> &[u8] {
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.size) }
+        }
+
+        fn entry(&self, no: usize) -> (OpId, u8, u64, usize) {
+            let start = HEADER_LEN + no * ENTRY_LEN;
+            let entry = &self.data()[start..start + ENTRY_LEN];
+            let opid: [u8; 32] = entry[..32].try_into().expect("fixed size");
+            let offset = u64::from_le_bytes(entry[33..41].try_into().expect("fixed size"));
+            let size = u32::from_le_bytes(entry[41..45].try_into().expect("fixed size"));
+            (OpId::from(opid), entry[32], offset, size as usize)
+        }
+
+        fn find(&self, opid: OpId) -> Option<usize> {
+            let (mut lo, mut hi) = (0, self.count);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                match self.entry(mid).0.cmp(&opid) {
+                    std::cmp::Ordering::Less => lo = mid + 1,
+                    std::cmp::Ordering::Greater => hi = mid,
+                    std::cmp::Ordering::Equal => return Some(mid),
+                }
+            }
+            None
+        }
+
+        fn decode(&self, no: usize) -> Result<SharedOp, DeserializeError> {
+            let (_, kind, offset, size) = self.entry(no);
+            let offset =
+                usize::try_from(offset).expect("offsets are checked when the store is opened");
+            let data = Confined::try_from(self.data()[offset..offset + size].to_vec())
+                .map_err(strict_encoding::DecodeError::from)?;
+            Ok(match kind {
+                KIND_GENESIS => Arc::new(Genesis::from_strict_serialized::<U32>(data)?).into(),
+                KIND_TRANSITION => {
+                    Arc::new(Transition::from_strict_serialized::<U32>(data)?).into()
+                }
+                KIND_EXTENSION => Arc::new(Extension::from_strict_serialized::<U32>(data)?).into(),
+                _ => unreachable!("operation kinds are checked when the store is opened"),
+            })
+        }
+    }
+
+    impl HistoryStore for MmapStore {
+        fn contains(&self, opid: OpId) -> bool { self.find(opid).is_some() }
+
+        /// Loads the operation from the mapped file. Operations which can't be
+        /// decoded, or which ids don't match the index, are treated as absent.
+        fn load(&self, opid: OpId) -> Option<SharedOp> {
+            let op = self.decode(self.find(opid)?).ok()?;
+            (op.as_op_ref().id() == opid).then_some(op)
+        }
+
+        fn len(&self) -> usize { self.count }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyOrdSet;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{AssignmentType, Input, Inputs, Transition, TransitionBundle};
+
+    #[test]
+    fn mem_state_store() {
+        let mut consignment = Consignment::strict_dumb();
+        let opout = Opout::new(consignment.genesis.id(), AssignmentType::with(1), 0);
+        let mut transition = Transition::strict_dumb();
+        transition.inputs = Inputs::from(TinyOrdSet::try_from_iter([Input::with(opout)]).unwrap());
+        let mut anchored = AnchoredBundle::strict_dumb();
+        anchored.bundle =
+            TransitionBundle::try_from_transitions([(0, transition.clone())]).unwrap();
+        let bundle_id = anchored.bundle.bundle_id();
+        consignment.bundles.push(anchored.clone()).unwrap();
+
+        let mut store = MemStateStore::new();
+        store.put_consignment(&consignment).unwrap();
+        assert_eq!(
+            store.operation(transition.id()).unwrap(),
+            Some(SharedOp::from(Arc::new(transition.clone())))
+        );
+        assert!(store.operation(consignment.genesis.id()).unwrap().is_some());
+        assert_eq!(store.bundle(bundle_id).unwrap(), Some(anchored));
+        assert_eq!(store.spender(opout).unwrap(), Some(transition.id()));
+        assert_eq!(
+            store
+                .spender(Opout::new(transition.id(), opout.ty, 0))
+                .unwrap(),
+            None
+        );
+
+        let state = ContractState::strict_dumb();
+        let contract_id = state.contract_id();
+        assert_eq!(store.state(contract_id).unwrap(), None);
+        store.put_state(state.clone()).unwrap();
+        assert_eq!(store.state(contract_id).unwrap(), Some(state));
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "mmap"))]
+    fn mmap_store() {
+        use crate::{ContractHistory, Extension, Genesis};
+
+        let genesis = Genesis::strict_dumb();
+        let transition = Transition::strict_dumb();
+        let ops = [
+            SharedOp::from(Arc::new(genesis.clone())),
+            SharedOp::from(Arc::new(transition.clone())),
+            SharedOp::from(Arc::new(Extension::strict_dumb())),
+        ];
+        let path = std::env::temp_dir().join(format!("rgb-mmap-store-{}", std::process::id()));
+        MmapStore::create(&path, &ops).unwrap();
+
+        let store = unsafe { MmapStore::open(&path) }.unwrap();
+        assert_eq!(store.len(), 3);
+        for op in &ops {
+            assert_eq!(store.load(op.as_op_ref().id()).as_ref(), Some(op));
+        }
+        assert!(!store.contains(OpId::from([0xFF; 32])));
+
+        let mut history =
+            ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+        assert_eq!(history.operations().len(), 1);
+        history.attach_store(Arc::new(store));
+        assert!(history.operations().is_empty());
+        assert_eq!(history.operation(transition.id()), Some(SharedOp::from(Arc::new(transition))));
+        assert!(history.ancestry(genesis.id()).is_ok());
+
+        // Index entries follow the header and start with the operation id,
+        // followed by the operation kind byte
+        let data = std::fs::read(&path).unwrap();
+        let mut unknown_kind = data.clone();
+        unknown_kind[16 + 32] = 3;
+        std::fs::write(&path, unknown_kind).unwrap();
+        assert_eq!(unsafe { MmapStore::open(&path) }.unwrap_err(), MmapStoreError::InvalidIndex);
+
+        let mut unsorted = data;
+        unsorted.copy_within(16 + 45..16 + 45 + 32, 16);
+        std::fs::write(&path, unsorted).unwrap();
+        assert_eq!(unsafe { MmapStore::open(&path) }.unwrap_err(), MmapStoreError::InvalidIndex);
+
+        std::fs::write(&path, b"RGBOPS\x00\x02").unwrap();
+        assert_eq!(unsafe { MmapStore::open(&path) }.unwrap_err(), MmapStoreError::InvalidHeader);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Asset tickers and names.
+//!
+//! Global state holding human-readable asset identification must use
+//! [`Ticker`] and [`Name`] types instead of raw strings. Both are restricted
+//! to printable ASCII characters and must not start or end with a space, which
+//! is enforced when the data are decoded, such that malformed names can't
+//! enter consensus data.
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io;
+use std::str::FromStr;
+
+use strict_encoding::stl::AsciiPrintable;
+use strict_encoding::{
+    DecodeError, ReadTuple, RestrictedString, StrictDecode, StrictDumb, StrictEncode,
+    StrictProduct, StrictTuple, StrictType, TypedRead, TypedWrite,
+};
+
+use crate::LIB_NAME_RGB;
+
+/// Errors parsing asset tickers and names.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum NameError {
+    /// the string is empty.
+    Empty,
+
+    /// the string length {len} exceeds the limit of {max} characters.
+    TooLong { len: usize, max: usize },
+
+    /// the string contains non-printable or non-ASCII character U+{0:04X}.
+    InvalidChar(u32),
+
+    /// the string starts or ends with a space.
+    SurroundingSpace,
+}
+
+fn check_name(s: &str, max: usize) -> Result<(), NameError> {
+    if s.is_empty() {
+        return Err(NameError::Empty);
+    }
+    if let Some(c) = s.chars().find(|c| !(' '..='~').contains(c)) {
+        return Err(NameError::InvalidChar(c as u32));
+    }
+    if s.len() > max {
+        return Err(NameError::TooLong { len: s.len(), max });
+    }
+    if s.starts_with(' ') || s.ends_with(' ') {
+        return Err(NameError::SurroundingSpace);
+    }
+    Ok(())
+}
+
+macro_rules! impl_name {
+    ($ty:ident, $max:literal, $dumb:literal) => {
+        impl $ty {
+            /// Maximal length of the string, in characters.
+            pub const MAX_LEN: usize = $max;
+
+            pub fn as_str(&self) -> &str { &self.0 }
+        }
+
+        impl FromStr for $ty {
+            type Err = NameError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                check_name(s, $max)?;
+                Ok(Self(s.to_owned()))
+            }
+        }
+
+        impl TryFrom<String> for $ty {
+            type Error = NameError;
+
+            fn try_from(s: String) -> Result<Self, Self::Error> {
+                check_name(&s, $max)?;
+                Ok(Self(s))
+            }
+        }
+
+        impl From<$ty> for String {
+            fn from(name: $ty) -> Self { name.0 }
+        }
+
+        impl Display for $ty {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+        }
+
+        impl Debug for $ty {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($ty)).field(&self.0).finish()
+            }
+        }
+
+        impl StrictDumb for $ty {
+            fn strict_dumb() -> Self { Self(s!($dumb)) }
+        }
+        impl StrictType for $ty {
+            const STRICT_LIB_NAME: &'static str = LIB_NAME_RGB;
+        }
+        impl StrictProduct for $ty {}
+        impl StrictTuple for $ty {
+            const FIELD_COUNT: u8 = 1;
+        }
+        impl StrictEncode for $ty {
+            fn strict_encode<W: TypedWrite>(&self, writer: W) -> io::Result<W> {
+                let s = RestrictedString::<AsciiPrintable, 1, $max>::from_bytes(self.0.as_bytes())
+                    .expect("invariant");
+                writer.write_newtype::<Self>(&s)
+            }
+        }
+        impl StrictDecode for $ty {
+            fn strict_decode(reader: &mut impl TypedRead) -> Result<Self, DecodeError> {
+                reader.read_tuple(|r| {
+                    let s: RestrictedString<AsciiPrintable, 1, $max> = r.read_field()?;
+                    let s = String::from_utf8(s.as_bytes().to_vec())
+                        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+                    Self::try_from(s)
+                        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))
+                })
+            }
+        }
+    };
+}
+
+/// Asset ticker, consisting of 1 to 8 printable ASCII characters without
+/// leading and trailing spaces.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", try_from = "String", into = "String")
+)]
+pub struct Ticker(String);
+
+impl_name!(Ticker, 8, "DUMB");
+
+/// Asset name, consisting of 1 to 40 printable ASCII characters without
+/// leading and trailing spaces.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", try_from = "String", into = "String")
+)]
+pub struct Name(String);
+
+impl_name!(Name, 40, "Dumb");
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, SmallVec};
+    use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+    use super::*;
+    use crate::RevealedData;
+
+    impl StrictSerialize for Ticker {}
+    impl StrictDeserialize for Ticker {}
+
+    #[test]
+    fn ticker() {
+        assert_eq!(Ticker::from_str("USDT").unwrap().as_str(), "USDT");
+        assert_eq!(Ticker::from_str(""), Err(NameError::Empty));
+        assert_eq!(Ticker::from_str("TETHERUSD"), Err(NameError::TooLong { len: 9, max: 8 }));
+        assert_eq!(Ticker::from_str(" USDT"), Err(NameError::SurroundingSpace));
+        assert_eq!(Ticker::from_str("US\tDT"), Err(NameError::InvalidChar(0x09)));
+        assert_eq!(Ticker::from_str("US\u{00C9}"), Err(NameError::InvalidChar(0xC9)));
+        assert_eq!(Name::from_str("Tether USD").unwrap().to_string(), "Tether USD");
+        assert_eq!(Name::from_str("Tether USD "), Err(NameError::SurroundingSpace));
+
+        let ticker = Ticker::from_str("USDT").unwrap();
+        let data = ticker.to_strict_serialized::<{ usize::MAX }>().unwrap();
+        assert_eq!(Ticker::from_strict_serialized::<{ usize::MAX }>(data).unwrap(), ticker);
+        let data = RevealedData::from_typed(&ticker).unwrap();
+        assert_eq!(data, RevealedData::from(SmallVec::try_from(b"\x04USDT".to_vec()).unwrap()));
+
+        let malformed = Confined::try_from(b"\x05USDT ".to_vec()).unwrap();
+        assert!(Ticker::from_strict_serialized::<{ usize::MAX }>(malformed).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Witness transactions embedded into consignments.
+//!
+//! A consignment may carry raw witness transactions together with proofs of
+//! their inclusion into mined blocks, allowing validation without access to
+//! the blockchain. Since the proofs are checked only against the block
+//! headers they contain, the validator doesn't use embedded witnesses
+//! directly; instead they are imported into [`crate::validation::SpvResolver`],
+//! which verifies them against a trusted chain of block headers.
+
+use amplify::confinement::TinyVec;
+use amplify::{ByteArray, Bytes32};
+use bp::{BlockHeader, Tx, Txid};
+use commit_verify::{DigestExt, Sha256};
+
+use crate::{Layer1, LIB_NAME_RGB};
+
+/// Errors verifying [`SpvProof`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum SpvError {
+    /// transaction is not committed into the block merkle root.
+    MerkleRoot,
+
+    /// block header has invalid difficulty target.
+    InvalidTarget,
+
+    /// block header hash doesn't satisfy its difficulty target.
+    ProofOfWork,
+
+    /// block header difficulty is below the minimum required.
+    InsufficientWork,
+
+    /// block header doesn't follow the previous header of the chain.
+    Disconnected,
+
+    /// block is not a part of the verified header chain.
+    UnknownBlock,
+}
+
+/// Proof of a transaction inclusion into a mined block.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct SpvProof {
+    /// Height of the block.
+    pub height: u32,
+    /// Header of the block.
+    pub header: BlockHeader,
+    /// Position of the transaction in the block.
+    pub position: u32,
+    /// Hashes of the sibling nodes on the path from the transaction to the
+    /// block merkle root.
+    pub merkle_path: TinyVec<Bytes32>,
+}
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    let mut engine = Sha256::default();
+    engine.input_raw(data);
+    let mut double = Sha256::default();
+    double.input_raw(&engine.finish());
+    double.finish()
+}
+
+/// Computes block header hash in the internal byte order.
+pub fn header_hash(header: &BlockHeader) -> [u8; 32] {
+    let mut data = Vec::with_capacity(80);
+    data.extend(header.version.to_le_bytes());
+    data.extend(header.prev_block_hash.to_byte_array());
+    data.extend(header.merkle_root.to_byte_array());
+    data.extend(header.time.to_le_bytes());
+    data.extend(header.bits.to_le_bytes());
+    data.extend(header.nonce.to_le_bytes());
+    sha256d(&data)
+}
+
+/// Checks that the block header hash satisfies the difficulty target encoded
+/// in the header.
+pub fn check_pow(header: &BlockHeader) -> Result<(), SpvError> {
+    let target = compact_target(header.bits).ok_or(SpvError::InvalidTarget)?;
+    let mut hash = header_hash(header);
+    hash.reverse();
+    if hash > target {
+        return Err(SpvError::ProofOfWork);
+    }
+    Ok(())
+}
+
+impl SpvProof {
+    /// Computes block hash in the internal byte order.
+    pub fn block_hash(&self) -> [u8; 32] { header_hash(&self.header) }
+
+    /// Computes block merkle root from the transaction id and the merkle path.
+    pub fn merkle_root(&self, txid: Txid) -> [u8; 32] {
+        let mut node = txid.to_byte_array();
+        let mut position = self.position;
+        for sibling in &self.merkle_path {
+            let mut data = [0u8; 64];
+            if position & 1 == 0 {
+                data[..32].copy_from_slice(&node);
+                data[32..].copy_from_slice(sibling.as_slice());
+            } else {
+                data[..32].copy_from_slice(sibling.as_slice());
+                data[32..].copy_from_slice(&node);
+            }
+            node = sha256d(&data);
+            position >>= 1;
+        }
+        node
+    }
+
+    /// Verifies that the transaction is included into the block and that the
+    /// block header satisfies its own proof of work target.
+    ///
+    /// NB: The verification doesn't check that the block is a part of the
+    /// most-work chain, neither that its difficulty matches the consensus
+    /// rules; thus it must be used only for the witnesses known to the
+    /// verifier from other sources.
+    pub fn verify(&self, txid: Txid) -> Result<(), SpvError> {
+        if self.merkle_root(txid) != self.header.merkle_root.to_byte_array() {
+            return Err(SpvError::MerkleRoot);
+        }
+        check_pow(&self.header)
+    }
+}
+
+/// Expands compact difficulty target representation into a big-endian
+/// 256-bit number.
+pub fn compact_target(bits: u32) -> Option<[u8; 32]> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007F_FFFF;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 {
+        return None;
+    }
+    let mut target = [0u8; 32];
+    if exponent <= 3 {
+        let value = mantissa >> (8 * (3 - exponent));
+        target[28..].copy_from_slice(&value.to_be_bytes());
+    } else if exponent <= 32 {
+        let bytes = mantissa.to_be_bytes();
+        target[32 - exponent..35 - exponent].copy_from_slice(&bytes[1..]);
+    } else {
+        return None;
+    }
+    Some(target)
+}
+
+/// Witness transaction embedded into a consignment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct EmbeddedWitness {
+    pub layer1: Layer1,
+    pub tx: Tx,
+    /// Proof of the transaction being mined, if the transaction is mined.
+    pub spv: Option<SpvProof>,
+}
+
+impl EmbeddedWitness {
+    pub fn txid(&self) -> Txid { self.tx.txid() }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use bp::BlockHash;
+
+    use super::*;
+
+    fn genesis_block() -> (Txid, SpvProof) {
+        let txid =
+            Txid::from_str("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b")
+                .unwrap();
+        let proof = SpvProof {
+            height: 0,
+            header: BlockHeader {
+                version: 1,
+                prev_block_hash: BlockHash::from_byte_array([0u8; 32]),
+                merkle_root: Bytes32::from_byte_array(txid.to_byte_array()),
+                time: 1231006505,
+                bits: 0x1d00ffff,
+                nonce: 2083236893,
+            },
+            position: 0,
+            merkle_path: empty!(),
+        };
+        (txid, proof)
+    }
+
+    #[test]
+    fn genesis_block_proof() {
+        let (txid, proof) = genesis_block();
+        let mut hash = proof.block_hash();
+        hash.reverse();
+        assert_eq!(
+            Bytes32::from_byte_array(hash).to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(proof.verify(txid), Ok(()));
+
+        let mut wrong_tx = proof.clone();
+        wrong_tx.merkle_path = tiny_vec![Bytes32::from_byte_array([1u8; 32])];
+        assert_eq!(wrong_tx.verify(txid), Err(SpvError::MerkleRoot));
+
+        let mut wrong_nonce = proof;
+        wrong_nonce.header.nonce += 1;
+        assert_eq!(wrong_nonce.verify(txid), Err(SpvError::ProofOfWork));
+    }
+}
@@ -0,0 +1,537 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C foreign function interface for consignment validation, contract state
+//! queries and id computation.
+//!
+//! All data are passed across the interface in their strict-serialized form.
+//! Functions return [`RgbStatus`] code and write results into caller-provided
+//! memory. Contract state is exposed as an opaque pointer, which must be freed
+//! with [`rgb_contract_state_free`].
+//!
+//! Panics never cross the interface: if the library panics, the function
+//! returns [`RgbStatus::Internal`].
+
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use amplify::{Array, Wrapper};
+use bp::{ConsensusDecode, Tx, Txid};
+use commit_verify::CommitmentId;
+use strict_encoding::StrictDecode;
+
+use crate::untrusted::deserialize_untrusted;
+use crate::validation::{ResolveTx, TxResolverError, Validity};
+use crate::{
+    AssignmentType, ChainNet, Consignment, ContractState, Genesis, Layer1, OnLayer1, StateView,
+    Transition, TransitionBundle,
+};
+
+/// Status code returned by all FFI functions.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub enum RgbStatus {
+    /// Operation has succeeded.
+    Ok = 0,
+    /// One of the pointers passed to the function is null.
+    NullPointer = 1,
+    /// Provided data can't be deserialized.
+    DecodeError = 2,
+    /// Requested item is not known.
+    OutOfRange = 3,
+    /// Value doesn't fit into the output type.
+    ValueOverflow = 4,
+    /// Internal error of the library; the output is not written.
+    Internal = 5,
+}
+
+/// Result of consignment validation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub enum RgbValidity {
+    Valid = 0,
+    UnminedTerminals = 1,
+    UnresolvedTransactions = 2,
+    Invalid = 3,
+}
+
+impl From<Validity> for RgbValidity {
+    fn from(validity: Validity) -> Self {
+        match validity {
+            Validity::Valid => RgbValidity::Valid,
+            Validity::UnminedTerminals => RgbValidity::UnminedTerminals,
+            Validity::UnresolvedTransactions => RgbValidity::UnresolvedTransactions,
+            Validity::Invalid => RgbValidity::Invalid,
+        }
+    }
+}
+
+/// 32-byte identifier (contract id, operation id, bundle id, txid etc).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct RgbId {
+    pub bytes: [u8; 32],
+}
+
+impl<T: Wrapper<Inner = Array<u8, 32, REV>>, const REV: bool> From<T> for RgbId {
+    fn from(id: T) -> Self {
+        RgbId {
+            bytes: id.into_inner().into_inner(),
+        }
+    }
+}
+
+/// Fungible state assigned to a transaction output.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(C)]
+pub struct RgbFungibleOutput {
+    pub opid: RgbId,
+    pub assignment_type: u16,
+    pub no: u16,
+    /// Layer 1 of the output: 0 for Bitcoin, 1 for Liquid.
+    pub layer1: u8,
+    pub txid: RgbId,
+    pub vout: u32,
+    pub value: u64,
+}
+
+/// Callback resolving witness transactions during the consignment validation.
+///
+/// The callback receives the context pointer provided by the caller of
+/// [`rgb_consignment_validate`], layer 1 code (0 for Bitcoin, 1 for Liquid),
+/// transaction id and a buffer of `buf_len` bytes, into which it must write
+/// the consensus-serialized transaction, putting its length into `tx_len`.
+///
+/// Return value must be 0 if the transaction is resolved, 1 if the transaction
+/// is not known and any other value in case of an error. If the buffer is too
+/// small, the callback must set `tx_len` to the required size and return 0 -
+/// the callback is called again with a larger buffer. Transactions larger
+/// than the maximum block size are treated as resolver errors.
+pub type RgbResolveTx = extern "C" fn(
+    ctx: *mut c_void,
+    layer1: u8,
+    txid: *const RgbId,
+    buf: *mut u8,
+    buf_len: usize,
+    tx_len: *mut usize,
+) -> i32;
+
+const TX_BUF_LEN: usize = 400_000;
+/// Maximum size of a consensus-serialized transaction, which can't exceed the
+/// block weight limit.
+const TX_MAX_LEN: usize = 4_000_000;
+
+struct FfiResolver {
+    callback: RgbResolveTx,
+    ctx: *mut c_void,
+}
+
+impl ResolveTx for FfiResolver {
+    fn resolve_tx(&self, layer1: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+        let id = RgbId::from(txid);
+        let mut buf = vec![0u8; TX_BUF_LEN];
+        loop {
+            let mut tx_len = 0usize;
+            let res = (self.callback)(
+                self.ctx,
+                layer1 as u8,
+                &id,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut tx_len,
+            );
+            match res {
+                0 if tx_len > TX_MAX_LEN => {
+                    return Err(TxResolverError::Other(
+                        txid,
+                        format!("resolver requested buffer of {tx_len} bytes"),
+                    ));
+                }
+                0 if tx_len > buf.len() => buf.resize(tx_len, 0),
+                0 => {
+                    return Tx::consensus_deserialize(&buf[..tx_len])
+                        .map_err(|err| TxResolverError::Other(txid, err.to_string()));
+                }
+                1 => return Err(TxResolverError::Unknown(txid)),
+                code => {
+                    return Err(TxResolverError::Other(
+                        txid,
+                        format!("resolver returned error code {code}"),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+unsafe fn decode<T: StrictDecode>(data: *const u8, len: usize) -> Result<T, RgbStatus> {
+    if data.is_null() {
+        return Err(RgbStatus::NullPointer);
+    }
+    deserialize_untrusted(slice::from_raw_parts(data, len)).map_err(|_| RgbStatus::DecodeError)
+}
+
+unsafe fn write<T>(out: *mut T, val: T) -> RgbStatus {
+    if out.is_null() {
+        return RgbStatus::NullPointer;
+    }
+    ptr::write(out, val);
+    RgbStatus::Ok
+}
+
+/// Runs body of an exported function, converting panics into
+/// [`RgbStatus::Internal`], since unwinding across the interface aborts the
+/// host process.
+fn guard(f: impl FnOnce() -> RgbStatus) -> RgbStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(RgbStatus::Internal)
+}
+
+macro_rules! try_status {
+    ($expr:expr) => {
+        match $expr {
+            Ok(val) => val,
+            Err(status) => return status,
+        }
+    };
+}
+
+/// Computes id of a strict-serialized consignment.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for [`RgbId`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_consignment_id(
+    data: *const u8,
+    len: usize,
+    out: *mut RgbId,
+) -> RgbStatus {
+    guard(|| {
+        let consignment = try_status!(decode::<Consignment>(data, len));
+        write(out, consignment.consignment_id().into())
+    })
+}
+
+/// Computes contract id of a strict-serialized genesis.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for [`RgbId`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_genesis_contract_id(
+    data: *const u8,
+    len: usize,
+    out: *mut RgbId,
+) -> RgbStatus {
+    guard(|| {
+        let genesis = try_status!(decode::<Genesis>(data, len));
+        write(out, genesis.commitment_id().into())
+    })
+}
+
+/// Computes operation id of a strict-serialized state transition.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for [`RgbId`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_transition_id(
+    data: *const u8,
+    len: usize,
+    out: *mut RgbId,
+) -> RgbStatus {
+    guard(|| {
+        let transition = try_status!(decode::<Transition>(data, len));
+        write(out, transition.commitment_id().into())
+    })
+}
+
+/// Computes bundle id of a strict-serialized transition bundle.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for [`RgbId`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_bundle_id(data: *const u8, len: usize, out: *mut RgbId) -> RgbStatus {
+    guard(|| {
+        let bundle = try_status!(decode::<TransitionBundle>(data, len));
+        write(out, bundle.bundle_id().into())
+    })
+}
+
+/// Validates strict-serialized consignment, resolving witness transactions
+/// with the provided callback. `chain_net` is the [`ChainNet`] code of the
+/// network the resolver retrieves transactions from; for unknown codes
+/// [`RgbStatus::OutOfRange`] is returned.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for [`RgbValidity`]. `resolver` may be null, in which case
+/// [`RgbStatus::NullPointer`] is returned. `ctx` is passed to the `resolver`
+/// as is.
+#[no_mangle]
+pub unsafe extern "C" fn rgb_consignment_validate(
+    data: *const u8,
+    len: usize,
+    chain_net: u8,
+    resolver: Option<RgbResolveTx>,
+    ctx: *mut c_void,
+    out: *mut RgbValidity,
+) -> RgbStatus {
+    guard(|| {
+        let Some(callback) = resolver else {
+            return RgbStatus::NullPointer;
+        };
+        let Ok(chain_net) = ChainNet::try_from(chain_net) else {
+            return RgbStatus::OutOfRange;
+        };
+        let consignment = try_status!(decode::<Consignment>(data, len));
+        let resolver = FfiResolver { callback, ctx };
+        let status = consignment.validate(&resolver, chain_net);
+        write(out, status.validity().into())
+    })
+}
+
+/// Deserializes contract state, returning an opaque pointer to it in `out`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must point to writable
+/// memory for a pointer. The returned pointer must be freed with
+/// [`rgb_contract_state_free`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_contract_state_new(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut ContractState,
+) -> RgbStatus {
+    guard(|| {
+        if out.is_null() {
+            return RgbStatus::NullPointer;
+        }
+        let state = try_status!(decode::<ContractState>(data, len));
+        write(out, Box::into_raw(Box::new(state)))
+    })
+}
+
+/// Frees contract state created with [`rgb_contract_state_new`].
+///
+/// # Safety
+///
+/// `state` must be either null or a pointer returned by
+/// [`rgb_contract_state_new`] which was not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn rgb_contract_state_free(state: *mut ContractState) {
+    if !state.is_null() {
+        // Panics in destructors can't be reported to the caller
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(state))));
+    }
+}
+
+/// Returns contract id of the contract state.
+///
+/// # Safety
+///
+/// `state` must be a valid pointer returned by [`rgb_contract_state_new`] and
+/// `out` must point to writable memory for [`RgbId`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_contract_state_contract_id(
+    state: *const ContractState,
+    out: *mut RgbId,
+) -> RgbStatus {
+    guard(|| {
+        let Some(state) = state.as_ref() else {
+            return RgbStatus::NullPointer;
+        };
+        write(out, state.contract_id().into())
+    })
+}
+
+/// Returns number of known fungible outputs of a given assignment type.
+///
+/// # Safety
+///
+/// `state` must be a valid pointer returned by [`rgb_contract_state_new`] and
+/// `out` must point to writable memory for `usize`.
+#[no_mangle]
+pub unsafe extern "C" fn rgb_contract_state_fungible_count(
+    state: *const ContractState,
+    assignment_type: u16,
+    out: *mut usize,
+) -> RgbStatus {
+    guard(|| {
+        let Some(state) = state.as_ref() else {
+            return RgbStatus::NullPointer;
+        };
+        let count = state
+            .fungible_outputs(AssignmentType::with(assignment_type), StateView::Pending)
+            .count();
+        write(out, count)
+    })
+}
+
+/// Returns fungible output of a given assignment type with the given index.
+/// If the output value doesn't fit into 64 bits, [`RgbStatus::ValueOverflow`]
+/// is returned.
+///
+/// # Safety
+///
+/// `state` must be a valid pointer returned by [`rgb_contract_state_new`] and
+/// `out` must point to writable memory for [`RgbFungibleOutput`].
+#[no_mangle]
+pub unsafe extern "C" fn rgb_contract_state_fungible_get(
+    state: *const ContractState,
+    assignment_type: u16,
+    index: usize,
+    out: *mut RgbFungibleOutput,
+) -> RgbStatus {
+    guard(|| {
+        let Some(state) = state.as_ref() else {
+            return RgbStatus::NullPointer;
+        };
+        let Some(output) = state
+            .fungible_outputs(AssignmentType::with(assignment_type), StateView::Pending)
+            .nth(index)
+        else {
+            return RgbStatus::OutOfRange;
+        };
+        let Some(value) = output.state.value.as_u64() else {
+            return RgbStatus::ValueOverflow;
+        };
+        let (layer1, outpoint) = output.output.into_parts();
+        write(out, RgbFungibleOutput {
+            opid: output.opout.op.into(),
+            assignment_type: output.opout.ty.to_inner(),
+            no: output.opout.no,
+            layer1: layer1 as u8,
+            txid: outpoint.txid.into(),
+            vout: outpoint.vout.into_u32(),
+            value,
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use strict_encoding::{StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::Operation;
+
+    #[test]
+    fn genesis_id() {
+        let genesis = Genesis::strict_dumb();
+        let data = genesis.to_strict_serialized::<U32>().unwrap();
+        let mut id = RgbId { bytes: [0u8; 32] };
+        let status = unsafe { rgb_genesis_contract_id(data.as_ptr(), data.len(), &mut id) };
+        assert_eq!(status, RgbStatus::Ok);
+        assert_eq!(id, genesis.contract_id().into());
+
+        let status = unsafe { rgb_genesis_contract_id(ptr::null(), 0, &mut id) };
+        assert_eq!(status, RgbStatus::NullPointer);
+        let status = unsafe { rgb_genesis_contract_id(data.as_ptr(), 1, &mut id) };
+        assert_eq!(status, RgbStatus::DecodeError);
+    }
+
+    #[test]
+    fn contract_state() {
+        let state = ContractState::strict_dumb();
+        let data = state.to_strict_serialized::<U32>().unwrap();
+        let mut ptr = ptr::null_mut();
+        unsafe {
+            assert_eq!(rgb_contract_state_new(data.as_ptr(), data.len(), &mut ptr), RgbStatus::Ok);
+            let mut id = RgbId { bytes: [0u8; 32] };
+            assert_eq!(rgb_contract_state_contract_id(ptr, &mut id), RgbStatus::Ok);
+            assert_eq!(id, state.contract_id().into());
+            let mut count = 1;
+            assert_eq!(rgb_contract_state_fungible_count(ptr, 0, &mut count), RgbStatus::Ok);
+            assert_eq!(count, 0);
+            let mut output = RgbFungibleOutput {
+                opid: id,
+                assignment_type: 0,
+                no: 0,
+                layer1: 0,
+                txid: id,
+                vout: 0,
+                value: 0,
+            };
+            assert_eq!(
+                rgb_contract_state_fungible_get(ptr, 0, 0, &mut output),
+                RgbStatus::OutOfRange
+            );
+            rgb_contract_state_free(ptr);
+        }
+    }
+
+    #[test]
+    fn resolver() {
+        extern "C" fn oversized(
+            _: *mut c_void,
+            _: u8,
+            _: *const RgbId,
+            _: *mut u8,
+            _: usize,
+            tx_len: *mut usize,
+        ) -> i32 {
+            unsafe { *tx_len = usize::MAX };
+            0
+        }
+
+        let resolver = FfiResolver {
+            callback: oversized,
+            ctx: ptr::null_mut(),
+        };
+        let txid = Txid::from([0x11; 32]);
+        assert!(matches!(
+            resolver.resolve_tx(Layer1::Bitcoin, txid),
+            Err(TxResolverError::Other(id, _)) if id == txid
+        ));
+
+        let consignment = Consignment::strict_dumb();
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+        let mut validity = RgbValidity::Valid;
+        let status = unsafe {
+            rgb_consignment_validate(
+                data.as_ptr(),
+                data.len(),
+                0,
+                None,
+                ptr::null_mut(),
+                &mut validity,
+            )
+        };
+        assert_eq!(status, RgbStatus::NullPointer);
+    }
+
+    #[test]
+    fn panics() {
+        assert_eq!(guard(|| RgbStatus::Ok), RgbStatus::Ok);
+        assert_eq!(guard(|| panic!("internal error")), RgbStatus::Internal);
+    }
+}
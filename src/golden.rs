@@ -0,0 +1,462 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Commitment id stability regression suite.
+//!
+//! Each consensus type is instantiated with deterministic data covering all of
+//! its fields and state variants. The layout descriptor of the type is the
+//! SHA256 hash of its commit-encoding, which captures field order, length
+//! prefixes and concealment rules; for the types having commitment ids the id
+//! is recorded as well. Any change in the commit-encoding makes
+//! `golden_hashes` test fail, since it alters derivation of `OpId`,
+//! `ContractId`, `BundleId` or `SchemaId`.
+//!
+//! Consensus-critical! If the test fails, the change must be reverted unless
+//! it is a deliberate consensus change - in the latter case update the golden
+//! values with the ones reported by the test.
+
+use std::collections::BTreeSet;
+
+use amplify::confinement::{Confined, TinyOrdMap, TinyOrdSet};
+use amplify::hex::{FromHex, ToHex};
+use bp::seals::txout::{CloseMethod, TxPtr};
+use bp::Txid;
+use commit_verify::{CommitEncode, CommitmentId, Conceal, Digest, DigestExt, Sha256};
+use strict_encoding::{StrictDeserialize, StrictDumb, StrictEncode, StrictWriter};
+
+use crate::{
+    AltLayer1, AltLayer1Set, AssetTag, Assign, AssignmentType, Assignments, AttachId,
+    BlindingFactor, ChainNet, ChainProof, ContractId, Extension, ExtensionType, Ffv, Genesis,
+    GenesisSeal, GlobalState, GlobalStateType, GraphSeal, Input, Inputs, IssueReserveProof,
+    MediaType, OpId, Operation, Opout, PaymentTag, Redeemed, RevealedAttach, RevealedData,
+    RevealedPaymentTag, RevealedValue, SchemaId, SealDefinition, SubSchema, Transition,
+    TransitionBundle, TransitionType, TypedAssigns, Valencies, ValencyType, VoidState,
+};
+
+/// Golden values: name of the consensus type or id and its hex value.
+const GOLDEN: &[(&str, &str)] = &[
+    ("SecretSeal<GraphSeal>", "f09c8d21269a8551780920a95a2350d386969b08b87f3cc7e1e06f7a15d2e39d"),
+    ("SecretSeal<GenesisSeal>", "7ebf23a1460514fdbf2ffa3b222e3623650d1ad9ba00ae238a6736d53a484e3d"),
+    ("ConcealedFungible", "4d0b0d17315b2ac333b1838f1e05b3cd6ab08455d1770e67e5d578af102f8dc6"),
+    ("ConcealedData", "e1c5f017db4cdb93b4e6b6d4d24ac0346f1803783328b5d178b3cf215e9a7b74"),
+    ("ConcealedAttach", "d9dfa2136d16727788f420e51e1bb1fc7fbfafadf52cabdecfa9c6053180ea48"),
+    ("ConcealedPaymentTag", "d0da11efc521d4012f28e4ae72c95f80761628fbe452affee2df8e891bbea27c"),
+    ("Assign<PaymentTag>", "e915a63e22fe881e97d892a7e54506c3d59159786f86571909e09c40a3737fbd"),
+    ("Assignments<GraphSeal>", "e286e3b8605f53cd7ef16b0e665130cd653683e92a9b9e1249b2b21be692a147"),
+    (
+        "Assignments<GenesisSeal>",
+        "0377099fa99cb4ed56ed74ac02b3d2495abf4fa88365ef0e8e4f406aeebbbcec",
+    ),
+    ("GlobalState", "2973d4c7aa67a11674067f9df065d751fc396d4a293d260e2408ef0486aa11d9"),
+    ("Valencies", "a901697a2eb89b19da3f788c6042f15cf90810808d466d57ccfe3eaa4cdae2e9"),
+    ("Inputs", "29493a3f59709b86ec21f35bdd7456f2936cd121f04e8748cbe655108c0f2ca6"),
+    ("Genesis", "4d5c4014df913d85dd545a5c518e8150c04cd320403578722befdf2dcafe2548"),
+    ("Transition", "3ff0a606c95a6c1076d4f3c6c685e5301b58aa33039ef1a7bc36fe9b0cfbaea7"),
+    ("Extension", "ffe972b49c1034353370e74ceae047326fea23e5aa449682fbc587c4fae4faca"),
+    ("ContractId", "6ee4ff589f43b8af74326120964ccead854e808a1715cd73d369cb06e45a3c91"),
+    (
+        "ContractId(BitcoinMainnet)",
+        "ff0e053c96167c925c256050d15f8cdecedc98a89de1405d541450cdd538be18",
+    ),
+    ("OpId(Transition)", "19b0a0c4f7128c3a5900389b7eedb461cfc1bdf413934ce32c559ed453547eb1"),
+    ("OpId(Extension)", "054de9f902bac702e604d1626937500d5da6a06800ef6375c0fc31c0597520f2"),
+    ("BundleId", "6985401ef56d0b0a96aff4cc792af50afa8aad180e46af2d09fc91c4df4eed63"),
+    ("SchemaId", "b928065015d861184fb16f669b494206baabfbf51e96311e86ff5fa573a71242"),
+];
+
+/// Commit-encoding of a transition in the layout preceding the commitment to
+/// the schema id, and the id it had under the `v02` transition tag.
+const BASELINE_TRANSITION: &str = "00006666666666666666666666666666666666666666666666666666666666666666\
+                                   0a0008006d6574616461746100000000";
+const BASELINE_TRANSITION_ID: &str =
+    "7ae63ad09d2d4e4c673350a1577b489a74556e8ab127ada1bce3d0a756cc6b4c";
+
+/// Strict encoding of a bitcoin mainnet genesis in the layout preceding the
+/// chain network, issue reserves and chain proof fields, and the contract ids
+/// it had with `testnet` flag unset and set.
+const BASELINE_GENESIS: &str = "00005555555555555555555555555555555555555555555555555555555555555555\
+                                000008006d6574616461746101010001000600676f6c64656e0000";
+const BASELINE_CONTRACT_ID: &str =
+    "61860f389abdea4152998ae3e555539977880fdf347f4fb643746aafa7306777";
+const BASELINE_TESTNET_CONTRACT_ID: &str =
+    "62f2c6f74f4c95aa785998a3d0ea313bd9d641637d044bec386186adb4935160";
+
+fn layout(val: &impl CommitEncode) -> String {
+    let mut engine = Sha256::default();
+    val.commit_encode(&mut engine);
+    engine.finalize().to_hex()
+}
+
+fn graph_seal(vout: u32) -> SealDefinition<GraphSeal> {
+    SealDefinition::Bitcoin(GraphSeal::with_blinding(
+        CloseMethod::TapretFirst,
+        TxPtr::WitnessTx,
+        vout,
+        0x1122_3344_5566_7788,
+    ))
+}
+
+fn genesis_seal(vout: u32) -> SealDefinition<GenesisSeal> {
+    SealDefinition::Liquid(GenesisSeal::with_blinding(
+        CloseMethod::OpretFirst,
+        Txid::from([0xA5; 32]),
+        vout,
+        0x8877_6655_4433_2211,
+    ))
+}
+
+fn value() -> RevealedValue {
+    RevealedValue::with_blinding(100_000u64, BlindingFactor::EMPTY, AssetTag::from([0x11; 32]))
+}
+
+fn data() -> RevealedData { RevealedData::from(Confined::try_from(b"golden".to_vec()).unwrap()) }
+
+fn attach() -> RevealedAttach {
+    RevealedAttach {
+        id: AttachId::from([0x22; 32]),
+        media_type: MediaType::Any,
+        salt: 0xDEAD_BEEF,
+        size: 0,
+    }
+}
+
+/// Assignments containing all state types, including concealed ones.
+fn assignments<Seal: crate::ExposedSeal>(
+    seal: impl Fn(u32) -> SealDefinition<Seal>,
+) -> Assignments<Seal> {
+    let mut map = TinyOrdMap::new();
+    map.insert(
+        AssignmentType::with(1),
+        TypedAssigns::Declarative(
+            Confined::try_from(vec![
+                Assign::revealed(seal(0), VoidState::default()),
+                Assign::Confidential {
+                    seal: seal(1).conceal(),
+                    state: VoidState::default(),
+                    payment_tag: None,
+                },
+            ])
+            .unwrap(),
+        ),
+    )
+    .unwrap();
+    map.insert(
+        AssignmentType::with(2),
+        TypedAssigns::Fungible(
+            Confined::try_from(vec![
+                Assign::revealed(seal(2), value()),
+                Assign::ConfidentialSeal {
+                    seal: seal(3).conceal(),
+                    state: value(),
+                    payment_tag: None,
+                },
+            ])
+            .unwrap(),
+        ),
+    )
+    .unwrap();
+    map.insert(
+        AssignmentType::with(3),
+        TypedAssigns::Structured(
+            Confined::try_from(vec![
+                Assign::revealed(seal(4), data()),
+                Assign::ConfidentialState {
+                    seal: seal(5),
+                    state: data().conceal(),
+                    payment_tag: None,
+                },
+            ])
+            .unwrap(),
+        ),
+    )
+    .unwrap();
+    map.insert(
+        AssignmentType::with(4),
+        TypedAssigns::Attachment(
+            Confined::try_from_iter([Assign::revealed(seal(6), attach())]).unwrap(),
+        ),
+    )
+    .unwrap();
+    Assignments::from(map)
+}
+
+fn payment_tag() -> RevealedPaymentTag {
+    RevealedPaymentTag {
+        tag: PaymentTag::from([0x88; 32]),
+        salt: 0xC0FF_EE00,
+    }
+}
+
+fn globals() -> GlobalState {
+    let mut globals = GlobalState::default();
+    globals.add_state(GlobalStateType::with(1), data()).unwrap();
+    globals
+        .add_state(GlobalStateType::with(1), RevealedData::strict_dumb())
+        .unwrap();
+    globals.add_state(GlobalStateType::with(2), data()).unwrap();
+    globals
+}
+
+fn valencies() -> Valencies {
+    Valencies::from(
+        TinyOrdSet::try_from_iter([ValencyType::with(1), ValencyType::with(2)]).unwrap(),
+    )
+}
+
+fn inputs() -> Inputs {
+    Inputs::from(
+        TinyOrdSet::try_from_iter([
+            Input::with(Opout::new(OpId::from([0x33; 32]), AssignmentType::with(2), 0)),
+            Input::with(Opout::new(OpId::from([0x44; 32]), AssignmentType::with(1), 5)),
+        ])
+        .unwrap(),
+    )
+}
+
+fn genesis() -> Genesis {
+    Genesis {
+        ffv: Ffv::default(),
+        schema_id: SchemaId::from([0x55; 32]),
+        chain_net: ChainNet::BitcoinTestnet,
+        alt_layers1: AltLayer1Set::from(TinyOrdSet::try_from_iter([AltLayer1::Liquid]).unwrap()),
+        metadata: Confined::try_from(b"metadata".to_vec()).unwrap(),
+        globals: globals(),
+        assignments: assignments(genesis_seal),
+        valencies: valencies(),
+        reserves: IssueReserveProof::default(),
+        chain_proof: ChainProof::default(),
+    }
+}
+
+fn transition() -> Transition {
+    Transition {
+        ffv: Ffv::default(),
+        contract_id: ContractId::from([0x66; 32]),
+        schema_id: SchemaId::from([0x55; 32]),
+        transition_type: TransitionType::with(10),
+        metadata: Confined::try_from(b"metadata".to_vec()).unwrap(),
+        globals: globals(),
+        inputs: inputs(),
+        assignments: assignments(graph_seal),
+        valencies: valencies(),
+        proof: None,
+        signature: None,
+    }
+}
+
+fn extension() -> Extension {
+    let mut redeemed = TinyOrdMap::new();
+    redeemed
+        .insert(ValencyType::with(1), OpId::from([0x77; 32]))
+        .unwrap();
+    Extension {
+        ffv: Ffv::default(),
+        contract_id: ContractId::from([0x66; 32]),
+        schema_id: SchemaId::from([0x55; 32]),
+        extension_type: ExtensionType::with(20),
+        metadata: Confined::try_from(b"metadata".to_vec()).unwrap(),
+        globals: globals(),
+        assignments: assignments(genesis_seal),
+        redeemed: Redeemed::from(redeemed),
+        valencies: valencies(),
+    }
+}
+
+fn snapshot() -> Vec<(&'static str, String)> {
+    let bundle =
+        TransitionBundle::try_from_transitions([(0, transition()), (3, transition())]).unwrap();
+    vec![
+        ("SecretSeal<GraphSeal>", graph_seal(0).conceal().to_hex()),
+        ("SecretSeal<GenesisSeal>", genesis_seal(0).conceal().to_hex()),
+        ("ConcealedFungible", layout(&value().conceal())),
+        ("ConcealedData", layout(&data().conceal())),
+        ("ConcealedAttach", layout(&attach().conceal())),
+        ("ConcealedPaymentTag", payment_tag().conceal().to_hex()),
+        (
+            "Assign<PaymentTag>",
+            layout(&Assign::revealed(graph_seal(0), value()).with_payment_tag(payment_tag())),
+        ),
+        ("Assignments<GraphSeal>", layout(&assignments(graph_seal))),
+        ("Assignments<GenesisSeal>", layout(&assignments(genesis_seal))),
+        ("GlobalState", layout(&globals())),
+        ("Valencies", layout(&valencies())),
+        ("Inputs", layout(&inputs())),
+        ("Genesis", layout(&genesis())),
+        ("Transition", layout(&transition())),
+        ("Extension", layout(&extension())),
+        ("ContractId", genesis().commitment_id().to_hex()),
+        ("ContractId(BitcoinMainnet)", {
+            let mut genesis = genesis();
+            genesis.chain_net = ChainNet::BitcoinMainnet;
+            genesis.commitment_id().to_hex()
+        }),
+        ("OpId(Transition)", transition().commitment_id().to_hex()),
+        ("OpId(Extension)", extension().commitment_id().to_hex()),
+        ("BundleId", bundle.commitment_id().to_hex()),
+        ("SchemaId", SubSchema::strict_dumb().commitment_id().to_hex()),
+    ]
+}
+
+#[test]
+fn golden_hashes() {
+    let snapshot = snapshot();
+    let report = snapshot
+        .iter()
+        .map(|(name, hash)| format!("    (\"{name}\", \"{hash}\"),"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let golden = GOLDEN
+        .iter()
+        .map(|(name, hash)| (*name, hash.to_string()))
+        .collect::<Vec<_>>();
+    assert!(
+        golden == snapshot,
+        "commitment layout of consensus types has changed; if this is a deliberate consensus \
+         change, update the golden values to:\n{report}"
+    );
+}
+
+#[test]
+fn contract_id_domains() {
+    let chain_nets = [
+        ChainNet::BitcoinMainnet,
+        ChainNet::BitcoinTestnet,
+        ChainNet::BitcoinSignet,
+        ChainNet::BitcoinRegtest,
+        ChainNet::LiquidMainnet,
+        ChainNet::LiquidTestnet,
+    ];
+    let mut ids = chain_nets
+        .into_iter()
+        .map(|chain_net| {
+            let mut genesis = genesis();
+            genesis.chain_net = chain_net;
+            genesis.contract_id()
+        })
+        .collect::<BTreeSet<_>>();
+    assert_eq!(ids.len(), chain_nets.len());
+
+    let mut genesis = genesis();
+    genesis.ffv = Ffv(1);
+    ids.insert(genesis.contract_id());
+    assert_eq!(ids.len(), chain_nets.len() + 1);
+}
+
+/// Checks that the value round-trips through both human-readable and binary
+/// serde formats. Values are compared by their strict encoding, since
+/// equality of assignments is defined via concealed state, which includes a
+/// random range proof placeholder.
+#[cfg(feature = "serde")]
+fn roundtrip<T>(name: &str, val: T)
+where T: serde_crate::Serialize + serde_crate::de::DeserializeOwned + strict_encoding::StrictEncode
+{
+    let strict = |val: &T| {
+        val.strict_encode(StrictWriter::in_memory(u32::MAX as usize))
+            .expect("in-memory encoding")
+            .unbox()
+    };
+    let json = serde_json::to_string(&val).unwrap_or_else(|e| panic!("{name} to json: {e}"));
+    let decoded: T =
+        serde_json::from_str(&json).unwrap_or_else(|e| panic!("{name} from json: {e}\n{json}"));
+    assert_eq!(strict(&decoded), strict(&val), "{name} json");
+    let bin = bincode::serialize(&val).unwrap_or_else(|e| panic!("{name} to bincode: {e}"));
+    let decoded: T =
+        bincode::deserialize(&bin).unwrap_or_else(|e| panic!("{name} from bincode: {e}"));
+    assert_eq!(strict(&decoded), strict(&val), "{name} bincode");
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_roundtrip() {
+    use crate::{ContractHistory, ContractState, Operation, Schema};
+
+    let genesis = genesis();
+    let bundle =
+        TransitionBundle::try_from_transitions([(0, transition()), (3, transition())]).unwrap();
+    let history = ContractHistory::with(genesis.schema_id, None, genesis.contract_id(), &genesis);
+    let state = ContractState {
+        schema: Schema::strict_dumb(),
+        history: history.clone(),
+    };
+
+    roundtrip("SealDefinition<GraphSeal>", graph_seal(1));
+    roundtrip("SealDefinition<GenesisSeal>", genesis_seal(1));
+    roundtrip("SecretSeal", graph_seal(1).conceal());
+    roundtrip("Assignments<GraphSeal>", assignments(graph_seal));
+    roundtrip("Genesis", genesis);
+    roundtrip("Transition", transition());
+    roundtrip("Extension", extension());
+    roundtrip("TransitionBundle", bundle);
+    roundtrip("SubSchema", SubSchema::strict_dumb());
+    roundtrip("ContractHistory", history);
+    roundtrip("ContractState", state);
+}
+
+#[test]
+fn transition_id_domain() {
+    let baseline = Vec::<u8>::from_hex(BASELINE_TRANSITION).unwrap();
+    let id = |tag: [u8; 32], encoding: &[u8]| {
+        let mut engine = Sha256::from_tag(tag);
+        engine.input_raw(encoding);
+        engine.finish().to_hex()
+    };
+    assert_eq!(id(*b"urn:lnpbp:rgb:transition:v02#23B", &baseline), BASELINE_TRANSITION_ID);
+
+    let mut transition = Transition::strict_dumb();
+    transition.contract_id = ContractId::from([0x66; 32]);
+    transition.schema_id = SchemaId::from([0x55; 32]);
+    transition.transition_type = TransitionType::with(10);
+    transition.metadata = Confined::try_from(b"metadata".to_vec()).unwrap();
+    // The only layout change is the schema id following the contract id
+    let mut encoding = vec![];
+    transition.commit_encode(&mut encoding);
+    let mut expected = baseline[..34].to_vec();
+    expected.extend_from_slice(&[0x55; 32]);
+    expected.extend_from_slice(&baseline[34..]);
+    assert_eq!(encoding, expected);
+    // which is committed under a new tag, such that the ids of the two layouts
+    // can't collide
+    assert_ne!(Transition::TAG, *b"urn:lnpbp:rgb:transition:v02#23B");
+    assert_eq!(transition.id().to_hex(), id(Transition::TAG, &encoding));
+    assert_ne!(transition.id().to_hex(), BASELINE_TRANSITION_ID);
+}
+
+#[test]
+fn baseline_contract_id() {
+    let strict = |val: &dyn Fn(StrictWriter<Vec<u8>>) -> StrictWriter<Vec<u8>>| {
+        val(StrictWriter::in_memory(u16::MAX as usize)).unbox()
+    };
+    let mut data = Vec::<u8>::from_hex(BASELINE_GENESIS).unwrap();
+    // Fields appended to the genesis after the baseline layout, in their empty
+    // form
+    data.extend(strict(&|w| IssueReserveProof::default().strict_encode(w).unwrap()));
+    data.extend(strict(&|w| ChainProof::default().strict_encode(w).unwrap()));
+    let mut genesis =
+        Genesis::from_strict_serialized::<{ u16::MAX as usize }>(Confined::try_from(data).unwrap())
+            .unwrap();
+    assert_eq!(genesis.chain_net, ChainNet::BitcoinMainnet);
+    assert_eq!(genesis.contract_id().to_hex(), BASELINE_CONTRACT_ID);
+
+    genesis.chain_net = ChainNet::BitcoinTestnet;
+    assert_eq!(genesis.contract_id().to_hex(), BASELINE_TESTNET_CONTRACT_ID);
+    assert_ne!(BASELINE_CONTRACT_ID, BASELINE_TESTNET_CONTRACT_ID);
+}
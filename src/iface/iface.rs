@@ -0,0 +1,168 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::{TinyOrdMap, TinyOrdSet};
+use amplify::{ByteArray, Bytes32};
+use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32};
+use commit_verify::{CommitStrategy, CommitmentId};
+use strict_encoding::{FieldName, StrictDeserialize, StrictSerialize, TypeName};
+use strict_types::SemId;
+
+use crate::LIB_NAME_RGB;
+
+/// Interface identifier.
+///
+/// Interface identifier commits to all of the interface data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct IfaceId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl ToBaid58<32> for IfaceId {
+    const HRI: &'static str = "if";
+    const CHUNKING: Option<Chunking> = CHUNKING_32;
+    fn to_baid58_payload(&self) -> [u8; 32] { self.to_byte_array() }
+    fn to_baid58_string(&self) -> String { self.to_string() }
+}
+impl FromBaid58<32> for IfaceId {}
+impl Display for IfaceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            f.write_str("urn:lnp-bp:if:")?;
+        }
+        if f.sign_minus() {
+            write!(f, "{:.2}", self.to_baid58())
+        } else {
+            write!(f, "{:#.2}", self.to_baid58())
+        }
+    }
+}
+impl FromStr for IfaceId {
+    type Err = Baid58ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_baid58_maybe_chunked_str(s.trim_start_matches("urn:lnp-bp:"), ':', '#')
+    }
+}
+impl IfaceId {
+    pub fn to_mnemonic(&self) -> String { self.to_baid58().mnemonic() }
+}
+
+/// Global state declared by an interface.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct GlobalIface {
+    /// Semantic type id of the state; any type matches if not specified.
+    pub sem_id: Option<SemId>,
+    pub required: bool,
+    /// Whether the state may contain more than a single item.
+    pub multiple: bool,
+}
+
+/// Kind of the owned state declared by an interface.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum OwnedIface {
+    #[strict_type(dumb)]
+    Any,
+    Rights,
+    Amount,
+    AnyData,
+    AnyAttach,
+    Data(SemId),
+}
+
+/// Owned state declared by an interface.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AssignIface {
+    pub owned_state: OwnedIface,
+    pub required: bool,
+}
+
+/// Contract interface.
+///
+/// All valencies, state transitions and state extensions declared by the
+/// interface must be provided by its implementations.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct Iface {
+    pub name: TypeName,
+    pub global_state: TinyOrdMap<FieldName, GlobalIface>,
+    pub assignments: TinyOrdMap<FieldName, AssignIface>,
+    pub valencies: TinyOrdSet<FieldName>,
+    pub transitions: TinyOrdSet<FieldName>,
+    pub extensions: TinyOrdSet<FieldName>,
+}
+
+impl CommitStrategy for Iface {
+    type Strategy = commit_verify::strategies::Strict;
+}
+
+impl CommitmentId for Iface {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:iface:v001#202402A";
+    type Id = IfaceId;
+}
+
+impl StrictSerialize for Iface {}
+impl StrictDeserialize for Iface {}
+
+impl Iface {
+    #[inline]
+    pub fn iface_id(&self) -> IfaceId { self.commitment_id() }
+}
@@ -0,0 +1,428 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use amplify::confinement::TinyOrdMap;
+use amplify::{ByteArray, Bytes32};
+use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32};
+use commit_verify::{CommitStrategy, CommitmentId};
+use strict_encoding::{FieldName, StrictDeserialize, StrictSerialize};
+
+use super::{Iface, IfaceId, OwnedIface};
+use crate::{
+    AssignmentType, ExtensionType, GlobalStateType, Schema, SchemaId, SchemaRoot, StateSchema,
+    TransitionType, ValencyType, LIB_NAME_RGB,
+};
+
+/// Interface implementation identifier.
+///
+/// Interface implementation identifier commits to all of the implementation
+/// data.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", transparent)
+)]
+pub struct ImplId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl ToBaid58<32> for ImplId {
+    const HRI: &'static str = "im";
+    const CHUNKING: Option<Chunking> = CHUNKING_32;
+    fn to_baid58_payload(&self) -> [u8; 32] { self.to_byte_array() }
+    fn to_baid58_string(&self) -> String { self.to_string() }
+}
+impl FromBaid58<32> for ImplId {}
+impl Display for ImplId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            f.write_str("urn:lnp-bp:im:")?;
+        }
+        if f.sign_minus() {
+            write!(f, "{:.2}", self.to_baid58())
+        } else {
+            write!(f, "{:#.2}", self.to_baid58())
+        }
+    }
+}
+impl FromStr for ImplId {
+    type Err = Baid58ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_baid58_maybe_chunked_str(s.trim_start_matches("urn:lnp-bp:"), ':', '#')
+    }
+}
+impl ImplId {
+    pub fn to_mnemonic(&self) -> String { self.to_baid58().mnemonic() }
+}
+
+/// Inconsistencies between interface implementation, the interface and the
+/// schema.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum ImplInconsistency {
+    /// implementation is made for interface {actual}, while {expected} was
+    /// provided.
+    IfaceMismatch { expected: IfaceId, actual: IfaceId },
+
+    /// implementation is made for schema {actual}, while {expected} was
+    /// provided.
+    SchemaMismatch {
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+
+    /// implementation binds '{0}' which is not declared by the interface.
+    IfaceAbsent(FieldName),
+
+    /// '{0}' is bound to a type which is not defined by the schema.
+    SchemaAbsent(FieldName),
+
+    /// '{0}' is bound to the same type as some other name.
+    DuplicateType(FieldName),
+
+    /// '{0}' required by the interface is not implemented.
+    Unimplemented(FieldName),
+
+    /// state of '{0}' defined by the schema doesn't match the interface.
+    StateMismatch(FieldName),
+}
+
+/// Binding of the names declared by an interface to the type ids of a schema.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct IfaceImpl {
+    pub iface_id: IfaceId,
+    pub schema_id: SchemaId,
+    pub global_state: TinyOrdMap<FieldName, GlobalStateType>,
+    pub assignments: TinyOrdMap<FieldName, AssignmentType>,
+    pub valencies: TinyOrdMap<FieldName, ValencyType>,
+    pub transitions: TinyOrdMap<FieldName, TransitionType>,
+    pub extensions: TinyOrdMap<FieldName, ExtensionType>,
+}
+
+impl CommitStrategy for IfaceImpl {
+    type Strategy = commit_verify::strategies::Strict;
+}
+
+impl CommitmentId for IfaceImpl {
+    const TAG: [u8; 32] = *b"urn:lnpbp:rgb:iface-impl#202402A";
+    type Id = ImplId;
+}
+
+impl StrictSerialize for IfaceImpl {}
+impl StrictDeserialize for IfaceImpl {}
+
+impl IfaceImpl {
+    #[inline]
+    pub fn impl_id(&self) -> ImplId { self.commitment_id() }
+
+    pub fn global_type(&self, name: &FieldName) -> Option<GlobalStateType> {
+        self.global_state.get(name).copied()
+    }
+
+    pub fn assignment_type(&self, name: &FieldName) -> Option<AssignmentType> {
+        self.assignments.get(name).copied()
+    }
+
+    pub fn valency_type(&self, name: &FieldName) -> Option<ValencyType> {
+        self.valencies.get(name).copied()
+    }
+
+    pub fn transition_type(&self, name: &FieldName) -> Option<TransitionType> {
+        self.transitions.get(name).copied()
+    }
+
+    pub fn extension_type(&self, name: &FieldName) -> Option<ExtensionType> {
+        self.extensions.get(name).copied()
+    }
+
+    pub fn global_name(&self, ty: GlobalStateType) -> Option<&FieldName> {
+        find_name(&self.global_state, ty)
+    }
+
+    pub fn assignment_name(&self, ty: AssignmentType) -> Option<&FieldName> {
+        find_name(&self.assignments, ty)
+    }
+
+    pub fn valency_name(&self, ty: ValencyType) -> Option<&FieldName> {
+        find_name(&self.valencies, ty)
+    }
+
+    pub fn transition_name(&self, ty: TransitionType) -> Option<&FieldName> {
+        find_name(&self.transitions, ty)
+    }
+
+    pub fn extension_name(&self, ty: ExtensionType) -> Option<&FieldName> {
+        find_name(&self.extensions, ty)
+    }
+
+    /// Verifies that the implementation binds all names required by the
+    /// interface to the types defined by the schema, and that the state
+    /// defined by the schema matches the one declared by the interface.
+    pub fn check<Root: SchemaRoot>(
+        &self,
+        iface: &Iface,
+        schema: &Schema<Root>,
+    ) -> Result<(), Vec<ImplInconsistency>> {
+        let mut errors = vec![];
+
+        let iface_id = iface.iface_id();
+        if self.iface_id != iface_id {
+            errors.push(ImplInconsistency::IfaceMismatch {
+                expected: iface_id,
+                actual: self.iface_id,
+            });
+        }
+        let schema_id = schema.schema_id();
+        if self.schema_id != schema_id {
+            errors.push(ImplInconsistency::SchemaMismatch {
+                expected: schema_id,
+                actual: self.schema_id,
+            });
+        }
+
+        check_bindings(
+            &self.global_state,
+            |name| iface.global_state.contains_key(name),
+            iface
+                .global_state
+                .iter()
+                .filter(|(_, global)| global.required)
+                .map(|(name, _)| name),
+            &mut errors,
+        );
+        for (name, ty) in &self.global_state {
+            let (Some(global), Some(schema)) =
+                (iface.global_state.get(name), schema.global_types.get(ty))
+            else {
+                continue;
+            };
+            if global.sem_id.map(|sem_id| sem_id != schema.sem_id) == Some(true) ||
+                (!global.multiple && schema.max_items > 1)
+            {
+                errors.push(ImplInconsistency::StateMismatch(name.clone()));
+            }
+        }
+        check_known(&self.global_state, |ty| schema.global_types.contains_key(ty), &mut errors);
+
+        check_bindings(
+            &self.assignments,
+            |name| iface.assignments.contains_key(name),
+            iface
+                .assignments
+                .iter()
+                .filter(|(_, assign)| assign.required)
+                .map(|(name, _)| name),
+            &mut errors,
+        );
+        for (name, ty) in &self.assignments {
+            let (Some(assign), Some(schema)) =
+                (iface.assignments.get(name), schema.owned_types.get(ty))
+            else {
+                continue;
+            };
+            let matches = match (assign.owned_state, schema) {
+                (OwnedIface::Any, _) |
+                (OwnedIface::Rights, StateSchema::Declarative) |
+                (OwnedIface::Amount, StateSchema::Fungible(_)) |
+                (OwnedIface::AnyData, StateSchema::Structured(_)) |
+                (OwnedIface::AnyAttach, StateSchema::Attachment(_)) => true,
+                (OwnedIface::Data(sem_id), StateSchema::Structured(schema_sem_id)) => {
+                    sem_id == *schema_sem_id
+                }
+                _ => false,
+            };
+            if !matches {
+                errors.push(ImplInconsistency::StateMismatch(name.clone()));
+            }
+        }
+        check_known(&self.assignments, |ty| schema.owned_types.contains_key(ty), &mut errors);
+
+        check_bindings(
+            &self.valencies,
+            |name| iface.valencies.contains(name),
+            iface.valencies.iter(),
+            &mut errors,
+        );
+        check_known(&self.valencies, |ty| schema.valency_types.contains(ty), &mut errors);
+
+        check_bindings(
+            &self.transitions,
+            |name| iface.transitions.contains(name),
+            iface.transitions.iter(),
+            &mut errors,
+        );
+        check_known(&self.transitions, |ty| schema.transitions.contains_key(ty), &mut errors);
+
+        check_bindings(
+            &self.extensions,
+            |name| iface.extensions.contains(name),
+            iface.extensions.iter(),
+            &mut errors,
+        );
+        check_known(&self.extensions, |ty| schema.extensions.contains_key(ty), &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn find_name<T: Eq>(bindings: &TinyOrdMap<FieldName, T>, ty: T) -> Option<&FieldName> {
+    bindings
+        .iter()
+        .find(|(_, bound)| **bound == ty)
+        .map(|(name, _)| name)
+}
+
+fn check_known<T>(
+    bindings: &TinyOrdMap<FieldName, T>,
+    known: impl Fn(&T) -> bool,
+    errors: &mut Vec<ImplInconsistency>,
+) {
+    for (name, ty) in bindings {
+        if !known(ty) {
+            errors.push(ImplInconsistency::SchemaAbsent(name.clone()));
+        }
+    }
+}
+
+/// Checks that the bound names are declared by the interface, that all
+/// required names are bound and that no type is bound twice.
+fn check_bindings<'iface, T: Ord>(
+    bindings: &TinyOrdMap<FieldName, T>,
+    declared: impl Fn(&FieldName) -> bool,
+    required: impl Iterator<Item = &'iface FieldName>,
+    errors: &mut Vec<ImplInconsistency>,
+) {
+    let mut types = BTreeSet::new();
+    for (name, ty) in bindings {
+        if !declared(name) {
+            errors.push(ImplInconsistency::IfaceAbsent(name.clone()));
+        }
+        if !types.insert(ty) {
+            errors.push(ImplInconsistency::DuplicateType(name.clone()));
+        }
+    }
+    for name in required {
+        if !bindings.contains_key(name) {
+            errors.push(ImplInconsistency::Unimplemented(name.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::TinyOrdSet;
+    use strict_types::SemId;
+
+    use super::*;
+    use crate::iface::{AssignIface, GlobalIface};
+    use crate::{FungibleType, GlobalStateSchema, SubSchema, TransitionSchema};
+
+    fn iface() -> Iface {
+        Iface {
+            name: tn!("RGB20"),
+            global_state: tiny_bmap! {
+                fname!("Nominal") => GlobalIface { sem_id: None, required: true, multiple: false },
+            },
+            assignments: tiny_bmap! {
+                fname!("Assets") => AssignIface { owned_state: OwnedIface::Amount, required: true },
+            },
+            valencies: none!(),
+            transitions: TinyOrdSet::try_from_iter([fname!("Transfer")]).unwrap(),
+            extensions: none!(),
+        }
+    }
+
+    fn schema() -> SubSchema {
+        let mut schema = SubSchema::default();
+        schema
+            .global_types
+            .insert(GlobalStateType::with(0), GlobalStateSchema::once(SemId::from([1; 32])))
+            .unwrap();
+        schema
+            .owned_types
+            .insert(AssignmentType::with(1), StateSchema::Fungible(FungibleType::Unsigned64Bit))
+            .unwrap();
+        schema
+            .transitions
+            .insert(TransitionType::with(2), TransitionSchema::default())
+            .unwrap();
+        schema
+    }
+
+    #[test]
+    fn check() {
+        let iface = iface();
+        let schema = schema();
+        let mut iimpl = IfaceImpl {
+            iface_id: iface.iface_id(),
+            schema_id: schema.schema_id(),
+            global_state: tiny_bmap! { fname!("Nominal") => GlobalStateType::with(0) },
+            assignments: tiny_bmap! { fname!("Assets") => AssignmentType::with(1) },
+            valencies: none!(),
+            transitions: tiny_bmap! { fname!("Transfer") => TransitionType::with(2) },
+            extensions: none!(),
+        };
+        assert_eq!(iimpl.check(&iface, &schema), Ok(()));
+        assert_eq!(iimpl.transition_type(&fname!("Transfer")), Some(TransitionType::with(2)));
+        assert_eq!(iimpl.assignment_name(AssignmentType::with(1)), Some(&fname!("Assets")));
+
+        iimpl.transitions = none!();
+        iimpl
+            .assignments
+            .insert(fname!("Issued"), AssignmentType::with(1))
+            .unwrap();
+        iimpl
+            .global_state
+            .insert(fname!("Nominal"), GlobalStateType::with(3))
+            .unwrap();
+        assert_eq!(
+            iimpl.check(&iface, &schema),
+            Err(vec![
+                ImplInconsistency::SchemaAbsent(fname!("Nominal")),
+                ImplInconsistency::IfaceAbsent(fname!("Issued")),
+                ImplInconsistency::DuplicateType(fname!("Issued")),
+                ImplInconsistency::Unimplemented(fname!("Transfer")),
+            ])
+        );
+    }
+}
@@ -0,0 +1,37 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contract interfaces.
+//!
+//! Interface ([`Iface`]) is a schema-independent declaration of named contract
+//! state and operations, like "Nominal" global state, "Assets" owned state or
+//! "Transfer" state transition. Interface implementation ([`IfaceImpl`])
+//! binds the names to the type ids of a specific schema and can be verified
+//! against both the interface and the schema, allowing generic wallets to work
+//! with contracts of schemata unknown to them through a known interface.
+
+#[allow(clippy::module_inception)]
+mod iface;
+mod iimpl;
+
+pub use iface::{AssignIface, GlobalIface, Iface, IfaceId, OwnedIface};
+pub use iimpl::{IfaceImpl, ImplId, ImplInconsistency};
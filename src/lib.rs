@@ -46,10 +46,27 @@ extern crate core;
 
 pub mod contract;
 pub mod schema;
+pub mod iface;
+pub mod untrusted;
+#[cfg(test)]
+mod golden;
 pub mod validation;
 pub mod vm;
 #[cfg(feature = "stl")]
 pub mod stl;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "psbt")]
+pub mod psbt;
+#[cfg(feature = "bindings")]
+pub mod bindings;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "vectors")]
+pub mod vectors;
+
+#[cfg(feature = "bindings")]
+uniffi::setup_scaffolding!();
 
 pub mod prelude {
     pub use bp::dbc::AnchorId;
@@ -111,6 +128,10 @@ mod _reserved {
 )]
 pub struct Ffv(u16);
 
+impl Ffv {
+    pub const fn to_u16(&self) -> u16 { self.0 }
+}
+
 mod _ffv {
     use strict_encoding::{DecodeError, ReadTuple, StrictDecode, TypedRead};
 
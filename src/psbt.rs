@@ -0,0 +1,508 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers placing RGB commitments into partially signed bitcoin transactions
+//! (BIP-174 PSBTs of version 0).
+//!
+//! [`Psbt::rgb_commit`] selects the first OP_RETURN or taproot output of the
+//! unsigned transaction as the commitment host, depending on the close method,
+//! embeds an LNPBP-4 multi-protocol commitment to the transition bundles into
+//! it and stores MPC and DBC proofs in proprietary output fields, from which
+//! the anchor can be restored later with [`Psbt::rgb_anchor`].
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+
+use bp::dbc::tapret::{TapretKeyError, TapretPathProof, TapretProof};
+use bp::seals::txout::CloseMethod;
+use bp::{
+    dbc, ByteStr, ConsensusDataError, ConsensusDecode, ConsensusDecodeError, ConsensusEncode,
+    InternalPk, ScriptPubkey, Tx,
+};
+use commit_verify::mpc::{self, MerkleBlock, MerkleTree, MultiSource};
+use commit_verify::{CommitmentId, ConvolveCommit, TryCommitVerify};
+use strict_encoding::{StrictDecode, StrictEncode, StrictReader, StrictWriter};
+
+use crate::{Anchor, AnchorSet, AnchorSetError, ContractId, TransitionBundle};
+
+pub const PSBT_MAGIC: [u8; 5] = *b"psbt\xff";
+
+pub const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+pub const PSBT_GLOBAL_VERSION: u8 = 0xFB;
+pub const PSBT_OUT_TAP_INTERNAL_KEY: u8 = 0x05;
+pub const PSBT_OUT_TAP_TREE: u8 = 0x06;
+pub const PSBT_PROPRIETARY: u8 = 0xFC;
+
+pub const PSBT_MPC_PREFIX: &[u8] = b"MPC";
+/// Proprietary output key for the LNPBP-4 commitment embedded into the output.
+pub const PSBT_OUT_MPC_COMMITMENT: u8 = 0x00;
+/// Proprietary output key for the strict-serialized LNPBP-4 merkle block.
+pub const PSBT_OUT_MPC_PROOF: u8 = 0x01;
+
+pub const PSBT_TAPRET_PREFIX: &[u8] = b"TAPRET";
+/// Proprietary output key marking the output as a tapret commitment host.
+pub const PSBT_OUT_TAPRET_HOST: u8 = 0x00;
+/// Proprietary output key for the strict-serialized tapret proof.
+pub const PSBT_OUT_TAPRET_PROOF: u8 = 0x01;
+
+pub const PSBT_OPRET_PREFIX: &[u8] = b"OPRET";
+/// Proprietary output key marking the output as an opret commitment host.
+pub const PSBT_OUT_OPRET_HOST: u8 = 0x00;
+
+const PROOF_MAX_LEN: usize = u32::MAX as usize;
+
+/// Key-value map of a PSBT section, indexed by the full key (key type byte
+/// followed by the key data).
+pub type PsbtMap = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Errors processing PSBT data or placing RGB commitment into them.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PsbtError {
+    /// data do not start with PSBT magic bytes.
+    InvalidMagic,
+
+    /// invalid PSBT data. {0}
+    #[from]
+    #[from(ConsensusDataError)]
+    Decode(ConsensusDecodeError),
+
+    /// PSBT contains repeated value for the same key.
+    DuplicateKey(Vec<u8>),
+
+    /// PSBT lacks unsigned transaction.
+    NoUnsignedTx,
+
+    /// unsigned transaction of the PSBT contains signature or witness data.
+    SignedTx,
+
+    /// PSBT version {0} is not supported; only version 0 PSBTs are processed.
+    UnsupportedVersion(u32),
+
+    /// PSBT already contains RGB commitment in output {0}.
+    AlreadyCommitted(usize),
+
+    /// transaction has no output which can host {0} commitment.
+    NoHostOutput(CloseMethod),
+
+    /// OP_RETURN output {0} already contains data and can't host the
+    /// commitment.
+    OpretNotEmpty(usize),
+
+    /// taproot output {0} lacks information about its internal key.
+    NoInternalKey(usize),
+
+    /// taproot output {0} has an invalid internal key.
+    InvalidInternalKey(usize),
+
+    /// taproot output {0} has script paths; only key-only taproot outputs can
+    /// host tapret commitment.
+    TapTreeUnsupported(usize),
+
+    /// output {0} contains invalid RGB proofs.
+    InvalidProof(usize),
+
+    #[from]
+    #[display(inner)]
+    Mpc(mpc::Error),
+
+    #[from]
+    #[display(inner)]
+    Tapret(TapretKeyError),
+
+    #[from]
+    #[display(inner)]
+    AnchorSet(AnchorSetError),
+}
+
+/// Partially signed bitcoin transaction of version 0.
+///
+/// The structure parses only the parts of the PSBT required for placing the
+/// RGB commitment and keeps all other key-value pairs as-is, such that they
+/// are preserved during serialization.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Psbt {
+    pub unsigned_tx: Tx,
+    /// Global key-value pairs except the unsigned transaction.
+    pub global: PsbtMap,
+    pub inputs: Vec<PsbtMap>,
+    pub outputs: Vec<PsbtMap>,
+}
+
+impl Psbt {
+    /// Constructs PSBT with empty input and output maps from an unsigned
+    /// transaction.
+    pub fn new(unsigned_tx: Tx) -> Result<Self, PsbtError> {
+        if unsigned_tx
+            .inputs()
+            .any(|input| !input.sig_script.is_empty() || !input.witness.is_empty())
+        {
+            return Err(PsbtError::SignedTx);
+        }
+        Ok(Psbt {
+            inputs: vec![empty!(); unsigned_tx.inputs.len()],
+            outputs: vec![empty!(); unsigned_tx.outputs.len()],
+            global: empty!(),
+            unsigned_tx,
+        })
+    }
+
+    pub fn deserialize(data: impl AsRef<[u8]>) -> Result<Self, PsbtError> {
+        let data = data.as_ref();
+        if !data.starts_with(&PSBT_MAGIC) {
+            return Err(PsbtError::InvalidMagic);
+        }
+        let mut cursor = Cursor::new(&data[PSBT_MAGIC.len()..]);
+
+        let mut global = read_map(&mut cursor)?;
+        let tx = global
+            .remove(&vec![PSBT_GLOBAL_UNSIGNED_TX])
+            .ok_or(PsbtError::NoUnsignedTx)?;
+        if let Some(version) = global.get(&vec![PSBT_GLOBAL_VERSION]) {
+            let version = <[u8; 4]>::try_from(version.as_slice())
+                .map(u32::from_le_bytes)
+                .unwrap_or(u32::MAX);
+            if version != 0 {
+                return Err(PsbtError::UnsupportedVersion(version));
+            }
+        }
+        let mut psbt = Psbt::new(Tx::consensus_deserialize(tx)?)?;
+        psbt.global = global;
+        for input in &mut psbt.inputs {
+            *input = read_map(&mut cursor)?;
+        }
+        for output in &mut psbt.outputs {
+            *output = read_map(&mut cursor)?;
+        }
+        if cursor.position() as usize != data.len() - PSBT_MAGIC.len() {
+            return Err(ConsensusDataError::DataNotConsumed.into());
+        }
+        Ok(psbt)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = PSBT_MAGIC.to_vec();
+        let tx = self.unsigned_tx.consensus_serialize();
+        let global = self
+            .global
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()));
+        write_map(
+            &mut data,
+            [([PSBT_GLOBAL_UNSIGNED_TX].as_slice(), tx.as_slice())]
+                .into_iter()
+                .chain(global),
+        );
+        for map in self.inputs.iter().chain(&self.outputs) {
+            write_map(
+                &mut data,
+                map.iter()
+                    .map(|(key, value)| (key.as_slice(), value.as_slice())),
+            );
+        }
+        data
+    }
+
+    /// Returns index of the output marked as the opret commitment host, if
+    /// any.
+    pub fn opret_host(&self) -> Option<usize> {
+        self.marked_output(&proprietary_key(PSBT_OPRET_PREFIX, PSBT_OUT_OPRET_HOST))
+    }
+
+    /// Returns index of the output marked as the tapret commitment host, if
+    /// any.
+    pub fn tapret_host(&self) -> Option<usize> {
+        self.marked_output(&proprietary_key(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_HOST))
+    }
+
+    fn marked_output(&self, key: &Vec<u8>) -> Option<usize> {
+        self.outputs
+            .iter()
+            .position(|output| output.contains_key(key))
+    }
+
+    /// Places LNPBP-4 commitment to the transition bundles into the PSBT
+    /// using the given close method and returns the anchor set for the
+    /// bundles.
+    ///
+    /// The first OP_RETURN output, which must not contain any data yet, is
+    /// used as the host for opret commitment; the first taproot output, which
+    /// must have its internal key provided and no script paths, is used as
+    /// the host for tapret commitment. The host output is marked and gets MPC
+    /// and DBC proofs stored in its proprietary fields. The PSBT is not
+    /// modified if the method returns an error.
+    pub fn rgb_commit(
+        &mut self,
+        method: CloseMethod,
+        bundles: impl IntoIterator<Item = (ContractId, TransitionBundle)>,
+    ) -> Result<AnchorSet, PsbtError> {
+        let commitment_key = proprietary_key(PSBT_MPC_PREFIX, PSBT_OUT_MPC_COMMITMENT);
+        if let Some(no) = self.marked_output(&commitment_key) {
+            return Err(PsbtError::AlreadyCommitted(no));
+        }
+
+        let bundles = bundles.into_iter().collect::<BTreeMap<_, _>>();
+        let mut source = MultiSource::default();
+        for (contract_id, bundle) in &bundles {
+            source
+                .messages
+                .insert(mpc::ProtocolId::from(*contract_id), mpc::Message::from(bundle.bundle_id()))
+                .map_err(|_| mpc::Error::TooManyMessages(bundles.len()))?;
+        }
+        let tree = MerkleTree::try_commit(&source)?;
+        let commitment = tree.commitment_id();
+        let mpc_proof = MerkleBlock::from(tree);
+
+        let mut tx = self.unsigned_tx.clone();
+        let (host, dbc_proof, marker) = match method {
+            CloseMethod::OpretFirst => {
+                let host = tx
+                    .outputs
+                    .iter()
+                    .position(|txout| txout.script_pubkey.is_op_return())
+                    .ok_or(PsbtError::NoHostOutput(method))?;
+                let script = &mut tx.outputs[host].script_pubkey;
+                if *script != ScriptPubkey::op_return(&[]) {
+                    return Err(PsbtError::OpretNotEmpty(host));
+                }
+                *script = ScriptPubkey::op_return(commitment.as_slice());
+                let marker = (proprietary_key(PSBT_OPRET_PREFIX, PSBT_OUT_OPRET_HOST), vec![]);
+                (host, dbc::Proof::OpretFirst, marker)
+            }
+            CloseMethod::TapretFirst => {
+                let host = tx
+                    .outputs
+                    .iter()
+                    .position(|txout| txout.script_pubkey.is_p2tr())
+                    .ok_or(PsbtError::NoHostOutput(method))?;
+                let output = &self.outputs[host];
+                if output.contains_key(&vec![PSBT_OUT_TAP_TREE]) {
+                    return Err(PsbtError::TapTreeUnsupported(host));
+                }
+                let internal_pk = output
+                    .get(&vec![PSBT_OUT_TAP_INTERNAL_KEY])
+                    .ok_or(PsbtError::NoInternalKey(host))?;
+                let internal_pk = <[u8; 32]>::try_from(internal_pk.as_slice())
+                    .ok()
+                    .and_then(|key| InternalPk::from_byte_array(key).ok())
+                    .ok_or(PsbtError::InvalidInternalKey(host))?;
+                let script = &mut tx.outputs[host].script_pubkey;
+                if *script != ScriptPubkey::p2tr_key_only(internal_pk) {
+                    return Err(PsbtError::TapTreeUnsupported(host));
+                }
+                let (output_pk, proof) =
+                    internal_pk.convolve_commit(&TapretPathProof::root(0), &commitment)?;
+                *script = ScriptPubkey::p2tr_tweaked(output_pk);
+                let marker = (
+                    proprietary_key(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_PROOF),
+                    strict_serialize(&proof),
+                );
+                (host, dbc::Proof::TapretFirst(proof), marker)
+            }
+            _ => return Err(PsbtError::NoHostOutput(method)),
+        };
+
+        let anchor = Anchor::Bitcoin(dbc::Anchor {
+            txid: tx.txid(),
+            mpc_proof: mpc_proof.clone(),
+            dbc_proof,
+        });
+        let anchor_set = AnchorSet::with(anchor, bundles)?;
+
+        self.unsigned_tx = tx;
+        let output = &mut self.outputs[host];
+        if method == CloseMethod::TapretFirst {
+            output.insert(proprietary_key(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_HOST), vec![]);
+        }
+        output.insert(marker.0, marker.1);
+        output.insert(commitment_key, commitment.to_vec());
+        output.insert(
+            proprietary_key(PSBT_MPC_PREFIX, PSBT_OUT_MPC_PROOF),
+            strict_serialize(&mpc_proof),
+        );
+        Ok(anchor_set)
+    }
+
+    /// Restores anchor from the proofs stored in the PSBT by
+    /// [`Psbt::rgb_commit`]. Returns `None` if the PSBT doesn't contain RGB
+    /// commitment.
+    pub fn rgb_anchor(&self) -> Result<Option<Anchor<MerkleBlock>>, PsbtError> {
+        let Some(host) = self.marked_output(&proprietary_key(PSBT_MPC_PREFIX, PSBT_OUT_MPC_PROOF))
+        else {
+            return Ok(None);
+        };
+        let output = &self.outputs[host];
+        let mpc_proof = output
+            .get(&proprietary_key(PSBT_MPC_PREFIX, PSBT_OUT_MPC_PROOF))
+            .and_then(|data| strict_deserialize::<MerkleBlock>(data))
+            .ok_or(PsbtError::InvalidProof(host))?;
+        let dbc_proof =
+            match output.get(&proprietary_key(PSBT_TAPRET_PREFIX, PSBT_OUT_TAPRET_PROOF)) {
+                Some(data) => dbc::Proof::TapretFirst(
+                    strict_deserialize::<TapretProof>(data).ok_or(PsbtError::InvalidProof(host))?,
+                ),
+                None if self.opret_host() == Some(host) => dbc::Proof::OpretFirst,
+                None => return Err(PsbtError::InvalidProof(host)),
+            };
+        Ok(Some(Anchor::Bitcoin(dbc::Anchor {
+            txid: self.unsigned_tx.txid(),
+            mpc_proof,
+            dbc_proof,
+        })))
+    }
+}
+
+fn proprietary_key(prefix: &[u8], subtype: u8) -> Vec<u8> {
+    let mut key = vec![PSBT_PROPRIETARY, prefix.len() as u8];
+    key.extend(prefix);
+    key.push(subtype);
+    key
+}
+
+fn read_map(reader: &mut impl Read) -> Result<PsbtMap, PsbtError> {
+    let mut map = PsbtMap::new();
+    loop {
+        let key = ByteStr::consensus_decode(reader)?.into_vec();
+        if key.is_empty() {
+            return Ok(map);
+        }
+        let value = ByteStr::consensus_decode(reader)?.into_vec();
+        if map.contains_key(&key) {
+            return Err(PsbtError::DuplicateKey(key));
+        }
+        map.insert(key, value);
+    }
+}
+
+fn write_map<'a>(data: &mut Vec<u8>, map: impl IntoIterator<Item = (&'a [u8], &'a [u8])>) {
+    for (key, value) in map {
+        ByteStr::from(key.to_vec())
+            .consensus_encode(data)
+            .expect("in-memory writing can't fail");
+        ByteStr::from(value.to_vec())
+            .consensus_encode(data)
+            .expect("in-memory writing can't fail");
+    }
+    data.push(0x00);
+}
+
+fn strict_serialize(value: &impl StrictEncode) -> Vec<u8> {
+    value
+        .strict_encode(StrictWriter::in_memory(PROOF_MAX_LEN))
+        .expect("in-memory writing can't fail")
+        .unbox()
+}
+
+fn strict_deserialize<T: StrictDecode>(data: &[u8]) -> Option<T> {
+    let mut reader = StrictReader::in_memory(data.to_vec(), PROOF_MAX_LEN);
+    let value = T::strict_decode(&mut reader).ok()?;
+    (reader.unbox().position() as usize == data.len()).then_some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use amplify::confinement::Confined;
+    use bp::{LockTime, Outpoint, SeqNo, TxIn, TxOut, TxVer, Txid};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn psbt() -> Psbt {
+        let internal_pk = InternalPk::from_str(
+            "c5f93479093e2b8f724a79844cc10928dd44e9a390b539843fb83fbf842723f3",
+        )
+        .unwrap();
+        let tx = Tx {
+            version: TxVer::V2,
+            inputs: Confined::try_from(vec![TxIn {
+                prev_output: Outpoint::new(Txid::from([1; 32]), 0),
+                sig_script: default!(),
+                sequence: SeqNo::from_consensus_u32(u32::MAX),
+                witness: default!(),
+            }])
+            .unwrap(),
+            outputs: Confined::try_from(vec![
+                TxOut::new(ScriptPubkey::p2tr_key_only(internal_pk), 1000u64),
+                TxOut::new(ScriptPubkey::op_return(&[]), 0u64),
+            ])
+            .unwrap(),
+            lock_time: LockTime::default(),
+        };
+        let mut psbt = Psbt::new(tx).unwrap();
+        psbt.outputs[0]
+            .insert(vec![PSBT_OUT_TAP_INTERNAL_KEY], internal_pk.to_byte_array().to_vec());
+        psbt
+    }
+
+    fn bundles() -> [(ContractId, TransitionBundle); 2] {
+        [
+            (ContractId::from([1; 32]), TransitionBundle::strict_dumb()),
+            (ContractId::from([2; 32]), TransitionBundle::strict_dumb()),
+        ]
+    }
+
+    #[test]
+    fn rgb_commit() {
+        for (method, host) in [(CloseMethod::TapretFirst, 0), (CloseMethod::OpretFirst, 1)] {
+            let mut psbt = psbt();
+            assert_eq!(psbt.rgb_anchor(), Ok(None));
+            let anchor_set = psbt.rgb_commit(method, bundles()).unwrap();
+            assert_eq!(anchor_set.bundles().len(), 2);
+
+            let anchor = anchor_set.anchor();
+            assert_eq!(anchor.txid, psbt.unsigned_tx.txid());
+            let commitment = anchor.mpc_proof.commitment_id();
+            assert_eq!(anchor.dbc_proof.verify(&commitment, &psbt.unsigned_tx), Ok(()));
+            if method == CloseMethod::OpretFirst {
+                assert_eq!(psbt.opret_host(), Some(host));
+            } else {
+                assert_eq!(psbt.tapret_host(), Some(host));
+            }
+
+            let psbt = Psbt::deserialize(psbt.serialize()).unwrap();
+            assert_eq!(psbt.rgb_anchor(), Ok(Some(anchor.clone())));
+            assert_eq!(
+                psbt.clone().rgb_commit(method, bundles()),
+                Err(PsbtError::AlreadyCommitted(host))
+            );
+        }
+    }
+
+    #[test]
+    fn no_host() {
+        let mut psbt = psbt();
+        psbt.outputs[0].clear();
+        assert_eq!(
+            psbt.rgb_commit(CloseMethod::TapretFirst, bundles()),
+            Err(PsbtError::NoInternalKey(0))
+        );
+        psbt.unsigned_tx.outputs = Confined::try_from(vec![]).unwrap();
+        psbt.outputs.clear();
+        assert_eq!(
+            psbt.rgb_commit(CloseMethod::OpretFirst, bundles()),
+            Err(PsbtError::NoHostOutput(CloseMethod::OpretFirst))
+        );
+        assert_eq!(Psbt::deserialize(b"psbt"), Err(PsbtError::InvalidMagic));
+    }
+}
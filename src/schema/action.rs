@@ -0,0 +1,61 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Named state transition templates ("actions").
+//!
+//! A schema may declare templates for the typical state transitions, like
+//! "transfer", "burn" or "reissue". Each template refers to one of the schema
+//! transition types and narrows down the shape of its inputs, global state and
+//! assignments. Wallets construct transitions from the templates with
+//! [`crate::ActionBuilder`], which checks the transition against the template
+//! before it gets signed.
+
+use strict_encoding::FieldName;
+
+use super::operations::InputsSchema;
+use super::{AssignmentsSchema, GlobalSchema, Schema, SchemaRoot, TransitionType};
+use crate::LIB_NAME_RGB;
+
+/// Template of a state transition declared by a schema under some action name.
+///
+/// Only the global state, input and assignment types listed by the template
+/// may be used by the transitions constructed from it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ActionTemplate {
+    pub transition_type: TransitionType,
+    pub globals: GlobalSchema,
+    pub inputs: InputsSchema,
+    pub assignments: AssignmentsSchema,
+}
+
+impl<Root: SchemaRoot> Schema<Root> {
+    /// Returns template of the action with a given name, if declared by the
+    /// schema.
+    pub fn action(&self, name: &FieldName) -> Option<&ActionTemplate> { self.actions.get(name) }
+}
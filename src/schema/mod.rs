@@ -26,7 +26,9 @@ mod schema;
 pub mod script;
 mod state;
 mod occurrences;
+mod action;
 
+pub use action::ActionTemplate;
 pub use occurrences::{Occurrences, OccurrencesMismatch};
 pub use operations::{
     AssignmentType, AssignmentsSchema, ExtensionSchema, GenesisSchema, GlobalSchema, OpFullType,
@@ -37,4 +39,6 @@ pub use schema::{
     TransitionType,
 };
 pub use script::{Script, VmType};
-pub use state::{FungibleType, GlobalStateSchema, MediaType, StateSchema};
+pub use state::{
+    AttachLimits, FungibleType, GlobalRetention, GlobalStateSchema, MediaType, StateSchema,
+};
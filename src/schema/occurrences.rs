@@ -208,7 +208,7 @@ mod test {
     #[test]
     fn test_once_or_up_to_none_large() {
         let occurence: Occurrences = Occurrences::OnceOrMore;
-        occurence.check(core::u16::MAX).unwrap();
+        occurence.check(u16::MAX).unwrap();
     }
     #[test]
     #[should_panic(expected = "OccurrencesMismatch { min: 1, max: 65535, found: 0 }")]
@@ -242,7 +242,7 @@ mod test {
     #[test]
     fn test_none_or_up_to_none_large() {
         let occurence: Occurrences = Occurrences::NoneOrMore;
-        occurence.check(core::u16::MAX).unwrap();
+        occurence.check(u16::MAX).unwrap();
     }
     #[test]
     fn test_none_or_up_to_42_zero() {
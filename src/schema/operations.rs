@@ -25,7 +25,7 @@ use amplify::Wrapper;
 use strict_types::SemId;
 
 use super::{ExtensionType, GlobalStateType, Occurrences, TransitionType};
-use crate::LIB_NAME_RGB;
+use crate::{Capabilities, VerifyingKeyId, LIB_NAME_RGB};
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
 #[wrapper(FromStr, LowerHex, UpperHex)]
@@ -152,6 +152,10 @@ pub struct GenesisSchema {
     pub globals: GlobalSchema,
     pub assignments: AssignmentsSchema,
     pub valencies: ValencySchema,
+    /// Number of blocks which must be mined on top of the transaction defining
+    /// the seal of a genesis output before the output can be spent. Zero
+    /// value means that the outputs are spendable immediately.
+    pub maturity: u32,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
@@ -168,6 +172,11 @@ pub struct ExtensionSchema {
     pub redeems: ValencySchema,
     pub assignments: AssignmentsSchema,
     pub valencies: ValencySchema,
+    /// Number of blocks which must be mined on top of the transaction defining
+    /// the seal of an output of a state extension of this type before the
+    /// output can be spent. Zero value means that the outputs are spendable
+    /// immediately.
+    pub maturity: u32,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
@@ -184,6 +193,19 @@ pub struct TransitionSchema {
     pub inputs: InputsSchema,
     pub assignments: AssignmentsSchema,
     pub valencies: ValencySchema,
+    /// Verifying key of a zero-knowledge proof which must be provided by each
+    /// state transition of this type.
+    pub proof: Option<VerifyingKeyId>,
+    /// Genesis global state type holding an identity key (BIP-340 x-only
+    /// public key), which must sign each state transition of this type.
+    pub identity: Option<GlobalStateType>,
+    /// Capabilities which each spent right (declarative assignment) of the
+    /// given type must have for a state transition of this type to be valid.
+    pub capabilities: TinyOrdMap<AssignmentType, Capabilities>,
+    /// Number of blocks which must be mined on top of the witness transaction
+    /// of a state transition of this type before its outputs can be spent.
+    /// Zero value means that the outputs are spendable immediately.
+    pub maturity: u32,
 }
 
 impl OpSchema for GenesisSchema {
@@ -28,14 +28,16 @@ use amplify::confinement::{TinyOrdMap, TinyOrdSet};
 use amplify::{ByteArray, Bytes32};
 use baid58::{Baid58ParseError, Chunking, FromBaid58, ToBaid58, CHUNKING_32};
 use commit_verify::{CommitStrategy, CommitmentId};
-use strict_encoding::{StrictDecode, StrictDeserialize, StrictEncode, StrictSerialize, StrictType};
+use strict_encoding::{
+    FieldName, StrictDecode, StrictDeserialize, StrictEncode, StrictSerialize, StrictType,
+};
 use strict_types::TypeSystem;
 
 use super::{
-    AssignmentType, ExtensionSchema, GenesisSchema, Script, StateSchema, TransitionSchema,
-    ValencyType,
+    ActionTemplate, AssignmentType, ExtensionSchema, GenesisSchema, Script, StateSchema,
+    TransitionSchema, ValencyType,
 };
-use crate::{Ffv, GlobalStateSchema, Occurrences, LIB_NAME_RGB};
+use crate::{AttachLimits, Ffv, GlobalStateSchema, Occurrences, LIB_NAME_RGB};
 
 #[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Display)]
 #[wrapper(FromStr, LowerHex, UpperHex)]
@@ -159,6 +161,15 @@ pub struct Schema<Root: SchemaRoot> {
     pub genesis: GenesisSchema,
     pub extensions: TinyOrdMap<ExtensionType, ExtensionSchema>,
     pub transitions: TinyOrdMap<TransitionType, TransitionSchema>,
+    /// Named templates of the state transitions.
+    pub actions: TinyOrdMap<FieldName, ActionTemplate>,
+    /// Limits on the attachments assigned by each of the contract operations,
+    /// if any.
+    pub attach_limits: Option<AttachLimits>,
+    /// Heights after which the assignments of the given types expire and
+    /// can't be spent: a transition spending such an assignment must have its
+    /// witness mined at or below the height.
+    pub expiry: TinyOrdMap<AssignmentType, u32>,
 
     /// Type system
     pub type_system: TypeSystem,
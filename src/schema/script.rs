@@ -31,6 +31,11 @@ use crate::LIB_NAME_RGB;
 /// Virtual machine types.
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 #[display(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
 pub enum VmType {
     /// AluVM: pure functional register-based virtual machine designed for RGB
     /// and multiparty computing.
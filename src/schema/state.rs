@@ -20,7 +20,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use strict_encoding::constants::U64;
+use strict_encoding::constants::{U128, U64};
 use strict_types::SemId;
 
 use crate::{StateType, LIB_NAME_RGB};
@@ -97,6 +97,12 @@ pub enum FungibleType {
     #[default]
     #[display("64bit")]
     Unsigned64Bit = U64.into_code(),
+
+    #[display("128bit")]
+    Unsigned128Bit = U128.into_code(),
+
+    #[display("decimal")]
+    Decimal = 0x80,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -110,6 +116,7 @@ pub enum FungibleType {
 pub struct GlobalStateSchema {
     pub sem_id: SemId,
     pub max_items: u16,
+    pub retention: GlobalRetention,
 }
 
 impl GlobalStateSchema {
@@ -117,6 +124,7 @@ impl GlobalStateSchema {
         GlobalStateSchema {
             sem_id,
             max_items: 1,
+            retention: GlobalRetention::KeepAll,
         }
     }
 
@@ -124,6 +132,67 @@ impl GlobalStateSchema {
         GlobalStateSchema {
             sem_id,
             max_items: u16::MAX,
+            retention: GlobalRetention::KeepAll,
+        }
+    }
+
+    /// Global state which may be defined by each operation with up to `count`
+    /// items, of which only the most recent `count` items are kept in the
+    /// contract state.
+    pub fn keep_last(sem_id: SemId, count: u16) -> Self {
+        GlobalStateSchema {
+            sem_id,
+            max_items: count,
+            retention: GlobalRetention::KeepLast(count),
+        }
+    }
+}
+
+/// Policy for keeping global state items in the accumulated contract state.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = order)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub enum GlobalRetention {
+    /// All items ever defined by contract operations are kept.
+    #[strict_type(dumb)]
+    #[display("all")]
+    KeepAll,
+
+    /// Only the given number of the most recent items is kept; older items are
+    /// pruned from the contract state as new ones are added.
+    #[display("last {0}")]
+    KeepLast(u16),
+}
+
+/// Limits on the attachments which may be assigned by a single contract
+/// operation.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct AttachLimits {
+    /// Maximal number of attachments of all types, including concealed ones.
+    pub max_count: u16,
+    /// Maximal size of each of the attachments, in bytes, as declared by the
+    /// revealed attachment state. Attachments which don't declare their size
+    /// are invalid.
+    pub max_size: u64,
+}
+
+impl AttachLimits {
+    pub fn with(max_count: u16, max_size: u64) -> Self {
+        AttachLimits {
+            max_count,
+            max_size,
         }
     }
 }
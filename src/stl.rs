@@ -28,12 +28,17 @@ use strict_types::stl::{std_stl, strict_types_stl};
 use strict_types::typelib::LibBuilder;
 use strict_types::{CompileError, TypeLib};
 
-use crate::{AnchoredBundle, ContractState, Extension, Genesis, SubSchema, LIB_NAME_RGB};
+use crate::iface::{Iface, IfaceImpl};
+use crate::{
+    AnchoredBundle, ContractState, Extension, Genesis, Name, Precision, SubSchema, Ticker,
+    LIB_NAME_RGB,
+};
 
 /// Strict types id for the library providing data types for RGB consensus.
 pub const LIB_ID_RGB: &str =
-    "urn:ubideco:stl:5dLgsvZEbhQekV5j7ghFCTyu3JPZ1B99SqhqPEA4Fdc#pigment-evita-clone";
+    "urn:ubideco:stl:4TRBPSPF1DBUiHrW3pqENK9reNyf2cK4xvNazcvRuRFJ#vendor-driver-next";
 
+#[allow(clippy::result_large_err)]
 fn _rgb_core_stl() -> Result<TypeLib, CompileError> {
     LibBuilder::new(libname!(LIB_NAME_RGB), tiny_bset! {
         std_stl().to_dependency(),
@@ -48,6 +53,11 @@ fn _rgb_core_stl() -> Result<TypeLib, CompileError> {
     .transpile::<AnchoredBundle>()
     .transpile::<Extension>()
     .transpile::<ContractState>()
+    .transpile::<Iface>()
+    .transpile::<IfaceImpl>()
+    .transpile::<Ticker>()
+    .transpile::<Name>()
+    .transpile::<Precision>()
     .compile()
 }
 
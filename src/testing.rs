@@ -0,0 +1,294 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Property-based testing strategies producing structurally valid RGB data.
+//!
+//! Generated data are well-formed (i.e. they can be strict-serialized and
+//! committed to), but are not required to be valid against any schema.
+
+use std::fmt::Debug;
+
+use amplify::confinement::{Confined, SmallVec, TinyOrdMap, TinyOrdSet};
+use bp::seals::txout::{CloseMethod, TxPtr};
+use bp::Txid;
+use commit_verify::Conceal;
+use proptest::collection::{btree_map, btree_set, vec};
+use proptest::prelude::*;
+
+use crate::{
+    AssetTag, Assign, AssignPaymentTag, AssignmentType, Assignments, AttachId, BlindingFactor,
+    ContractId, ExposedSeal, ExposedState, Ffv, GenesisSeal, GlobalState, GlobalStateType,
+    GlobalValues, GraphSeal, Input, Inputs, MediaType, OpId, Opout, PaymentTag, RevealedAttach,
+    RevealedData, RevealedPaymentTag, RevealedValue, SchemaId, SealDefinition, Transition,
+    TransitionBundle, TransitionType, TypedAssigns, Valencies, ValencyType, VoidState,
+};
+
+/// Maximal number of items generated for collections.
+const MAX_ITEMS: usize = 4;
+
+fn bytes32() -> impl Strategy<Value = [u8; 32]> + Clone { any::<[u8; 32]>() }
+
+pub fn txid() -> impl Strategy<Value = Txid> + Clone { bytes32().prop_map(Txid::from) }
+
+pub fn contract_id() -> impl Strategy<Value = ContractId> + Clone {
+    bytes32().prop_map(ContractId::from)
+}
+
+pub fn schema_id() -> impl Strategy<Value = SchemaId> + Clone { bytes32().prop_map(SchemaId::from) }
+
+pub fn opid() -> impl Strategy<Value = OpId> + Clone { bytes32().prop_map(OpId::from) }
+
+pub fn asset_tag() -> impl Strategy<Value = AssetTag> + Clone { bytes32().prop_map(AssetTag::from) }
+
+pub fn blinding_factor() -> impl Strategy<Value = BlindingFactor> + Clone {
+    bytes32().prop_filter_map("invalid field element", |bytes| BlindingFactor::try_from(bytes).ok())
+}
+
+pub fn close_method() -> impl Strategy<Value = CloseMethod> + Clone {
+    prop_oneof![Just(CloseMethod::TapretFirst), Just(CloseMethod::OpretFirst)]
+}
+
+/// Seal which may point to the witness transaction, as used in state
+/// transitions.
+pub fn graph_seal() -> impl Strategy<Value = GraphSeal> + Clone {
+    let txptr = prop_oneof![Just(TxPtr::WitnessTx), txid().prop_map(TxPtr::Txid)];
+    (close_method(), txptr, any::<u32>(), any::<u64>()).prop_map(
+        |(method, txptr, vout, blinding)| GraphSeal::with_blinding(method, txptr, vout, blinding),
+    )
+}
+
+/// Seal pointing to an existing transaction output, as used in genesis and
+/// state extensions.
+pub fn genesis_seal() -> impl Strategy<Value = GenesisSeal> + Clone {
+    (close_method(), txid(), any::<u32>(), any::<u64>()).prop_map(
+        |(method, txid, vout, blinding)| GenesisSeal::with_blinding(method, txid, vout, blinding),
+    )
+}
+
+pub fn seal_definition<Seal: ExposedSeal>(
+    seal: impl Strategy<Value = Seal> + Clone,
+) -> impl Strategy<Value = SealDefinition<Seal>> + Clone {
+    prop_oneof![
+        seal.clone().prop_map(SealDefinition::Bitcoin),
+        seal.prop_map(SealDefinition::Liquid)
+    ]
+}
+
+pub fn revealed_value() -> impl Strategy<Value = RevealedValue> + Clone {
+    (any::<u64>(), blinding_factor(), asset_tag())
+        .prop_map(|(value, blinding, tag)| RevealedValue::with_blinding(value, blinding, tag))
+}
+
+pub fn revealed_data() -> impl Strategy<Value = RevealedData> + Clone {
+    vec(any::<u8>(), 0..256)
+        .prop_map(|data| RevealedData::from(SmallVec::try_from(data).expect("less than 256 items")))
+}
+
+pub fn media_type() -> impl Strategy<Value = MediaType> + Clone {
+    prop_oneof![Just(MediaType::Any)]
+}
+
+pub fn revealed_attach() -> impl Strategy<Value = RevealedAttach> + Clone {
+    (bytes32(), media_type(), any::<u64>(), any::<u64>()).prop_map(
+        |(id, media_type, salt, size)| RevealedAttach {
+            id: AttachId::from(id),
+            media_type,
+            salt,
+            size,
+        },
+    )
+}
+
+/// Optional payment tag of an assignment, which may be concealed.
+pub fn payment_tag() -> impl Strategy<Value = Option<AssignPaymentTag>> + Clone {
+    let revealed = (bytes32(), any::<u64>()).prop_map(|(tag, salt)| RevealedPaymentTag {
+        tag: PaymentTag::from(tag),
+        salt,
+    });
+    prop_oneof![
+        Just(None),
+        revealed
+            .clone()
+            .prop_map(|tag| Some(AssignPaymentTag::Revealed(tag))),
+        revealed.prop_map(|tag| Some(AssignPaymentTag::Concealed(tag.conceal()))),
+    ]
+}
+
+/// Assignment of the provided state to the provided seal, which may be
+/// revealed, fully concealed or have either its seal or state concealed.
+pub fn assign<State: ExposedState, Seal: ExposedSeal>(
+    state: impl Strategy<Value = State> + Clone,
+    seal: impl Strategy<Value = Seal> + Clone,
+) -> impl Strategy<Value = Assign<State, Seal>> + Clone
+where
+    State::Confidential: Debug,
+{
+    (seal_definition(seal), state, payment_tag(), 0u8..4).prop_map(
+        |(seal, state, payment_tag, kind)| match kind {
+            0 => Assign::Revealed {
+                seal,
+                state,
+                payment_tag,
+            },
+            1 => Assign::ConfidentialSeal {
+                seal: seal.conceal(),
+                state,
+                payment_tag,
+            },
+            2 => Assign::ConfidentialState {
+                seal,
+                state: state.conceal(),
+                payment_tag,
+            },
+            _ => Assign::Confidential {
+                seal: seal.conceal(),
+                state: state.conceal(),
+                payment_tag,
+            },
+        },
+    )
+}
+
+fn small_vec<T: Debug>(
+    item: impl Strategy<Value = T> + Clone,
+) -> impl Strategy<Value = SmallVec<T>> + Clone {
+    vec(item, 1..=MAX_ITEMS).prop_map(|items| Confined::try_from(items).expect("small number"))
+}
+
+pub fn typed_assigns<Seal: ExposedSeal>(
+    seal: impl Strategy<Value = Seal> + Clone,
+) -> impl Strategy<Value = TypedAssigns<Seal>> + Clone {
+    prop_oneof![
+        small_vec(assign(Just(VoidState::default()), seal.clone()))
+            .prop_map(TypedAssigns::Declarative),
+        small_vec(assign(revealed_value(), seal.clone())).prop_map(TypedAssigns::Fungible),
+        small_vec(assign(revealed_data(), seal.clone())).prop_map(TypedAssigns::Structured),
+        small_vec(assign(revealed_attach(), seal)).prop_map(TypedAssigns::Attachment),
+    ]
+}
+
+pub fn assignments<Seal: ExposedSeal>(
+    seal: impl Strategy<Value = Seal> + Clone,
+) -> impl Strategy<Value = Assignments<Seal>> + Clone {
+    btree_map(any::<u16>().prop_map(AssignmentType::with), typed_assigns(seal), 0..=MAX_ITEMS)
+        .prop_map(|map| Assignments::from(TinyOrdMap::try_from(map).expect("small number")))
+}
+
+pub fn opout() -> impl Strategy<Value = Opout> + Clone {
+    (opid(), any::<u16>(), any::<u16>())
+        .prop_map(|(op, ty, no)| Opout::new(op, AssignmentType::with(ty), no))
+}
+
+pub fn inputs() -> impl Strategy<Value = Inputs> + Clone {
+    btree_set(opout().prop_map(Input::with), 1..=MAX_ITEMS)
+        .prop_map(|set| Inputs::from(TinyOrdSet::try_from(set).expect("small number")))
+}
+
+pub fn global_state() -> impl Strategy<Value = GlobalState> + Clone {
+    btree_map(
+        any::<u16>().prop_map(GlobalStateType::with),
+        vec(revealed_data(), 1..=MAX_ITEMS)
+            .prop_map(|data| GlobalValues::from(Confined::try_from(data).expect("small number"))),
+        0..=MAX_ITEMS,
+    )
+    .prop_map(|map| GlobalState::from(TinyOrdMap::try_from(map).expect("small number")))
+}
+
+pub fn valencies() -> impl Strategy<Value = Valencies> + Clone {
+    btree_set(any::<u16>().prop_map(ValencyType::with), 0..=MAX_ITEMS)
+        .prop_map(|set| Valencies::from(TinyOrdSet::try_from(set).expect("small number")))
+}
+
+pub fn transition() -> impl Strategy<Value = Transition> + Clone {
+    (
+        contract_id(),
+        schema_id(),
+        any::<u16>(),
+        vec(any::<u8>(), 0..256),
+        global_state(),
+        inputs(),
+        assignments(graph_seal()),
+        valencies(),
+    )
+        .prop_map(
+            |(
+                contract_id,
+                schema_id,
+                transition_type,
+                metadata,
+                globals,
+                inputs,
+                assignments,
+                valencies,
+            )| {
+                Transition {
+                    ffv: Ffv::default(),
+                    contract_id,
+                    schema_id,
+                    transition_type: TransitionType::with(transition_type),
+                    metadata: Confined::try_from(metadata).expect("less than 256 items"),
+                    globals,
+                    inputs,
+                    assignments,
+                    valencies,
+                    proof: None,
+                    signature: None,
+                }
+            },
+        )
+}
+
+/// Transition bundle where each of the transitions closes seals in a distinct
+/// witness transaction input.
+pub fn transition_bundle() -> impl Strategy<Value = TransitionBundle> + Clone {
+    vec(transition(), 1..=MAX_ITEMS).prop_map(|transitions| {
+        TransitionBundle::try_from_transitions(
+            transitions
+                .into_iter()
+                .enumerate()
+                .map(|(no, transition)| (no as u16, transition)),
+        )
+        .expect("transitions use distinct inputs")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use commit_verify::CommitmentId;
+    use strict_encoding::{StrictDeserialize, StrictSerialize};
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn transition_roundtrip(transition in transition()) {
+            let data = transition.to_strict_serialized::<{ u32::MAX as usize }>().unwrap();
+            let decoded = Transition::from_strict_serialized::<{ u32::MAX as usize }>(data).unwrap();
+            prop_assert_eq!(decoded.commitment_id(), transition.commitment_id());
+        }
+
+        #[test]
+        fn bundle_concealment(bundle in transition_bundle()) {
+            prop_assert_eq!(bundle.conceal().bundle_id(), bundle.bundle_id());
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Strict decoding of untrusted in-memory data.
+//!
+//! Generic strict decoder allocates memory for collections basing on the
+//! length prefix before reading the collection items. Thus a few bytes of
+//! malformed data may claim a collection of billions of items, making decoder
+//! to run out of memory. [`UntrustedReader`] rejects lengths which exceed the
+//! size of the remaining data (each collection item takes at least one byte)
+//! before any allocation happens.
+
+use std::io;
+
+use amplify::num::u24;
+use strict_encoding::{
+    DecodeError, DeserializeError, FieldName, ReadStruct, ReadTuple, ReadUnion, StrictDecode,
+    StrictEnum, StrictStruct, StrictSum, StrictTuple, StrictUnion, TypedRead, VariantName,
+};
+
+use crate::validation::{CheckLimits, ConsensusLimits};
+
+/// Strict decoder reading from a byte slice, which never allocates more
+/// memory than the size of the data.
+#[derive(Clone, Debug)]
+pub struct UntrustedReader<'data> {
+    data: &'data [u8],
+    pos: usize,
+}
+
+impl<'data> UntrustedReader<'data> {
+    pub fn new(data: &'data [u8]) -> Self { UntrustedReader { data, pos: 0 } }
+
+    /// Number of bytes which were not read yet.
+    pub fn remaining(&self) -> usize { self.data.len() - self.pos }
+
+    fn take(&mut self, len: usize) -> io::Result<&'data [u8]> {
+        if len > self.remaining() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+}
+
+/// Deserializes strict-encoded data, requiring all data to be consumed.
+pub fn deserialize_untrusted<T: StrictDecode>(data: &[u8]) -> Result<T, DeserializeError> {
+    let mut reader = UntrustedReader::new(data);
+    let val = T::strict_decode(&mut reader)?;
+    if reader.remaining() > 0 {
+        return Err(DeserializeError::DataNotEntirelyConsumed);
+    }
+    Ok(val)
+}
+
+/// Deserializes strict-encoded data like [`deserialize_untrusted`], checking
+/// the decoded data against consensus limits. The limits are not enforced
+/// while decoding, thus the data is fully decoded before being rejected.
+pub fn deserialize_limited<T: StrictDecode + CheckLimits>(
+    data: &[u8],
+    limits: &ConsensusLimits,
+) -> Result<T, DeserializeError> {
+    let val = deserialize_untrusted::<T>(data)?;
+    val.check_limits(limits)
+        .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+    Ok(val)
+}
+
+impl<'data> TypedRead for UntrustedReader<'data> {
+    type TupleReader<'parent>
+        = UntrustedTupleReader<'parent, 'data>
+    where Self: 'parent;
+    type StructReader<'parent>
+        = UntrustedStructReader<'parent, 'data>
+    where Self: 'parent;
+    type UnionReader = Self;
+
+    fn read_union<T: StrictUnion>(
+        &mut self,
+        inner: impl FnOnce(VariantName, &mut Self::UnionReader) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError> {
+        let tag = u8::strict_decode(self)?;
+        let variant_name = T::variant_name_by_tag(tag).ok_or_else(|| {
+            DecodeError::UnionTagNotKnown(
+                T::strict_name()
+                    .unwrap_or_else(|| tn!("__unnamed"))
+                    .to_string(),
+                tag,
+            )
+        })?;
+        inner(variant_name, self)
+    }
+
+    fn read_enum<T: StrictEnum>(&mut self) -> Result<T, DecodeError>
+    where u8: From<T> {
+        let tag = u8::strict_decode(self)?;
+        T::try_from(tag).map_err(|_| {
+            DecodeError::EnumTagNotKnown(
+                T::strict_name()
+                    .unwrap_or_else(|| tn!("__unnamed"))
+                    .to_string(),
+                tag,
+            )
+        })
+    }
+
+    fn read_tuple<'parent, 'me, T: StrictTuple>(
+        &'me mut self,
+        inner: impl FnOnce(&mut Self::TupleReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        inner(&mut UntrustedTupleReader { parent: self })
+    }
+
+    fn read_struct<'parent, 'me, T: StrictStruct>(
+        &'me mut self,
+        inner: impl FnOnce(&mut Self::StructReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        inner(&mut UntrustedStructReader { parent: self })
+    }
+
+    unsafe fn _read_raw<const MAX_LEN: usize>(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        self.take(len).map(<[u8]>::to_vec)
+    }
+
+    unsafe fn _read_raw_array<const LEN: usize>(&mut self) -> io::Result<[u8; LEN]> {
+        let mut buf = [0u8; LEN];
+        buf.copy_from_slice(self.take(LEN)?);
+        Ok(buf)
+    }
+
+    unsafe fn _read_raw_len<const MAX_LEN: usize>(&mut self) -> Result<usize, DecodeError> {
+        let len = match MAX_LEN {
+            tiny if tiny <= u8::MAX as usize => u8::strict_decode(self)? as usize,
+            small if small <= u16::MAX as usize => u16::strict_decode(self)? as usize,
+            medium if medium <= u24::MAX.into_usize() => u24::strict_decode(self)?.into_usize(),
+            large if large <= u32::MAX as usize => u32::strict_decode(self)? as usize,
+            _ => u64::strict_decode(self)? as usize,
+        };
+        if len > self.remaining() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(len)
+    }
+}
+
+impl<'data> ReadUnion for UntrustedReader<'data> {
+    type TupleReader<'parent>
+        = UntrustedTupleReader<'parent, 'data>
+    where Self: 'parent;
+    type StructReader<'parent>
+        = UntrustedStructReader<'parent, 'data>
+    where Self: 'parent;
+
+    fn read_tuple<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        inner: impl FnOnce(&mut Self::TupleReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        inner(&mut UntrustedTupleReader { parent: self })
+    }
+
+    fn read_struct<'parent, 'me, T: StrictSum>(
+        &'me mut self,
+        inner: impl FnOnce(&mut Self::StructReader<'parent>) -> Result<T, DecodeError>,
+    ) -> Result<T, DecodeError>
+    where
+        Self: 'parent,
+        'me: 'parent,
+    {
+        inner(&mut UntrustedStructReader { parent: self })
+    }
+}
+
+#[derive(Debug)]
+pub struct UntrustedTupleReader<'parent, 'data> {
+    parent: &'parent mut UntrustedReader<'data>,
+}
+
+impl ReadTuple for UntrustedTupleReader<'_, '_> {
+    fn read_field<T: StrictDecode>(&mut self) -> Result<T, DecodeError> {
+        T::strict_decode(self.parent)
+    }
+}
+
+#[derive(Debug)]
+pub struct UntrustedStructReader<'parent, 'data> {
+    parent: &'parent mut UntrustedReader<'data>,
+}
+
+impl ReadStruct for UntrustedStructReader<'_, '_> {
+    fn read_field<T: StrictDecode>(&mut self, _field: FieldName) -> Result<T, DecodeError> {
+        T::strict_decode(self.parent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{LargeVec, U32};
+    use strict_encoding::{StrictDeserialize, StrictDumb, StrictSerialize};
+
+    use super::*;
+    use crate::Consignment;
+
+    #[test]
+    fn roundtrip() {
+        let consignment = Consignment::strict_dumb();
+        let data = consignment.to_strict_serialized::<U32>().unwrap();
+        assert_eq!(deserialize_untrusted::<Consignment>(&data).unwrap(), consignment);
+        assert_eq!(Consignment::from_strict_serialized::<U32>(data.clone()).unwrap(), consignment);
+
+        let mut data = data.into_inner();
+        data.push(0);
+        assert!(matches!(
+            deserialize_untrusted::<Consignment>(&data),
+            Err(DeserializeError::DataNotEntirelyConsumed)
+        ));
+    }
+
+    #[test]
+    fn excessive_length() {
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+        assert!(matches!(
+            deserialize_untrusted::<LargeVec<Consignment>>(&data),
+            Err(DeserializeError::Decode(DecodeError::Io(_)))
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioning of consensus rules.
+//!
+//! Each contract declares the consensus version it was issued under with the
+//! fast-forward version of its genesis. A newer version may also get activated
+//! for all contracts on some layer 1 starting from a given block height; in
+//! this case operations with witness transactions mined at or above that
+//! height are validated using the newer rules.
+
+use std::collections::BTreeMap;
+
+use bp::dbc;
+use bp::seals::txout::CloseMethod;
+
+use super::ConsensusLimits;
+use crate::{Ffv, Layer1};
+
+/// Version of RGB consensus rules.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[repr(u16)]
+#[non_exhaustive]
+pub enum ConsensusVersion {
+    /// Initial version of RGB v0.11 consensus.
+    #[default]
+    #[display("v1")]
+    V1 = 1,
+
+    /// RGB v0.11 consensus with the checks of the strings in the contract
+    /// state.
+    #[display("v2")]
+    V2 = 2,
+}
+
+impl ConsensusVersion {
+    /// The most recent consensus version known to this library.
+    pub const LATEST: ConsensusVersion = ConsensusVersion::V2;
+
+    /// Detects consensus version declared by a contract genesis fast-forward
+    /// version. Returns `None` if the version is not known to this library.
+    pub fn with_ffv(ffv: Ffv) -> Option<Self> {
+        match ffv.to_u16() {
+            0 => Some(ConsensusVersion::V1),
+            _ => None,
+        }
+    }
+
+    /// Returns set of consensus rules defined by the version.
+    pub fn rules(self) -> ConsensusRules {
+        match self {
+            ConsensusVersion::V1 => ConsensusRules {
+                close_methods: &[CloseMethod::OpretFirst, CloseMethod::TapretFirst],
+                check_strings: false,
+            },
+            ConsensusVersion::V2 => ConsensusRules {
+                close_methods: &[CloseMethod::OpretFirst, CloseMethod::TapretFirst],
+                check_strings: true,
+            },
+        }
+    }
+}
+
+/// Entry of the seal close method registry.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CloseMethodEntry {
+    /// Code under which the method is committed in seal definitions.
+    pub code: u8,
+    pub method: CloseMethod,
+    /// Consensus version which introduced the method.
+    pub since: ConsensusVersion,
+}
+
+/// Registry of seal close methods known to this library. Codes not present in
+/// the registry are reserved for the future close methods and are not valid
+/// under any of the known consensus versions.
+pub const CLOSE_METHODS: &[CloseMethodEntry] = &[
+    CloseMethodEntry {
+        code: CloseMethod::OpretFirst as u8,
+        method: CloseMethod::OpretFirst,
+        since: ConsensusVersion::V1,
+    },
+    CloseMethodEntry {
+        code: CloseMethod::TapretFirst as u8,
+        method: CloseMethod::TapretFirst,
+        since: ConsensusVersion::V1,
+    },
+];
+
+/// Errors resolving seal close methods.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum CloseMethodError {
+    /// seal close method code {0:#04x} is reserved for the future use.
+    Reserved(u8),
+
+    /// seal close method {0} is not allowed by the consensus rules.
+    NotAllowed(CloseMethod),
+
+    /// deterministic bitcoin commitment proof uses a close method unknown to
+    /// this library.
+    UnknownProof,
+}
+
+impl CloseMethodEntry {
+    /// Resolves close method by its code in the registry.
+    pub fn with_code(code: u8) -> Result<Self, CloseMethodError> {
+        CLOSE_METHODS
+            .iter()
+            .find(|entry| entry.code == code)
+            .copied()
+            .ok_or(CloseMethodError::Reserved(code))
+    }
+
+    /// Resolves close method used by a deterministic bitcoin commitment
+    /// proof, which is used to dispatch the commitment verification.
+    pub fn with_dbc_proof(proof: &dbc::Proof) -> Result<Self, CloseMethodError> {
+        let method = match proof {
+            dbc::Proof::OpretFirst => CloseMethod::OpretFirst,
+            dbc::Proof::TapretFirst(_) => CloseMethod::TapretFirst,
+            #[allow(unreachable_patterns)]
+            _ => return Err(CloseMethodError::UnknownProof),
+        };
+        Self::with_code(method as u8)
+    }
+}
+
+/// Rules which vary between consensus versions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ConsensusRules {
+    /// Seal close methods which may be used by witness transactions.
+    pub close_methods: &'static [CloseMethod],
+    /// Whether strings in the contract state are checked for normalization,
+    /// forbidden characters and length.
+    pub check_strings: bool,
+}
+
+impl ConsensusRules {
+    pub fn allows_close_method(&self, method: CloseMethod) -> bool {
+        self.close_methods.contains(&method)
+    }
+
+    /// Resolves close method by its code, checking that the method is allowed
+    /// by the rules.
+    pub fn close_method(&self, code: u8) -> Result<CloseMethod, CloseMethodError> {
+        let entry = CloseMethodEntry::with_code(code)?;
+        if !self.allows_close_method(entry.method) {
+            return Err(CloseMethodError::NotAllowed(entry.method));
+        }
+        Ok(entry.method)
+    }
+}
+
+/// Schedule of consensus version activations by layer 1 block height.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConsensusParams {
+    activations: BTreeMap<Layer1, BTreeMap<u32, ConsensusVersion>>,
+    limits: ConsensusLimits,
+}
+
+impl ConsensusParams {
+    pub fn new() -> Self { Self::default() }
+
+    /// Adds activation of a consensus version on a given layer 1 starting
+    /// from a block height.
+    pub fn with_activation(
+        mut self,
+        layer1: Layer1,
+        height: u32,
+        version: ConsensusVersion,
+    ) -> Self {
+        self.activations
+            .entry(layer1)
+            .or_default()
+            .insert(height, version);
+        self
+    }
+
+    /// Sets limits on the size of consensus data enforced by the validation.
+    pub fn with_limits(mut self, limits: ConsensusLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn limits(&self) -> &ConsensusLimits { &self.limits }
+
+    /// Determines consensus version which must be used for validating an
+    /// operation of a contract issued under `contract` version, if the
+    /// operation witness was mined at a given height. Witnesses with unknown
+    /// height are validated using the contract version.
+    pub fn version_at(
+        &self,
+        contract: ConsensusVersion,
+        layer1: Layer1,
+        height: Option<u32>,
+    ) -> ConsensusVersion {
+        let Some(height) = height else {
+            return contract;
+        };
+        self.activations
+            .get(&layer1)
+            .and_then(|schedule| schedule.range(..=height).next_back())
+            .map(|(_, version)| contract.max(*version))
+            .unwrap_or(contract)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn activation() {
+        let params = ConsensusParams::new().with_activation(
+            Layer1::Bitcoin,
+            800_000,
+            ConsensusVersion::LATEST,
+        );
+        let contract = ConsensusVersion::with_ffv(Ffv::default()).unwrap();
+        assert_eq!(contract, ConsensusVersion::V1);
+        assert_eq!(params.version_at(contract, Layer1::Bitcoin, None), contract);
+        assert_eq!(params.version_at(contract, Layer1::Bitcoin, Some(799_999)), contract);
+        assert_eq!(
+            params.version_at(contract, Layer1::Bitcoin, Some(800_000)),
+            ConsensusVersion::LATEST
+        );
+        assert_eq!(params.version_at(contract, Layer1::Liquid, Some(900_000)), contract);
+        assert!(contract
+            .rules()
+            .allows_close_method(CloseMethod::TapretFirst));
+        assert!(!contract.rules().check_strings);
+        assert!(ConsensusVersion::V2.rules().check_strings);
+    }
+
+    #[test]
+    fn close_methods() {
+        let rules = ConsensusVersion::V1.rules();
+        assert_eq!(rules.close_method(0x00), Ok(CloseMethod::OpretFirst));
+        assert_eq!(rules.close_method(0x01), Ok(CloseMethod::TapretFirst));
+        assert_eq!(rules.close_method(0x02), Err(CloseMethodError::Reserved(0x02)));
+        assert_eq!(
+            ConsensusRules {
+                close_methods: &[],
+                check_strings: false
+            }
+            .close_method(0x01),
+            Err(CloseMethodError::NotAllowed(CloseMethod::TapretFirst))
+        );
+        for entry in CLOSE_METHODS {
+            assert_eq!(CloseMethodEntry::with_code(entry.code), Ok(*entry));
+            assert!(entry.since.rules().allows_close_method(entry.method));
+        }
+        assert_eq!(
+            CloseMethodEntry::with_dbc_proof(&dbc::Proof::OpretFirst).map(|entry| entry.method),
+            Ok(CloseMethod::OpretFirst)
+        );
+    }
+}
@@ -27,8 +27,8 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
-    AnchoredBundle, AssetTag, AssignmentType, BundleId, Extension, Genesis, OpId, OpRef,
-    SecretSeal, SubSchema, Transition, TransitionBundle,
+    AnchoredBundle, AssetTag, AssignmentType, BundleId, EmbeddedWitness, Extension, Genesis,
+    HistoryCheckpoint, OpId, OpRef, SecretSeal, SubSchema, Transition, TransitionBundle,
 };
 
 /// Trait defining common data access API for all storage-related RGB structures
@@ -52,11 +52,15 @@ pub trait ConsignmentApi {
 
     /// Retrieves reference to a operation (genesis, state transition or state
     /// extension) matching the provided id, or `None` otherwise
-    fn operation(&self, opid: OpId) -> Option<OpRef>;
+    fn operation(&self, opid: OpId) -> Option<OpRef<'_>>;
 
     /// Contract genesis.
     fn genesis(&self) -> &Genesis;
 
+    /// Checkpoint replacing the validated prefix of the contract history, if
+    /// any.
+    fn checkpoint(&self) -> Option<&HistoryCheckpoint>;
+
     /// Returns reference to a state transition, if known, matching the provided
     /// id. If id is unknown, or corresponds to other type of the operation
     /// (genesis or state extensions) a error is returned.
@@ -102,4 +106,9 @@ pub trait ConsignmentApi {
     fn has_operation(&self, opid: OpId) -> bool;
 
     fn known_transitions_by_bundle_id(&self, bundle_id: BundleId) -> Option<Vec<&Transition>>;
+
+    /// Witness transactions embedded into the container. They are not trusted
+    /// by the validator and may be used only by importing them into a
+    /// resolver verifying them, like [`super::SpvResolver`].
+    fn witnesses(&self) -> &[EmbeddedWitness] { &[] }
 }
@@ -0,0 +1,257 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits on the size of consensus data.
+//!
+//! Consensus data types are confined in size, however the confinements are
+//! generous enough to allow a malicious party to create operations requiring
+//! a lot of resources to decode and validate. [`ConsensusLimits`] allows
+//! verifiers to set lower limits; a limit can't be set above the crate-level
+//! hard maximum defined by [`ConsensusLimits::HARD`].
+//!
+//! The limits are checked against already decoded data: by
+//! [`crate::untrusted::deserialize_limited`] and [`crate::ConsignmentReader`]
+//! after decoding each of the items, and by the validator for each of the
+//! operations. Memory used while decoding is not bounded by the limits;
+//! [`crate::untrusted::UntrustedReader`] bounds it by the size of the data.
+
+use amplify::confinement::{U16, U8};
+
+use crate::{
+    AnchoredBundle, Consignment, Extension, Genesis, Operation, Transition, TransitionBundle,
+};
+
+/// Errors checking data against consensus limits.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum LimitError {
+    /// number of assignments {0} exceeds the limit of {1}.
+    Assignments(u32, u32),
+
+    /// number of global state entries {0} exceeds the limit of {1}.
+    GlobalEntries(u32, u32),
+
+    /// metadata size {0} exceeds the limit of {1} bytes.
+    MetadataSize(u32, u32),
+
+    /// number of transitions {0} in a bundle exceeds the limit of {1}.
+    BundleSize(u32, u32),
+}
+
+/// Limits on the size of consensus data.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+pub struct ConsensusLimits {
+    max_assignments: u32,
+    max_global_entries: u32,
+    max_metadata_size: u32,
+    max_bundle_size: u32,
+    max_string_len: u32,
+}
+
+impl Default for ConsensusLimits {
+    fn default() -> Self { ConsensusLimits::HARD }
+}
+
+impl ConsensusLimits {
+    /// Crate-level hard maximums, which can't be exceeded by any
+    /// configuration.
+    ///
+    /// Limits on the metadata size, bundle size and string length match the
+    /// compile-time bounds of the confined collections holding the data, thus
+    /// the data exceeding them fail to decode. Limits on the total number of
+    /// assignments and global state entries are tighter than the bounds
+    /// following from the collection types, which are per state type.
+    pub const HARD: ConsensusLimits = ConsensusLimits {
+        max_assignments: U16 as u32,
+        max_global_entries: U16 as u32,
+        // `SmallBlob`
+        max_metadata_size: U16 as u32,
+        // `TinyOrdMap<OpId, BundleItem>`
+        max_bundle_size: U8 as u32,
+        // `RevealedData` is `SmallVec<u8>`
+        max_string_len: U16 as u32,
+    };
+
+    /// Maximal number of assignments of all types in a single operation.
+    pub fn max_assignments(&self) -> u32 { self.max_assignments }
+
+    /// Maximal number of global state entries of all types in a single
+    /// operation.
+    pub fn max_global_entries(&self) -> u32 { self.max_global_entries }
+
+    /// Maximal size of operation metadata, in bytes.
+    pub fn max_metadata_size(&self) -> u32 { self.max_metadata_size }
+
+    /// Maximal number of transitions in a bundle.
+    pub fn max_bundle_size(&self) -> u32 { self.max_bundle_size }
+
+    /// Maximal length of a string in the contract state, in characters.
+    pub fn max_string_len(&self) -> u32 { self.max_string_len }
+
+    /// Sets maximal number of assignments per operation, capped by the hard
+    /// maximum.
+    pub fn with_max_assignments(mut self, max: u32) -> Self {
+        self.max_assignments = max.min(Self::HARD.max_assignments);
+        self
+    }
+
+    /// Sets maximal number of global state entries per operation, capped by
+    /// the hard maximum.
+    pub fn with_max_global_entries(mut self, max: u32) -> Self {
+        self.max_global_entries = max.min(Self::HARD.max_global_entries);
+        self
+    }
+
+    /// Sets maximal size of operation metadata, capped by the hard maximum.
+    pub fn with_max_metadata_size(mut self, max: u32) -> Self {
+        self.max_metadata_size = max.min(Self::HARD.max_metadata_size);
+        self
+    }
+
+    /// Sets maximal number of transitions in a bundle, capped by the hard
+    /// maximum.
+    pub fn with_max_bundle_size(mut self, max: u32) -> Self {
+        self.max_bundle_size = max.min(Self::HARD.max_bundle_size);
+        self
+    }
+
+    /// Sets maximal length of a string in the contract state, capped by the
+    /// hard maximum.
+    pub fn with_max_string_len(mut self, max: u32) -> Self {
+        self.max_string_len = max.min(Self::HARD.max_string_len);
+        self
+    }
+
+    /// Checks operation against the limits.
+    pub fn check_operation(&self, op: &impl Operation) -> Result<(), LimitError> {
+        let assignments = op.assignments().count() as u32;
+        if assignments > self.max_assignments {
+            return Err(LimitError::Assignments(assignments, self.max_assignments));
+        }
+        let globals = op
+            .globals()
+            .values()
+            .map(|values| values.len() as u32)
+            .sum::<u32>();
+        if globals > self.max_global_entries {
+            return Err(LimitError::GlobalEntries(globals, self.max_global_entries));
+        }
+        let metadata = op.metadata().len() as u32;
+        if metadata > self.max_metadata_size {
+            return Err(LimitError::MetadataSize(metadata, self.max_metadata_size));
+        }
+        Ok(())
+    }
+
+    /// Checks bundle and all its known transitions against the limits.
+    pub fn check_bundle(&self, bundle: &TransitionBundle) -> Result<(), LimitError> {
+        let size = bundle.len() as u32;
+        if size > self.max_bundle_size {
+            return Err(LimitError::BundleSize(size, self.max_bundle_size));
+        }
+        bundle
+            .known_transitions()
+            .try_for_each(|transition| self.check_operation(transition))
+    }
+}
+
+/// Data which can be checked against [`ConsensusLimits`].
+pub trait CheckLimits {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError>;
+}
+
+impl CheckLimits for Genesis {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        limits.check_operation(self)
+    }
+}
+
+impl CheckLimits for Transition {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        limits.check_operation(self)
+    }
+}
+
+impl CheckLimits for Extension {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        limits.check_operation(self)
+    }
+}
+
+impl CheckLimits for TransitionBundle {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        limits.check_bundle(self)
+    }
+}
+
+impl CheckLimits for AnchoredBundle {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        limits.check_bundle(&self.bundle)
+    }
+}
+
+impl CheckLimits for Consignment {
+    fn check_limits(&self, limits: &ConsensusLimits) -> Result<(), LimitError> {
+        self.genesis.check_limits(limits)?;
+        self.bundles
+            .iter()
+            .try_for_each(|anchored| anchored.check_limits(limits))?;
+        self.extensions
+            .iter()
+            .try_for_each(|extension| extension.check_limits(limits))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::{Confined, SmallBlob};
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    #[test]
+    fn hard_caps() {
+        let limits = ConsensusLimits::default()
+            .with_max_metadata_size(4)
+            .with_max_bundle_size(1000);
+        assert_eq!(limits.max_bundle_size(), ConsensusLimits::HARD.max_bundle_size());
+
+        let mut transition = Transition::strict_dumb();
+        assert_eq!(transition.check_limits(&limits), Ok(()));
+        transition.metadata = Confined::try_from(vec![0u8; 5]).unwrap();
+        assert_eq!(transition.check_limits(&limits), Err(LimitError::MetadataSize(5, 4)));
+
+        let hard = ConsensusLimits::HARD;
+        let metadata = vec![0u8; hard.max_metadata_size() as usize + 1];
+        assert!(SmallBlob::try_from(metadata).is_err());
+    }
+}
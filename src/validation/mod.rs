@@ -20,16 +20,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+mod trace;
 mod script;
+mod consensus;
+mod limits;
 mod schema;
 mod model;
 mod state;
 mod validator;
 mod consignment;
 mod status;
+mod spv;
+mod strings;
+mod zk;
+mod policy;
+mod progress;
+mod cancel;
 
+pub use cancel::CancelToken;
+pub use consensus::{
+    CloseMethodEntry, CloseMethodError, ConsensusParams, ConsensusRules, ConsensusVersion,
+    CLOSE_METHODS,
+};
 pub use consignment::ConsignmentApi;
+pub use limits::{CheckLimits, ConsensusLimits, LimitError};
 pub(crate) use model::OpInfo;
+pub use policy::ValidationPolicy;
+pub use progress::{ProgressReporter, ValidationPhase};
 pub use script::VirtualMachine;
+pub use spv::SpvResolver;
 pub use status::{Failure, Info, Status, Validity, Warning};
-pub use validator::{ResolveTx, TxResolverError, Validator};
+pub use strings::{check_state_strings, check_string, check_strings, StringError, FORBIDDEN_CHARS};
+pub use trace::TRACE_TARGET;
+pub(crate) use validator::op_schema_failure;
+pub use validator::{ResolveTx, TxResolverError, ValidationHooks, Validator};
+pub use zk::ZkVerifier;
@@ -22,16 +22,17 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use amplify::confinement::{Confined, SmallBlob};
+use amplify::confinement::{Confined, SmallBlob, TinyOrdMap};
 use amplify::Wrapper;
 use strict_types::SemId;
 
 use crate::schema::{AssignmentsSchema, GlobalSchema, ValencySchema};
-use crate::validation::{ConsignmentApi, VirtualMachine};
+use crate::validation::{check_state_strings, ConsensusLimits, ConsignmentApi, VirtualMachine};
 use crate::{
-    validation, AssetTag, AssignmentType, Assignments, AssignmentsRef, ContractId, ExposedSeal,
-    GlobalState, GlobalStateSchema, GlobalValues, GraphSeal, Inputs, OpFullType, OpId, OpRef,
-    Operation, Opout, Redeemed, Schema, SchemaRoot, TransitionType, TypedAssigns, Valencies,
+    validation, AssetTag, Assign, AssignmentType, Assignments, AssignmentsRef, Capabilities,
+    ContractId, ExposedSeal, GlobalState, GlobalStateSchema, GlobalValues, GraphSeal, Inputs,
+    OpFullType, OpId, OpRef, Operation, Opout, PedersenContext, Redeemed, Schema, SchemaRoot,
+    TransitionType, TypedAssigns, Valencies,
 };
 
 impl<Root: SchemaRoot> Schema<Root> {
@@ -40,6 +41,8 @@ impl<Root: SchemaRoot> Schema<Root> {
         consignment: &C,
         op: OpRef,
         vm: &dyn VirtualMachine,
+        pedersen: &PedersenContext,
+        string_limits: Option<&ConsensusLimits>,
     ) -> validation::Status {
         let id = op.id();
 
@@ -135,31 +138,29 @@ impl<Root: SchemaRoot> Schema<Root> {
 
         let mut status = validation::Status::new();
 
-        // Validate type system
-        status += self.validate_type_system();
-        status += self.validate_metadata(id, *metadata_schema, op.metadata());
-        status += self.validate_global_state(id, op.globals(), global_schema);
-        let prev_state = if let OpRef::Transition(transition) = op {
-            let prev_state = extract_prev_state(consignment, id, &transition.inputs, &mut status);
-            status += self.validate_prev_state(id, &prev_state, owned_schema);
-            prev_state
-        } else {
-            Assignments::default()
-        };
-        let redeemed = if let OpRef::Extension(extension) = op {
-            let redeemed =
-                extract_redeemed_valencies(consignment, &extension.redeemed, &mut status);
-            status += self.validate_redeemed(id, &redeemed, redeem_schema);
-            redeemed
-        } else {
-            Valencies::default()
-        };
+        status += self.validate_metadata(id, *metadata_schema, op.metadata(), string_limits);
+        status += self.validate_global_state(id, op.globals(), global_schema, string_limits);
+        // Genesis and extensions have no inputs, while genesis and transitions
+        // do not redeem valencies; for them the schemata are empty as well
+        let prev_state = extract_prev_state(consignment, id, &op.inputs(), &mut status);
+        status += self.validate_prev_state(id, &prev_state, owned_schema);
+        if let (Some(transition_type), AssignmentsRef::Graph(assignments)) =
+            (op.transition_type(), op.assignments())
+        {
+            let required = self
+                .transitions
+                .get(&transition_type)
+                .map(|schema| &schema.capabilities);
+            status += self.validate_capabilities(id, required, &prev_state, assignments);
+        }
+        let redeemed = extract_redeemed_valencies(consignment, &op.redeemed(), &mut status);
+        status += self.validate_redeemed(id, &redeemed, redeem_schema);
         status += match op.assignments() {
             AssignmentsRef::Genesis(assignments) => {
-                self.validate_owned_state(id, assignments, assign_schema)
+                self.validate_owned_state(id, assignments, assign_schema, string_limits)
             }
             AssignmentsRef::Graph(assignments) => {
-                self.validate_owned_state(id, assignments, assign_schema)
+                self.validate_owned_state(id, assignments, assign_schema, string_limits)
             }
         };
 
@@ -173,6 +174,7 @@ impl<Root: SchemaRoot> Schema<Root> {
             &prev_state,
             &redeemed,
             consignment.asset_tags(),
+            pedersen,
         );
 
         // We need to run scripts as the very last step, since before that
@@ -182,33 +184,28 @@ impl<Root: SchemaRoot> Schema<Root> {
         status
     }
 
-    fn validate_type_system(&self) -> validation::Status {
-        validation::Status::new()
-        // TODO: Validate type system
-        // Currently, validation is performed at the level of state values, i.e.
-        // if the system is inconsistent (references semantic ids not
-        // present in it) this will be detected during state validation.
-        // We should not prohibit schema with inconsistent type system, instead
-        // we need just to issue warnings here if some of semantic ids are
-        // missed - and add information messages if some excessive semantic ids
-        // are present.
-    }
-
     fn validate_metadata(
         &self,
         opid: OpId,
         sem_id: SemId,
         metadata: &SmallBlob,
+        string_limits: Option<&ConsensusLimits>,
     ) -> validation::Status {
         let mut status = validation::Status::new();
 
-        if self
+        match self
             .type_system
             .strict_deserialize_type(sem_id, metadata.as_ref())
-            .is_err()
         {
-            status.add_failure(validation::Failure::SchemaInvalidMetadata(opid, sem_id));
-        };
+            Err(_) => {
+                status.add_failure(validation::Failure::SchemaInvalidMetadata(opid, sem_id));
+            }
+            Ok(val) => {
+                if let Err(err) = check_state_strings(val.as_val(), string_limits) {
+                    status.add_failure(validation::Failure::SchemaInvalidString(opid, sem_id, err));
+                }
+            }
+        }
 
         status
     }
@@ -218,6 +215,7 @@ impl<Root: SchemaRoot> Schema<Root> {
         opid: OpId,
         global: &GlobalState,
         global_schema: &GlobalSchema,
+        string_limits: Option<&ConsensusLimits>,
     ) -> validation::Status {
         let mut status = validation::Status::new();
 
@@ -239,7 +237,9 @@ impl<Root: SchemaRoot> Schema<Root> {
                 .map(Confined::unbox)
                 .unwrap_or_default();
 
-            let GlobalStateSchema { sem_id, max_items } = self.global_types.get(type_id).expect(
+            let GlobalStateSchema {
+                sem_id, max_items, ..
+            } = self.global_types.get(type_id).expect(
                 "if the field were absent, the schema would not be able to pass the internal \
                  validation and we would not reach this point",
             );
@@ -259,15 +259,20 @@ impl<Root: SchemaRoot> Schema<Root> {
 
             // Validating data types
             for data in set {
-                if self
-                    .type_system
-                    .strict_deserialize_type(*sem_id, data.as_ref())
-                    .is_err()
-                {
-                    status.add_failure(validation::Failure::SchemaInvalidGlobalValue(
-                        opid, *type_id, *sem_id,
-                    ));
-                };
+                match data.to_typed(&self.type_system, *sem_id) {
+                    Err(_) => {
+                        status.add_failure(validation::Failure::SchemaInvalidGlobalValue(
+                            opid, *type_id, *sem_id,
+                        ));
+                    }
+                    Ok(val) => {
+                        if let Err(err) = check_state_strings(val.as_val(), string_limits) {
+                            status.add_failure(validation::Failure::SchemaInvalidString(
+                                opid, *sem_id, err,
+                            ));
+                        }
+                    }
+                }
             }
         }
 
@@ -312,6 +317,62 @@ impl<Root: SchemaRoot> Schema<Root> {
         status
     }
 
+    fn validate_capabilities(
+        &self,
+        id: OpId,
+        required: Option<&TinyOrdMap<AssignmentType, Capabilities>>,
+        prev_state: &Assignments<GraphSeal>,
+        assignments: &Assignments<GraphSeal>,
+    ) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let capabilities_of = |assigns: Option<&TypedAssigns<GraphSeal>>| -> Vec<Capabilities> {
+            assigns
+                .map(TypedAssigns::as_declarative)
+                .unwrap_or_default()
+                .iter()
+                .map(|assign| assign.to_confidential_state().capabilities())
+                .collect()
+        };
+
+        for (ty, required) in required.into_iter().flatten() {
+            for spent in capabilities_of(prev_state.get(ty)) {
+                if !spent.contains(*required) {
+                    status.add_failure(validation::Failure::RightsCapabilityMissing(
+                        id, *ty, *required,
+                    ));
+                }
+            }
+        }
+
+        for (ty, assigns) in assignments.iter() {
+            if !assigns.is_declarative() {
+                continue;
+            }
+            let spent = capabilities_of(prev_state.get(ty));
+            let granted = capabilities_of(Some(assigns));
+            let held = spent
+                .iter()
+                .fold(Capabilities::NONE, |acc, caps| acc | *caps);
+            let escalated = granted
+                .iter()
+                .fold(Capabilities::NONE, |acc, caps| acc | *caps) &
+                !held;
+            if !escalated.is_empty() {
+                status.add_failure(validation::Failure::RightsCapabilityEscalation(
+                    id, *ty, escalated,
+                ));
+            }
+            let spent_count = spent.iter().filter(|caps| !caps.is_empty()).count();
+            let granted_count = granted.iter().filter(|caps| !caps.is_empty()).count();
+            if granted_count > spent_count && !held.contains(Capabilities::DELEGATE) {
+                status.add_failure(validation::Failure::RightsDelegationForbidden(id, *ty));
+            }
+        }
+
+        status
+    }
+
     fn validate_redeemed(
         &self,
         id: OpId,
@@ -337,6 +398,7 @@ impl<Root: SchemaRoot> Schema<Root> {
         id: OpId,
         owned_state: &Assignments<Seal>,
         assign_schema: &AssignmentsSchema,
+        string_limits: Option<&ConsensusLimits>,
     ) -> validation::Status {
         let mut status = validation::Status::new();
 
@@ -372,20 +434,74 @@ impl<Root: SchemaRoot> Schema<Root> {
             match owned_state.get(state_id) {
                 None => {}
                 Some(TypedAssigns::Declarative(set)) => set.iter().for_each(|data| {
-                    status += assignment.validate(&self.type_system, &id, *state_id, data)
+                    status +=
+                        assignment.validate(&self.type_system, &id, *state_id, data, string_limits)
                 }),
                 Some(TypedAssigns::Fungible(set)) => set.iter().for_each(|data| {
-                    status += assignment.validate(&self.type_system, &id, *state_id, data)
+                    status +=
+                        assignment.validate(&self.type_system, &id, *state_id, data, string_limits)
                 }),
                 Some(TypedAssigns::Structured(set)) => set.iter().for_each(|data| {
-                    status += assignment.validate(&self.type_system, &id, *state_id, data)
+                    status +=
+                        assignment.validate(&self.type_system, &id, *state_id, data, string_limits)
                 }),
                 Some(TypedAssigns::Attachment(set)) => set.iter().for_each(|data| {
-                    status += assignment.validate(&self.type_system, &id, *state_id, data)
+                    status +=
+                        assignment.validate(&self.type_system, &id, *state_id, data, string_limits)
                 }),
             };
         }
 
+        status += self.validate_attachments(id, owned_state);
+
+        status
+    }
+
+    fn validate_attachments<Seal: ExposedSeal>(
+        &self,
+        id: OpId,
+        owned_state: &Assignments<Seal>,
+    ) -> validation::Status {
+        let mut status = validation::Status::new();
+        let Some(attach_limits) = self.attach_limits else {
+            return status;
+        };
+
+        let mut count = 0u32;
+        for (state_type, assignments) in owned_state.iter() {
+            let TypedAssigns::Attachment(set) = assignments else {
+                continue;
+            };
+            count += set.len() as u32;
+            // Sizes of the concealed attachments can't be checked
+            for attach in set.iter().filter_map(Assign::as_revealed_state) {
+                match attach.size {
+                    0 => {
+                        status.add_failure(validation::Failure::AttachmentSizeUndeclared(
+                            id,
+                            *state_type,
+                        ));
+                    }
+                    size if size > attach_limits.max_size => {
+                        status.add_failure(validation::Failure::AttachmentSizeExceeded {
+                            opid: id,
+                            state_type: *state_type,
+                            size,
+                            max: attach_limits.max_size,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if count > attach_limits.max_count as u32 {
+            status.add_failure(validation::Failure::AttachmentCountExceeded {
+                opid: id,
+                count,
+                max: attach_limits.max_count,
+            });
+        }
+
         status
     }
 
@@ -439,9 +555,11 @@ pub struct OpInfo<'op> {
     pub redeemed: &'op Valencies,
     pub valencies: &'op Valencies,
     pub global: &'op GlobalState,
+    pub pedersen: &'op PedersenContext,
 }
 
 impl<'op> OpInfo<'op> {
+    #[allow(clippy::too_many_arguments)]
     pub fn with(
         contract_id: ContractId,
         id: OpId,
@@ -450,6 +568,7 @@ impl<'op> OpInfo<'op> {
         prev_state: &'op Assignments<GraphSeal>,
         redeemed: &'op Valencies,
         asset_tags: &'op BTreeMap<AssignmentType, AssetTag>,
+        pedersen: &'op PedersenContext,
     ) -> Self {
         OpInfo {
             id,
@@ -463,6 +582,7 @@ impl<'op> OpInfo<'op> {
             redeemed,
             valencies: op.valencies(),
             global: op.globals(),
+            pedersen,
         }
     }
 }
@@ -0,0 +1,56 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bp::Txid;
+
+use super::Status;
+use crate::{Genesis, Layer1, OpRef, SubSchema};
+
+/// Policy of an embedder adding non-consensus checks to the contract
+/// validation, like rejection of blacklisted schemata, limits on the history
+/// depth or requirements for the number of witness confirmations.
+///
+/// The checks are run alongside consensus validation, and the failures,
+/// warnings and information they report are added to the validation status
+/// (usually as their `Custom` variants). All checks do nothing by default.
+pub trait ValidationPolicy {
+    /// Checks the contract schema and genesis before the contract history is
+    /// validated.
+    fn check_contract(&self, _schema: &SubSchema, _genesis: &Genesis) -> Status { Status::new() }
+
+    /// Checks an operation of the contract history. The `depth` is the
+    /// distance, in operations, from the terminal transition of the validated
+    /// branch, which is zero for the terminal itself.
+    fn check_operation(&self, _operation: OpRef, _depth: u32) -> Status { Status::new() }
+
+    /// Checks the witness transaction of a state transition. The `height` is
+    /// the height of the block mining the witness, if it is known.
+    fn check_witness(
+        &self,
+        _operation: OpRef,
+        _layer1: Layer1,
+        _txid: Txid,
+        _height: Option<u32>,
+    ) -> Status {
+        Status::new()
+    }
+}
@@ -0,0 +1,64 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Phase of the consignment validation.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum ValidationPhase {
+    /// Validation of the schema.
+    Schema,
+    /// Validation of the contract genesis.
+    Genesis,
+    /// Verification of the reserves backing the issuance.
+    Reserves,
+    /// Verification of the block to which genesis commits preceding all
+    /// witness transactions.
+    ChainProof,
+    /// Verification of the history checkpoint proof.
+    Checkpoint,
+    /// Validation of the contract history operations, their anchors and
+    /// witnesses.
+    History,
+    /// Validation is complete.
+    Finished,
+}
+
+/// Receiver of the validation progress reports, allowing to display progress
+/// of long-running validations of large consignments.
+///
+/// The reporter is called at the start of each validation phase and after
+/// each validated operation of the contract history with the number of
+/// validated operations and the total number of operations in the
+/// consignment. Operations not belonging to the validated history are never
+/// validated, thus the number of validated operations may remain below the
+/// total one when the validation finishes.
+pub trait ProgressReporter {
+    fn report(&self, validated_ops: usize, total_ops: usize, phase: ValidationPhase);
+}
+
+impl<F> ProgressReporter for F
+where F: Fn(usize, usize, ValidationPhase)
+{
+    fn report(&self, validated_ops: usize, total_ops: usize, phase: ValidationPhase) {
+        self(validated_ops, total_ops, phase)
+    }
+}
@@ -20,8 +20,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeSet;
+
+use strict_types::{SemId, Ty};
+
 use crate::validation::Status;
-use crate::{validation, OpFullType, OpSchema, Schema, StateSchema, SubSchema, TransitionType};
+use crate::{
+    validation, GlobalRetention, OpFullType, OpSchema, Schema, StateSchema, SubSchema,
+    TransitionType,
+};
 
 impl SubSchema {
     pub fn verify(&self) -> validation::Status {
@@ -37,6 +44,50 @@ impl SubSchema {
         status
     }
 
+    fn verify_actions(&self) -> validation::Status {
+        let mut status = validation::Status::new();
+
+        let blank_transition = self.blank_transition();
+        for (name, action) in &self.actions {
+            let ty = action.transition_type;
+            let schema = match self.transitions.get(&ty) {
+                Some(schema) => schema,
+                None if ty == TransitionType::BLANK => &blank_transition,
+                None => {
+                    status.add_failure(validation::Failure::SchemaActionTransitionUnknown(
+                        name.clone(),
+                        ty,
+                    ));
+                    continue;
+                }
+            };
+            for ty in action.globals.keys() {
+                if !schema.globals.contains_key(ty) {
+                    status.add_failure(validation::Failure::SchemaActionGlobalTypeUnknown(
+                        name.clone(),
+                        *ty,
+                    ));
+                }
+            }
+            let inputs = action
+                .inputs
+                .keys()
+                .filter(|ty| !schema.inputs.contains_key(*ty));
+            let assignments = action
+                .assignments
+                .keys()
+                .filter(|ty| !schema.assignments.contains_key(*ty));
+            for ty in inputs.chain(assignments) {
+                status.add_failure(validation::Failure::SchemaActionAssignmentTypeUnknown(
+                    name.clone(),
+                    *ty,
+                ));
+            }
+        }
+
+        status
+    }
+
     fn verify_consistency(&self) -> validation::Status {
         let mut status = validation::Status::new();
 
@@ -47,6 +98,30 @@ impl SubSchema {
         for (type_id, schema) in &self.extensions {
             status += self.verify_operation(OpFullType::StateExtension(*type_id), schema);
         }
+        status += self.verify_actions();
+        for (type_id, schema) in &self.transitions {
+            match schema.identity {
+                Some(ty) if !self.genesis.globals.contains_key(&ty) => {
+                    status.add_failure(validation::Failure::SchemaIdentityGlobalUnknown(
+                        *type_id, ty,
+                    ));
+                }
+                _ => {}
+            }
+            for ty in schema.capabilities.keys() {
+                if !schema.inputs.contains_key(ty) ||
+                    self.owned_types.get(ty) != Some(&StateSchema::Declarative)
+                {
+                    status
+                        .add_failure(validation::Failure::SchemaCapabilityNotRights(*type_id, *ty));
+                }
+            }
+        }
+        for ty in self.expiry.keys() {
+            if !self.owned_types.contains_key(ty) {
+                status.add_failure(validation::Failure::SchemaExpiryTypeUnknown(*ty));
+            }
+        }
         // Check that the schema doesn't contain reserved type ids
         if self.transitions.contains_key(&TransitionType::BLANK) {
             status.add_failure(validation::Failure::SchemaBlankTransitionRedefined);
@@ -58,6 +133,11 @@ impl SubSchema {
                     *type_id,
                     schema.sem_id,
                 ));
+            } else {
+                status += self.verify_sem_id(schema.sem_id);
+            }
+            if schema.retention == GlobalRetention::KeepLast(0) {
+                status.add_failure(validation::Failure::SchemaGlobalRetentionZero(*type_id));
             }
         }
 
@@ -67,6 +147,8 @@ impl SubSchema {
                     status.add_failure(validation::Failure::SchemaOwnedSemIdUnknown(
                         *type_id, *sem_id,
                     ));
+                } else {
+                    status += self.verify_sem_id(*sem_id);
                 }
             }
         }
@@ -74,6 +156,27 @@ impl SubSchema {
         status
     }
 
+    /// Checks that all types referenced by a type, directly or indirectly, are
+    /// present in the type system, such that the state of the type can be
+    /// deserialized during the validation.
+    fn verify_sem_id(&self, sem_id: SemId) -> Status {
+        let mut status = validation::Status::new();
+        let mut visited = BTreeSet::new();
+        let mut queue = vec![sem_id];
+        while let Some(id) = queue.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            match self.type_system.get(id) {
+                Some(ty) => queue.extend(type_refs(ty)),
+                None => {
+                    status.add_failure(validation::Failure::SchemaSemIdIncomplete(sem_id, id));
+                }
+            }
+        }
+        status
+    }
+
     fn verify_operation(&self, op_type: OpFullType, schema: &impl OpSchema) -> Status {
         let mut status = validation::Status::new();
 
@@ -82,6 +185,8 @@ impl SubSchema {
                 op_type,
                 schema.metadata(),
             ));
+        } else {
+            status += self.verify_sem_id(schema.metadata());
         }
         if matches!(schema.inputs(), Some(inputs) if inputs.is_empty()) {
             status.add_failure(validation::Failure::SchemaOpEmptyInputs(op_type));
@@ -261,3 +366,17 @@ where T: OpSchema
         status
     }
 }
+
+/// Lists types directly referenced by a type.
+// We do not use `Ty::type_refs`, which doesn't iterate over the items of
+// collection types.
+fn type_refs(ty: &Ty<SemId>) -> Vec<SemId> {
+    match ty {
+        Ty::Primitive(_) | Ty::UnicodeChar | Ty::Enum(_) => vec![],
+        Ty::Union(variants) => variants.into_iter().map(|(_, id)| *id).collect(),
+        Ty::Tuple(fields) => fields.into_iter().copied().collect(),
+        Ty::Struct(fields) => fields.into_iter().map(|field| field.ty).collect(),
+        Ty::Array(id, _) | Ty::List(id, _) | Ty::Set(id, _) => vec![*id],
+        Ty::Map(key, value, _) => vec![*key, *value],
+    }
+}
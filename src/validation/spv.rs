@@ -0,0 +1,264 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation with SPV proofs of witness transactions.
+//!
+//! Clients without access to a full node may validate consignments using a
+//! chain of block headers starting from a trusted checkpoint and merkle proofs
+//! of the witness transaction inclusion into the blocks from that chain.
+
+use std::collections::BTreeMap;
+
+use amplify::ByteArray;
+use bp::{BlockHash, BlockHeader, Tx, Txid};
+
+use super::{ResolveTx, TxResolverError};
+use crate::{check_pow, compact_target, header_hash, EmbeddedWitness, Layer1, SpvError, SpvProof};
+
+/// Transaction resolver backed by a verified chain of block headers and SPV
+/// proofs of witness transactions.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SpvResolver {
+    layer1: Layer1,
+    checkpoint_height: u32,
+    /// Block hashes starting from the checkpoint, indexed by the height above
+    /// the checkpoint.
+    chain: Vec<[u8; 32]>,
+    /// Witness transactions with the height, position within the block and
+    /// timestamp of the block mining them.
+    witnesses: BTreeMap<Txid, (Tx, u32, u32, i64)>,
+}
+
+impl SpvResolver {
+    /// Constructs resolver from a trusted checkpoint block and a chain of
+    /// block headers following it.
+    ///
+    /// Each of the headers must connect to the previous one and must satisfy
+    /// its proof of work target, which must not be easier than `max_bits`
+    /// (given in the compact form).
+    pub fn with(
+        layer1: Layer1,
+        checkpoint_height: u32,
+        checkpoint: BlockHash,
+        headers: impl IntoIterator<Item = BlockHeader>,
+        max_bits: u32,
+    ) -> Result<Self, SpvError> {
+        let max_target = compact_target(max_bits).ok_or(SpvError::InvalidTarget)?;
+        let mut chain = vec![checkpoint.to_byte_array()];
+        for header in headers {
+            if Some(&header.prev_block_hash.to_byte_array()) != chain.last() {
+                return Err(SpvError::Disconnected);
+            }
+            let target = compact_target(header.bits).ok_or(SpvError::InvalidTarget)?;
+            if target > max_target {
+                return Err(SpvError::InsufficientWork);
+            }
+            check_pow(&header)?;
+            chain.push(header_hash(&header));
+        }
+        Ok(SpvResolver {
+            layer1,
+            checkpoint_height,
+            chain,
+            witnesses: empty!(),
+        })
+    }
+
+    /// Height of the last block in the verified header chain.
+    pub fn tip_height(&self) -> u32 { self.checkpoint_height + self.chain.len() as u32 - 1 }
+
+    /// Adds witness transaction, verifying its inclusion into a block from the
+    /// header chain.
+    pub fn add_witness(&mut self, tx: Tx, proof: &SpvProof) -> Result<(), SpvError> {
+        let txid = tx.txid();
+        proof.verify(txid)?;
+        let block_hash = proof
+            .height
+            .checked_sub(self.checkpoint_height)
+            .and_then(|depth| self.chain.get(depth as usize));
+        if block_hash != Some(&proof.block_hash()) {
+            return Err(SpvError::UnknownBlock);
+        }
+        self.witnesses
+            .insert(txid, (tx, proof.height, proof.position, proof.header.time as i64));
+        Ok(())
+    }
+
+    /// Adds witness transactions embedded into a consignment for the layer 1
+    /// of the resolver, verifying their SPV proofs against the header chain.
+    /// Witnesses without SPV proofs are not mined and are skipped.
+    ///
+    /// Fails on the first witness which proof doesn't verify or which block is
+    /// not a part of the header chain.
+    pub fn add_embedded<'w>(
+        &mut self,
+        witnesses: impl IntoIterator<Item = &'w EmbeddedWitness>,
+    ) -> Result<(), SpvError> {
+        for witness in witnesses {
+            if witness.layer1 != self.layer1 {
+                continue;
+            }
+            if let Some(proof) = &witness.spv {
+                self.add_witness(witness.tx.clone(), proof)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ResolveTx for SpvResolver {
+    fn resolve_tx(&self, layer1: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+        if layer1 != self.layer1 {
+            return Err(TxResolverError::Unknown(txid));
+        }
+        self.witnesses
+            .get(&txid)
+            .map(|(tx, ..)| tx.clone())
+            .ok_or(TxResolverError::Unknown(txid))
+    }
+
+    fn resolve_height(&self, layer1: Layer1, txid: Txid) -> Option<u32> {
+        if layer1 != self.layer1 {
+            return None;
+        }
+        self.witnesses.get(&txid).map(|(_, height, ..)| *height)
+    }
+
+    fn resolve_block_height(&self, layer1: Layer1, block_hash: BlockHash) -> Option<u32> {
+        if layer1 != self.layer1 {
+            return None;
+        }
+        self.chain
+            .iter()
+            .position(|hash| *hash == block_hash.to_byte_array())
+            .map(|depth| self.checkpoint_height + depth as u32)
+    }
+
+    fn resolve_tx_pos(&self, layer1: Layer1, txid: Txid) -> Option<u32> {
+        if layer1 != self.layer1 {
+            return None;
+        }
+        self.witnesses.get(&txid).map(|(_, _, pos, _)| *pos)
+    }
+
+    fn resolve_timestamp(&self, layer1: Layer1, txid: Txid) -> Option<i64> {
+        if layer1 != self.layer1 {
+            return None;
+        }
+        self.witnesses.get(&txid).map(|(.., timestamp)| *timestamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::Bytes32;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+    use crate::{WitnessAnchor, WitnessId, WitnessOrd, WitnessPos};
+
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    fn mine(prev_block_hash: [u8; 32], merkle_root: [u8; 32]) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: 1,
+            prev_block_hash: BlockHash::from_byte_array(prev_block_hash),
+            merkle_root: Bytes32::from_byte_array(merkle_root),
+            time: 1231006505,
+            bits: REGTEST_BITS,
+            nonce: 0,
+        };
+        while check_pow(&header).is_err() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    #[test]
+    fn spv_resolver() {
+        let tx = Tx::strict_dumb();
+        let txid = tx.txid();
+        let checkpoint = [0xA5u8; 32];
+        let header1 = mine(checkpoint, [0u8; 32]);
+        let header2 = mine(header_hash(&header1), txid.to_byte_array());
+
+        let headers = [header1, header2];
+        let checkpoint = BlockHash::from_byte_array(checkpoint);
+        let mut resolver =
+            SpvResolver::with(Layer1::Bitcoin, 100, checkpoint, headers, REGTEST_BITS).unwrap();
+        assert_eq!(resolver.tip_height(), 102);
+
+        let mut proof = SpvProof {
+            height: 101,
+            header: header2,
+            position: 0,
+            merkle_path: empty!(),
+        };
+        assert_eq!(resolver.add_witness(tx.clone(), &proof), Err(SpvError::UnknownBlock));
+        assert_eq!(resolver.resolve_height(Layer1::Bitcoin, txid), None);
+
+        proof.height = 102;
+        resolver.add_witness(tx.clone(), &proof).unwrap();
+        assert_eq!(resolver.resolve_height(Layer1::Bitcoin, txid), Some(102));
+        assert_eq!(resolver.resolve_height(Layer1::Liquid, txid), None);
+        assert_eq!(resolver.resolve_tx_pos(Layer1::Bitcoin, txid), Some(0));
+        assert_eq!(resolver.resolve_timestamp(Layer1::Bitcoin, txid), Some(1231006505));
+        assert_eq!(resolver.resolve_witness_anchor(WitnessId::Bitcoin(txid)), WitnessAnchor {
+            witness_ord: WitnessOrd::OnChain(WitnessPos::new(102, 1231006505).unwrap()),
+            witness_id: WitnessId::Bitcoin(txid),
+        });
+        let unknown = WitnessId::Bitcoin(Txid::from([0x11; 32]));
+        assert_eq!(resolver.resolve_witness_anchor(unknown), WitnessAnchor::from_mempool(unknown));
+        let block_hash = BlockHash::from_byte_array(header_hash(&header1));
+        assert_eq!(resolver.resolve_block_height(Layer1::Bitcoin, block_hash), Some(101));
+        assert_eq!(resolver.resolve_block_height(Layer1::Liquid, block_hash), None);
+        assert_eq!(resolver.resolve_tx(Layer1::Bitcoin, txid).unwrap(), tx);
+
+        // Self-mined header which is not a part of the trusted chain
+        let mut embedded = EmbeddedWitness {
+            layer1: Layer1::Bitcoin,
+            tx: tx.clone(),
+            spv: Some(SpvProof {
+                height: 101,
+                header: mine([0x5Au8; 32], txid.to_byte_array()),
+                position: 0,
+                merkle_path: empty!(),
+            }),
+        };
+        let mut trusted =
+            SpvResolver::with(Layer1::Bitcoin, 100, checkpoint, headers, REGTEST_BITS).unwrap();
+        assert_eq!(trusted.add_embedded([&embedded]), Err(SpvError::UnknownBlock));
+        assert!(trusted.resolve_tx(Layer1::Bitcoin, txid).is_err());
+        embedded.spv = Some(proof);
+        trusted.add_embedded([&embedded]).unwrap();
+        assert_eq!(trusted.resolve_height(Layer1::Bitcoin, txid), Some(102));
+
+        assert_eq!(
+            SpvResolver::with(Layer1::Bitcoin, 100, checkpoint, headers, 0x1d00ffff),
+            Err(SpvError::InsufficientWork)
+        );
+        assert_eq!(
+            SpvResolver::with(Layer1::Bitcoin, 100, checkpoint, [header1, header1], REGTEST_BITS),
+            Err(SpvError::Disconnected)
+        );
+    }
+}
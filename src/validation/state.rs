@@ -23,6 +23,7 @@
 use strict_types::TypeSystem;
 
 use crate::schema::AssignmentType;
+use crate::validation::{check_state_strings, ConsensusLimits};
 use crate::{
     validation, Assign, ConfidentialState, ExposedSeal, ExposedState, OpId, StateCommitment,
     StateData, StateSchema,
@@ -35,6 +36,7 @@ impl StateSchema {
         opid: &OpId,
         state_type: AssignmentType,
         data: &Assign<State, Seal>,
+        string_limits: Option<&ConsensusLimits>,
     ) -> validation::Status {
         let mut status = validation::Status::new();
         match data {
@@ -85,6 +87,7 @@ impl StateSchema {
                             found: attach.media_type,
                         });
                     }
+                    (StateSchema::Attachment(_), StateData::Attachment(_)) => {}
                     (StateSchema::Fungible(schema), StateData::Fungible(v))
                         if v.value.fungible_type() != *schema =>
                     {
@@ -97,14 +100,20 @@ impl StateSchema {
                     }
                     (StateSchema::Fungible(_), StateData::Fungible(_)) => {}
                     (StateSchema::Structured(sem_id), StateData::Structured(data)) => {
-                        if type_system
-                            .strict_deserialize_type(*sem_id, data.as_ref())
-                            .is_err()
-                        {
-                            status.add_failure(validation::Failure::SchemaInvalidOwnedValue(
-                                *opid, state_type, *sem_id,
-                            ));
-                        };
+                        match data.to_typed(type_system, *sem_id) {
+                            Err(_) => {
+                                status.add_failure(validation::Failure::SchemaInvalidOwnedValue(
+                                    *opid, state_type, *sem_id,
+                                ));
+                            }
+                            Ok(val) => {
+                                if let Err(err) = check_state_strings(val.as_val(), string_limits) {
+                                    status.add_failure(validation::Failure::SchemaInvalidString(
+                                        *opid, *sem_id, err,
+                                    ));
+                                }
+                            }
+                        }
                     }
                     // all other options are mismatches
                     (state_schema, found) => {
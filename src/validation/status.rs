@@ -26,16 +26,25 @@ use std::fmt::{self, Display, Formatter};
 
 use bp::dbc::anchor;
 use bp::seals::txout::blind::ChainBlindSeal;
-use bp::{seals, Txid};
+use bp::seals::txout::CloseMethod;
+use bp::{seals, BlockHash, Outpoint, Txid};
+use strict_encoding::FieldName;
 use strict_types::SemId;
 
+use super::{ConsensusVersion, LimitError, StringError};
 use crate::contract::Opout;
 use crate::schema::{self, SchemaId};
 use crate::{
-    BundleId, Layer1, OccurrencesMismatch, OpFullType, OpId, SealDefinition, SecretSeal, StateType,
+    BundleId, Capabilities, ChainNet, CheckpointId, ContractId, Ffv, Layer1, OccurrencesMismatch,
+    OpFullType, OpId, SealDefinition, SecretSeal, StateType, VerifyingKeyId,
 };
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
 #[repr(u8)]
 pub enum Validity {
     #[display("is valid")]
@@ -189,9 +198,16 @@ impl Status {
 )]
 #[display(doc_comments)]
 pub enum Failure {
-    /// the contract network doesn't match (validator runs in testnet={0}
-    /// configuration).
-    NetworkMismatch(bool),
+    /// the contract is issued on {contract} network, while the validator runs
+    /// on {validator}.
+    NetworkMismatch {
+        contract: ChainNet,
+        validator: ChainNet,
+    },
+
+    /// contract genesis declares consensus version {0} which is not known to
+    /// this software.
+    ConsensusVersionUnknown(Ffv),
 
     /// schema {actual} provided for the consignment validation doesn't match
     /// schema {expected} used by the contract. This means that the consignment
@@ -202,18 +218,37 @@ pub enum Failure {
         /// Actual schema id provided by the consignment.
         actual: SchemaId,
     },
+    /// operation {opid} commits to schema {actual} instead of the contract
+    /// schema {expected}.
+    OperationSchemaMismatch {
+        opid: OpId,
+        expected: SchemaId,
+        actual: SchemaId,
+    },
+    /// operation {opid} belongs to contract {actual} and can't be a part of
+    /// the history of contract {expected}.
+    OperationContractMismatch {
+        opid: OpId,
+        expected: ContractId,
+        actual: ContractId,
+    },
     /// schema uses reserved type for the blank state transition.
     SchemaBlankTransitionRedefined,
 
     /// schema global state #{0} uses semantic data type absent in type library
     /// ({1}).
     SchemaGlobalSemIdUnknown(schema::GlobalStateType, SemId),
+    /// schema global state #{0} has retention policy keeping no items.
+    SchemaGlobalRetentionZero(schema::GlobalStateType),
     /// schema owned state #{0} uses semantic data type absent in type library
     /// ({1}).
     SchemaOwnedSemIdUnknown(schema::AssignmentType, SemId),
     /// schema metadata in {0} uses semantic data type absent in type library
     /// ({1}).
     SchemaOpMetaSemIdUnknown(OpFullType, SemId),
+    /// semantic data type {0} used by the schema references type {1} absent
+    /// in type library.
+    SchemaSemIdIncomplete(SemId, SemId),
 
     /// schema for {0} has zero inputs.
     SchemaOpEmptyInputs(OpFullType),
@@ -223,6 +258,22 @@ pub enum Failure {
     SchemaOpAssignmentTypeUnknown(OpFullType, schema::AssignmentType),
     /// schema for {0} references undeclared valency type {1}.
     SchemaOpValencyTypeUnknown(OpFullType, schema::ValencyType),
+    /// schema action {0} references undeclared state transition type {1}.
+    SchemaActionTransitionUnknown(FieldName, schema::TransitionType),
+    /// schema action {0} references global state type {1} not used by its
+    /// state transition type.
+    SchemaActionGlobalTypeUnknown(FieldName, schema::GlobalStateType),
+    /// schema action {0} references owned state type {1} not used by its
+    /// state transition type.
+    SchemaActionAssignmentTypeUnknown(FieldName, schema::AssignmentType),
+    /// state transition type {0} requires identity key from global state type
+    /// {1}, which is not defined by genesis.
+    SchemaIdentityGlobalUnknown(schema::TransitionType, schema::GlobalStateType),
+    /// state transition type {0} requires capabilities from assignment type
+    /// {1}, which is not a declarative input of the transition.
+    SchemaCapabilityNotRights(schema::TransitionType, schema::AssignmentType),
+    /// schema defines expiry height for undeclared owned state type {0}.
+    SchemaExpiryTypeUnknown(schema::AssignmentType),
 
     /// invalid schema - no match with root schema requirements for global state
     /// type #{0}.
@@ -288,6 +339,9 @@ pub enum Failure {
     /// invalid owned state value in operation {0}, state type #{1} which does
     /// not match semantic type id {2}.
     SchemaInvalidOwnedValue(OpId, schema::AssignmentType, SemId),
+    /// string data in operation {0} of semantic type id {1} violates
+    /// consensus rules: {2}
+    SchemaInvalidString(OpId, SemId, StringError),
     /// invalid number of input entries of type {1} in operation {0} - {2}  
     SchemaInputOccurrences(OpId, schema::AssignmentType, OccurrencesMismatch),
     /// invalid number of assignment entries of type {1} in operation {0} - {2}
@@ -315,6 +369,12 @@ pub enum Failure {
     },
     /// transition {0} references non-existing previous output {1}.
     NoPrevOut(OpId, Opout),
+    /// output {opout} is spent by both transition {first} and {second}.
+    DoubleSpend {
+        opout: Opout,
+        first: OpId,
+        second: OpId,
+    },
     /// seal defined in the history as a part of operation output {0} is
     /// confidential and can't be validated.
     ConfidentialSeal(Opout),
@@ -328,6 +388,23 @@ pub enum Failure {
     /// seal {1:?} is defined on {0} which is not in the set of layers allowed
     /// by the contract genesis.
     SealInvalidLayer1(Layer1, SealDefinition<ChainBlindSeal>),
+    /// transition {opid} closes seal using {method} method, which is not
+    /// allowed by consensus {version}.
+    SealCloseMethodUnsupported {
+        opid: OpId,
+        version: ConsensusVersion,
+        method: CloseMethod,
+    },
+    /// transition {opid} closes seal using {seal} method, while its witness
+    /// transaction commitment uses {anchor} method.
+    SealCloseMethodMismatch {
+        opid: OpId,
+        seal: CloseMethod,
+        anchor: CloseMethod,
+    },
+    /// transition {opid} is anchored with a deterministic bitcoin commitment
+    /// of unknown type.
+    AnchorCloseMethodUnknown { opid: OpId },
     /// transition {0} doesn't close seal with the witness transaction {1}.
     /// Details: {2}
     SealInvalid(OpId, Txid, seals::txout::VerifyError),
@@ -368,6 +445,20 @@ pub enum Failure {
         expected: schema::MediaType,
         found: schema::MediaType,
     },
+    /// operation {opid} assigns {count} attachments, while schema allows at
+    /// most {max}.
+    AttachmentCountExceeded { opid: OpId, count: u32, max: u16 },
+    /// attachment in {opid}/{state_type} declares size of {size} bytes, while
+    /// schema allows at most {max} bytes.
+    AttachmentSizeExceeded {
+        opid: OpId,
+        state_type: schema::AssignmentType,
+        size: u64,
+        max: u64,
+    },
+    /// attachment in {0}/{1} doesn't declare its size, while schema limits
+    /// the attachment size.
+    AttachmentSizeUndeclared(OpId, schema::AssignmentType),
     /// state in {opid}/{state_type} is of {found} type, while schema requires
     /// it to be {expected}.
     FungibleTypeMismatch {
@@ -381,6 +472,127 @@ pub enum Failure {
     /// operation {0} is invalid: {1}
     ScriptFailure(OpId, String),
 
+    // Zero-knowledge proof errors
+    /// transition {0} doesn't provide zero-knowledge proof for verifying key
+    /// {1} required by the schema.
+    ZkProofMissing(OpId, VerifyingKeyId),
+    /// transition {0} provides zero-knowledge proof not required by the
+    /// schema.
+    ZkProofUnexpected(OpId),
+    /// transition {opid} provides zero-knowledge proof for verifying key
+    /// {actual}, while schema requires {expected}.
+    ZkProofKeyMismatch {
+        opid: OpId,
+        expected: VerifyingKeyId,
+        actual: VerifyingKeyId,
+    },
+    /// zero-knowledge proof of transition {0} for verifying key {1} can't be
+    /// checked since no proof verifier is provided.
+    ZkVerifierAbsent(OpId, VerifyingKeyId),
+    /// invalid zero-knowledge proof in transition {0}: {1}
+    ZkProofInvalid(OpId, String),
+
+    // Identity signature errors
+    /// genesis doesn't provide a valid identity key in global state {1},
+    /// which is required to sign transition {0}.
+    IdentityKeyInvalid(OpId, schema::GlobalStateType),
+    /// transition {0} lacks identity signature required by the schema.
+    IdentitySigMissing(OpId),
+    /// transition {0} provides identity signature not required by the schema.
+    IdentitySigUnexpected(OpId),
+    /// transition {0} has invalid identity signature.
+    IdentitySigInvalid(OpId),
+
+    // Rights capability errors
+    /// transition {0} spends right of type {1} lacking capabilities {2}
+    /// required by the schema.
+    RightsCapabilityMissing(OpId, schema::AssignmentType, Capabilities),
+    /// transition {0} assigns rights of type {1} with capabilities {2} not
+    /// held by the spent rights.
+    RightsCapabilityEscalation(OpId, schema::AssignmentType, Capabilities),
+    /// transition {0} delegates capabilities of rights of type {1} to more
+    /// owners than spent, while the spent rights lack delegate capability.
+    RightsDelegationForbidden(OpId, schema::AssignmentType),
+
+    // Maturity errors
+    /// transition {opid} spends output {opout} which requires {maturity}
+    /// blocks to be mined on top of its witness, while only {depth} blocks
+    /// were mined.
+    OutputImmature {
+        opid: OpId,
+        opout: Opout,
+        maturity: u32,
+        depth: u32,
+    },
+    /// maturity of output {1} spent by transition {0} can't be checked since
+    /// the height of some of the witness transactions is unknown.
+    OutputMaturityUnknown(OpId, Opout),
+    /// transition {opid} spends output {opout} which expired at height
+    /// {expiry}, while its witness transaction is mined at height {height}.
+    OutputExpired {
+        opid: OpId,
+        opout: Opout,
+        expiry: u32,
+        height: u32,
+    },
+    /// expiry of output {1} spent by transition {0} can't be checked since
+    /// the height of the witness transaction is unknown.
+    OutputExpiryUnknown(OpId, Opout),
+
+    // Resource limits
+    /// operation {0} exceeds consensus limits: {1}
+    OperationLimitExceeded(OpId, LimitError),
+    /// bundle {0} exceeds consensus limits: {1}
+    BundleLimitExceeded(BundleId, LimitError),
+
+    // History checkpoint errors
+    /// history checkpoint {checkpoint} is made for contract {actual}, while
+    /// the consignment contract is {expected}.
+    CheckpointContractMismatch {
+        checkpoint: CheckpointId,
+        expected: ContractId,
+        actual: ContractId,
+    },
+    /// frontier operation {1} of history checkpoint {0} is absent from the
+    /// consignment.
+    CheckpointFrontierAbsent(CheckpointId, OpId),
+    /// history checkpoint {0} can't be checked since no proof verifier is
+    /// provided.
+    CheckpointVerifierAbsent(CheckpointId),
+    /// invalid proof of history checkpoint {0}: {1}
+    CheckpointProofInvalid(CheckpointId, String),
+
+    // Reserve errors
+    /// transaction of the reserve output {0} claimed by the genesis can't be
+    /// retrieved.
+    ReserveNoTx(Outpoint),
+    /// reserve output {0} claimed by the genesis doesn't exist.
+    ReserveOutputAbsent(Outpoint),
+    /// reserve output {0} claimed by the genesis is mined at height {1}, above
+    /// the height at which the reserves are claimed.
+    ReserveOutputTooRecent(Outpoint, u32),
+    /// reserves claimed by the genesis can't be checked since the height of
+    /// the transaction with output {0} is unknown.
+    ReserveHeightUnknown(Outpoint),
+    /// genesis claims {claimed} sats of reserves, while the reserve outputs
+    /// hold only {available} sats.
+    ReserveInsufficient { claimed: u64, available: u64 },
+
+    // Chain proof errors
+    /// block {0} to which the genesis commits as a proof of its creation time
+    /// is unknown.
+    ChainProofUnknownBlock(BlockHash),
+    /// witness transaction {txid} is mined at height {height}, while the
+    /// genesis commits to a later block at height {block_height}.
+    ChainProofWitnessPrecedes {
+        txid: Txid,
+        height: u32,
+        block_height: u32,
+    },
+
+    /// validation was cancelled before completion.
+    ValidationCancelled,
+
     /// Custom error by external services on top of RGB Core.
     #[display(inner)]
     Custom(String),
@@ -402,6 +614,12 @@ pub enum Warning {
     ExcessiveOperation(OpId),
     /// terminal witness transaction {0} is not yet mined.
     TerminalWitnessNotMined(Txid),
+    /// maturity of output {1} spent by terminal transition {0} is not checked
+    /// since its witness transaction is not yet mined.
+    OutputMaturityUnchecked(OpId, Opout),
+    /// expiry of output {1} spent by terminal transition {0} is not checked
+    /// since its witness transaction is not yet mined.
+    OutputExpiryUnchecked(OpId, Opout),
 
     /// Custom warning by external services on top of RGB Core.
     #[display(inner)]
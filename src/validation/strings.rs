@@ -0,0 +1,151 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks of the string data in the contract state.
+//!
+//! Strict types allow arbitrary unicode strings in the state, so two strings
+//! which are rendered identically may have different byte representation
+//! and thus produce different commitments. This allows spoofing of asset
+//! tickers and names. To prevent that, all strings in the state must be in
+//! the unicode normalization form C (NFC), must not exceed the length set by
+//! [`ConsensusLimits::max_string_len`] and must not contain control and
+//! bidirectional formatting characters.
+//!
+//! The checks are a part of the consensus rules starting from
+//! [`super::ConsensusVersion::V2`]; operations validated under the earlier
+//! versions are not checked.
+
+use strict_types::StrictVal;
+use unicode_normalization::is_nfc;
+
+use super::ConsensusLimits;
+
+/// Ranges of characters which are not allowed in strings.
+///
+/// Includes C0 and C1 control characters and unicode bidirectional
+/// formatting characters, which may be used to change visual representation
+/// of a string.
+pub const FORBIDDEN_CHARS: [(char, char); 5] = [
+    ('\u{0000}', '\u{001F}'),
+    ('\u{007F}', '\u{009F}'),
+    ('\u{200E}', '\u{200F}'),
+    ('\u{202A}', '\u{202E}'),
+    ('\u{2066}', '\u{2069}'),
+];
+
+/// Errors checking string data in the contract state.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate", rename_all = "camelCase")
+)]
+#[display(doc_comments)]
+pub enum StringError {
+    /// string length {len} exceeds the limit of {max} characters.
+    TooLong { len: u32, max: u32 },
+
+    /// string is not in unicode normalization form C.
+    NotNormalized,
+
+    /// string contains forbidden character U+{0:04X}.
+    ForbiddenChar(u32),
+}
+
+/// Checks a single string against the consensus rules and limits.
+pub fn check_string(s: &str, limits: &ConsensusLimits) -> Result<(), StringError> {
+    let len = s.chars().count() as u32;
+    let max = limits.max_string_len();
+    if len > max {
+        return Err(StringError::TooLong { len, max });
+    }
+    if let Some(c) = s.chars().find(|c| {
+        FORBIDDEN_CHARS
+            .iter()
+            .any(|(start, end)| (start..=end).contains(&c))
+    }) {
+        return Err(StringError::ForbiddenChar(c as u32));
+    }
+    if !is_nfc(s) {
+        return Err(StringError::NotNormalized);
+    }
+    Ok(())
+}
+
+/// Checks all strings contained in a strict value, including nested ones.
+pub fn check_strings(val: &StrictVal, limits: &ConsensusLimits) -> Result<(), StringError> {
+    match val {
+        StrictVal::String(s) => check_string(s, limits),
+        StrictVal::Unit | StrictVal::Number(_) | StrictVal::Bytes(_) | StrictVal::Enum(_) => Ok(()),
+        StrictVal::Union(_, inner) => check_strings(inner, limits),
+        StrictVal::Tuple(items) | StrictVal::List(items) | StrictVal::Set(items) => items
+            .iter()
+            .try_for_each(|item| check_strings(item, limits)),
+        StrictVal::Struct(fields) => fields
+            .values()
+            .try_for_each(|field| check_strings(field, limits)),
+        StrictVal::Map(entries) => entries.iter().try_for_each(|(key, value)| {
+            check_strings(key, limits)?;
+            check_strings(value, limits)
+        }),
+    }
+}
+
+/// Checks strings in a state value if the consensus rules of the operation
+/// require it, which is indicated by the presence of `limits`.
+pub fn check_state_strings(
+    val: &StrictVal,
+    limits: Option<&ConsensusLimits>,
+) -> Result<(), StringError> {
+    limits.map_or(Ok(()), |limits| check_strings(val, limits))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strings() {
+        let limits = ConsensusLimits::default().with_max_string_len(8);
+        assert_eq!(check_string("USDT", &limits), Ok(()));
+        assert_eq!(check_string("Caf\u{00E9}", &limits), Ok(()));
+        assert_eq!(check_string("Cafe\u{0301}", &limits), Err(StringError::NotNormalized));
+        assert_eq!(check_string("US\u{202E}DT", &limits), Err(StringError::ForbiddenChar(0x202E)));
+        assert_eq!(check_string("US\nDT", &limits), Err(StringError::ForbiddenChar(0x0A)));
+        assert_eq!(
+            check_string("Tether USD", &limits),
+            Err(StringError::TooLong { len: 10, max: 8 })
+        );
+
+        let val = StrictVal::Struct(
+            [
+                (fname!("ticker"), StrictVal::str("USDT")),
+                (fname!("name"), StrictVal::Tuple(vec![StrictVal::str("Cafe\u{0301}")])),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(check_strings(&val, &limits), Err(StringError::NotNormalized));
+        assert_eq!(check_state_strings(&val, Some(&limits)), Err(StringError::NotNormalized));
+        assert_eq!(check_state_strings(&val, None), Ok(()));
+    }
+}
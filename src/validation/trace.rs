@@ -0,0 +1,138 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumentation of the validation pipeline with log records, enabled by the
+//! `trace` feature. Validator reports entering and leaving the validation of
+//! each contract, history branch, operation and bundle (with the time spent
+//! on it) at the `trace` level, and a summary of the work done at the `debug`
+//! level, all under [`TRACE_TARGET`] log target.
+
+#[cfg(feature = "trace")]
+use std::cell::Cell;
+#[cfg(feature = "trace")]
+use std::fmt::{self, Display, Formatter};
+
+/// Log target used by the validator.
+pub const TRACE_TARGET: &str = "rgb::validation";
+
+/// Opens named validation span for an identifier, which lasts until the end
+/// of the current block.
+macro_rules! trace_span {
+    ($name:literal, $id:expr) => {
+        #[cfg(feature = "trace")]
+        let _span = $crate::validation::trace::Span::enter($name, $id);
+    };
+}
+
+/// Increments validation counter.
+macro_rules! trace_count {
+    ($counter:expr) => {
+        #[cfg(feature = "trace")]
+        $counter.set($counter.get() + 1);
+    };
+}
+
+/// Counters of the work done by the validator.
+#[cfg(feature = "trace")]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct ValidationStats {
+    /// Number of operations validated against the schema.
+    pub operations: Cell<u32>,
+    /// Number of bundles validated against their anchors and witnesses.
+    pub bundles: Cell<u32>,
+    /// Number of checked MPC and deterministic bitcoin commitments.
+    pub commitments: Cell<u32>,
+    /// Number of calls to the resolver of witness transactions.
+    pub resolver_calls: Cell<u32>,
+}
+
+#[cfg(feature = "trace")]
+impl Display for ValidationStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} operations, {} bundles, {} commitments checked, {} resolver calls",
+            self.operations.get(),
+            self.bundles.get(),
+            self.commitments.get(),
+            self.resolver_calls.get()
+        )
+    }
+}
+
+#[cfg(feature = "trace")]
+pub(crate) struct Span {
+    name: &'static str,
+    id: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "trace")]
+impl Span {
+    pub fn enter(name: &'static str, id: impl Display) -> Self {
+        let id = log::log_enabled!(target: TRACE_TARGET, log::Level::Trace).then(|| id.to_string());
+        if let Some(id) = &id {
+            log::trace!(target: TRACE_TARGET, "validating {name} {id}");
+        }
+        Span {
+            name,
+            id,
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(id) = &self.id else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        log::trace!(target: TRACE_TARGET, "done {} {id} in {:?}", self.name, self.start.elapsed());
+        #[cfg(target_arch = "wasm32")]
+        log::trace!(target: TRACE_TARGET, "done {} {id}", self.name);
+    }
+}
+
+#[cfg(all(test, feature = "trace"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let stats = ValidationStats::default();
+        trace_count!(stats.operations);
+        trace_count!(stats.operations);
+        trace_count!(stats.resolver_calls);
+        {
+            trace_span!("operation", "test");
+            trace_count!(stats.bundles);
+        }
+        assert_eq!(
+            stats.to_string(),
+            "2 operations, 1 bundles, 0 commitments checked, 1 resolver calls"
+        );
+    }
+}
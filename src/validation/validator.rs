@@ -23,17 +23,24 @@
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use bp::seals::txout::{TxPtr, Witness};
-use bp::{dbc, Tx, Txid};
+use bp::{dbc, BlockHash, Tx, Txid};
 use commit_verify::mpc;
 use single_use_seals::SealWitness;
 
 use super::status::{Failure, Warning};
-use super::{ConsignmentApi, Status, Validity, VirtualMachine};
+#[cfg(feature = "trace")]
+use super::trace::ValidationStats;
+use super::{
+    CancelToken, CloseMethodEntry, ConsensusLimits, ConsensusParams, ConsensusVersion,
+    ConsignmentApi, ProgressReporter, Status, ValidationPhase, ValidationPolicy, Validity,
+    VirtualMachine, ZkVerifier,
+};
 use crate::vm::AluRuntime;
 use crate::{
-    AltLayer1, Anchor, AnchoredBundle, BundleId, ContractId, GraphSeal, Layer1, OpId, OpRef,
-    Operation, Opout, Schema, SchemaId, SchemaRoot, Script, SealDefinition, SubSchema, Transition,
-    TransitionBundle, TypedAssigns,
+    AltLayer1, Anchor, AnchoredBundle, BundleId, ChainNet, ContractId, GraphSeal, Layer1, OnLayer1,
+    OpId, OpRef, Operation, Opout, PedersenContext, Schema, SchemaId, SchemaRoot, Script,
+    SealDefinition, SubSchema, Transition, TransitionBundle, TransitionType, TypedAssigns,
+    WitnessAnchor, WitnessId, WitnessOrd, WitnessPos,
 };
 
 #[derive(Clone, Debug, Display, Error, From)]
@@ -47,6 +54,52 @@ pub enum TxResolverError {
 
 pub trait ResolveTx {
     fn resolve_tx(&self, layer1: Layer1, txid: Txid) -> Result<Tx, TxResolverError>;
+
+    /// Returns height of the block mining the transaction, if known. The
+    /// height is used to detect consensus version activated for the witness.
+    fn resolve_height(&self, _layer1: Layer1, _txid: Txid) -> Option<u32> { None }
+
+    /// Returns position of the transaction within the block mining it, if
+    /// known. The position is used to order operations anchored in the same
+    /// block (see [`crate::WitnessPos::with_tx_pos`]).
+    fn resolve_tx_pos(&self, _layer1: Layer1, _txid: Txid) -> Option<u32> { None }
+
+    /// Returns timestamp of the block mining the transaction, if known.
+    fn resolve_timestamp(&self, _layer1: Layer1, _txid: Txid) -> Option<i64> { None }
+
+    /// Constructs anchor of the witness transaction ordering contract state
+    /// defined by the operations it commits to, using the height, timestamp
+    /// and position within the block reported by the resolver. Witnesses with
+    /// unknown height or timestamp are considered not mined.
+    fn resolve_witness_anchor(&self, witness_id: WitnessId) -> WitnessAnchor {
+        let (layer1, txid) = witness_id.into_parts();
+        let witness_ord = self
+            .resolve_height(layer1, txid)
+            .zip(self.resolve_timestamp(layer1, txid))
+            .and_then(|(height, timestamp)| WitnessPos::new(height, timestamp))
+            .map(|pos| pos.with_tx_pos(self.resolve_tx_pos(layer1, txid).unwrap_or_default()))
+            .map_or(WitnessOrd::OffChain, WitnessOrd::OnChain);
+        WitnessAnchor {
+            witness_ord,
+            witness_id,
+        }
+    }
+
+    /// Returns height of the block with a given hash, if the block is known
+    /// to be part of the blockchain. The height is used to verify the genesis
+    /// chain proof (see [`crate::ChainProof`]).
+    fn resolve_block_height(&self, _layer1: Layer1, _block_hash: BlockHash) -> Option<u32> { None }
+}
+
+/// Embedder-provided hooks run alongside the consensus validation.
+#[derive(Copy, Clone, Default)]
+pub struct ValidationHooks<'a> {
+    /// Policy performing additional non-consensus checks.
+    pub policy: Option<&'a dyn ValidationPolicy>,
+    /// Receiver of the validation progress reports.
+    pub progress: Option<&'a dyn ProgressReporter>,
+    /// Token for cancelling the validation.
+    pub cancel: Option<&'a CancelToken>,
 }
 
 pub struct Validator<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx> {
@@ -57,20 +110,40 @@ pub struct Validator<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx> {
     schema_id: SchemaId,
     genesis_id: OpId,
     contract_id: ContractId,
+    version: ConsensusVersion,
+    params: ConsensusParams,
     layers1: BTreeSet<Layer1>,
     anchor_index: BTreeMap<OpId, &'consignment Anchor>,
     end_transitions: Vec<(&'consignment Transition, BundleId)>,
     validation_index: BTreeSet<OpId>,
     anchor_validation_index: BTreeSet<OpId>,
+    frontier: BTreeSet<OpId>,
+    schema_rejected: BTreeSet<OpId>,
+    spent: BTreeMap<Opout, OpId>,
 
     vm: Box<dyn VirtualMachine + 'consignment>,
+    pedersen: PedersenContext,
     resolver: &'resolver R,
+    zk_verifier: Option<&'resolver dyn ZkVerifier>,
+    hooks: ValidationHooks<'resolver>,
+    cancelled: bool,
+    /// Total number of operations in the consignment, computed only when
+    /// progress is reported.
+    total_ops: usize,
+    #[cfg(feature = "trace")]
+    stats: ValidationStats,
 }
 
 impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
     Validator<'consignment, 'resolver, C, R>
 {
-    fn init(consignment: &'consignment C, resolver: &'resolver R) -> Self {
+    fn init(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        params: &ConsensusParams,
+        zk_verifier: Option<&'resolver dyn ZkVerifier>,
+        hooks: ValidationHooks<'resolver>,
+    ) -> Self {
         // We use validation status object to store all detected failures and
         // warnings
         let mut status = Status::default();
@@ -81,6 +154,12 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         let contract_id = genesis.contract_id();
         let schema_id = genesis.schema_id;
 
+        // Consensus version the contract was issued under
+        let version = ConsensusVersion::with_ffv(genesis.ffv).unwrap_or_else(|| {
+            status.add_failure(Failure::ConsensusVersionUnknown(genesis.ffv));
+            ConsensusVersion::LATEST
+        });
+
         // Create indexes
         let mut anchor_index = BTreeMap::<OpId, &Anchor>::new();
         for AnchoredBundle {
@@ -91,6 +170,9 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
             if !TransitionBundle::validate(bundle) {
                 status.add_failure(Failure::BundleInvalid(bundle.bundle_id()));
             }
+            if let Err(err) = params.limits().check_bundle(bundle) {
+                status.add_failure(Failure::BundleLimitExceeded(bundle.bundle_id(), err));
+            }
             for transition in bundle.values().filter_map(|item| item.transition.as_ref()) {
                 let opid = transition.id();
                 anchor_index.insert(opid, anchor);
@@ -129,14 +211,41 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         // Validation index is used to check that all transitions presented in the
         // consignment were validated. Also, we use it to avoid double schema
         // validations for transitions.
-        let validation_index = BTreeSet::<OpId>::new();
+        let mut validation_index = BTreeSet::<OpId>::new();
 
         // Index used to avoid repeated validations of the same anchor+transition pairs
-        let anchor_validation_index = BTreeSet::<OpId>::new();
+        let mut anchor_validation_index = BTreeSet::<OpId>::new();
+
+        // Operations from the history checkpoint frontier are attested by the
+        // checkpoint proof, thus we neither validate them nor their ancestors
+        let mut frontier = BTreeSet::<OpId>::new();
+        if let Some(checkpoint) = consignment.checkpoint() {
+            let checkpoint_id = checkpoint.checkpoint_id();
+            if checkpoint.contract_id != contract_id {
+                status.add_failure(Failure::CheckpointContractMismatch {
+                    checkpoint: checkpoint_id,
+                    expected: contract_id,
+                    actual: checkpoint.contract_id,
+                });
+            }
+            for opid in &checkpoint.frontier {
+                if !consignment.has_operation(*opid) {
+                    status.add_failure(Failure::CheckpointFrontierAbsent(checkpoint_id, *opid));
+                }
+            }
+            frontier.extend(checkpoint.frontier.iter().copied());
+            validation_index.extend(frontier.iter().copied());
+            anchor_validation_index.extend(frontier.iter().copied());
+        }
 
-        let mut layers1 = bset! { Layer1::Bitcoin };
+        let mut layers1 = bset! { genesis.chain_net.layer1() };
         layers1.extend(genesis.alt_layers1.iter().map(AltLayer1::layer1));
 
+        let total_ops = match hooks.progress {
+            Some(_) => consignment.op_ids_except(&empty!()).len(),
+            None => 0,
+        };
+
         let vm = match &consignment.schema().script {
             Script::AluVM(lib) => {
                 Box::new(AluRuntime::new(lib)) as Box<dyn VirtualMachine + 'consignment>
@@ -149,16 +258,93 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
             schema_id,
             genesis_id,
             contract_id,
+            version,
+            params: params.clone(),
             layers1,
             anchor_index,
             end_transitions,
             validation_index,
             anchor_validation_index,
+            frontier,
+            schema_rejected: empty!(),
+            spent: empty!(),
             vm,
+            pedersen: PedersenContext::new(),
             resolver,
+            zk_verifier,
+            hooks,
+            cancelled: false,
+            total_ops,
+            #[cfg(feature = "trace")]
+            stats: none!(),
+        }
+    }
+
+    /// Retrieves witness transaction from the resolver.
+    ///
+    /// Witnesses embedded into the consignment are never used here, since
+    /// nothing proves they are mined; they are taken into account only if the
+    /// caller imports them into the resolver, for instance with
+    /// [`super::SpvResolver::add_embedded`].
+    fn resolve_tx(&self, layer1: Layer1, txid: Txid) -> Result<Tx, TxResolverError> {
+        trace_count!(self.stats.resolver_calls);
+        self.resolver.resolve_tx(layer1, txid)
+    }
+
+    /// Retrieves witness transaction height from the resolver.
+    fn resolve_height(&self, layer1: Layer1, txid: Txid) -> Option<u32> {
+        trace_count!(self.stats.resolver_calls);
+        self.resolver.resolve_height(layer1, txid)
+    }
+
+    fn report_progress(&self, phase: ValidationPhase) {
+        if let Some(progress) = self.hooks.progress {
+            progress.report(self.validation_index.len(), self.total_ops, phase);
         }
     }
 
+    /// Checks whether the validation was cancelled, reporting the
+    /// cancellation in the status once.
+    fn is_cancelled(&mut self) -> bool {
+        if !self.cancelled && self.hooks.cancel.map(CancelToken::is_cancelled) == Some(true) {
+            self.cancelled = true;
+            self.status.add_failure(Failure::ValidationCancelled);
+        }
+        self.cancelled
+    }
+
+    fn witness_height(&self, anchor: &Anchor) -> Option<u32> {
+        match anchor {
+            Anchor::Bitcoin(a) | Anchor::Liquid(a) => self.resolve_height(anchor.layer1(), a.txid),
+        }
+    }
+
+    /// Limits for checking strings in the operation state, if the string
+    /// checks are required by the consensus version the operation is validated
+    /// under. Transitions are validated under the version activated at the
+    /// height of their witness; genesis and extensions under the contract
+    /// version.
+    fn string_limits(&self, operation: OpRef) -> Option<&ConsensusLimits> {
+        let version = match operation {
+            OpRef::Transition(_) => self
+                .anchor_index
+                .get(&operation.id())
+                .map(|anchor| {
+                    self.params.version_at(
+                        self.version,
+                        anchor.layer1(),
+                        self.witness_height(anchor),
+                    )
+                })
+                .unwrap_or(self.version),
+            OpRef::Genesis(_) | OpRef::Extension(_) => self.version,
+        };
+        version
+            .rules()
+            .check_strings
+            .then_some(self.params.limits())
+    }
+
     /// Validation procedure takes a schema object, root schema (if any),
     /// resolver function returning transaction and its fee for a given
     /// transaction id, and returns a validation object listing all detected
@@ -168,28 +354,83 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
     /// the status object, but the validation continues for the rest of the
     /// consignment data. This can help it debugging and detecting all problems
     /// with the consignment.
-    pub fn validate(consignment: &'consignment C, resolver: &'resolver R, testnet: bool) -> Status {
-        let mut validator = Validator::init(consignment, resolver);
+    ///
+    /// Validation fails if the contract is issued on a network different from
+    /// `chain_net`, which must be the network the resolver retrieves witness
+    /// transactions from.
+    pub fn validate(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+    ) -> Status {
+        Self::validate_with_hooks(
+            consignment,
+            resolver,
+            chain_net,
+            &ConsensusParams::default(),
+            None,
+            ValidationHooks::default(),
+        )
+    }
+
+    /// Validates consignment like [`Validator::validate`], applying consensus
+    /// versions activated according to the provided parameters and verifying
+    /// zero-knowledge proofs required by the schema and the proof of the
+    /// history checkpoint with `zk_verifier`. If no verifier is provided,
+    /// transitions requiring proofs and consignments with compressed
+    /// history fail validation.
+    ///
+    /// The embedder-provided `hooks` run non-consensus checks of the
+    /// validation policy, receive validation progress reports and may cancel
+    /// the validation. If validation is cancelled, the status reports all
+    /// problems detected before the cancellation and
+    /// [`Failure::ValidationCancelled`].
+    pub fn validate_with_hooks(
+        consignment: &'consignment C,
+        resolver: &'resolver R,
+        chain_net: ChainNet,
+        params: &ConsensusParams,
+        zk_verifier: Option<&'resolver dyn ZkVerifier>,
+        hooks: ValidationHooks<'resolver>,
+    ) -> Status {
+        let mut validator = Validator::init(consignment, resolver, params, zk_verifier, hooks);
+        trace_span!("contract", validator.contract_id);
 
+        validator.report_progress(ValidationPhase::Schema);
         validator.validate_schema(consignment.schema());
+        if let Some(policy) = validator.hooks.policy {
+            validator.status += policy.check_contract(consignment.schema(), consignment.genesis());
+        }
 
         // If the network mismatches there is no point in validating the contract since
         // all witness transactions will be missed. Thus, we return early (however after
         // schema validation, which is not network-specific).
-        if testnet != validator.consignment.genesis().testnet {
-            validator
-                .status
-                .add_failure(Failure::NetworkMismatch(testnet));
+        let contract_chain_net = validator.consignment.genesis().chain_net;
+        if chain_net != contract_chain_net {
+            validator.status.add_failure(Failure::NetworkMismatch {
+                contract: contract_chain_net,
+                validator: chain_net,
+            });
+            validator.report_progress(ValidationPhase::Finished);
             return validator.status;
         }
 
         // We must return here, since if the schema is not valid there is no reason to
         // validate contract nodes against it: it will produce a plenty of errors
-        if validator.status.validity() == Validity::Invalid {
+        if validator.status.validity() == Validity::Invalid || validator.is_cancelled() {
+            validator.report_progress(ValidationPhase::Finished);
             return validator.status;
         }
 
         validator.validate_contract(consignment.schema());
+        #[cfg(feature = "trace")]
+        log::debug!(
+            target: super::TRACE_TARGET,
+            "validated contract {}: {}",
+            validator.contract_id,
+            validator.stats
+        );
+        validator.report_progress(ValidationPhase::Finished);
 
         // Done. Returning status report with all possible failures, issues, warnings
         // and notifications about transactions we were unable to obtain.
@@ -213,13 +454,31 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         }
 
         // [VALIDATION]: Validate genesis
+        self.report_progress(ValidationPhase::Genesis);
+        self.validate_limits(OpRef::Genesis(self.consignment.genesis()));
         self.status += schema.validate(
             self.consignment,
             OpRef::Genesis(self.consignment.genesis()),
             self.vm.as_ref(),
+            &self.pedersen,
+            self.string_limits(OpRef::Genesis(self.consignment.genesis())),
         );
         self.validation_index.insert(self.genesis_id);
 
+        // [VALIDATION]: Verify reserves backing the issuance
+        self.report_progress(ValidationPhase::Reserves);
+        self.validate_reserves();
+
+        // [VALIDATION]: Verify that genesis chain proof precedes all the witnesses
+        self.report_progress(ValidationPhase::ChainProof);
+        self.validate_chain_proof();
+
+        // [VALIDATION]: Verify proof of the history checkpoint replacing the pruned
+        //               part of the contract history
+        self.report_progress(ValidationPhase::Checkpoint);
+        self.validate_checkpoint();
+
+        self.report_progress(ValidationPhase::History);
         // [VALIDATION]: Iterating over each endpoint, reconstructing operation
         //               graph up to genesis for each one of them.
         // NB: We are not aiming to validate the consignment as a whole, but instead
@@ -228,6 +487,10 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         for (operation, bundle_id) in self.end_transitions.clone() {
             self.validate_branch(schema, operation, bundle_id);
         }
+        // Partially validated history can't be analyzed any further
+        if self.is_cancelled() {
+            return;
+        }
         // Replace missed (not yet mined) endpoint witness transaction failures
         // with a dedicated type
         for (operation, _) in &self.end_transitions {
@@ -253,6 +516,36 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                 }
             }
         }
+        // Maturity and expiry of the outputs spent by endpoints can't be known
+        // until their witness transactions are mined
+        for (operation, _) in &self.end_transitions {
+            let opid = operation.id();
+            let Some(anchor) = self.anchor_index.get(&opid) else {
+                continue;
+            };
+            if self.witness_height(anchor).is_some() {
+                continue;
+            }
+            let txid = match anchor {
+                Anchor::Bitcoin(anchor) | Anchor::Liquid(anchor) => anchor.txid,
+            };
+            let warnings = &mut self.status.warnings;
+            let len = warnings.len();
+            self.status.failures.retain(|failure| match failure {
+                Failure::OutputMaturityUnknown(id, opout) if *id == opid => {
+                    warnings.push(Warning::OutputMaturityUnchecked(opid, *opout));
+                    false
+                }
+                Failure::OutputExpiryUnknown(id, opout) if *id == opid => {
+                    warnings.push(Warning::OutputExpiryUnchecked(opid, *opout));
+                    false
+                }
+                _ => true,
+            });
+            if warnings.len() > len && !self.status.unmined_terminals.contains(&txid) {
+                self.status.unmined_terminals.push(txid);
+            }
+        }
 
         // Generate warning if some of the transitions within the consignment were
         // excessive (i.e. not part of validation_index). Nothing critical, but still
@@ -268,7 +561,9 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         transition: &'consignment Transition,
         bundle_id: BundleId,
     ) {
-        let mut queue: VecDeque<OpRef> = VecDeque::new();
+        trace_span!("branch", transition.id());
+        // Queued operations with their distance from the terminal transition
+        let mut queue: VecDeque<(OpRef, u32)> = VecDeque::new();
 
         // Instead of constructing complex graph structures or using a recursions we
         // utilize queue to keep the track of the upstream (ancestor) nodes and make
@@ -279,14 +574,45 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         // change to a given operation is valid against the schema + committed
         // into bitcoin transaction graph with proper anchor. That is what we are
         // checking in the code below:
-        queue.push_back(OpRef::Transition(transition));
-        while let Some(operation) = queue.pop_front() {
+        queue.push_back((OpRef::Transition(transition), 0));
+        while let Some((operation, depth)) = queue.pop_front() {
+            if self.is_cancelled() {
+                return;
+            }
             let opid = operation.id();
 
             // [VALIDATION]: Verify operation against the schema and scripts
             if !self.validation_index.contains(&opid) {
-                self.status += schema.validate(self.consignment, operation, self.vm.as_ref());
+                trace_span!("operation", opid);
+                trace_count!(self.stats.operations);
+                if self.validate_op_schema(schema, operation) {
+                    self.validate_limits(operation);
+                    self.status += schema.validate(
+                        self.consignment,
+                        operation,
+                        self.vm.as_ref(),
+                        &self.pedersen,
+                        self.string_limits(operation),
+                    );
+                    if let OpRef::Transition(transition) = operation {
+                        self.validate_prev_outs(transition);
+                        self.validate_proof(schema, transition);
+                        self.validate_identity(schema, transition);
+                    }
+                    if let Some(policy) = self.hooks.policy {
+                        self.status += policy.check_operation(operation, depth);
+                    }
+                } else {
+                    self.schema_rejected.insert(opid);
+                }
                 self.validation_index.insert(opid);
+                self.report_progress(ValidationPhase::History);
+            }
+
+            // History preceding checkpoint frontier is attested by the checkpoint proof,
+            // while operations not matching the schema are not validated any further
+            if self.frontier.contains(&opid) || self.schema_rejected.contains(&opid) {
+                continue;
             }
 
             match operation {
@@ -299,6 +625,9 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                     // have a corresponding anchor
                     if let Some(anchor) = self.anchor_index.get(&opid).cloned() {
                         if !self.anchor_validation_index.contains(&opid) {
+                            trace_span!("bundle", bundle_id);
+                            trace_count!(self.stats.bundles);
+                            trace_count!(self.stats.commitments);
                             // Ok, now we have the `operation` and the `anchor`, let's do all
                             // required checks
 
@@ -310,6 +639,16 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                             }
 
                             self.validate_transition(transition, bundle_id, anchor);
+                            self.validate_maturity(schema, transition, anchor);
+                            self.validate_expiry(schema, transition, anchor);
+                            if let Some(policy) = self.hooks.policy {
+                                let txid = match anchor {
+                                    Anchor::Bitcoin(a) | Anchor::Liquid(a) => a.txid,
+                                };
+                                let height = self.witness_height(anchor);
+                                self.status +=
+                                    policy.check_witness(operation, anchor.layer1(), txid, height);
+                            }
                             self.anchor_validation_index.insert(opid);
                         }
                     } else {
@@ -330,7 +669,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                         })
                     });
 
-                    queue.extend(parent_nodes);
+                    queue.extend(parent_nodes.map(|prev_op| (prev_op, depth + 1)));
                 }
                 OpRef::Extension(extension) => {
                     for (valency, prev_id) in &extension.redeemed {
@@ -352,13 +691,347 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                             continue;
                         }
 
-                        queue.push_back(prev_op);
+                        queue.push_back((prev_op, depth + 1));
                     }
                 }
             }
         }
     }
 
+    /// Checks that the operation commits to the contract schema and uses
+    /// operation type declared by it. Operations failing the check are not
+    /// validated any further, since all other checks will fail with
+    /// meaningless errors.
+    fn validate_op_schema<Root: SchemaRoot>(
+        &mut self,
+        schema: &Schema<Root>,
+        operation: OpRef,
+    ) -> bool {
+        match op_schema_failure(schema, self.schema_id, self.contract_id, operation) {
+            Some(failure) => {
+                self.status.add_failure(failure);
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn validate_limits(&mut self, operation: OpRef) {
+        // [VALIDATION]: Check operation size against consensus limits
+        if let Err(err) = self.params.limits().check_operation(&operation) {
+            self.status
+                .add_failure(Failure::OperationLimitExceeded(operation.id(), err));
+        }
+    }
+
+    fn validate_prev_outs(&mut self, transition: &'consignment Transition) {
+        let opid = transition.id();
+        for input in &transition.inputs {
+            let prevout = input.prev_out;
+            let Opout { op, ty, no } = prevout;
+
+            // [VALIDATION]: Check that each input is spent only once within the history
+            let first = *self.spent.entry(prevout).or_insert(opid);
+            if first != opid {
+                self.status.add_failure(Failure::DoubleSpend {
+                    opout: prevout,
+                    first,
+                    second: opid,
+                });
+            }
+
+            // [VALIDATION]: Check that the input references existing ancestor output
+            let Some(prev_op) = self.consignment.operation(op) else {
+                self.status.add_failure(Failure::OperationAbsent(op));
+                continue;
+            };
+            let Some(variant) = prev_op.assignments_by_type(ty) else {
+                self.status.add_failure(Failure::NoPrevState {
+                    opid,
+                    prev_id: op,
+                    state_type: ty,
+                });
+                continue;
+            };
+            if no >= variant.len_u16() {
+                self.status.add_failure(Failure::NoPrevOut(opid, prevout));
+            }
+        }
+    }
+
+    fn validate_maturity<Root: SchemaRoot>(
+        &mut self,
+        schema: &Schema<Root>,
+        transition: &'consignment Transition,
+        anchor: &'consignment Anchor,
+    ) {
+        let opid = transition.id();
+        let height = self.witness_height(anchor);
+
+        // [VALIDATION]: Check that spent outputs have reached maturity required by
+        //               the schema
+        for input in &transition.inputs {
+            let opout = input.prev_out;
+            let Some(prev) = self.consignment.operation(opout.op) else {
+                continue;
+            };
+            let maturity = match prev {
+                OpRef::Genesis(_) => schema.genesis.maturity,
+                OpRef::Transition(prev) => schema
+                    .transitions
+                    .get(&prev.transition_type)
+                    .map(|transition_schema| transition_schema.maturity)
+                    .unwrap_or_default(),
+                OpRef::Extension(prev) => schema
+                    .extensions
+                    .get(&prev.extension_type)
+                    .map(|extension_schema| extension_schema.maturity)
+                    .unwrap_or_default(),
+            };
+            if maturity == 0 {
+                continue;
+            }
+            // Genesis and state extensions have no witness, thus their outputs
+            // mature since the transaction defined by the output seal is mined
+            let prev_height = match prev {
+                OpRef::Transition(_) => self
+                    .anchor_index
+                    .get(&opout.op)
+                    .and_then(|prev_anchor| self.witness_height(prev_anchor)),
+                OpRef::Genesis(_) | OpRef::Extension(_) => prev
+                    .assignments_by_type(opout.ty)
+                    .and_then(|variant| variant.revealed_seal_at(opout.no).ok().flatten())
+                    .and_then(SealDefinition::output)
+                    .and_then(|output| {
+                        let (layer1, outpoint) = output.into_parts();
+                        self.resolve_height(layer1, outpoint.txid)
+                    }),
+            };
+            let (Some(height), Some(prev_height)) = (height, prev_height) else {
+                self.status
+                    .add_failure(Failure::OutputMaturityUnknown(opid, opout));
+                continue;
+            };
+            let depth = height.saturating_sub(prev_height);
+            if depth < maturity {
+                self.status.add_failure(Failure::OutputImmature {
+                    opid,
+                    opout,
+                    maturity,
+                    depth,
+                });
+            }
+        }
+    }
+
+    fn validate_expiry<Root: SchemaRoot>(
+        &mut self,
+        schema: &Schema<Root>,
+        transition: &'consignment Transition,
+        anchor: &'consignment Anchor,
+    ) {
+        let opid = transition.id();
+        let height = self.witness_height(anchor);
+
+        // [VALIDATION]: Check that spent outputs have not expired at the height of
+        //               the witness transaction
+        for input in &transition.inputs {
+            let opout = input.prev_out;
+            let Some(expiry) = schema.expiry.get(&opout.ty).copied() else {
+                continue;
+            };
+            let Some(height) = height else {
+                self.status
+                    .add_failure(Failure::OutputExpiryUnknown(opid, opout));
+                continue;
+            };
+            if height > expiry {
+                self.status.add_failure(Failure::OutputExpired {
+                    opid,
+                    opout,
+                    expiry,
+                    height,
+                });
+            }
+        }
+    }
+
+    fn validate_reserves(&mut self) {
+        let genesis = self.consignment.genesis();
+        let reserves = &genesis.reserves;
+        if reserves.is_empty() {
+            return;
+        }
+        let layer1 = genesis.chain_net.layer1();
+        let mut available = 0u64;
+        for outpoint in &reserves.utxos {
+            let txid = outpoint.txid;
+            let tx = match self.resolve_tx(layer1, txid) {
+                Ok(tx) => tx,
+                Err(_) => {
+                    self.status.unresolved_txids.push(txid);
+                    self.status.add_failure(Failure::ReserveNoTx(*outpoint));
+                    continue;
+                }
+            };
+            let Some(output) = tx.outputs.get(outpoint.vout.to_usize()) else {
+                self.status
+                    .add_failure(Failure::ReserveOutputAbsent(*outpoint));
+                continue;
+            };
+            match self.resolve_height(layer1, txid) {
+                None => {
+                    self.status
+                        .add_failure(Failure::ReserveHeightUnknown(*outpoint));
+                }
+                Some(height) if height > reserves.height => {
+                    self.status
+                        .add_failure(Failure::ReserveOutputTooRecent(*outpoint, height));
+                }
+                Some(_) => {}
+            }
+            available = available.saturating_add(output.value.sats());
+        }
+        if available < reserves.amount {
+            self.status.add_failure(Failure::ReserveInsufficient {
+                claimed: reserves.amount,
+                available,
+            });
+        }
+    }
+
+    fn validate_chain_proof(&mut self) {
+        let genesis = self.consignment.genesis();
+        let chain_proof = genesis.chain_proof;
+        if chain_proof.is_empty() {
+            return;
+        }
+        let layer1 = genesis.chain_net.layer1();
+        let Some(block_height) = self
+            .resolver
+            .resolve_block_height(layer1, chain_proof.block_hash)
+        else {
+            self.status
+                .add_failure(Failure::ChainProofUnknownBlock(chain_proof.block_hash));
+            return;
+        };
+        let witnesses = self
+            .anchor_index
+            .values()
+            .filter(|anchor| anchor.layer1() == layer1)
+            .map(|anchor| match anchor {
+                Anchor::Bitcoin(a) | Anchor::Liquid(a) => a.txid,
+            })
+            .collect::<BTreeSet<_>>();
+        for txid in witnesses {
+            // Witnesses which are not mined yet are always created after the block
+            if let Some(height) = self.resolve_height(layer1, txid) {
+                if height <= block_height {
+                    self.status.add_failure(Failure::ChainProofWitnessPrecedes {
+                        txid,
+                        height,
+                        block_height,
+                    });
+                }
+            }
+        }
+    }
+
+    fn validate_checkpoint(&mut self) {
+        let Some(checkpoint) = self.consignment.checkpoint() else {
+            return;
+        };
+        let checkpoint_id = checkpoint.checkpoint_id();
+        match self.zk_verifier {
+            None => {
+                self.status
+                    .add_failure(Failure::CheckpointVerifierAbsent(checkpoint_id));
+            }
+            Some(verifier) => {
+                if let Err(err) = verifier.verify_checkpoint(self.schema_id, checkpoint) {
+                    self.status
+                        .add_failure(Failure::CheckpointProofInvalid(checkpoint_id, err));
+                }
+            }
+        }
+    }
+
+    fn validate_proof<Root: SchemaRoot>(
+        &mut self,
+        schema: &Schema<Root>,
+        transition: &'consignment Transition,
+    ) {
+        let opid = transition.id();
+        let required = schema
+            .transitions
+            .get(&transition.transition_type)
+            .and_then(|transition_schema| transition_schema.proof);
+
+        // [VALIDATION]: Verify zero-knowledge proof required by the schema
+        match (required, &transition.proof) {
+            (None, None) => {}
+            (None, Some(_)) => {
+                self.status.add_failure(Failure::ZkProofUnexpected(opid));
+            }
+            (Some(vk), None) => {
+                self.status.add_failure(Failure::ZkProofMissing(opid, vk));
+            }
+            (Some(vk), Some(proof)) if proof.vk != vk => {
+                self.status.add_failure(Failure::ZkProofKeyMismatch {
+                    opid,
+                    expected: vk,
+                    actual: proof.vk,
+                });
+            }
+            (Some(vk), Some(proof)) => match self.zk_verifier {
+                None => {
+                    self.status.add_failure(Failure::ZkVerifierAbsent(opid, vk));
+                }
+                Some(verifier) => {
+                    if let Err(err) = verifier.verify(vk, transition, proof.data.as_slice()) {
+                        self.status.add_failure(Failure::ZkProofInvalid(opid, err));
+                    }
+                }
+            },
+        }
+    }
+
+    fn validate_identity<Root: SchemaRoot>(
+        &mut self,
+        schema: &Schema<Root>,
+        transition: &'consignment Transition,
+    ) {
+        let opid = transition.id();
+        let required = schema
+            .transitions
+            .get(&transition.transition_type)
+            .and_then(|transition_schema| transition_schema.identity);
+
+        // [VALIDATION]: Verify signature with the genesis identity key required
+        //               by the schema
+        match (required, &transition.signature) {
+            (None, None) => {}
+            (None, Some(_)) => {
+                self.status
+                    .add_failure(Failure::IdentitySigUnexpected(opid));
+            }
+            (Some(_), None) => {
+                self.status.add_failure(Failure::IdentitySigMissing(opid));
+            }
+            (Some(ty), Some(_)) => {
+                let genesis = self.consignment.genesis();
+                let Some(identity) = genesis.identity_key(ty) else {
+                    self.status
+                        .add_failure(Failure::IdentityKeyInvalid(opid, ty));
+                    return;
+                };
+                if !transition.verify_signature(identity) {
+                    self.status.add_failure(Failure::IdentitySigInvalid(opid));
+                }
+            }
+        }
+    }
+
     fn validate_transition(
         &mut self,
         transition: &'consignment Transition,
@@ -373,7 +1046,7 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
 
         // Check that the anchor is committed into a transaction spending all of the
         // transition inputs.
-        match self.resolver.resolve_tx(layer1, txid) {
+        match self.resolve_tx(layer1, txid) {
             Err(_) => {
                 // We wre unable to retrieve corresponding transaction, so can't check.
                 // Reporting this incident and continuing further. Why this happens? No
@@ -390,7 +1063,9 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
             }
             Ok(witness_tx) => {
                 let witness = Witness::with(witness_tx, anchor.clone());
-                self.validate_witness(transition, witness, bundle_id, anchor)
+                let height = self.resolve_height(layer1, txid);
+                let version = self.params.version_at(self.version, layer1, height);
+                self.validate_witness(transition, witness, bundle_id, anchor, version)
             }
         }
     }
@@ -401,35 +1076,39 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         witness: Witness,
         bundle_id: BundleId,
         anchor: &'consignment dbc::Anchor<mpc::MerkleProof>,
+        version: ConsensusVersion,
     ) {
+        let rules = version.rules();
         let opid = transition.id();
         let txid = witness.txid;
 
+        // Verification of the deterministic bitcoin commitment is dispatched by
+        // its close method, which must be the same as used by all closed seals
+        let dbc_method = match CloseMethodEntry::with_dbc_proof(&witness.proof) {
+            Ok(entry) => Some(entry.method),
+            Err(_) => {
+                self.status
+                    .add_failure(Failure::AnchorCloseMethodUnknown { opid });
+                None
+            }
+        };
+
         // Checking that witness transaction closes seals defined by transition previous
         // outputs.
         let mut seals = vec![];
         for input in &transition.inputs {
             let Opout { op, ty, no } = input.prev_out;
 
-            let Some(prev_op) = self.consignment.operation(op) else {
-                // Node, referenced as the ancestor, was not found in the consignment.
-                // Usually this means that the consignment data are broken
-                self.status.add_failure(Failure::OperationAbsent(op));
-                continue;
-            };
-
-            let Some(variant) = prev_op.assignments_by_type(ty) else {
-                self.status.add_failure(Failure::NoPrevState {
-                    opid,
-                    prev_id: op,
-                    state_type: ty,
-                });
+            // Inputs not referencing existing ancestor outputs are reported by
+            // `validate_prev_outs`
+            let Some(variant) = self
+                .consignment
+                .operation(op)
+                .and_then(|prev_op| prev_op.assignments_by_type(ty))
+            else {
                 continue;
             };
-
             let Ok(seal) = variant.revealed_seal_at(no) else {
-                self.status
-                    .add_failure(Failure::NoPrevOut(opid, input.prev_out));
                 continue;
             };
             let Some(seal) = seal else {
@@ -491,16 +1170,35 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
                     Anchor::Bitcoin(_) | Anchor::Liquid(_),
                 ) => seal.resolve(txid),
             };
+            if !rules.allows_close_method(seal.method) {
+                self.status
+                    .add_failure(Failure::SealCloseMethodUnsupported {
+                        opid,
+                        version,
+                        method: seal.method,
+                    });
+                continue;
+            }
+            if let Some(method) = dbc_method.filter(|method| *method != seal.method) {
+                self.status.add_failure(Failure::SealCloseMethodMismatch {
+                    opid,
+                    seal: seal.method,
+                    anchor: method,
+                });
+                continue;
+            }
             seals.push(seal);
         }
 
         let message = mpc::Message::from(bundle_id);
+        trace_count!(self.stats.commitments);
         match anchor.convolve(self.contract_id, message) {
             Err(_) => {
                 self.status.add_failure(Failure::MpcInvalid(opid, txid));
             }
             Ok(commitment) => {
                 // [VALIDATION]: CHECKING SINGLE-USE-SEALS
+                trace_count!(self.stats.commitments);
                 witness
                     .verify_many_seals(&seals, &commitment)
                     .map_err(|err| {
@@ -520,3 +1218,42 @@ impl<'consignment, 'resolver, C: ConsignmentApi, R: ResolveTx>
         }
     }
 }
+
+/// Checks that the operation commits to the contract schema, belongs to the
+/// contract and uses operation type declared by the schema, returning failure
+/// of the first check which didn't pass.
+pub(crate) fn op_schema_failure<Root: SchemaRoot>(
+    schema: &Schema<Root>,
+    schema_id: SchemaId,
+    contract_id: ContractId,
+    operation: OpRef,
+) -> Option<Failure> {
+    let opid = operation.id();
+    // [VALIDATION]: Check that the operation commits to the contract schema
+    if operation.schema_id() != schema_id {
+        return Some(Failure::OperationSchemaMismatch {
+            opid,
+            expected: schema_id,
+            actual: operation.schema_id(),
+        });
+    }
+    // [VALIDATION]: Check that the operation is not replayed from another contract
+    //               sharing the same schema
+    if operation.contract_id() != contract_id {
+        return Some(Failure::OperationContractMismatch {
+            opid,
+            expected: contract_id,
+            actual: operation.contract_id(),
+        });
+    }
+    // [VALIDATION]: Check that the operation type is declared by the schema
+    match (operation.transition_type(), operation.extension_type()) {
+        (Some(ty), _) if ty != TransitionType::BLANK && !schema.transitions.contains_key(&ty) => {
+            Some(Failure::SchemaUnknownTransitionType(opid, ty))
+        }
+        (_, Some(ty)) if !schema.extensions.contains_key(&ty) => {
+            Some(Failure::SchemaUnknownExtensionType(opid, ty))
+        }
+        _ => None,
+    }
+}
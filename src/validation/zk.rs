@@ -0,0 +1,53 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{HistoryCheckpoint, SchemaId, Transition, VerifyingKeyId};
+
+/// Verifier of zero-knowledge proofs for state transitions, required by
+/// schemas for checking state invariants which are too heavy for the
+/// validation scripts.
+///
+/// The implementation must know the verifying key for each of the supported
+/// verifying key ids and construct the public inputs from the transition
+/// data, which include its operation id.
+pub trait ZkVerifier {
+    /// Verifies the proof for the transition with the verifying key, returning
+    /// error description if the proof is invalid or the key is not known.
+    fn verify(
+        &self,
+        vk: VerifyingKeyId,
+        transition: &Transition,
+        proof: &[u8],
+    ) -> Result<(), String>;
+
+    /// Verifies the proof of a history checkpoint for a contract with the
+    /// given schema, returning error description if the proof is invalid or
+    /// doesn't match the schema policy. Verifiers not supporting history
+    /// checkpoints reject all of them.
+    fn verify_checkpoint(
+        &self,
+        _schema_id: SchemaId,
+        _checkpoint: &HistoryCheckpoint,
+    ) -> Result<(), String> {
+        Err(s!("history checkpoints are not supported"))
+    }
+}
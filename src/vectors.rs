@@ -0,0 +1,234 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Written in 2019-2023 by
+//     Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2023 LNP/BP Standards Association. All rights reserved.
+// Copyright (C) 2019-2023 Dr Maxim Orlovsky. All rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test vectors for commitment ids, used for cross-implementation consensus
+//! testing.
+//!
+//! Test vectors are provided as a JSON array of objects, each containing kind
+//! of the data, hex-encoded strict serialization of the data and hex-encoded
+//! commitment id:
+//!
+//! ```json
+//! [
+//!   { "kind": "transition", "data": "<hex>", "id": "<hex>" }
+//! ]
+//! ```
+//!
+//! Fixtures have the same format, but without the `id` field, which is
+//! computed by [`TestVector::generate`].
+
+use amplify::hex::{FromHex, ToHex};
+use amplify::{Bytes32, Wrapper};
+use commit_verify::{CommitmentId, Conceal};
+use strict_encoding::StrictDecode;
+
+use crate::untrusted::deserialize_untrusted;
+use crate::{Genesis, GraphSeal, Operation, SealDefinition, Transition, TransitionBundle};
+
+/// Errors in test vectors.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum VectorError {
+    /// test vectors are not valid JSON. Details: {0}
+    Json(String),
+
+    /// test vector #{0} data are not valid hex string.
+    Hex(usize),
+
+    /// test vector #{0} data can't be deserialized as {1}.
+    Decode(usize, VectorKind),
+
+    /// test vector #{0} has no id.
+    NoId(usize),
+
+    /// test vector #{0} has id {1}, while data commit to {2}.
+    Mismatch(usize, String, String),
+}
+
+/// Kind of data in the test vector, defining which commitment id is computed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub enum VectorKind {
+    /// Genesis, committing to contract id.
+    #[display("genesis")]
+    Genesis,
+
+    /// State transition, committing to operation id.
+    #[display("transition")]
+    Transition,
+
+    /// Transition bundle, committing to bundle id.
+    #[display("bundle")]
+    Bundle,
+
+    /// Revealed seal definition, committing to secret seal.
+    #[display("seal")]
+    Seal,
+}
+
+/// Single test vector.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+pub struct TestVector {
+    pub kind: VectorKind,
+    /// Hex-encoded strict serialization of the data.
+    pub data: String,
+    /// Hex-encoded commitment id of the data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+fn decode<T: StrictDecode>(data: Vec<u8>) -> Option<T> { deserialize_untrusted(&data).ok() }
+
+fn id_hex(id: impl Wrapper<Inner = Bytes32>) -> String { id.into_inner().to_hex() }
+
+impl TestVector {
+    /// Constructs test vector for the provided kind and strict-serialized data,
+    /// computing its commitment id.
+    pub fn with(kind: VectorKind, data: impl AsRef<[u8]>) -> Result<Self, VectorError> {
+        TestVector {
+            kind,
+            data: data.as_ref().to_hex(),
+            id: None,
+        }
+        .generate(0)
+    }
+
+    /// Computes hex-encoded commitment id of the test vector data. Index of
+    /// the vector is used for error reporting.
+    pub fn compute_id(&self, index: usize) -> Result<String, VectorError> {
+        let data = Vec::<u8>::from_hex(&self.data).map_err(|_| VectorError::Hex(index))?;
+        let err = || VectorError::Decode(index, self.kind);
+        Ok(match self.kind {
+            VectorKind::Genesis => id_hex(decode::<Genesis>(data).ok_or_else(err)?.contract_id()),
+            VectorKind::Transition => id_hex(decode::<Transition>(data).ok_or_else(err)?.id()),
+            VectorKind::Bundle => id_hex(
+                decode::<TransitionBundle>(data)
+                    .ok_or_else(err)?
+                    .commitment_id(),
+            ),
+            VectorKind::Seal => id_hex(
+                decode::<SealDefinition<GraphSeal>>(data)
+                    .ok_or_else(err)?
+                    .conceal(),
+            ),
+        })
+    }
+
+    /// Fills in the commitment id of the test vector.
+    pub fn generate(self, index: usize) -> Result<Self, VectorError> {
+        let id = self.compute_id(index)?;
+        Ok(TestVector {
+            id: Some(id),
+            ..self
+        })
+    }
+
+    /// Verifies that the test vector id matches its data.
+    pub fn verify(&self, index: usize) -> Result<(), VectorError> {
+        let expected = self.id.as_ref().ok_or(VectorError::NoId(index))?;
+        let id = self.compute_id(index)?;
+        if !id.eq_ignore_ascii_case(expected) {
+            return Err(VectorError::Mismatch(index, expected.clone(), id));
+        }
+        Ok(())
+    }
+}
+
+/// Set of test vectors.
+#[derive(Wrapper, Clone, Eq, PartialEq, Hash, Debug, Default, From)]
+#[wrapper(Deref)]
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "serde_crate", transparent)]
+pub struct TestVectors(Vec<TestVector>);
+
+impl TestVectors {
+    pub fn from_json(json: &str) -> Result<Self, VectorError> {
+        serde_json::from_str(json).map_err(|err| VectorError::Json(err.to_string()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("test vectors are always serializable")
+    }
+
+    /// Computes commitment ids for all vectors.
+    pub fn generate(self) -> Result<Self, VectorError> {
+        self.0
+            .into_iter()
+            .enumerate()
+            .map(|(index, vector)| vector.generate(index))
+            .collect::<Result<_, _>>()
+            .map(TestVectors)
+    }
+
+    /// Verifies all vectors, returning errors for the vectors which failed
+    /// verification.
+    pub fn verify(&self) -> Vec<VectorError> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, vector)| vector.verify(index).err())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use amplify::confinement::U32;
+    use strict_encoding::{StrictDumb, StrictSerialize};
+
+    use super::*;
+
+    #[test]
+    fn generate_verify() {
+        let transition = Transition::strict_dumb();
+        let data = transition.to_strict_serialized::<U32>().unwrap();
+        let vector = TestVector::with(VectorKind::Transition, data).unwrap();
+        assert_eq!(vector.id, Some(transition.id().to_hex()));
+
+        let vectors = TestVectors::from(vec![vector.clone()]);
+        let vectors = TestVectors::from_json(&vectors.to_json()).unwrap();
+        assert!(vectors.verify().is_empty());
+
+        let wrong = TestVector {
+            id: Some(Bytes32::from([0u8; 32]).to_hex()),
+            ..vector
+        };
+        assert!(matches!(wrong.verify(1), Err(VectorError::Mismatch(1, ..))));
+    }
+
+    #[test]
+    fn fixture() {
+        let seal = SealDefinition::Bitcoin(GraphSeal::strict_dumb());
+        let fixture = format!(
+            r#"[{{"kind":"seal","data":"{}"}}]"#,
+            seal.to_strict_serialized::<U32>().unwrap().to_hex()
+        );
+        let vectors = TestVectors::from_json(&fixture)
+            .unwrap()
+            .generate()
+            .unwrap();
+        assert_eq!(vectors[0].id, Some(seal.conceal().to_hex()));
+    }
+}
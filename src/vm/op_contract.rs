@@ -30,15 +30,11 @@ use aluvm::library::{CodeEofError, LibSite, Read, Write};
 use aluvm::reg::{CoreRegs, Reg16, RegA, RegS};
 use amplify::num::u4;
 use amplify::Wrapper;
-use commit_verify::CommitVerify;
 use strict_encoding::StrictSerialize;
 
 use super::opcodes::*;
 use crate::validation::OpInfo;
-use crate::{
-    Assign, AssignmentType, BlindingFactor, GlobalStateType, PedersenCommitment, RevealedValue,
-    TypedAssigns,
-};
+use crate::{AssignmentType, BlindingFactor, GlobalStateType, RevealedValue, TypedAssigns};
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
 pub enum ContractOp {
@@ -176,8 +172,10 @@ impl InstructionSet for ContractOp {
                 match prev_state {
                     TypedAssigns::Fungible(state) => state
                         .iter()
-                        .map(Assign::to_confidential_state)
-                        .map(|s| s.commitment.into_inner())
+                        .map(|assign| match assign.as_revealed_state() {
+                            Some(value) => context.pedersen.commit(value),
+                            None => assign.to_confidential_state().commitment,
+                        })
                         .collect::<Vec<_>>(),
                     _ => fail!(),
                 }
@@ -194,8 +192,10 @@ impl InstructionSet for ContractOp {
                 match new_state {
                     TypedAssigns::Fungible(state) => state
                         .iter()
-                        .map(Assign::to_confidential_state)
-                        .map(|s| s.commitment.into_inner())
+                        .map(|assign| match assign.as_revealed_state() {
+                            Some(value) => context.pedersen.commit(value),
+                            None => assign.to_confidential_state().commitment,
+                        })
                         .collect::<Vec<_>>(),
                     _ => fail!(),
                 }
@@ -256,7 +256,10 @@ impl InstructionSet for ContractOp {
                 else {
                     fail!()
                 };
-                regs.set(RegA::A64, *reg, state.map(|s| s.value.as_u64()));
+                let Some(value) = state.map(|s| s.value.as_u64().ok_or(())).transpose().ok() else {
+                    fail!()
+                };
+                regs.set(RegA::A64, *reg, value);
             }
             ContractOp::LdG(state_type, index, reg) => {
                 let Some(state) = context
@@ -279,11 +282,7 @@ impl InstructionSet for ContractOp {
             ContractOp::PcVs(state_type) => {
                 let inputs = load_inputs!(state_type);
                 let outputs = load_outputs!(state_type);
-                if !secp256k1_zkp::verify_commitments_sum_to_equal(
-                    secp256k1_zkp::SECP256K1,
-                    &inputs,
-                    &outputs,
-                ) {
+                if !context.pedersen.verify_sum(&inputs, &outputs) {
                     fail!()
                 }
             }
@@ -307,14 +306,10 @@ impl InstructionSet for ContractOp {
                 };
                 let sum = RevealedValue::with_blinding(sum, BlindingFactor::EMPTY, *tag);
 
-                let inputs = [PedersenCommitment::commit(&sum).into_inner()];
+                let inputs = [context.pedersen.commit(&sum)];
                 let outputs = load_outputs!(owned_state);
 
-                if !secp256k1_zkp::verify_commitments_sum_to_equal(
-                    secp256k1_zkp::SECP256K1,
-                    &inputs,
-                    &outputs,
-                ) {
+                if !context.pedersen.verify_sum(&inputs, &outputs) {
                     fail!()
                 }
             }
@@ -181,6 +181,12 @@ impl StrictDecode for AluScript {
                 .map(|(id, lib)| {
                     let lib = Lib::deserialize(lib)
                         .map_err(|err| DecodeError::DataIntegrityError(err.to_string()))?;
+                    if lib.id() != id {
+                        return Err(DecodeError::DataIntegrityError(format!(
+                            "library {id} has id {} mismatching its content",
+                            lib.id()
+                        )));
+                    }
                     Ok((id, lib))
                 })
                 .collect::<Result<BTreeMap<_, _>, DecodeError>>()?;